@@ -0,0 +1,660 @@
+use crate::deck::*;
+use crate::equity::remaining_cards;
+use crate::hand::{score_hand, HandRules};
+use crate::holdem::Street;
+use crate::indexer::HandIndexer;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+use std::fs;
+
+/// The streets in the order they are dealt.
+const STREETS: [Street; 4] = [Street::Preflop, Street::Flop, Street::Turn, Street::River];
+
+/// What hands are compared by when they are put in buckets.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Feature {
+    /// The expected hand strength, which is how often the hand beats a random hand once the
+    /// board is out, averaged over the ways the board can run out.
+    Strength,
+    /// How the hand strength on the river is spread over the ways the board can run out, as a
+    /// histogram with the given number of bins. Hands are compared by the earth mover's
+    /// distance between their histograms, which keeps draws apart from made hands with the
+    /// same expected strength.
+    Distribution(usize),
+}
+
+impl Feature {
+    /// Parses the name used for the feature on the command line.
+    pub fn parse(name: &str, bins: usize) -> Option<Self> {
+        match name {
+            "strength" => Some(Feature::Strength),
+            "distribution" => Some(Feature::Distribution(bins)),
+            _ => None,
+        }
+    }
+
+    /// Returns the feature of the hand, estimated from `trials` runouts of the board.
+    pub fn of<R: Rng>(
+        &self,
+        hole: &CardCollection,
+        board: &CardCollection,
+        trials: u32,
+        rng: &mut R,
+    ) -> Vec<f64> {
+        match *self {
+            Feature::Strength => vec![expected_hand_strength(hole, board, trials, rng)],
+            Feature::Distribution(bins) => strength_histogram(hole, board, bins, trials, rng),
+        }
+    }
+
+    /// Returns how far apart two hands' features are.
+    pub fn distance(&self, a: &[f64], b: &[f64]) -> f64 {
+        match *self {
+            Feature::Strength => squared_distance(a, b),
+            Feature::Distribution(_) => earth_movers_distance(a, b),
+        }
+    }
+
+    /// Returns the expected hand strength a feature stands for.
+    fn strength(&self, feature: &[f64]) -> f64 {
+        match *self {
+            Feature::Strength => feature.first().copied().unwrap_or(0.0),
+            Feature::Distribution(bins) => feature
+                .iter()
+                .enumerate()
+                .map(|(bin, share)| share * (bin as f64 + 0.5) / bins as f64)
+                .sum(),
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Feature::Strength => write!(f, "strength"),
+            Feature::Distribution(bins) => write!(f, "distribution {}", bins),
+        }
+    }
+}
+
+/// How an abstraction is built.
+#[derive(Clone, Copy)]
+pub struct AbstractionConfig {
+    /// The number of buckets on each street, starting with preflop.
+    pub buckets: [usize; 4],
+    /// What hands are compared by before the river. On the river there is nothing left to
+    /// come, so hands are always compared by their strength.
+    pub feature: Feature,
+    /// The number of random hands clustered on each street after the flop is dealt. Every
+    /// hand is clustered preflop.
+    pub samples: usize,
+    /// The number of runouts of the board each hand's feature is estimated from.
+    pub trials: u32,
+    /// The most rounds of k-means run on each street.
+    pub iterations: usize,
+}
+
+/// The buckets of one street, each one described by the feature of the hand at its centre.
+struct StreetBuckets {
+    street: Street,
+    feature: Feature,
+    centroids: Vec<Vec<f64>>,
+}
+
+/// The hold'em hands of each street grouped into buckets of similar hands, numbered from the
+/// weakest bucket up.
+pub struct Abstraction {
+    // the runouts a hand's feature is estimated from when it is bucketed
+    trials: u32,
+    streets: Vec<StreetBuckets>,
+}
+
+impl Abstraction {
+    /// Builds the abstraction by working out the feature of hands on each street and
+    /// clustering them with k-means.
+    pub fn build<R: Rng>(config: &AbstractionConfig, rng: &mut R) -> Result<Self, String> {
+        if config.buckets.contains(&0) {
+            return Err("every street needs at least one bucket".to_string());
+        }
+        if config.feature == Feature::Distribution(0) {
+            return Err("the distributions need at least one bin".to_string());
+        }
+        if config.samples == 0 || config.trials == 0 {
+            return Err("the hands need sampling at least once".to_string());
+        }
+
+        let mut streets = vec![];
+        for (street, buckets) in STREETS.into_iter().zip(config.buckets) {
+            let feature = match street {
+                Street::River => Feature::Strength,
+                _ => config.feature,
+            };
+            let points = match street {
+                Street::Preflop => preflop_features(feature, config.trials, rng),
+                _ => (0..config.samples)
+                    .map(|_| {
+                        let (hole, board) = random_hand(street, rng);
+                        feature.of(&hole, &board, config.trials, rng)
+                    })
+                    .collect(),
+            };
+            let mut centroids = kmeans(
+                &points,
+                buckets,
+                config.iterations,
+                |a, b| feature.distance(a, b),
+                rng,
+            );
+            centroids.sort_by(|a, b| feature.strength(a).total_cmp(&feature.strength(b)));
+            streets.push(StreetBuckets {
+                street,
+                feature,
+                centroids,
+            });
+        }
+
+        Ok(Abstraction {
+            trials: config.trials,
+            streets,
+        })
+    }
+
+    /// Returns the number of buckets on the street.
+    pub fn buckets(&self, street: Street) -> usize {
+        self.street(street)
+            .map_or(0, |buckets| buckets.centroids.len())
+    }
+
+    /// Returns the expected hand strength of the hand at the centre of each of the street's
+    /// buckets.
+    pub fn strengths(&self, street: Street) -> Vec<f64> {
+        self.street(street).map_or(vec![], |buckets| {
+            buckets
+                .centroids
+                .iter()
+                .map(|centroid| buckets.feature.strength(centroid))
+                .collect()
+        })
+    }
+
+    /// Returns the bucket the hand is in, going by the number of cards on the board, or
+    /// `None` if that isn't a street.
+    pub fn bucket<R: Rng>(
+        &self,
+        hole: &CardCollection,
+        board: &CardCollection,
+        rng: &mut R,
+    ) -> Option<usize> {
+        let street = STREETS
+            .into_iter()
+            .find(|street| street.board_cards() == board.0.len())?;
+        let buckets = self.street(street)?;
+        let feature = buckets.feature.of(hole, board, self.trials, rng);
+
+        nearest(&buckets.centroids, &feature, |a, b| {
+            buckets.feature.distance(a, b)
+        })
+    }
+
+    /// Parses an abstraction written out by `save`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut abstraction = Abstraction {
+            trials: 1,
+            streets: vec![],
+        };
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let parsed = match words.as_slice() {
+                [] => Ok(()),
+                ["trials", trials] => match trials.parse() {
+                    Ok(trials) if trials > 0 => {
+                        abstraction.trials = trials;
+                        Ok(())
+                    }
+                    _ => Err(format!("{} isn't a number of trials", trials)),
+                },
+                ["street", street, feature @ ..] => parse_street(street, feature).map(|street| {
+                    abstraction.streets.push(street);
+                }),
+                ["bucket", values @ ..] => match abstraction.streets.last_mut() {
+                    Some(street) => parse_centroid(street.feature, values)
+                        .map(|centroid| street.centroids.push(centroid)),
+                    None => Err("a bucket has to come after its street".to_string()),
+                },
+                _ => Err(format!("don't know what '{}' means", line.trim())),
+            };
+            if let Err(err) = parsed {
+                return Err(format!("line {}: {}", index + 1, err));
+            }
+        }
+
+        for street in STREETS {
+            if abstraction.buckets(street) == 0 {
+                return Err(format!("the {} has no buckets", street));
+            }
+        }
+
+        Ok(abstraction)
+    }
+
+    /// Reads the abstraction saved at the path.
+    pub fn load(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(err) => Err(format!("couldn't read {}: {}", path, err)),
+        }
+    }
+
+    /// Writes the abstraction to the path, with a setting per line and each street followed by
+    /// its buckets:
+    ///
+    /// ```text
+    /// trials 32
+    /// street flop distribution 4
+    /// bucket 0.5 0.25 0.25 0
+    /// ```
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_string()).map_err(|err| format!("couldn't write {}: {}", path, err))
+    }
+
+    fn street(&self, street: Street) -> Option<&StreetBuckets> {
+        self.streets.iter().find(|buckets| buckets.street == street)
+    }
+}
+
+impl fmt::Display for Abstraction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# hold'em card abstraction")?;
+        writeln!(f, "trials {}", self.trials)?;
+        for buckets in &self.streets {
+            writeln!(f, "street {} {}", buckets.street, buckets.feature)?;
+            for centroid in &buckets.centroids {
+                let values: Vec<String> = centroid.iter().map(|value| value.to_string()).collect();
+                writeln!(f, "bucket {}", values.join(" "))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns how often the hole cards beat a random hand on the complete board, counting a tie
+/// as half a win.
+pub fn hand_strength(hole: &CardCollection, board: &CardCollection) -> f64 {
+    let others = remaining_cards(HandRules::Standard, &[hole, board]);
+    let mut hand: Vec<Card> = [hole.0.as_slice(), board.0.as_slice()].concat();
+    let score = score_hand(&hand, HandRules::Standard);
+
+    let mut won = 0.0;
+    let mut hands = 0;
+    for (place, first) in others.iter().enumerate() {
+        for second in &others[place + 1..] {
+            hand[0] = first.clone();
+            hand[1] = second.clone();
+            let other = score_hand(&hand, HandRules::Standard);
+            won += match score.cmp(&other) {
+                std::cmp::Ordering::Greater => 1.0,
+                std::cmp::Ordering::Equal => 0.5,
+                std::cmp::Ordering::Less => 0.0,
+            };
+            hands += 1;
+        }
+    }
+
+    won / hands as f64
+}
+
+/// Returns the hand strength averaged over `trials` random runouts of the rest of the board.
+pub fn expected_hand_strength<R: Rng>(
+    hole: &CardCollection,
+    board: &CardCollection,
+    trials: u32,
+    rng: &mut R,
+) -> f64 {
+    let strengths = river_strengths(hole, board, trials, rng);
+
+    strengths.iter().sum::<f64>() / strengths.len() as f64
+}
+
+/// Returns the share of `trials` random runouts of the rest of the board that leave the hand
+/// strength in each of `bins` equal ranges from 0 to 1.
+pub fn strength_histogram<R: Rng>(
+    hole: &CardCollection,
+    board: &CardCollection,
+    bins: usize,
+    trials: u32,
+    rng: &mut R,
+) -> Vec<f64> {
+    let strengths = river_strengths(hole, board, trials, rng);
+    let mut histogram = vec![0.0; bins];
+    for strength in &strengths {
+        let bin = ((strength * bins as f64) as usize).min(bins - 1);
+        histogram[bin] += 1.0 / strengths.len() as f64;
+    }
+
+    histogram
+}
+
+/// Returns the squared Euclidean distance between two points.
+pub fn squared_distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum()
+}
+
+/// Returns the earth mover's distance between two histograms with the same bins, which is the
+/// least work needed to move one's shares into the shape of the other's.
+pub fn earth_movers_distance(a: &[f64], b: &[f64]) -> f64 {
+    let mut carried = 0.0;
+    let mut distance = 0.0;
+    for (a, b) in a.iter().zip(b) {
+        carried += a - b;
+        distance += carried.abs();
+    }
+
+    distance
+}
+
+/// Clusters the points into `k` groups with k-means and returns the point at the centre of
+/// each group. The starting centres are picked with k-means++, and it stops early once no
+/// point changes group.
+pub fn kmeans<R: Rng>(
+    points: &[Vec<f64>],
+    k: usize,
+    iterations: usize,
+    distance: impl Fn(&[f64], &[f64]) -> f64,
+    rng: &mut R,
+) -> Vec<Vec<f64>> {
+    let Some(first) = points.choose(rng) else {
+        return vec![];
+    };
+    // k-means++ picks each new centre with a chance in proportion to the squared distance
+    // from the nearest centre picked so far
+    let mut centroids = vec![first.clone()];
+    let mut nearest_distance: Vec<f64> = points
+        .iter()
+        .map(|point| distance(point, first).powi(2))
+        .collect();
+    while centroids.len() < k {
+        let total: f64 = nearest_distance.iter().sum();
+        if total <= 0.0 {
+            // every point is already a centre
+            break;
+        }
+        let mut left = rng.gen::<f64>() * total;
+        let mut picked = points.len() - 1;
+        for (place, weight) in nearest_distance.iter().enumerate() {
+            if left < *weight {
+                picked = place;
+                break;
+            }
+            left -= weight;
+        }
+        let centroid = points[picked].clone();
+        for (point, nearest) in points.iter().zip(nearest_distance.iter_mut()) {
+            *nearest = nearest.min(distance(point, &centroid).powi(2));
+        }
+        centroids.push(centroid);
+    }
+
+    let mut groups = vec![usize::MAX; points.len()];
+    for _ in 0..iterations {
+        let mut changed = false;
+        for (point, group) in points.iter().zip(groups.iter_mut()) {
+            let nearest = nearest(&centroids, point, &distance).unwrap_or(0);
+            changed |= *group != nearest;
+            *group = nearest;
+        }
+        if !changed {
+            break;
+        }
+        // a group that loses all its points keeps its old centre
+        let mut sums = vec![vec![0.0; first.len()]; centroids.len()];
+        let mut counts = vec![0; centroids.len()];
+        for (point, group) in points.iter().zip(&groups) {
+            for (sum, value) in sums[*group].iter_mut().zip(point) {
+                *sum += value;
+            }
+            counts[*group] += 1;
+        }
+        for ((centroid, sum), count) in centroids.iter_mut().zip(sums).zip(counts) {
+            if count > 0 {
+                *centroid = sum.into_iter().map(|sum| sum / count as f64).collect();
+            }
+        }
+    }
+
+    centroids
+}
+
+/// Returns the place of the centre nearest the point.
+fn nearest(
+    centroids: &[Vec<f64>],
+    point: &[f64],
+    distance: impl Fn(&[f64], &[f64]) -> f64,
+) -> Option<usize> {
+    centroids
+        .iter()
+        .map(|centroid| distance(point, centroid))
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(place, _)| place)
+}
+
+/// Returns the hand strength on each of `trials` random runouts of the rest of the board, or
+/// just the hand strength if the board is complete.
+fn river_strengths<R: Rng>(
+    hole: &CardCollection,
+    board: &CardCollection,
+    trials: u32,
+    rng: &mut R,
+) -> Vec<f64> {
+    let needed = 5usize.saturating_sub(board.0.len());
+    if needed == 0 {
+        return vec![hand_strength(hole, board)];
+    }
+    let mut deck = remaining_cards(HandRules::Standard, &[hole, board]);
+    (0..trials.max(1))
+        .map(|_| {
+            let (runout, _) = deck.partial_shuffle(rng, needed);
+            let river = CardCollection([board.0.as_slice(), runout].concat());
+            hand_strength(hole, &river)
+        })
+        .collect()
+}
+
+/// Returns the feature of every preflop hand. Hands that only differ by their suits share a
+/// feature, so it is worked out once for each starting hand.
+fn preflop_features<R: Rng>(feature: Feature, trials: u32, rng: &mut R) -> Vec<Vec<f64>> {
+    let deck = remaining_cards(HandRules::Standard, &[]);
+    let board = CardCollection(vec![]);
+    let indexer = HandIndexer::holdem(Street::Preflop);
+    let mut features: Vec<Option<Vec<f64>>> = vec![None; indexer.size() as usize];
+    let mut points = vec![];
+    for (place, first) in deck.iter().enumerate() {
+        for second in &deck[place + 1..] {
+            let hole = CardCollection(vec![first.clone(), second.clone()]);
+            let index = indexer.index(&hole, &board).unwrap_or_default() as usize;
+            let point =
+                features[index].get_or_insert_with(|| feature.of(&hole, &board, trials, rng));
+            points.push(point.clone());
+        }
+    }
+
+    points
+}
+
+/// Deals random hole cards and the board of the street.
+fn random_hand<R: Rng>(street: Street, rng: &mut R) -> (CardCollection, CardCollection) {
+    let mut deck = remaining_cards(HandRules::Standard, &[]);
+    let (cards, _) = deck.partial_shuffle(rng, 2 + street.board_cards());
+    let (hole, board) = cards.split_at(2);
+
+    (
+        CardCollection(hole.to_vec()),
+        CardCollection(board.to_vec()),
+    )
+}
+
+fn parse_street(street: &str, feature: &[&str]) -> Result<StreetBuckets, String> {
+    let Some(street) = Street::parse(street) else {
+        return Err(format!("{} isn't a street", street));
+    };
+    let feature = match feature {
+        ["strength"] => Feature::Strength,
+        ["distribution", bins] => match bins.parse() {
+            Ok(bins) if bins > 0 => Feature::Distribution(bins),
+            _ => return Err(format!("{} isn't a number of bins", bins)),
+        },
+        _ => return Err(format!("{} isn't a feature", feature.join(" "))),
+    };
+
+    Ok(StreetBuckets {
+        street,
+        feature,
+        centroids: vec![],
+    })
+}
+
+fn parse_centroid(feature: Feature, values: &[&str]) -> Result<Vec<f64>, String> {
+    let centroid: Vec<f64> = values
+        .iter()
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("{} isn't a number", value))
+        })
+        .collect::<Result<_, String>>()?;
+    let expected = match feature {
+        Feature::Strength => 1,
+        Feature::Distribution(bins) => bins,
+    };
+    if centroid.len() != expected {
+        return Err(format!(
+            "the bucket has {} numbers instead of {}",
+            centroid.len(),
+            expected
+        ));
+    }
+
+    Ok(centroid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn cards(codes: &str) -> CardCollection {
+        CardCollection(codes.split_whitespace().filter_map(Card::parse).collect())
+    }
+
+    #[test]
+    fn kmeans_finds_well_separated_groups() {
+        let points: Vec<Vec<f64>> = [0.0, 1.0, 2.0, 10.0, 11.0, 12.0, 20.0, 21.0, 22.0]
+            .iter()
+            .map(|&value| vec![value, -value])
+            .collect();
+        let mut rng = StdRng::seed_from_u64(46);
+        let mut centroids = kmeans(&points, 3, 20, squared_distance, &mut rng);
+        centroids.sort_by(|a, b| a[0].total_cmp(&b[0]));
+        assert!(centroids == vec![vec![1.0, -1.0], vec![11.0, -11.0], vec![21.0, -21.0]]);
+
+        // there can't be more groups than different points
+        let same = vec![vec![3.0]; 4];
+        assert!(kmeans(&same, 2, 20, squared_distance, &mut rng) == vec![vec![3.0]]);
+        assert!(kmeans(&[], 2, 20, squared_distance, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn earth_movers_distance_is_the_work_of_moving_the_shares() {
+        assert_eq!(
+            earth_movers_distance(&[1.0, 0.0, 0.0], &[1.0, 0.0, 0.0]),
+            0.0
+        );
+        // moving everything two bins along
+        assert_eq!(
+            earth_movers_distance(&[1.0, 0.0, 0.0], &[0.0, 0.0, 1.0]),
+            2.0
+        );
+        assert_eq!(
+            earth_movers_distance(&[0.0, 0.0, 1.0], &[1.0, 0.0, 0.0]),
+            2.0
+        );
+        // moving each half one bin along
+        assert_eq!(
+            earth_movers_distance(&[0.5, 0.5, 0.0], &[0.0, 0.5, 0.5]),
+            1.0
+        );
+        // unlike the squared distance, which can't tell a near miss from a far one
+        assert_eq!(
+            squared_distance(&[1.0, 0.0, 0.0], &[0.0, 1.0, 0.0]),
+            squared_distance(&[1.0, 0.0, 0.0], &[0.0, 0.0, 1.0])
+        );
+    }
+
+    #[test]
+    fn strength_histograms_spread_the_runouts_over_the_bins() {
+        let mut rng = StdRng::seed_from_u64(46);
+        // a complete board leaves a single strength
+        let nuts = strength_histogram(&cards("As Ks"), &cards("Qs Js Ts 2d 3c"), 4, 10, &mut rng);
+        assert!(nuts == vec![0.0, 0.0, 0.0, 1.0]);
+
+        // a flush draw either gets there or is left with ace high
+        let draw = strength_histogram(&cards("Ah 5h"), &cards("Kh 8h 2c"), 5, 200, &mut rng);
+        assert_eq!(draw.len(), 5);
+        assert!((draw.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(draw[4] > 0.25 && draw[4] < 0.5);
+        assert!(draw[0] + draw[1] + draw[2] + draw[3] > 0.5);
+
+        // the same seed gives the same histogram
+        let again = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            strength_histogram(&cards("Ah 5h"), &cards("Kh 8h 2c"), 5, 50, &mut rng)
+        };
+        assert!(again(1) == again(1));
+    }
+
+    #[test]
+    fn saved_abstractions_parse_back_the_same() {
+        let config = AbstractionConfig {
+            buckets: [3, 3, 2, 2],
+            feature: Feature::Distribution(4),
+            samples: 20,
+            trials: 2,
+            iterations: 5,
+        };
+        let mut rng = StdRng::seed_from_u64(46);
+        let abstraction = Abstraction::build(&config, &mut rng).unwrap();
+        let path = std::env::temp_dir().join(format!("abstraction-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        abstraction.save(path).unwrap();
+        let loaded = Abstraction::load(path);
+        fs::remove_file(path).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.to_string(), abstraction.to_string());
+        for street in STREETS {
+            assert_eq!(loaded.buckets(street), abstraction.buckets(street));
+            assert!(loaded.strengths(street) == abstraction.strengths(street));
+        }
+        let bucket = |abstraction: &Abstraction| {
+            let mut rng = StdRng::seed_from_u64(7);
+            abstraction.bucket(&cards("Ah Ad"), &cards("2c 7d Ks"), &mut rng)
+        };
+        assert_eq!(bucket(&loaded), bucket(&abstraction));
+        // the strongest flop bucket holds an overpair
+        assert_eq!(bucket(&loaded), Some(2));
+    }
+
+    #[test]
+    fn broken_abstractions_are_rejected() {
+        assert!(Abstraction::parse("trials 0").is_err());
+        assert!(Abstraction::parse("bucket 0.5").is_err());
+        assert!(Abstraction::parse("street flop distribution 2\nbucket 0.5").is_err());
+        // the turn and river are missing
+        let text = "street preflop strength\nbucket 0.5\nstreet flop strength\nbucket 0.5";
+        assert!(Abstraction::parse(text).is_err());
+    }
+}
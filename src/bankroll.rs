@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A way chips move in or out of a player's bankroll.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Movement {
+    /// Money the player brought to the game.
+    Deposit,
+    /// Money the player took home.
+    Withdrawal,
+    BuyIn,
+    TopUp,
+    Rebuy,
+    /// Chips taken off a table back into the bankroll.
+    CashOut,
+}
+
+impl Movement {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "deposit" => Some(Movement::Deposit),
+            "withdrawal" => Some(Movement::Withdrawal),
+            "buy-in" => Some(Movement::BuyIn),
+            "top-up" => Some(Movement::TopUp),
+            "rebuy" => Some(Movement::Rebuy),
+            "cash-out" => Some(Movement::CashOut),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the movement adds to the bankroll rather than taking from it.
+    fn credits(&self) -> bool {
+        matches!(*self, Movement::Deposit | Movement::CashOut)
+    }
+}
+
+impl fmt::Display for Movement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Movement::Deposit => write!(f, "deposit"),
+            Movement::Withdrawal => write!(f, "withdrawal"),
+            Movement::BuyIn => write!(f, "buy-in"),
+            Movement::TopUp => write!(f, "top-up"),
+            Movement::Rebuy => write!(f, "rebuy"),
+            Movement::CashOut => write!(f, "cash-out"),
+        }
+    }
+}
+
+/// A single chip movement, along with the player's bankroll after it.
+#[derive(Clone)]
+pub struct LedgerEntry {
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+    pub player: String,
+    pub movement: Movement,
+    /// The table chips moved to or from, if any.
+    pub table: Option<usize>,
+    pub amount: u32,
+    pub balance: u32,
+}
+
+impl fmt::Display for LedgerEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let table = match self.table {
+            Some(table) => table.to_string(),
+            None => "-".to_string(),
+        };
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            self.time, self.player, self.movement, table, self.amount, self.balance
+        )
+    }
+}
+
+impl LedgerEntry {
+    /// Parses a line of the ledger file, which holds an entry per line with its fields
+    /// separated by tabs.
+    fn parse(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [time, player, movement, table, amount, balance] = fields.as_slice() else {
+            return None;
+        };
+        let table = match *table {
+            "-" => None,
+            table => Some(table.parse().ok()?),
+        };
+
+        Some(LedgerEntry {
+            time: time.parse().ok()?,
+            player: player.to_string(),
+            movement: Movement::parse(movement)?,
+            table,
+            amount: amount.parse().ok()?,
+            balance: balance.parse().ok()?,
+        })
+    }
+}
+
+/// The players' bankrolls, kept by name so they carry over between sessions. Every chip
+/// movement is written to the ledger, and the bankrolls are rebuilt from it when the server
+/// starts again.
+pub struct Bank {
+    balances: HashMap<String, u32>,
+    entries: Vec<LedgerEntry>,
+    // the ledger file new entries are appended to, if the bank is kept on disk
+    file: Option<File>,
+}
+
+impl Bank {
+    /// Creates a bank that only lives as long as the server.
+    pub fn new() -> Self {
+        Bank {
+            balances: HashMap::new(),
+            entries: vec![],
+            file: None,
+        }
+    }
+
+    /// Opens the ledger file at the path, creating it if it doesn't exist, and rebuilds the
+    /// bankrolls from the entries in it.
+    pub fn open(path: &str) -> Result<Self, String> {
+        let mut bank = Bank::new();
+        if let Ok(file) = File::open(path) {
+            for (index, line) in BufReader::new(file).lines().enumerate() {
+                let line = line.map_err(|err| format!("couldn't read {}: {}", path, err))?;
+                let Some(entry) = LedgerEntry::parse(&line) else {
+                    return Err(format!(
+                        "line {} of {} isn't a ledger entry",
+                        index + 1,
+                        path
+                    ));
+                };
+                bank.balances.insert(entry.player.clone(), entry.balance);
+                bank.entries.push(entry);
+            }
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| format!("couldn't open {}: {}", path, err))?;
+        bank.file = Some(file);
+
+        Ok(bank)
+    }
+
+    pub fn balance(&self, player: &str) -> u32 {
+        self.balances.get(player).copied().unwrap_or(0)
+    }
+
+    pub fn entries(&self) -> &Vec<LedgerEntry> {
+        &self.entries
+    }
+
+    /// Moves chips into or out of the player's bankroll and writes it to the ledger, returning
+    /// the new balance. Nothing changes if the player can't cover it, the bankroll can't hold
+    /// it or the ledger can't be written.
+    pub fn record(
+        &mut self,
+        player: &str,
+        movement: Movement,
+        table: Option<usize>,
+        amount: u32,
+    ) -> Result<u32, String> {
+        let balance = self.balance(player);
+        let balance = if movement.credits() {
+            balance
+                .checked_add(amount)
+                .ok_or("that's more chips than a bankroll can hold")?
+        } else if amount <= balance {
+            balance - amount
+        } else {
+            return Err(format!("your bankroll is only {}", balance));
+        };
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let entry = LedgerEntry {
+            time,
+            player: player.to_string(),
+            movement,
+            table,
+            amount,
+            balance,
+        };
+        if let Some(file) = self.file.as_mut() {
+            writeln!(file, "{}", entry)
+                .map_err(|err| format!("couldn't write the ledger: {}", err))?;
+        }
+        self.balances.insert(player.to_string(), balance);
+        self.entries.push(entry);
+
+        Ok(balance)
+    }
+
+    /// Returns how much each player is up or down over the whole ledger: what they took home
+    /// plus what is left in their bankroll, less what they brought. Players still sitting at a
+    /// table should cash out first.
+    pub fn settlement(&self) -> Vec<(String, i64)> {
+        let mut net: Vec<(String, i64)> = vec![];
+        for entry in &self.entries {
+            let change = match entry.movement {
+                Movement::Deposit => -(entry.amount as i64),
+                Movement::Withdrawal => entry.amount as i64,
+                _ => 0,
+            };
+            match net.iter_mut().find(|(player, _)| *player == entry.player) {
+                Some((_, total)) => *total += change,
+                None => net.push((entry.player.clone(), change)),
+            }
+        }
+        for (player, total) in net.iter_mut() {
+            *total += self.balance(player) as i64;
+        }
+
+        net
+    }
+}
+
+impl Default for Bank {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_refuses_what_a_bankroll_cannot_cover_or_hold() {
+        let mut bank = Bank::new();
+        assert_eq!(bank.record("alice", Movement::Deposit, None, 100), Ok(100));
+        assert!(bank.record("alice", Movement::BuyIn, Some(1), 101).is_err());
+        assert_eq!(bank.record("alice", Movement::BuyIn, Some(1), 60), Ok(40));
+        assert!(bank
+            .record("alice", Movement::CashOut, Some(1), u32::MAX)
+            .is_err());
+        // nothing is written for a refused movement
+        assert_eq!(bank.balance("alice"), 40);
+        assert_eq!(bank.entries().len(), 2);
+    }
+}
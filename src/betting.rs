@@ -0,0 +1,527 @@
+use std::fmt;
+
+/// The rules deciding how much a player may bet or raise, picked per table.
+#[derive(Clone, Copy, PartialEq)]
+pub enum BettingStructure {
+    /// Any bet from the big blind up to the whole stack.
+    NoLimit { big_blind: u32 },
+    /// Any bet from the big blind up to the size of the pot after calling.
+    PotLimit { big_blind: u32 },
+    /// Bets and raises are exactly the small bet on the early streets and the big bet on the
+    /// later streets, with at most `raise_cap` bets and raises per street unless heads-up.
+    FixedLimit {
+        small_bet: u32,
+        big_bet: u32,
+        raise_cap: u8,
+    },
+    /// Any bet or raise between the minimum and maximum, and at least the size of the last raise.
+    SpreadLimit { min_bet: u32, max_bet: u32 },
+}
+
+impl BettingStructure {
+    /// Returns the same structure played at new stakes. Fixed limit bets are the big blind and
+    /// twice the big blind, and spread limit bets range from the small blind to the big blind.
+    pub fn with_stakes(&self, small_blind: u32, big_blind: u32) -> Self {
+        match *self {
+            BettingStructure::NoLimit { .. } => BettingStructure::NoLimit { big_blind },
+            BettingStructure::PotLimit { .. } => BettingStructure::PotLimit { big_blind },
+            BettingStructure::FixedLimit { raise_cap, .. } => BettingStructure::FixedLimit {
+                small_bet: big_blind,
+                big_bet: big_blind * 2,
+                raise_cap,
+            },
+            BettingStructure::SpreadLimit { .. } => BettingStructure::SpreadLimit {
+                min_bet: small_blind,
+                max_bet: big_blind,
+            },
+        }
+    }
+
+    /// Parses the name a client uses for the betting limit, at the given stakes.
+    pub fn parse(limit: &str, small_blind: u32, big_blind: u32) -> Option<Self> {
+        let structure = match limit {
+            "nl" => BettingStructure::NoLimit { big_blind: 0 },
+            "pl" => BettingStructure::PotLimit { big_blind: 0 },
+            "fl" => BettingStructure::FixedLimit {
+                small_bet: 0,
+                big_bet: 0,
+                raise_cap: 4,
+            },
+            "sl" => BettingStructure::SpreadLimit {
+                min_bet: 0,
+                max_bet: 0,
+            },
+            _ => return None,
+        };
+
+        Some(structure.with_stakes(small_blind, big_blind))
+    }
+
+    /// Returns the name a client uses for the betting limit, which `parse` reads.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            BettingStructure::NoLimit { .. } => "nl",
+            BettingStructure::PotLimit { .. } => "pl",
+            BettingStructure::FixedLimit { .. } => "fl",
+            BettingStructure::SpreadLimit { .. } => "sl",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    Fold,
+    Check,
+    Call,
+    /// Bets or raises so the player's total bet for the round is the given amount.
+    Raise(u32),
+}
+
+impl Action {
+    /// Parses an action typed by a player, such as "call" or "raise 40". Bets and raises are
+    /// given as the total bet for the round.
+    pub fn parse(input: &str) -> Option<Self> {
+        let words: Vec<&str> = input.split_whitespace().collect();
+        match words.as_slice() {
+            ["fold"] => Some(Action::Fold),
+            ["check"] => Some(Action::Check),
+            ["call"] => Some(Action::Call),
+            ["bet" | "raise", amount] => amount.parse().ok().map(Action::Raise),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Action::Fold => write!(f, "fold"),
+            Action::Check => write!(f, "check"),
+            Action::Call => write!(f, "call"),
+            Action::Raise(amount) => write!(f, "raise to {}", amount),
+        }
+    }
+}
+
+/// The actions the player to act may take, as worked out by the betting round.
+#[derive(Clone, PartialEq)]
+pub struct LegalActions {
+    /// The chips needed to call, 0 if the player can check.
+    pub call: u32,
+    /// The smallest and largest total bets the player may raise to, if they can raise at all.
+    pub raise: Option<(u32, u32)>,
+}
+
+impl LegalActions {
+    /// Returns an error describing why if the action isn't one of these.
+    pub fn check(&self, action: Action) -> Result<(), String> {
+        match action {
+            Action::Fold | Action::Call => Ok(()),
+            Action::Check if self.call != 0 => Err(format!("can't check, {} to call", self.call)),
+            Action::Check => Ok(()),
+            Action::Raise(amount) => match self.raise {
+                None => Err("raising isn't allowed".to_string()),
+                Some((min, max)) if amount < min || amount > max => {
+                    Err(format!("can only raise to between {} and {}", min, max))
+                }
+                Some(_) => Ok(()),
+            },
+        }
+    }
+
+    /// Returns the action taken for a player who can't act themselves, which is to check if
+    /// possible and fold otherwise.
+    pub fn default_action(&self) -> Action {
+        if self.call == 0 {
+            Action::Check
+        } else {
+            Action::Fold
+        }
+    }
+}
+
+impl fmt::Display for LegalActions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.call == 0 {
+            write!(f, "check")?;
+        } else {
+            write!(f, "fold, call {}", self.call)?;
+        }
+        match self.raise {
+            Some((min, max)) if min == max => write!(f, ", raise to {}", min),
+            Some((min, max)) => write!(f, ", raise to {}-{}", min, max),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A single round of betting. Players are referred to by seat index, and action moves to the
+/// next seat that hasn't folded and still has chips behind.
+pub struct BettingRound {
+    structure: BettingStructure,
+    // whether fixed limit bets use the big bet on this street
+    big_bet_street: bool,
+    // the chips each player has behind
+    stacks: Vec<u32>,
+    // the chips each player has put in during this round
+    bets: Vec<u32>,
+    folded: Vec<bool>,
+    // whether each player has acted since the last full raise
+    acted: Vec<bool>,
+    // the chips in the pot from earlier rounds
+    pot: u32,
+    current_bet: u32,
+    // the size of the last full bet or raise, which the next raise must at least match
+    last_raise: u32,
+    // the number of full bets and raises made so far, for the fixed limit cap
+    raises: u8,
+    to_act: Option<usize>,
+}
+
+impl BettingRound {
+    /// Starts a round of betting where `first_to_act` acts first. `pot` is the chips already in
+    /// the pot from earlier rounds and `big_bet_street` picks the big bet in fixed limit games.
+    pub fn new(
+        structure: BettingStructure,
+        stacks: Vec<u32>,
+        folded: Vec<bool>,
+        pot: u32,
+        big_bet_street: bool,
+        first_to_act: usize,
+    ) -> Self {
+        let players = stacks.len();
+        let mut round = BettingRound {
+            structure,
+            big_bet_street,
+            stacks,
+            bets: vec![0; players],
+            folded,
+            acted: vec![false; players],
+            pot,
+            current_bet: 0,
+            last_raise: 0,
+            raises: 0,
+            to_act: None,
+        };
+        round.to_act = round.next_to_act(first_to_act);
+
+        round
+    }
+
+    pub fn stacks(&self) -> &Vec<u32> {
+        &self.stacks
+    }
+
+    pub fn bets(&self) -> &Vec<u32> {
+        &self.bets
+    }
+
+    pub fn folded(&self) -> &Vec<bool> {
+        &self.folded
+    }
+
+    /// Returns the player whose turn it is, or `None` once the round is complete.
+    pub fn to_act(&self) -> Option<usize> {
+        self.to_act
+    }
+
+    /// Returns the chips in the pot, including the bets made in this round.
+    pub fn pot(&self) -> u32 {
+        self.pot + self.bets.iter().sum::<u32>()
+    }
+
+    /// Posts a forced bet such as a blind or bring-in for the player without counting as their
+    /// action, returning how many chips were actually posted.
+    pub fn post_blind(&mut self, player: usize, amount: u32) -> u32 {
+        let amount = amount.min(self.stacks[player]);
+        self.stacks[player] -= amount;
+        self.bets[player] += amount;
+        if self.bets[player] > self.current_bet {
+            self.current_bet = self.bets[player];
+            self.last_raise = self.last_raise.max(self.current_bet);
+        }
+        // a full big blind is the opening bet towards the fixed limit cap, a bring-in isn't
+        if self.fixed_bet().is_some_and(|bet| self.current_bet >= bet) {
+            self.raises = self.raises.max(1);
+        }
+        // a player all in from the blind has nothing left to act with
+        self.to_act = self.to_act.and_then(|to_act| self.next_to_act(to_act));
+
+        amount
+    }
+
+    /// Returns the actions the player to act may take, or `None` if the round is complete.
+    pub fn legal_actions(&self) -> Option<LegalActions> {
+        let player = self.to_act?;
+        let call = (self.current_bet - self.bets[player]).min(self.stacks[player]);
+        let all_in = self.bets[player] + self.stacks[player];
+        // having acted since the last full raise, the player faces an all in for less than a
+        // full raise, which doesn't reopen the betting for them
+        if all_in <= self.current_bet || !self.anyone_left_to_raise(player) || self.acted[player] {
+            return Some(LegalActions { call, raise: None });
+        }
+
+        let (min, max) = match self.structure {
+            BettingStructure::NoLimit { big_blind } => {
+                (self.current_bet + self.last_raise.max(big_blind), all_in)
+            }
+            BettingStructure::PotLimit { big_blind } => {
+                // a pot sized raise is the pot after calling, on top of the call
+                let pot_after_call = self.pot() + (self.current_bet - self.bets[player]);
+                (
+                    self.current_bet + self.last_raise.max(big_blind),
+                    self.current_bet + pot_after_call,
+                )
+            }
+            BettingStructure::FixedLimit { raise_cap, .. } => {
+                let heads_up = self.folded.iter().filter(|folded| !**folded).count() == 2;
+                if self.raises >= raise_cap && !heads_up {
+                    return Some(LegalActions { call, raise: None });
+                }
+                let bet = self.fixed_bet().unwrap_or(0);
+                // a bet short of the full bet, like a bring-in, is completed to it
+                let to = if self.current_bet < bet {
+                    bet
+                } else {
+                    self.current_bet + bet
+                };
+                (to, to)
+            }
+            BettingStructure::SpreadLimit { min_bet, max_bet } => (
+                self.current_bet + self.last_raise.max(min_bet).min(max_bet),
+                self.current_bet + max_bet,
+            ),
+        };
+
+        // a player without enough chips for a full raise may still go all in
+        Some(LegalActions {
+            call,
+            raise: Some((min.min(all_in), max.min(all_in))),
+        })
+    }
+
+    /// Applies the action for the player to act and moves the action on. Returns an error
+    /// describing why if the action isn't legal, in which case nothing changes.
+    pub fn act(&mut self, action: Action) -> Result<(), String> {
+        let Some(player) = self.to_act else {
+            return Err("the betting round is already complete".to_string());
+        };
+        let Some(legal) = self.legal_actions() else {
+            return Err("the betting round is already complete".to_string());
+        };
+        legal.check(action)?;
+
+        match action {
+            Action::Fold => {
+                self.folded[player] = true;
+            }
+            Action::Check => {}
+            Action::Call => {
+                self.stacks[player] -= legal.call;
+                self.bets[player] += legal.call;
+            }
+            Action::Raise(amount) => {
+                let raise = amount - self.current_bet;
+                let completes = self
+                    .fixed_bet()
+                    .is_some_and(|bet| self.current_bet < bet && amount >= bet);
+                self.stacks[player] -= amount - self.bets[player];
+                self.bets[player] = amount;
+                self.current_bet = amount;
+                // an all in for less than a full raise doesn't reopen the betting, but
+                // completing a bring-in to the full bet does
+                if raise >= self.last_raise || completes {
+                    self.last_raise = raise;
+                    self.raises += 1;
+                    self.acted = vec![false; self.acted.len()];
+                }
+            }
+        }
+        self.acted[player] = true;
+        self.to_act = self.next_to_act(player + 1);
+
+        Ok(())
+    }
+
+    /// Returns whether the round is over, either because every player has matched the bet or
+    /// because only one player is left in the hand.
+    pub fn is_complete(&self) -> bool {
+        self.to_act.is_none()
+    }
+
+    /// Returns the first player from the given seat onwards who still has to act.
+    fn next_to_act(&self, from: usize) -> Option<usize> {
+        let players = self.stacks.len();
+        if self.folded.iter().filter(|folded| !**folded).count() <= 1 {
+            return None;
+        }
+        // nobody is left to bet against if everyone else is all in and the bet is matched
+        let with_chips: Vec<usize> = (0..players)
+            .filter(|player| !self.folded[*player] && self.stacks[*player] > 0)
+            .collect();
+        if with_chips.len() == 1 && self.bets[with_chips[0]] >= self.current_bet {
+            return None;
+        }
+        (0..players)
+            .map(|offset| (from + offset) % players)
+            .find(|player| {
+                !self.folded[*player]
+                    && self.stacks[*player] > 0
+                    && (!self.acted[*player] || self.bets[*player] < self.current_bet)
+            })
+    }
+
+    /// Returns the size of a bet or raise on this street in fixed limit games.
+    fn fixed_bet(&self) -> Option<u32> {
+        match self.structure {
+            BettingStructure::FixedLimit { big_bet, .. } if self.big_bet_street => Some(big_bet),
+            BettingStructure::FixedLimit { small_bet, .. } => Some(small_bet),
+            _ => None,
+        }
+    }
+
+    /// Returns whether any other player could still respond to a raise from this player.
+    fn anyone_left_to_raise(&self, player: usize) -> bool {
+        (0..self.stacks.len())
+            .any(|other| other != player && !self.folded[other] && self.stacks[other] > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round(structure: BettingStructure, stacks: Vec<u32>) -> BettingRound {
+        let players = stacks.len();
+        BettingRound::new(structure, stacks, vec![false; players], 0, false, 0)
+    }
+
+    #[test]
+    fn short_all_in_does_not_reopen_the_betting() {
+        let mut round = round(
+            BettingStructure::NoLimit { big_blind: 10 },
+            vec![1000, 1000, 25],
+        );
+        round.act(Action::Raise(20)).unwrap();
+        round.act(Action::Call).unwrap();
+        round.act(Action::Raise(25)).unwrap();
+        // both players already acted on the bet of 20, so they may only call the extra 5
+        let legal = round.legal_actions().unwrap();
+        assert_eq!(round.to_act(), Some(0));
+        assert_eq!(legal.call, 5);
+        assert_eq!(legal.raise, None);
+        assert!(round.act(Action::Raise(60)).is_err());
+        round.act(Action::Call).unwrap();
+        assert_eq!(round.legal_actions().unwrap().raise, None);
+        round.act(Action::Call).unwrap();
+        assert!(round.is_complete());
+    }
+
+    #[test]
+    fn short_all_in_leaves_raising_open_to_players_yet_to_act() {
+        let mut round = round(
+            BettingStructure::NoLimit { big_blind: 10 },
+            vec![1000, 25, 1000],
+        );
+        round.act(Action::Raise(20)).unwrap();
+        round.act(Action::Raise(25)).unwrap();
+        // the full raise was 20, so the next raise is to at least 45
+        assert_eq!(round.legal_actions().unwrap().raise, Some((45, 1000)));
+        round.act(Action::Raise(45)).unwrap();
+        // a full raise reopens the betting for the first player
+        assert_eq!(round.to_act(), Some(0));
+        assert_eq!(round.legal_actions().unwrap().raise, Some((65, 1000)));
+    }
+
+    #[test]
+    fn no_limit_raises_are_at_least_the_last_raise() {
+        let mut round = round(BettingStructure::NoLimit { big_blind: 10 }, vec![1000; 3]);
+        round.post_blind(1, 5);
+        round.post_blind(2, 10);
+        assert_eq!(round.legal_actions().unwrap().raise, Some((20, 1000)));
+        round.act(Action::Raise(50)).unwrap();
+        // the raise was 40, so the next raise is to at least 90
+        let legal = round.legal_actions().unwrap();
+        assert_eq!(legal.call, 45);
+        assert_eq!(legal.raise, Some((90, 1000)));
+        assert!(legal.check(Action::Raise(80)).is_err());
+        assert!(legal.check(Action::Check).is_err());
+        assert!(legal.check(Action::Raise(1000)).is_ok());
+    }
+
+    #[test]
+    fn fixed_limit_big_blind_counts_towards_the_cap() {
+        let structure = BettingStructure::FixedLimit {
+            small_bet: 10,
+            big_bet: 20,
+            raise_cap: 4,
+        };
+        let mut round = round(structure, vec![1000; 3]);
+        round.post_blind(1, 5);
+        round.post_blind(2, 10);
+        // the big blind is the bet, so three raises reach the cap of four
+        assert_eq!(round.legal_actions().unwrap().raise, Some((20, 20)));
+        round.act(Action::Raise(20)).unwrap();
+        round.act(Action::Raise(30)).unwrap();
+        round.act(Action::Raise(40)).unwrap();
+        let legal = round.legal_actions().unwrap();
+        assert_eq!(legal.call, 20);
+        assert_eq!(legal.raise, None);
+    }
+
+    #[test]
+    fn fixed_limit_bring_in_is_completed_to_the_small_bet() {
+        let structure = BettingStructure::FixedLimit {
+            small_bet: 10,
+            big_bet: 20,
+            raise_cap: 4,
+        };
+        let mut round = round(structure, vec![1000; 3]);
+        round.post_blind(2, 5);
+        let legal = round.legal_actions().unwrap();
+        assert_eq!(legal.call, 5);
+        assert_eq!(legal.raise, Some((10, 10)));
+        round.act(Action::Raise(10)).unwrap();
+        // completing is the first bet, so three more raises reach the cap
+        assert_eq!(round.legal_actions().unwrap().raise, Some((20, 20)));
+        round.act(Action::Raise(20)).unwrap();
+        round.act(Action::Raise(30)).unwrap();
+        round.act(Action::Raise(40)).unwrap();
+        assert_eq!(round.to_act(), Some(1));
+        assert_eq!(round.legal_actions().unwrap().raise, None);
+    }
+
+    #[test]
+    fn fixed_limit_cap_lifts_heads_up() {
+        let structure = BettingStructure::FixedLimit {
+            small_bet: 10,
+            big_bet: 20,
+            raise_cap: 1,
+        };
+        let mut round = round(structure, vec![1000; 2]);
+        round.post_blind(0, 5);
+        round.post_blind(1, 10);
+        assert_eq!(round.legal_actions().unwrap().raise, Some((20, 20)));
+    }
+
+    #[test]
+    fn pot_limit_raises_up_to_the_pot() {
+        let mut round = round(BettingStructure::PotLimit { big_blind: 10 }, vec![1000; 3]);
+        round.post_blind(1, 5);
+        round.post_blind(2, 10);
+        // calling 10 makes the pot 25, so the raise can be to 35
+        assert_eq!(round.legal_actions().unwrap().raise, Some((20, 35)));
+    }
+
+    #[test]
+    fn spread_limit_raises_stay_within_the_spread() {
+        let structure = BettingStructure::SpreadLimit {
+            min_bet: 5,
+            max_bet: 10,
+        };
+        let mut round = round(structure, vec![1000; 2]);
+        assert_eq!(round.legal_actions().unwrap().raise, Some((5, 10)));
+        round.act(Action::Raise(8)).unwrap();
+        assert_eq!(round.legal_actions().unwrap().raise, Some((16, 18)));
+    }
+}
@@ -0,0 +1,443 @@
+use crate::betting::{Action, LegalActions};
+use crate::deck::*;
+use crate::equity::monte_carlo_equity;
+use crate::hand::{assign_hand_rankings_with_rules, get_best_hand_with_rules, Hand};
+use crate::holdem::Street;
+use crate::lobby::GameType;
+use crate::strategy::{Decision, Strategy};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt;
+
+/// The hands the tight-aggressive bot plays before the flop, best tier first. Hands in the
+/// first tier are raised and reraised, hands in the second are raised and can call a raise,
+/// and hands in the last are only raised when nobody has raised yet.
+const PREFLOP_CHART: [&str; 3] = [
+    "AA KK QQ JJ AKs AKo",
+    "TT 99 AQs AQo AJs KQs",
+    "88 77 66 55 44 33 22 ATs A9s A8s A7s A6s A5s A4s A3s A2s KJs KTs QJs QTs JTs T9s 98s 87s \
+     AJo ATo KQo",
+];
+/// The number of deals the equity bot samples for each decision.
+const EQUITY_TRIALS: u32 = 500;
+
+/// The bots that can take a seat.
+#[derive(Clone, Copy, PartialEq)]
+pub enum BotKind {
+    Random,
+    CallingStation,
+    TightAggressive,
+    Equity,
+}
+
+impl BotKind {
+    /// Parses the name used for the bot on the command line and by clients.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "random" => Some(BotKind::Random),
+            "station" => Some(BotKind::CallingStation),
+            "tag" => Some(BotKind::TightAggressive),
+            "equity" => Some(BotKind::Equity),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the bot knows how to play the game. The tight-aggressive and equity bots
+    /// only understand two hole cards and a board.
+    pub fn plays(&self, game: GameType) -> bool {
+        match *self {
+            BotKind::Random | BotKind::CallingStation => true,
+            BotKind::TightAggressive | BotKind::Equity => {
+                matches!(game, GameType::Holdem | GameType::ShortDeck)
+            }
+        }
+    }
+
+    /// Creates a bot of this kind.
+    pub fn create(&self) -> Box<dyn Strategy + Send> {
+        match *self {
+            BotKind::Random => Box::new(RandomBot::new()),
+            BotKind::CallingStation => Box::new(CallingStation),
+            BotKind::TightAggressive => Box::new(TightAggressive),
+            BotKind::Equity => Box::new(EquityBot::new()),
+        }
+    }
+}
+
+impl fmt::Display for BotKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BotKind::Random => write!(f, "random"),
+            BotKind::CallingStation => write!(f, "station"),
+            BotKind::TightAggressive => write!(f, "tag"),
+            BotKind::Equity => write!(f, "equity"),
+        }
+    }
+}
+
+/// Folds, calls or raises at random, but never folds when it can check.
+pub struct RandomBot {
+    rng: StdRng,
+}
+
+impl RandomBot {
+    pub fn new() -> Self {
+        RandomBot {
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl Default for RandomBot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Strategy for RandomBot {
+    fn decide(&mut self, decision: &Decision) -> Action {
+        let legal = &decision.legal;
+        match (self.rng.gen_range(0..3), legal.raise) {
+            (0, _) => legal.default_action(),
+            (2, Some((min, max))) => Action::Raise(self.rng.gen_range(min..=max)),
+            _ => check_or_call(legal),
+        }
+    }
+
+    fn choose_runs(&mut self, max_runs: u32) -> u32 {
+        self.rng.gen_range(1..=max_runs)
+    }
+}
+
+/// Checks or calls whatever it faces.
+pub struct CallingStation;
+
+impl Strategy for CallingStation {
+    fn decide(&mut self, decision: &Decision) -> Action {
+        check_or_call(&decision.legal)
+    }
+}
+
+/// Plays few hands before the flop, picked from a chart, and bets its good hands after it.
+pub struct TightAggressive;
+
+impl TightAggressive {
+    fn preflop(&self, decision: &Decision) -> Action {
+        let big_blind = decision.big_blind;
+        let raised = decision.bet > big_blind;
+        let open = raise_to(&decision.legal, 3 * big_blind);
+        let reraise = raise_to(&decision.legal, 3 * decision.bet);
+        match preflop_tier(&decision.hole_cards) {
+            Some(0) if raised => reraise,
+            Some(_) if !raised => open,
+            Some(1) if decision.bet <= 4 * big_blind => check_or_call(&decision.legal),
+            _ => decision.legal.default_action(),
+        }
+    }
+
+    fn postflop(&self, decision: &Decision) -> Action {
+        let legal = &decision.legal;
+        let pot = decision.pot;
+        match made_hand_strength(decision) {
+            Strength::Strong if decision.bet == 0 => raise_to(legal, pot * 2 / 3),
+            Strength::Strong => raise_to(legal, 3 * decision.bet),
+            Strength::Medium if decision.bet == 0 => raise_to(legal, pot / 2),
+            Strength::Medium if legal.call * 2 <= pot => check_or_call(legal),
+            _ => legal.default_action(),
+        }
+    }
+}
+
+impl Strategy for TightAggressive {
+    fn decide(&mut self, decision: &Decision) -> Action {
+        if decision.street == Some(Street::Preflop) {
+            self.preflop(decision)
+        } else {
+            self.postflop(decision)
+        }
+    }
+}
+
+/// Estimates its equity against random hands with Monte Carlo, raising when well ahead of its
+/// share of the pot and calling when the pot odds are good enough.
+pub struct EquityBot {
+    trials: u32,
+    rng: StdRng,
+}
+
+impl EquityBot {
+    pub fn new() -> Self {
+        EquityBot {
+            trials: EQUITY_TRIALS,
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl Default for EquityBot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Strategy for EquityBot {
+    fn decide(&mut self, decision: &Decision) -> Action {
+        let legal = &decision.legal;
+        let equity = monte_carlo_equity(
+            &decision.hole_cards,
+            &decision.board,
+            decision.opponents,
+            decision.rules,
+            self.trials,
+            &mut self.rng,
+        );
+        // a raise needs a good margin over an even share of the pot
+        let share = 1.0 / (decision.opponents + 1) as f64;
+        if equity >= share + (1.0 - share) / 3.0 {
+            return raise_to(legal, decision.bet + decision.pot);
+        }
+        if legal.call == 0 {
+            return Action::Check;
+        }
+        let pot_odds = legal.call as f64 / (decision.pot + legal.call) as f64;
+        if equity >= pot_odds {
+            Action::Call
+        } else {
+            Action::Fold
+        }
+    }
+
+    fn choose_runs(&mut self, max_runs: u32) -> u32 {
+        max_runs
+    }
+}
+
+/// How good a hand is after the flop, as the tight-aggressive bot sees it.
+#[derive(Clone, Copy, PartialEq)]
+enum Strength {
+    /// Two pair or better, made with the hole cards.
+    Strong,
+    /// Top pair or an overpair.
+    Medium,
+    Weak,
+}
+
+fn made_hand_strength(decision: &Decision) -> Strength {
+    let hole = &decision.hole_cards;
+    let board = &decision.board;
+    let cards = CardCollection::concat(hole.clone(), board.clone());
+    let hand = get_best_hand_with_rules(cards.clone(), decision.rules);
+
+    // on the river a hand no better than the board alone isn't worth anything
+    if board.0.len() == 5 {
+        let rankings =
+            assign_hand_rankings_with_rules(vec![cards.clone(), board.clone()], decision.rules);
+        if rankings[0] == rankings[1] {
+            return Strength::Weak;
+        }
+    }
+    if made_with_hole_cards(&hand, hole, &cards) {
+        return Strength::Strong;
+    }
+    let top = board
+        .0
+        .iter()
+        .map(|card| card.value.value())
+        .max()
+        .unwrap_or(0);
+    let values: Vec<u8> = hole.0.iter().map(|card| card.value.value()).collect();
+    let overpair = values.len() == 2 && values[0] == values[1] && values[0] > top;
+    if overpair || values.contains(&top) {
+        Strength::Medium
+    } else {
+        Strength::Weak
+    }
+}
+
+/// Returns whether the hand is two pair or better with the hole cards playing a part in it, so
+/// a pocket pair on a paired board isn't two pair and trips on the board aren't a set.
+fn made_with_hole_cards(hand: &Hand, hole: &CardCollection, cards: &CardCollection) -> bool {
+    let count = |value: u8| {
+        cards
+            .0
+            .iter()
+            .filter(|card| card.value.value() == value)
+            .count()
+    };
+    let mut values: Vec<u8> = hole.0.iter().map(|card| card.value.value()).collect();
+    values.sort();
+    values.dedup();
+    let paired = values.iter().filter(|value| count(**value) >= 2).count();
+    let trips = values.iter().any(|value| count(*value) >= 3);
+
+    match hand {
+        Hand::HighCard(_) | Hand::Pair(_) => false,
+        Hand::TwoPair(_) => paired >= 2,
+        Hand::Set(_) | Hand::FullHouse(_) | Hand::FourOfAKind(_) => trips,
+        _ => true,
+    }
+}
+
+/// Returns the tier of the preflop chart the hole cards are in, if any.
+fn preflop_tier(hole: &CardCollection) -> Option<usize> {
+    let name = starting_hand_name(hole)?;
+    PREFLOP_CHART
+        .iter()
+        .position(|tier| tier.split_whitespace().any(|hand| hand == name))
+}
+
+/// Returns the starting hand written the way charts write it, higher card first, as in "AKs",
+/// "T9o" or "77".
+pub fn starting_hand_name(hole: &CardCollection) -> Option<String> {
+    let [first, second] = hole.0.as_slice() else {
+        return None;
+    };
+    let (high, low) = if first.value.value() >= second.value.value() {
+        (first, second)
+    } else {
+        (second, first)
+    };
+    let mut name = format!("{}{}", high.value.letter(), low.value.letter());
+    if high.value != low.value {
+        name.push(if high.suit == low.suit { 's' } else { 'o' });
+    }
+
+    Some(name)
+}
+
+fn check_or_call(legal: &LegalActions) -> Action {
+    if legal.call == 0 {
+        Action::Check
+    } else {
+        Action::Call
+    }
+}
+
+/// Raises as close to the total bet as the player is allowed to, or checks or calls if they
+/// can't raise.
+fn raise_to(legal: &LegalActions, total: u32) -> Action {
+    match legal.raise {
+        Some((min, max)) => Action::Raise(total.clamp(min, max)),
+        None => check_or_call(legal),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::betting::BettingStructure;
+    use crate::hand::HandRules;
+    use crate::holdem::HoldemGame;
+    use crate::lobby::TableSettings;
+    use crate::timer::TimerSettings;
+
+    fn cards(codes: &str) -> CardCollection {
+        CardCollection(codes.split_whitespace().filter_map(Card::parse).collect())
+    }
+
+    /// A heads-up decision with a big blind of 2 and 100 chips behind.
+    fn decision(hole: &str, board: &str, pot: u32, bet: u32, call: u32) -> Decision {
+        let board = cards(board);
+        let street = match board.0.len() {
+            0 => Street::Preflop,
+            3 => Street::Flop,
+            4 => Street::Turn,
+            _ => Street::River,
+        };
+        let min = (bet * 2).max(bet + 2);
+        Decision {
+            seat: 0,
+            hole_cards: cards(hole),
+            board,
+            street: Some(street),
+            up_cards: vec![],
+            pot,
+            bet,
+            chips: 100,
+            big_blind: 2,
+            opponents: 1,
+            rules: HandRules::Standard,
+            legal: LegalActions {
+                call,
+                raise: Some((min, 100)),
+            },
+        }
+    }
+
+    #[test]
+    fn every_bot_only_takes_legal_actions() {
+        let mut game = HoldemGame::new(TableSettings {
+            game: GameType::Holdem,
+            structure: BettingStructure::NoLimit { big_blind: 2 },
+            small_blind: 1,
+            big_blind: 2,
+            seats: 4,
+            broadcast_delay: None,
+            timer: TimerSettings::default(),
+            max_orbits_sat_out: None,
+            max_runs: 1,
+            min_buy_in: 40,
+            max_buy_in: 200,
+        });
+        game.seed_deck(43);
+        let mut bots: Vec<Box<dyn Strategy>> = vec![
+            Box::new(RandomBot {
+                rng: StdRng::seed_from_u64(1),
+            }),
+            Box::new(CallingStation),
+            Box::new(TightAggressive),
+            Box::new(EquityBot {
+                trials: 50,
+                rng: StdRng::seed_from_u64(2),
+            }),
+        ];
+        for seat in 0..4 {
+            game.sit_down(seat, 100).unwrap();
+        }
+
+        for _ in 0..30 {
+            if game.start_hand().is_err() {
+                break;
+            }
+            while let Some(decision) = game.decision() {
+                let action = bots[decision.seat].decide(&decision);
+                assert!(decision.legal.check(action).is_ok());
+                game.act(action).unwrap();
+            }
+        }
+        assert!(game.hands_played() > 1);
+    }
+
+    #[test]
+    fn tight_aggressive_plays_its_preflop_chart() {
+        let mut bot = TightAggressive;
+        // opening with any charted hand and folding the rest
+        assert!(bot.decide(&decision("Ah As", "", 3, 2, 1)) == Action::Raise(6));
+        assert!(bot.decide(&decision("8h 7h", "", 3, 2, 1)) == Action::Raise(6));
+        assert!(bot.decide(&decision("7d 2c", "", 3, 2, 1)) == Action::Fold);
+        // the big blind checks its option instead of folding
+        assert!(bot.decide(&decision("7d 2c", "", 4, 2, 0)) == Action::Check);
+
+        // facing a raise to 8 the first tier reraises, the second calls and the rest fold
+        assert!(bot.decide(&decision("Kh Kd", "", 11, 8, 6)) == Action::Raise(24));
+        assert!(bot.decide(&decision("Th Td", "", 11, 8, 6)) == Action::Call);
+        assert!(bot.decide(&decision("8h 7h", "", 11, 8, 6)) == Action::Fold);
+        // but the second tier won't call more than four big blinds
+        assert!(bot.decide(&decision("Th Td", "", 15, 12, 10)) == Action::Fold);
+    }
+
+    #[test]
+    fn tight_aggressive_only_counts_hands_its_hole_cards_make() {
+        let mut bot = TightAggressive;
+        // two pair made with both hole cards bets two thirds of the pot
+        assert!(bot.decide(&decision("Ah Kd", "As Kc 7d", 12, 0, 0)) == Action::Raise(8));
+        // a pocket pair under a paired board is only a pair, so it checks
+        assert!(bot.decide(&decision("2h 2d", "7s 7c Kd", 12, 0, 0)) == Action::Check);
+        // as is a hand that only plays the trips on the board
+        assert!(bot.decide(&decision("4h 3d", "7s 7c 7d", 12, 0, 0)) == Action::Check);
+        // an overpair on a paired board is a medium hand and bets half the pot
+        assert!(bot.decide(&decision("Ah Ad", "7s 7c Kd", 12, 0, 0)) == Action::Raise(6));
+        // a set made with a pocket pair is strong
+        assert!(bot.decide(&decision("2h 2d", "2s 7c Kd", 12, 0, 0)) == Action::Raise(8));
+    }
+}
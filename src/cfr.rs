@@ -0,0 +1,653 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// The number of times the exploitability is reported while solving.
+const CHECKPOINTS: u64 = 10;
+
+/// What happens at a point of a two player zero-sum game.
+pub enum Node {
+    /// The game is over, with what the first player wins. The second player loses as much.
+    Terminal(f64),
+    /// Chance picks what happens next, such as the cards dealt, with the probability of each
+    /// outcome.
+    Chance(Vec<f64>),
+    /// A player picks one of their actions knowing only what the information set describes,
+    /// so every point of the game with the same information set has to be played the same way.
+    Decision {
+        player: usize,
+        info_set: String,
+        actions: usize,
+    },
+}
+
+/// A two player zero-sum game the solver can walk through.
+pub trait Game {
+    type State: Clone;
+
+    /// Returns the state at the start of the game.
+    fn root(&self) -> Self::State;
+
+    /// Returns what happens at the state.
+    fn node(&self, state: &Self::State) -> Node;
+
+    /// Returns the state after the chance outcome or action numbered `choice` is picked.
+    fn play(&self, state: &Self::State, choice: usize) -> Self::State;
+
+    /// Names the actions of the decision at the state, in order.
+    fn action_names(&self, state: &Self::State) -> Vec<String>;
+}
+
+/// The ways of minimizing counterfactual regret.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Algorithm {
+    /// Walks the whole game every iteration, updating both players at once.
+    Vanilla,
+    /// Walks the whole game for each player in turn, keeping regrets from going below zero and
+    /// counting later iterations for more in the average strategy. It converges much faster
+    /// than vanilla CFR.
+    CfrPlus,
+    /// Monte Carlo CFR with external sampling, which only samples one outcome of each chance
+    /// event and one action of the opponent's, making each iteration much cheaper.
+    ExternalSampling,
+}
+
+impl Algorithm {
+    /// Parses the name used for the algorithm on the command line.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "vanilla" => Some(Algorithm::Vanilla),
+            "cfr+" => Some(Algorithm::CfrPlus),
+            "mccfr" => Some(Algorithm::ExternalSampling),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Algorithm::Vanilla => write!(f, "vanilla"),
+            Algorithm::CfrPlus => write!(f, "cfr+"),
+            Algorithm::ExternalSampling => write!(f, "mccfr"),
+        }
+    }
+}
+
+/// The probability of each action at each information set. Information sets that are missing
+/// are played with every action equally likely.
+pub type Profile = HashMap<String, Vec<f64>>;
+
+/// What the solver has learnt about an information set.
+struct InfoSet {
+    regrets: Vec<f64>,
+    // the regrets of the current iteration, added once it is over so every point of the game
+    // in the information set is played with the same strategy
+    pending: Vec<f64>,
+    strategy_sum: Vec<f64>,
+}
+
+impl InfoSet {
+    fn new(actions: usize) -> Self {
+        InfoSet {
+            regrets: vec![0.0; actions],
+            pending: vec![0.0; actions],
+            strategy_sum: vec![0.0; actions],
+        }
+    }
+
+    /// Returns the strategy of the current iteration, playing each action in proportion to its
+    /// positive regret.
+    fn strategy(&self) -> Vec<f64> {
+        normalize(self.regrets.iter().map(|regret| regret.max(0.0)).collect())
+    }
+
+    fn average_strategy(&self) -> Vec<f64> {
+        normalize(self.strategy_sum.clone())
+    }
+}
+
+/// Solves a game by counterfactual regret minimization. The average of the strategies it plays
+/// over the iterations converges to a Nash equilibrium.
+pub struct Solver<G: Game> {
+    game: G,
+    algorithm: Algorithm,
+    info_sets: HashMap<String, InfoSet>,
+    iterations: u64,
+    // the generator Monte Carlo CFR samples from
+    rng: StdRng,
+}
+
+impl<G: Game> Solver<G> {
+    pub fn new(game: G, algorithm: Algorithm, seed: u64) -> Self {
+        Solver {
+            game,
+            algorithm,
+            info_sets: HashMap::new(),
+            iterations: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn game(&self) -> &G {
+        &self.game
+    }
+
+    /// Returns the number of iterations run so far.
+    pub fn iterations(&self) -> u64 {
+        self.iterations
+    }
+
+    /// Runs more iterations.
+    pub fn solve(&mut self, iterations: u64) {
+        let root = self.game.root();
+        for _ in 0..iterations {
+            self.iterations += 1;
+            match self.algorithm {
+                Algorithm::Vanilla => {
+                    self.cfr(&root, None, [1.0; 3]);
+                    self.apply_regrets();
+                }
+                Algorithm::CfrPlus => {
+                    for player in 0..2 {
+                        self.cfr(&root, Some(player), [1.0; 3]);
+                        self.apply_regrets();
+                    }
+                }
+                Algorithm::ExternalSampling => {
+                    for player in 0..2 {
+                        self.external_sampling(&root, player);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the average strategy over the iterations so far.
+    pub fn average_strategy(&self) -> Profile {
+        self.info_sets
+            .iter()
+            .map(|(key, info_set)| (key.clone(), info_set.average_strategy()))
+            .collect()
+    }
+
+    /// Walks the game below the state, returning its value to the first player and working out
+    /// the regrets of the traversing player, or both players if there isn't one. The reach is
+    /// the probability of each player and then chance playing to the state.
+    fn cfr(&mut self, state: &G::State, traverser: Option<usize>, reach: [f64; 3]) -> f64 {
+        match self.game.node(state) {
+            Node::Terminal(value) => value,
+            Node::Chance(probabilities) => {
+                let mut value = 0.0;
+                for (outcome, probability) in probabilities.into_iter().enumerate() {
+                    if probability > 0.0 {
+                        let next = self.game.play(state, outcome);
+                        let mut reach = reach;
+                        reach[2] *= probability;
+                        value += probability * self.cfr(&next, traverser, reach);
+                    }
+                }
+                value
+            }
+            Node::Decision {
+                player,
+                info_set,
+                actions,
+            } => {
+                let strategy = self
+                    .info_sets
+                    .entry(info_set.clone())
+                    .or_insert_with(|| InfoSet::new(actions))
+                    .strategy();
+                let mut values = vec![0.0; actions];
+                let mut value = 0.0;
+                for action in 0..actions {
+                    let next = self.game.play(state, action);
+                    let mut reach = reach;
+                    reach[player] *= strategy[action];
+                    values[action] = self.cfr(&next, traverser, reach);
+                    value += strategy[action] * values[action];
+                }
+                if traverser.is_some_and(|traverser| traverser != player) {
+                    return value;
+                }
+
+                // the values are the first player's, so the second player's regrets are the
+                // other way round
+                let sign = if player == 0 { 1.0 } else { -1.0 };
+                let counterfactual_reach = reach[1 - player] * reach[2];
+                let weight = match self.algorithm {
+                    Algorithm::CfrPlus => self.iterations as f64,
+                    _ => 1.0,
+                };
+                if let Some(info_set) = self.info_sets.get_mut(&info_set) {
+                    for action in 0..actions {
+                        info_set.pending[action] +=
+                            sign * counterfactual_reach * (values[action] - value);
+                        info_set.strategy_sum[action] += weight * reach[player] * strategy[action];
+                    }
+                }
+                value
+            }
+        }
+    }
+
+    /// Adds the regrets of the iteration just run.
+    fn apply_regrets(&mut self) {
+        let floor = self.algorithm == Algorithm::CfrPlus;
+        for info_set in self.info_sets.values_mut() {
+            for (regret, pending) in info_set.regrets.iter_mut().zip(&mut info_set.pending) {
+                *regret += *pending;
+                if floor {
+                    *regret = regret.max(0.0);
+                }
+                *pending = 0.0;
+            }
+        }
+    }
+
+    /// Walks the game below the state trying every action of the traversing player but only
+    /// a sampled outcome of chance and action of the opponent's, and returns its sampled value
+    /// to the traversing player.
+    fn external_sampling(&mut self, state: &G::State, traverser: usize) -> f64 {
+        match self.game.node(state) {
+            Node::Terminal(value) if traverser == 0 => value,
+            Node::Terminal(value) => -value,
+            Node::Chance(probabilities) => {
+                let outcome = sample(&probabilities, &mut self.rng);
+                let next = self.game.play(state, outcome);
+                self.external_sampling(&next, traverser)
+            }
+            Node::Decision {
+                player,
+                info_set,
+                actions,
+            } => {
+                let entry = self
+                    .info_sets
+                    .entry(info_set.clone())
+                    .or_insert_with(|| InfoSet::new(actions));
+                let strategy = entry.strategy();
+                if player != traverser {
+                    for (sum, probability) in entry.strategy_sum.iter_mut().zip(&strategy) {
+                        *sum += probability;
+                    }
+                    let action = sample(&strategy, &mut self.rng);
+                    let next = self.game.play(state, action);
+                    return self.external_sampling(&next, traverser);
+                }
+
+                let mut values = vec![0.0; actions];
+                let mut value = 0.0;
+                for action in 0..actions {
+                    let next = self.game.play(state, action);
+                    values[action] = self.external_sampling(&next, traverser);
+                    value += strategy[action] * values[action];
+                }
+                if let Some(info_set) = self.info_sets.get_mut(&info_set) {
+                    for (regret, action_value) in info_set.regrets.iter_mut().zip(&values) {
+                        *regret += action_value - value;
+                    }
+                }
+                value
+            }
+        }
+    }
+}
+
+/// Returns what the first player wins on average when both players play the profile.
+pub fn expected_value<G: Game>(game: &G, profile: &Profile) -> f64 {
+    fn value<G: Game>(game: &G, profile: &Profile, state: &G::State) -> f64 {
+        match game.node(state) {
+            Node::Terminal(value) => value,
+            Node::Chance(probabilities) => weighted_value(game, profile, state, &probabilities),
+            Node::Decision {
+                info_set, actions, ..
+            } => {
+                let strategy = profile_strategy(profile, &info_set, actions);
+                weighted_value(game, profile, state, &strategy)
+            }
+        }
+    }
+    fn weighted_value<G: Game>(
+        game: &G,
+        profile: &Profile,
+        state: &G::State,
+        probabilities: &[f64],
+    ) -> f64 {
+        probabilities
+            .iter()
+            .enumerate()
+            .filter(|(_, probability)| **probability > 0.0)
+            .map(|(choice, probability)| {
+                probability * value(game, profile, &game.play(state, choice))
+            })
+            .sum()
+    }
+
+    value(game, profile, &game.root())
+}
+
+/// Returns how much a best response to the profile wins on average, taking turns in each seat.
+/// It is zero for a Nash equilibrium.
+pub fn exploitability<G: Game>(game: &G, profile: &Profile) -> f64 {
+    let total: f64 = (0..2)
+        .map(|player| best_response_value(game, profile, player))
+        .sum();
+
+    total / 2.0
+}
+
+/// Returns what the player wins on average playing a best response to the other player
+/// playing the profile.
+pub fn best_response_value<G: Game>(game: &G, profile: &Profile, player: usize) -> f64 {
+    let mut best_response = BestResponse {
+        game,
+        profile,
+        player,
+        histories: HashMap::new(),
+        actions: HashMap::new(),
+    };
+    let root = game.root();
+    best_response.collect_histories(&root, 1.0);
+
+    best_response.value(&root)
+}
+
+/// Works out a best response, one information set at a time.
+struct BestResponse<'a, G: Game> {
+    game: &'a G,
+    profile: &'a Profile,
+    player: usize,
+    // the states in each of the player's information sets, with the probability of the
+    // opponent and chance playing to them
+    histories: HashMap<String, Vec<(G::State, f64)>>,
+    // the best action at each information set worked out so far
+    actions: HashMap<String, usize>,
+}
+
+impl<G: Game> BestResponse<'_, G> {
+    fn collect_histories(&mut self, state: &G::State, reach: f64) {
+        match self.game.node(state) {
+            Node::Terminal(_) => {}
+            Node::Chance(probabilities) => {
+                for (outcome, probability) in probabilities.into_iter().enumerate() {
+                    if probability > 0.0 {
+                        let next = self.game.play(state, outcome);
+                        self.collect_histories(&next, reach * probability);
+                    }
+                }
+            }
+            Node::Decision {
+                player,
+                info_set,
+                actions,
+            } if player == self.player => {
+                self.histories
+                    .entry(info_set)
+                    .or_default()
+                    .push((state.clone(), reach));
+                for action in 0..actions {
+                    let next = self.game.play(state, action);
+                    self.collect_histories(&next, reach);
+                }
+            }
+            Node::Decision {
+                info_set, actions, ..
+            } => {
+                let strategy = profile_strategy(self.profile, &info_set, actions);
+                for (action, probability) in strategy.into_iter().enumerate() {
+                    if probability > 0.0 {
+                        let next = self.game.play(state, action);
+                        self.collect_histories(&next, reach * probability);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the value of the state to the player.
+    fn value(&mut self, state: &G::State) -> f64 {
+        match self.game.node(state) {
+            Node::Terminal(value) if self.player == 0 => value,
+            Node::Terminal(value) => -value,
+            Node::Chance(probabilities) => self.weighted_value(state, &probabilities),
+            Node::Decision {
+                player,
+                info_set,
+                actions,
+            } if player == self.player => {
+                let action = self.best_action(&info_set, actions);
+                self.value(&self.game.play(state, action))
+            }
+            Node::Decision {
+                info_set, actions, ..
+            } => {
+                let strategy = profile_strategy(self.profile, &info_set, actions);
+                self.weighted_value(state, &strategy)
+            }
+        }
+    }
+
+    fn weighted_value(&mut self, state: &G::State, probabilities: &[f64]) -> f64 {
+        let mut value = 0.0;
+        for (choice, probability) in probabilities.iter().enumerate() {
+            if *probability > 0.0 {
+                value += probability * self.value(&self.game.play(state, choice));
+            }
+        }
+        value
+    }
+
+    /// Returns the action that does best over every state in the information set.
+    fn best_action(&mut self, info_set: &str, actions: usize) -> usize {
+        if let Some(action) = self.actions.get(info_set) {
+            return *action;
+        }
+        let histories = self.histories.remove(info_set).unwrap_or_default();
+        let mut totals = vec![0.0; actions];
+        for (state, reach) in &histories {
+            for (action, total) in totals.iter_mut().enumerate() {
+                *total += reach * self.value(&self.game.play(state, action));
+            }
+        }
+        let mut best = 0;
+        for action in 1..actions {
+            if totals[action] > totals[best] {
+                best = action;
+            }
+        }
+        self.actions.insert(info_set.to_string(), best);
+
+        best
+    }
+}
+
+/// Returns each information set's actions with their probabilities under the profile, sorted
+/// by information set.
+pub fn describe_profile<G: Game>(game: &G, profile: &Profile) -> Vec<(String, Vec<(String, f64)>)> {
+    fn collect<G: Game>(game: &G, state: &G::State, names: &mut BTreeMap<String, Vec<String>>) {
+        let choices = match game.node(state) {
+            Node::Terminal(_) => 0,
+            Node::Chance(probabilities) => probabilities.len(),
+            Node::Decision {
+                info_set, actions, ..
+            } => {
+                names
+                    .entry(info_set)
+                    .or_insert_with(|| game.action_names(state));
+                actions
+            }
+        };
+        for choice in 0..choices {
+            collect(game, &game.play(state, choice), names);
+        }
+    }
+    let mut names = BTreeMap::new();
+    collect(game, &game.root(), &mut names);
+
+    names
+        .into_iter()
+        .map(|(info_set, names)| {
+            let strategy = profile_strategy(profile, &info_set, names.len());
+            (info_set, names.into_iter().zip(strategy).collect())
+        })
+        .collect()
+}
+
+/// Solves the game, reporting the exploitability as it goes, and prints the strategy it finds.
+pub fn run<G: Game>(game: G, algorithm: Algorithm, iterations: u64, seed: u64) {
+    let mut solver = Solver::new(game, algorithm, seed);
+    let step = (iterations / CHECKPOINTS).max(1);
+    while solver.iterations() < iterations {
+        solver.solve(step.min(iterations - solver.iterations()));
+        let profile = solver.average_strategy();
+        println!(
+            "Iteration {:>8}  exploitability {:.6}",
+            solver.iterations(),
+            exploitability(solver.game(), &profile)
+        );
+    }
+
+    let profile = solver.average_strategy();
+    println!(
+        "The first player wins {:.6} a hand on average",
+        expected_value(solver.game(), &profile)
+    );
+    for (info_set, actions) in describe_profile(solver.game(), &profile) {
+        let actions: Vec<String> = actions
+            .iter()
+            .map(|(name, probability)| format!("{} {:.3}", name, probability))
+            .collect();
+        println!("{:<24} {}", info_set, actions.join("  "));
+    }
+}
+
+/// Returns the profile's strategy at the information set.
+fn profile_strategy(profile: &Profile, info_set: &str, actions: usize) -> Vec<f64> {
+    match profile.get(info_set) {
+        Some(strategy) if strategy.len() == actions => strategy.clone(),
+        _ => vec![1.0 / actions as f64; actions],
+    }
+}
+
+/// Scales the weights to add up to one, or makes them equal if they are all zero.
+fn normalize(weights: Vec<f64>) -> Vec<f64> {
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return vec![1.0 / weights.len() as f64; weights.len()];
+    }
+
+    weights.into_iter().map(|weight| weight / total).collect()
+}
+
+/// Picks an outcome with the given probabilities.
+fn sample<R: Rng>(probabilities: &[f64], rng: &mut R) -> usize {
+    let mut left = rng.gen::<f64>();
+    for (outcome, probability) in probabilities.iter().enumerate() {
+        if left < *probability {
+            return outcome;
+        }
+        left -= probability;
+    }
+
+    // rounding can leave a little over, which goes to the last possible outcome
+    probabilities
+        .iter()
+        .rposition(|probability| *probability > 0.0)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rock, paper, scissors, with the second player picking without seeing the first's pick.
+    struct RockPaperScissors;
+
+    impl Game for RockPaperScissors {
+        type State = Vec<usize>;
+
+        fn root(&self) -> Vec<usize> {
+            vec![]
+        }
+
+        fn node(&self, state: &Vec<usize>) -> Node {
+            match state.as_slice() {
+                [first, second] => Node::Terminal(match (3 + first - second) % 3 {
+                    0 => 0.0,
+                    1 => 1.0,
+                    _ => -1.0,
+                }),
+                _ => Node::Decision {
+                    player: state.len(),
+                    info_set: format!("player {}", state.len()),
+                    actions: 3,
+                },
+            }
+        }
+
+        fn play(&self, state: &Vec<usize>, choice: usize) -> Vec<usize> {
+            let mut next = state.clone();
+            next.push(choice);
+            next
+        }
+
+        fn action_names(&self, _: &Vec<usize>) -> Vec<String> {
+            vec![
+                "rock".to_string(),
+                "paper".to_string(),
+                "scissors".to_string(),
+            ]
+        }
+    }
+
+    #[test]
+    fn every_algorithm_finds_the_equilibrium() {
+        for (algorithm, iterations) in [
+            (Algorithm::Vanilla, 2_000),
+            (Algorithm::CfrPlus, 2_000),
+            (Algorithm::ExternalSampling, 20_000),
+        ] {
+            let mut solver = Solver::new(RockPaperScissors, algorithm, 7);
+            solver.solve(iterations);
+            assert_eq!(solver.iterations(), iterations);
+            let profile = solver.average_strategy();
+            for strategy in profile.values() {
+                for probability in strategy {
+                    assert!((probability - 1.0 / 3.0).abs() < 0.02, "{}", algorithm);
+                }
+            }
+            assert!(expected_value(&RockPaperScissors, &profile).abs() < 0.02);
+            assert!(exploitability(&RockPaperScissors, &profile) < 0.05);
+        }
+    }
+
+    #[test]
+    fn best_responses_punish_a_predictable_profile() {
+        let mut profile = Profile::new();
+        profile.insert("player 0".to_string(), vec![1.0, 0.0, 0.0]);
+        profile.insert("player 1".to_string(), vec![1.0, 0.0, 0.0]);
+        assert_eq!(expected_value(&RockPaperScissors, &profile), 0.0);
+        assert_eq!(best_response_value(&RockPaperScissors, &profile, 0), 1.0);
+        assert_eq!(best_response_value(&RockPaperScissors, &profile, 1), 1.0);
+        assert_eq!(exploitability(&RockPaperScissors, &profile), 1.0);
+
+        // information sets missing from the profile are played uniformly
+        let uniform = Profile::new();
+        assert_eq!(exploitability(&RockPaperScissors, &uniform), 0.0);
+    }
+
+    #[test]
+    fn algorithms_parse_their_own_names() {
+        for algorithm in [
+            Algorithm::Vanilla,
+            Algorithm::CfrPlus,
+            Algorithm::ExternalSampling,
+        ] {
+            assert!(Algorithm::parse(&algorithm.to_string()) == Some(algorithm));
+        }
+        assert!(Algorithm::parse("dcfr").is_none());
+    }
+}
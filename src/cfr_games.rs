@@ -0,0 +1,521 @@
+use crate::bots::starting_hand_name;
+use crate::cfr::{Game, Node};
+use crate::deck::*;
+use crate::equity::remaining_cards;
+use crate::hand::{score_hand, HandRules};
+
+/// A move in the betting of the solvable games.
+#[derive(Clone, Copy, PartialEq)]
+enum Move {
+    Fold,
+    Check,
+    Call,
+    /// Puts in the chips, calling any bet and raising by the rest.
+    Bet(u32),
+}
+
+/// The betting of a hand between two players, who take turns with the first player acting
+/// first in every round.
+#[derive(Clone)]
+struct Betting {
+    // the chips each player has put in
+    contributed: [u32; 2],
+    // the moves so far, with a slash between the rounds
+    history: String,
+    to_act: usize,
+    // the bets and raises made in the round
+    raises: usize,
+    // the moves made in the round
+    moves: usize,
+    folded: Option<usize>,
+    round_over: bool,
+    // whether the bets are written with the total they bet to, for games with more than one
+    // bet size
+    sized: bool,
+}
+
+impl Betting {
+    fn new(ante: u32, sized: bool) -> Self {
+        Betting {
+            contributed: [ante; 2],
+            history: String::new(),
+            to_act: 0,
+            raises: 0,
+            moves: 0,
+            folded: None,
+            round_over: false,
+            sized,
+        }
+    }
+
+    fn to_call(&self) -> u32 {
+        self.contributed[1 - self.to_act] - self.contributed[self.to_act]
+    }
+
+    /// Returns the moves the player to act can make, given the chips they can bet.
+    fn moves(&self, bets: &[u32]) -> Vec<Move> {
+        let mut moves = if self.to_call() == 0 {
+            vec![Move::Check]
+        } else {
+            vec![Move::Fold, Move::Call]
+        };
+        moves.extend(bets.iter().map(|bet| Move::Bet(*bet)));
+
+        moves
+    }
+
+    fn name(&self, action: Move) -> String {
+        match action {
+            Move::Fold => "fold".to_string(),
+            Move::Check => "check".to_string(),
+            Move::Call => "call".to_string(),
+            Move::Bet(_) if !self.sized && self.to_call() == 0 => "bet".to_string(),
+            Move::Bet(_) if !self.sized => "raise".to_string(),
+            Move::Bet(chips) if self.to_call() == 0 => format!("bet {}", chips),
+            Move::Bet(chips) => format!("raise to {}", self.contributed[self.to_act] + chips),
+        }
+    }
+
+    fn play(&mut self, action: Move) {
+        let player = self.to_act;
+        match action {
+            Move::Fold => {
+                self.folded = Some(player);
+                self.round_over = true;
+                self.history.push('f');
+            }
+            Move::Check => {
+                // the round is over once both players have checked
+                self.round_over = self.moves > 0;
+                self.history.push('k');
+            }
+            Move::Call => {
+                self.contributed[player] += self.to_call();
+                self.round_over = true;
+                self.history.push('c');
+            }
+            Move::Bet(chips) => {
+                self.contributed[player] += chips;
+                self.raises += 1;
+                self.history.push('b');
+                if self.sized {
+                    self.history.push_str(&self.contributed[player].to_string());
+                }
+            }
+        }
+        self.moves += 1;
+        self.to_act = 1 - player;
+    }
+
+    fn next_round(&mut self) {
+        self.history.push('/');
+        self.to_act = 0;
+        self.raises = 0;
+        self.moves = 0;
+        self.round_over = false;
+    }
+
+    /// Returns what the first player wins given the winner at showdown, if it wasn't a tie,
+    /// when each player has already put in `stake` chips before the betting.
+    fn payoff(&self, winner: Option<usize>, stake: f64) -> f64 {
+        match self.folded.map(|folded| 1 - folded).or(winner) {
+            Some(0) => stake + self.contributed[1] as f64,
+            Some(_) => -(stake + self.contributed[0] as f64),
+            None => 0.0,
+        }
+    }
+}
+
+/// A heads-up limit poker game small enough to solve exactly. Each player antes and is dealt
+/// one card, then there's a round of betting for each bet size, with a card dealt to the board
+/// before each round after the first. At showdown a card paired with the board wins, and
+/// otherwise the higher card does.
+pub struct LimitGame {
+    cards: Vec<Card>,
+    ante: u32,
+    // the size of the bets and raises in each round
+    bet_sizes: Vec<u32>,
+    // the most bets and raises allowed in a round
+    max_raises: usize,
+}
+
+/// Where a hand of a limit game is up to.
+#[derive(Clone)]
+pub struct LimitState {
+    hands: Vec<Card>,
+    board: Vec<Card>,
+    round: usize,
+    betting: Betting,
+}
+
+impl LimitGame {
+    /// Creates Kuhn poker, played with a jack, queen and king, a one chip ante and a single
+    /// round of betting where the only bet is one chip.
+    pub fn kuhn() -> Self {
+        LimitGame {
+            cards: cards_of(&[CardSuit::Spade]),
+            ante: 1,
+            bet_sizes: vec![1],
+            max_raises: 1,
+        }
+    }
+
+    /// Creates Leduc hold'em, played with two each of the jacks, queens and kings, a one chip
+    /// ante and two rounds of betting, with bets of two chips and then four and at most two of
+    /// them a round.
+    pub fn leduc() -> Self {
+        LimitGame {
+            cards: cards_of(&[CardSuit::Spade, CardSuit::Heart]),
+            ante: 1,
+            bet_sizes: vec![2, 4],
+            max_raises: 2,
+        }
+    }
+
+    fn moves(&self, state: &LimitState) -> Vec<Move> {
+        let betting = &state.betting;
+        let mut bets = vec![];
+        if betting.raises < self.max_raises {
+            bets.push(betting.to_call() + self.bet_sizes[state.round]);
+        }
+
+        betting.moves(&bets)
+    }
+
+    /// Returns how good the card is at showdown.
+    fn strength(&self, card: &Card, board: &[Card]) -> u8 {
+        let paired = board.iter().any(|other| other.value == card.value);
+        card.value.value() + if paired { 100 } else { 0 }
+    }
+}
+
+impl Game for LimitGame {
+    type State = LimitState;
+
+    fn root(&self) -> LimitState {
+        LimitState {
+            hands: vec![],
+            board: vec![],
+            round: 0,
+            betting: Betting::new(self.ante, false),
+        }
+    }
+
+    fn node(&self, state: &LimitState) -> Node {
+        let cards = self.cards.len();
+        if state.hands.is_empty() {
+            let deals = cards * (cards - 1);
+            return Node::Chance(vec![1.0 / deals as f64; deals]);
+        }
+        let betting = &state.betting;
+        if betting.folded.is_some() {
+            return Node::Terminal(betting.payoff(None, 0.0));
+        }
+        if betting.round_over {
+            if state.round + 1 < self.bet_sizes.len() {
+                let left = cards - 2 - state.board.len();
+                return Node::Chance(vec![1.0 / left as f64; left]);
+            }
+            let first = self.strength(&state.hands[0], &state.board);
+            let second = self.strength(&state.hands[1], &state.board);
+            let winner = match first.cmp(&second) {
+                std::cmp::Ordering::Greater => Some(0),
+                std::cmp::Ordering::Less => Some(1),
+                std::cmp::Ordering::Equal => None,
+            };
+            return Node::Terminal(betting.payoff(winner, 0.0));
+        }
+
+        let player = betting.to_act;
+        let mut info_set = state.hands[player].value.letter().to_string();
+        for card in &state.board {
+            info_set.push(' ');
+            info_set.push(card.value.letter());
+        }
+        Node::Decision {
+            player,
+            info_set: format!("{}:{}", info_set, betting.history),
+            actions: self.moves(state).len(),
+        }
+    }
+
+    fn play(&self, state: &LimitState, choice: usize) -> LimitState {
+        let mut state = state.clone();
+        if state.hands.is_empty() {
+            // the deals are numbered by the first card and then the second of the ones left
+            let others = self.cards.len() - 1;
+            let first = choice / others;
+            let mut second = choice % others;
+            if second >= first {
+                second += 1;
+            }
+            state.hands = vec![self.cards[first].clone(), self.cards[second].clone()];
+        } else if state.betting.round_over {
+            let card = self
+                .cards
+                .iter()
+                .filter(|card| !state.hands.contains(card) && !state.board.contains(card))
+                .nth(choice);
+            state.board.extend(card.cloned());
+            state.round += 1;
+            state.betting.next_round();
+        } else if let Some(action) = self.moves(&state).get(choice) {
+            state.betting.play(*action);
+        }
+
+        state
+    }
+
+    fn action_names(&self, state: &LimitState) -> Vec<String> {
+        self.moves(state)
+            .into_iter()
+            .map(|action| state.betting.name(action))
+            .collect()
+    }
+}
+
+/// The river of a heads-up hold'em hand, with the bets abstracted to a few sizes. Each player
+/// is dealt one of the hands in their range at random, and the first player acts first.
+pub struct RiverSubgame {
+    board: CardCollection,
+    ranges: [Vec<CardCollection>; 2],
+    // the chips in the pot before the river
+    pot: u32,
+    // the chips each player has left to bet
+    stack: u32,
+    // the bets and raises allowed, as fractions of the pot after calling, besides going all in
+    bet_sizes: Vec<f64>,
+    max_raises: usize,
+    // each pair of hands that can be dealt together, by their places in the ranges
+    deals: Vec<(usize, usize)>,
+    // the score of each hand in the ranges with the board
+    scores: [Vec<u32>; 2],
+}
+
+/// Where a hand of a river subgame is up to.
+#[derive(Clone)]
+pub struct RiverState {
+    deal: Option<(usize, usize)>,
+    betting: Betting,
+}
+
+impl RiverSubgame {
+    pub fn new(
+        board: CardCollection,
+        ranges: [Vec<CardCollection>; 2],
+        pot: u32,
+        stack: u32,
+        bet_sizes: Vec<f64>,
+        max_raises: usize,
+    ) -> Result<Self, String> {
+        if board.0.len() != 5 {
+            return Err("the river board needs five cards".to_string());
+        }
+        if bet_sizes.iter().any(|size| *size <= 0.0) {
+            return Err("the bet sizes have to be more than nothing".to_string());
+        }
+        let mut deals = vec![];
+        for (first, hand) in ranges[0].iter().enumerate() {
+            for (second, other) in ranges[1].iter().enumerate() {
+                if !hand.0.iter().any(|card| other.0.contains(card)) {
+                    deals.push((first, second));
+                }
+            }
+        }
+        if deals.is_empty() {
+            return Err("the ranges don't have any hands that can be dealt together".to_string());
+        }
+        let score = |hand: &CardCollection| {
+            let cards = CardCollection::concat(hand.clone(), board.clone());
+            score_hand(&cards.0, HandRules::Standard)
+        };
+        let scores = [
+            ranges[0].iter().map(score).collect(),
+            ranges[1].iter().map(score).collect(),
+        ];
+
+        Ok(RiverSubgame {
+            board,
+            ranges,
+            pot,
+            stack,
+            bet_sizes,
+            max_raises,
+            deals,
+            scores,
+        })
+    }
+
+    pub fn board(&self) -> &CardCollection {
+        &self.board
+    }
+
+    fn moves(&self, state: &RiverState) -> Vec<Move> {
+        let betting = &state.betting;
+        let to_call = betting.to_call();
+        let left = self.stack - betting.contributed[betting.to_act];
+        let mut bets = vec![];
+        if betting.raises < self.max_raises && left > to_call {
+            let pot = self.pot + betting.contributed[0] + betting.contributed[1] + to_call;
+            for size in &self.bet_sizes {
+                let chips = to_call + (pot as f64 * size).round() as u32;
+                if chips > to_call && chips < left && !bets.contains(&chips) {
+                    bets.push(chips);
+                }
+            }
+            bets.push(left);
+        }
+
+        betting.moves(&bets)
+    }
+}
+
+impl Game for RiverSubgame {
+    type State = RiverState;
+
+    fn root(&self) -> RiverState {
+        RiverState {
+            deal: None,
+            betting: Betting::new(0, true),
+        }
+    }
+
+    fn node(&self, state: &RiverState) -> Node {
+        let Some((first, second)) = state.deal else {
+            return Node::Chance(vec![1.0 / self.deals.len() as f64; self.deals.len()]);
+        };
+        let betting = &state.betting;
+        // the pot before the river is split evenly between what the players put in
+        let stake = self.pot as f64 / 2.0;
+        if betting.round_over {
+            let (first, second) = (self.scores[0][first], self.scores[1][second]);
+            let winner = match first.cmp(&second) {
+                std::cmp::Ordering::Greater => Some(0),
+                std::cmp::Ordering::Less => Some(1),
+                std::cmp::Ordering::Equal => None,
+            };
+            return Node::Terminal(betting.payoff(winner, stake));
+        }
+
+        let player = betting.to_act;
+        let hand = [first, second][player];
+        let cards: String = self.ranges[player][hand].0.iter().map(Card::code).collect();
+        Node::Decision {
+            player,
+            info_set: format!("{}:{}", cards, betting.history),
+            actions: self.moves(state).len(),
+        }
+    }
+
+    fn play(&self, state: &RiverState, choice: usize) -> RiverState {
+        let mut state = state.clone();
+        if state.deal.is_none() {
+            state.deal = self.deals.get(choice).copied();
+        } else if let Some(action) = self.moves(&state).get(choice) {
+            state.betting.play(*action);
+        }
+
+        state
+    }
+
+    fn action_names(&self, state: &RiverState) -> Vec<String> {
+        self.moves(state)
+            .into_iter()
+            .map(|action| state.betting.name(action))
+            .collect()
+    }
+}
+
+/// Parses a range written as starting hands and exact hands, as in "AA KK AKs QJo T9 AhKd".
+/// A starting hand without an "s" or "o" counts both the suited and offsuit hands. Hands that
+/// use one of the board's cards are left out.
+pub fn parse_range(text: &str, board: &CardCollection) -> Result<Vec<CardCollection>, String> {
+    let deck = remaining_cards(HandRules::Standard, &[]);
+    let mut range: Vec<CardCollection> = vec![];
+    for hand in text.split(|c: char| c == ',' || c.is_whitespace()) {
+        if hand.is_empty() {
+            continue;
+        }
+        let exact = if hand.len() >= 4 {
+            let (first, second) = hand.split_at(hand.len() / 2);
+            Card::parse(first).zip(Card::parse(second))
+        } else {
+            None
+        };
+        let mut matched = vec![];
+        for (place, first) in deck.iter().enumerate() {
+            for second in &deck[place + 1..] {
+                // the higher card goes first, the way hands are written
+                let mut hole = CardCollection(vec![first.clone(), second.clone()]);
+                if second.value.value() > first.value.value() {
+                    hole.0.reverse();
+                }
+                let is_hand = match &exact {
+                    Some(exact) => {
+                        (exact.0 == *first && exact.1 == *second)
+                            || (exact.0 == *second && exact.1 == *first)
+                    }
+                    None => starting_hand_name(&hole).is_some_and(|name| {
+                        name == hand || (hand.len() == 2 && name.starts_with(hand))
+                    }),
+                };
+                if is_hand {
+                    matched.push(hole);
+                }
+            }
+        }
+        if matched.is_empty() {
+            return Err(format!("{} isn't a hand", hand));
+        }
+        for hole in matched {
+            let blocked = hole.0.iter().any(|card| board.0.contains(card));
+            let duplicate = range.iter().any(|other| other.0 == hole.0);
+            if !blocked && !duplicate {
+                range.push(hole);
+            }
+        }
+    }
+
+    Ok(range)
+}
+
+/// Returns a jack, queen and king of each of the suits.
+fn cards_of(suits: &[CardSuit]) -> Vec<Card> {
+    let mut cards = vec![];
+    for value in [CardValue::Jack, CardValue::Queen, CardValue::King] {
+        for suit in suits {
+            cards.push(Card {
+                suit: suit.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+
+    cards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfr::{expected_value, exploitability, Algorithm, Solver};
+
+    #[test]
+    fn kuhn_is_worth_minus_one_eighteenth_to_the_first_player() {
+        let mut solver = Solver::new(LimitGame::kuhn(), Algorithm::CfrPlus, 0);
+        solver.solve(2_000);
+        let profile = solver.average_strategy();
+        let value = expected_value(solver.game(), &profile);
+        assert!((value + 1.0 / 18.0).abs() < 1e-3, "{}", value);
+        assert!(exploitability(solver.game(), &profile) < 1e-3);
+    }
+
+    #[test]
+    fn leduc_is_worth_about_minus_0_0856_to_the_first_player() {
+        let mut solver = Solver::new(LimitGame::leduc(), Algorithm::CfrPlus, 0);
+        solver.solve(150);
+        let profile = solver.average_strategy();
+        let value = expected_value(solver.game(), &profile);
+        assert!((value + 0.0856).abs() < 2e-3, "{}", value);
+        assert!(exploitability(solver.game(), &profile) < 1e-2);
+    }
+}
@@ -0,0 +1,27 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::thread;
+
+/// Connects to the server and relays lines typed on stdin to it, printing everything the
+/// server sends back.
+pub fn run(ip_address: &str, port: u32) -> io::Result<()> {
+    let stream = TcpStream::connect(format!("{}:{}", ip_address, port))?;
+    let reader = BufReader::new(stream.try_clone()?);
+    thread::spawn(move || {
+        for line in reader.lines() {
+            match line {
+                Ok(line) => println!("{}", line),
+                Err(_) => break,
+            }
+        }
+        println!("Disconnected from the server");
+        std::process::exit(0);
+    });
+
+    let mut writer = stream;
+    for line in io::stdin().lock().lines() {
+        writeln!(writer, "{}", line?)?;
+    }
+
+    Ok(())
+}
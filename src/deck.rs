@@ -1,6 +1,7 @@
 use colored::*;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{thread_rng, Rng, SeedableRng};
 use std::fmt;
 
 #[derive(Clone, PartialEq)]
@@ -11,6 +12,29 @@ pub enum CardSuit {
     Club,
 }
 
+impl CardSuit {
+    /// Returns the letter the suit is written with in hand histories, as in the "h" of "Ah".
+    pub fn letter(&self) -> char {
+        match *self {
+            CardSuit::Heart => 'h',
+            CardSuit::Diamond => 'd',
+            CardSuit::Spade => 's',
+            CardSuit::Club => 'c',
+        }
+    }
+
+    /// Parses the letter the suit is written with in hand histories.
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter.to_ascii_lowercase() {
+            'h' => Some(CardSuit::Heart),
+            'd' => Some(CardSuit::Diamond),
+            's' => Some(CardSuit::Spade),
+            'c' => Some(CardSuit::Club),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for CardSuit {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -32,7 +56,7 @@ impl fmt::Display for CardSuit {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum CardValue {
     Two = 2,
     Three = 3,
@@ -75,6 +99,19 @@ impl CardValue {
     pub fn value(&self) -> u8 {
         self.clone() as u8
     }
+
+    /// Returns the letter the value is written with in hand histories and charts, as in the
+    /// "T" of "Td".
+    pub fn letter(&self) -> char {
+        match *self {
+            CardValue::Ten => 'T',
+            CardValue::Jack => 'J',
+            CardValue::Queen => 'Q',
+            CardValue::King => 'K',
+            CardValue::Ace => 'A',
+            _ => char::from(b'0' + self.value()),
+        }
+    }
 }
 
 impl fmt::Display for CardValue {
@@ -123,12 +160,41 @@ impl fmt::Display for CardValue {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct Card {
     pub suit: CardSuit,
     pub value: CardValue,
 }
 
+impl Card {
+    /// Parses a card written as its value and suit, as in "Ah", "Td" or "10d".
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut chars = text.trim().chars();
+        let suit = CardSuit::from_letter(chars.next_back()?)?;
+        let value = match chars.as_str().to_ascii_uppercase().as_str() {
+            "T" | "10" => 10,
+            "J" => 11,
+            "Q" => 12,
+            "K" => 13,
+            "A" => 14,
+            digit => match digit.parse() {
+                Ok(value) if (2..=9).contains(&value) => value,
+                _ => return None,
+            },
+        };
+
+        Some(Card {
+            suit,
+            value: CardValue::new(value),
+        })
+    }
+
+    /// Returns the card written as its value and suit, as in "Ah" or "Td".
+    pub fn code(&self) -> String {
+        format!("{}{}", self.value.letter(), self.suit.letter())
+    }
+}
+
 impl fmt::Display for Card {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.value.value() == 10 {
@@ -194,18 +260,14 @@ impl fmt::Display for CardCollection {
 pub struct Deck {
     cards: Vec<Card>,
     current_card: usize,
+    // the generator shuffles come from once the deck is seeded
+    rng: Option<StdRng>,
 }
 
 impl Deck {
     /// Creates a new deck of cards.
     pub fn new() -> Self {
-        let suits: Vec<CardSuit> = vec![
-            CardSuit::Heart,
-            CardSuit::Diamond,
-            CardSuit::Spade,
-            CardSuit::Club,
-        ];
-        let values: Vec<CardValue> = vec![
+        Deck::with_values(vec![
             CardValue::Two,
             CardValue::Three,
             CardValue::Four,
@@ -219,8 +281,33 @@ impl Deck {
             CardValue::Queen,
             CardValue::King,
             CardValue::Ace,
+        ])
+    }
+
+    /// Creates a new short deck of cards, which has the twos through fives removed.
+    pub fn new_short_deck() -> Self {
+        Deck::with_values(vec![
+            CardValue::Six,
+            CardValue::Seven,
+            CardValue::Eight,
+            CardValue::Nine,
+            CardValue::Ten,
+            CardValue::Jack,
+            CardValue::Queen,
+            CardValue::King,
+            CardValue::Ace,
+        ])
+    }
+
+    /// Creates a deck with a card of each suit for each of the given values.
+    fn with_values(values: Vec<CardValue>) -> Self {
+        let suits: Vec<CardSuit> = vec![
+            CardSuit::Heart,
+            CardSuit::Diamond,
+            CardSuit::Spade,
+            CardSuit::Club,
         ];
-        let mut cards: Vec<Card> = Vec::with_capacity(52);
+        let mut cards: Vec<Card> = Vec::with_capacity(suits.len() * values.len());
         for suit in &suits {
             for value in &values {
                 cards.push(Card {
@@ -232,6 +319,7 @@ impl Deck {
         Deck {
             cards,
             current_card: 0,
+            rng: None,
         }
     }
 
@@ -247,11 +335,69 @@ impl Deck {
         Some(CardCollection(cards))
     }
 
+    /// Returns the next cards that would be popped off of the deck, leaving them on it.
+    pub fn peek_cards(&self, num: u8) -> Option<CardCollection> {
+        let cards = self
+            .cards
+            .get(self.current_card..self.current_card + num as usize)?;
+
+        Some(CardCollection(cards.to_vec()))
+    }
+
+    /// Returns how many cards are left to be popped off of the deck.
+    pub fn cards_left(&self) -> usize {
+        self.cards.len() - self.current_card
+    }
+
     /// Adds all cards back to the deck and shuffles them.
     pub fn shuffle(&mut self) {
         self.current_card = 0;
-        let mut rng = thread_rng();
-        self.cards.shuffle(&mut rng);
+        match self.rng.as_mut() {
+            Some(rng) => self.cards.shuffle(rng),
+            None => self.cards.shuffle(&mut thread_rng()),
+        }
+    }
+
+    /// Shuffles cards that were dealt back in with the cards left, like the discards of a draw
+    /// game once the deck runs short. Cards that weren't dealt from this deck are left out.
+    pub fn reshuffle(&mut self, cards: &[Card]) {
+        let mut dealt = self.cards[..self.current_card].to_vec();
+        let mut left = self.cards[self.current_card..].to_vec();
+        for card in cards {
+            if let Some(index) = dealt.iter().position(|dealt| dealt == card) {
+                left.push(dealt.remove(index));
+            }
+        }
+        match self.rng.as_mut() {
+            Some(rng) => left.shuffle(rng),
+            None => left.shuffle(&mut thread_rng()),
+        }
+        self.current_card = dealt.len();
+        self.cards = [dealt, left].concat();
+    }
+
+    /// Puts the cards back in their starting order and shuffles them from a generator seeded
+    /// with the seed from now on, so decks seeded the same way deal the same cards.
+    pub fn seed(&mut self, seed: u64) {
+        self.cards
+            .sort_by_key(|card| (card.suit.clone() as u8, card.value.value()));
+        self.current_card = 0;
+        self.rng = Some(StdRng::seed_from_u64(seed));
+    }
+
+    /// Returns a seed to seed the deck with next, drawn from its own generator once it is
+    /// seeded so that seeded decks keep dealing the same cards.
+    pub fn next_seed(&mut self) -> u64 {
+        match self.rng.as_mut() {
+            Some(rng) => rng.gen(),
+            None => thread_rng().gen(),
+        }
+    }
+}
+
+impl Default for Deck {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -0,0 +1,853 @@
+use crate::betting::{Action, BettingRound};
+use crate::deck::*;
+use crate::events::TableEvent;
+use crate::hand::{assign_five_card_hand_rankings, HandRules};
+use crate::lobby::{TableGame, TableSettings};
+use crate::showdown::{build_pots, split_pots};
+use crate::strategy::Decision;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DrawPhase {
+    /// The betting round after the deal.
+    FirstBetting,
+    /// Players discard and receive replacement cards.
+    Draw,
+    /// The betting round after the draw, followed by the showdown.
+    FinalBetting,
+}
+
+#[derive(Clone)]
+pub struct DrawPlayer {
+    pub chips: u32,
+    pub cards: CardCollection,
+    pub folded: bool,
+    // whether this player has taken their turn in the draw
+    drawn: bool,
+    // the chips this player has put in over the hand
+    contributed: u32,
+}
+
+/// A game of Five-Card Draw. Each hand starts with antes and five cards to every player,
+/// followed by a betting round, a single draw and a final betting round. Players draw in turn
+/// starting left of the button, which moves on a seat each hand, and once the deck runs short
+/// the cards discarded so far are shuffled in with what is left of it.
+pub struct DrawGame {
+    deck: Deck,
+    players: Vec<DrawPlayer>,
+    phase: Option<DrawPhase>,
+    ante: u32,
+    button: usize,
+    // the cards thrown away in the draw so far this hand
+    discards: Vec<Card>,
+}
+
+impl DrawGame {
+    /// Creates a new game with a player seated for each of the starting stacks.
+    pub fn new(stacks: Vec<u32>, ante: u32) -> Self {
+        let players = stacks
+            .into_iter()
+            .map(|chips| DrawPlayer {
+                chips,
+                cards: CardCollection(vec![]),
+                folded: false,
+                drawn: false,
+                contributed: 0,
+            })
+            .collect();
+
+        DrawGame {
+            deck: Deck::new(),
+            players,
+            phase: None,
+            ante,
+            button: 0,
+            discards: vec![],
+        }
+    }
+
+    /// Seeds the deck, so games seeded the same way deal the same cards.
+    pub fn seed_deck(&mut self, seed: u64) {
+        self.deck.seed(seed);
+    }
+
+    /// Changes the player's stack between hands.
+    pub fn set_chips(&mut self, player: usize, chips: u32) {
+        self.players[player].chips = chips;
+    }
+
+    pub fn players(&self) -> &Vec<DrawPlayer> {
+        &self.players
+    }
+
+    pub fn phase(&self) -> Option<DrawPhase> {
+        self.phase
+    }
+
+    pub fn button(&self) -> usize {
+        self.button
+    }
+
+    /// Returns the player whose turn it is to draw, or `None` outside the draw or once everyone
+    /// still in the hand has drawn.
+    pub fn to_draw(&self) -> Option<usize> {
+        if self.phase != Some(DrawPhase::Draw) {
+            return None;
+        }
+        let players = self.players.len();
+        (1..=players)
+            .map(|offset| (self.button + offset) % players)
+            .find(|index| !self.players[*index].folded && !self.players[*index].drawn)
+    }
+
+    /// Returns the total number of chips in the pot.
+    pub fn pot(&self) -> u32 {
+        self.players.iter().map(|player| player.contributed).sum()
+    }
+
+    /// Shuffles the deck, collects the antes and deals five cards to every player. Players
+    /// without any chips sit the hand out.
+    pub fn start_hand(&mut self) {
+        self.deck.shuffle();
+        self.discards.clear();
+        self.phase = Some(DrawPhase::FirstBetting);
+        for player in self.players.iter_mut() {
+            player.cards = CardCollection(vec![]);
+            player.contributed = 0;
+            player.drawn = false;
+            player.folded = player.chips == 0;
+        }
+
+        for index in 0..self.players.len() {
+            if !self.players[index].folded {
+                self.put_in_pot(index, self.ante);
+                self.players[index].cards = match self.deck.pop_cards(5) {
+                    Some(cards) => cards,
+                    None => panic!("ran out of cards while dealing five-card draw"),
+                };
+            }
+        }
+    }
+
+    /// Moves on from the first betting round to the draw, or from the draw to the final betting
+    /// round once every player still in the hand has drawn.
+    pub fn next_phase(&mut self) -> Option<DrawPhase> {
+        let phase = match self.phase? {
+            DrawPhase::FirstBetting => DrawPhase::Draw,
+            DrawPhase::Draw => {
+                if self.players.iter().any(|p| !p.folded && !p.drawn) {
+                    return None;
+                }
+                DrawPhase::FinalBetting
+            }
+            DrawPhase::FinalBetting => return None,
+        };
+        self.phase = Some(phase);
+
+        Some(phase)
+    }
+
+    /// Moves chips from the player's stack into the pot, returning how many were actually put in
+    /// since a player can't bet more than they have.
+    pub fn put_in_pot(&mut self, player: usize, amount: u32) -> u32 {
+        let player = &mut self.players[player];
+        let amount = amount.min(player.chips);
+        player.chips -= amount;
+        player.contributed += amount;
+
+        amount
+    }
+
+    pub fn fold(&mut self, player: usize) {
+        self.players[player].folded = true;
+    }
+
+    /// Discards the cards at the given indices of the player's hand and replaces them with cards
+    /// from the deck, returning the new cards. Returns `None` without changing anything if it
+    /// isn't the player's turn to draw, an index is invalid or even the earlier discards can't
+    /// cover the replacements. Discarding no cards stands pat.
+    pub fn draw(&mut self, player: usize, discards: Vec<usize>) -> Option<CardCollection> {
+        if self.to_draw() != Some(player) {
+            return None;
+        }
+        let mut discards = discards;
+        discards.sort();
+        discards.dedup();
+        let hand = &self.players[player];
+        if discards.iter().any(|index| *index >= hand.cards.0.len()) {
+            return None;
+        }
+        if self.deck.cards_left() < discards.len() {
+            if self.deck.cards_left() + self.discards.len() < discards.len() {
+                return None;
+            }
+            // the player's own discards stay out of the reshuffle
+            self.deck.reshuffle(&self.discards);
+            self.discards.clear();
+        }
+
+        let replacements = self.deck.pop_cards(discards.len() as u8)?;
+        let hand = &mut self.players[player];
+        for index in discards.iter().rev() {
+            self.discards.push(hand.cards.0.remove(*index));
+        }
+        hand.cards = CardCollection::concat(hand.cards.clone(), replacements.clone());
+        hand.drawn = true;
+
+        Some(replacements)
+    }
+
+    /// Awards the pot to the players still in the hand, splitting it into side pots as needed,
+    /// and returns how many chips each player won. The button moves on for the next hand.
+    pub fn showdown(&mut self) -> Vec<u32> {
+        let contributions: Vec<u32> = self.players.iter().map(|p| p.contributed).collect();
+        let folded: Vec<bool> = self.players.iter().map(|p| p.folded).collect();
+        let pots = build_pots(&contributions, &folded);
+
+        let active: Vec<usize> = (0..self.players.len())
+            .filter(|index| !folded[*index])
+            .collect();
+        let mut rankings = vec![u8::MAX; self.players.len()];
+        if active.len() == 1 {
+            rankings[active[0]] = 1;
+        } else {
+            let hands = active
+                .iter()
+                .map(|index| self.players[*index].cards.clone())
+                .collect();
+            for (index, ranking) in active.iter().zip(assign_five_card_hand_rankings(hands)) {
+                rankings[*index] = ranking;
+            }
+        }
+
+        let winnings = split_pots(pots, rankings);
+        for (player, chips) in self.players.iter_mut().zip(&winnings) {
+            player.chips += chips;
+            player.contributed = 0;
+        }
+        self.phase = None;
+        self.button = (self.button + 1) % self.players.len();
+
+        winnings
+    }
+
+    /// Returns the player's hand with the position of each card printed beneath it, so they can
+    /// pick which cards to discard.
+    pub fn view(&self, viewer: usize) -> String {
+        numbered_hand(&self.players[viewer].cards)
+    }
+}
+
+/// Returns the hand with the position of each card printed beneath it, as `parse_discards`
+/// reads them.
+pub fn numbered_hand(cards: &CardCollection) -> String {
+    let mut view = format!("{}", cards);
+    for index in 0..cards.0.len() {
+        view.push_str(&format!("   {}    ", index + 1));
+    }
+    view.push('\n');
+
+    view
+}
+
+/// A player sitting at a draw table.
+#[derive(Clone)]
+struct DrawSeat {
+    chips: u32,
+    sitting_out: bool,
+    // the hands dealt since the player sat out
+    hands_sat_out: u32,
+}
+
+/// A Five-Card Draw table, dealing each hand on a `DrawGame` between the players sitting in with
+/// chips and betting before and after the draw with a `BettingRound`. The table's small blind
+/// is the ante and there are no blinds, so the first player left of the button opens the
+/// betting. Fixed limit bets are the small bet before the draw and the big bet after it.
+///
+/// With no blinds to miss, players sit out and back in as they like, and are removed once they
+/// have sat out `max_orbits_sat_out` orbits, counting an orbit as a hand for each seat.
+pub struct DrawTable {
+    settings: TableSettings,
+    game: DrawGame,
+    seats: Vec<Option<DrawSeat>>,
+    // whether each seat was dealt into the hand in progress or the last hand
+    dealt: Vec<bool>,
+    round: Option<BettingRound>,
+    hands_played: u32,
+}
+
+impl DrawTable {
+    pub fn new(settings: TableSettings) -> Self {
+        DrawTable {
+            settings,
+            game: DrawGame::new(vec![0; settings.seats], settings.small_blind),
+            seats: vec![None; settings.seats],
+            dealt: vec![false; settings.seats],
+            round: None,
+            hands_played: 0,
+        }
+    }
+
+    /// Returns the game of the hand in progress, or of the last hand between hands.
+    pub fn game(&self) -> &DrawGame {
+        &self.game
+    }
+
+    /// Seeds the deck, so tables seeded the same way deal the same cards to the same seats.
+    pub fn seed_deck(&mut self, seed: u64) {
+        self.game.seed_deck(seed);
+    }
+
+    pub fn hands_played(&self) -> u32 {
+        self.hands_played
+    }
+
+    /// Returns the total number of chips in the pot, including the current round's bets.
+    pub fn pot(&self) -> u32 {
+        let bets: u32 = match &self.round {
+            Some(round) => round.bets().iter().sum(),
+            None => 0,
+        };
+
+        self.game.pot() + bets
+    }
+
+    /// Plays the hand on until someone has to act or draw, moving on to the draw and the final
+    /// betting round as the players finish each, and settling the pot once the hand is over.
+    fn progress(&mut self, events: &mut Vec<TableEvent>) {
+        loop {
+            if let Some(round) = &self.round {
+                if let Some(seat) = round.to_act() {
+                    events.push(TableEvent::ToAct {
+                        seat,
+                        pot: self.pot(),
+                    });
+                    return;
+                }
+                let bets = round.bets().clone();
+                self.round = None;
+                for (seat, bet) in bets.into_iter().enumerate() {
+                    self.game.put_in_pot(seat, bet);
+                }
+                let in_hand = self.game.players().iter().filter(|p| !p.folded).count();
+                if in_hand <= 1 || self.game.phase() == Some(DrawPhase::FinalBetting) {
+                    return self.settle(events);
+                }
+                self.game.next_phase();
+            }
+
+            if let Some(seat) = self.game.to_draw() {
+                events.push(TableEvent::ToAct {
+                    seat,
+                    pot: self.pot(),
+                });
+                return;
+            }
+            if self.game.next_phase() != Some(DrawPhase::FinalBetting) {
+                return;
+            }
+            self.start_round(true);
+        }
+    }
+
+    /// Starts a betting round with the players still in the hand, opened by the first of them
+    /// left of the button.
+    fn start_round(&mut self, big_bet_street: bool) {
+        let (stacks, folded) = self
+            .game
+            .players()
+            .iter()
+            .map(|player| (player.chips, player.folded))
+            .unzip();
+        self.round = Some(BettingRound::new(
+            self.settings.structure,
+            stacks,
+            folded,
+            self.game.pot(),
+            big_bet_street,
+            self.game.button() + 1,
+        ));
+    }
+
+    /// Shows the hands left in at showdown, splits the pot and ends the hand.
+    fn settle(&mut self, events: &mut Vec<TableEvent>) {
+        let active: Vec<usize> = (0..self.seats.len())
+            .filter(|seat| self.dealt[*seat] && !self.game.players()[*seat].folded)
+            .collect();
+        if active.len() > 1 {
+            for seat in active {
+                events.push(TableEvent::Showdown {
+                    seat,
+                    cards: self.game.players()[seat].cards.clone(),
+                });
+            }
+        }
+
+        let winnings = self.game.showdown();
+        for (seat, amount) in winnings.into_iter().enumerate() {
+            if amount > 0 {
+                events.push(TableEvent::PotWon { seat, amount });
+            }
+        }
+        self.sync_stacks();
+        events.push(TableEvent::HandFinished {
+            hand: self.hands_played,
+        });
+    }
+
+    /// Copies the stacks of the players still in the hand back from the betting round, or from
+    /// the game outside the betting rounds.
+    fn sync_stacks(&mut self) {
+        for (seat, player) in self.seats.iter_mut().enumerate() {
+            let Some(player) = player else {
+                continue;
+            };
+            if !self.dealt[seat] || self.game.players()[seat].folded {
+                continue;
+            }
+            player.chips = match &self.round {
+                Some(round) => round.stacks()[seat],
+                None => self.game.players()[seat].chips,
+            };
+        }
+    }
+
+    /// Returns whether the seat was dealt into the hand in progress and hasn't folded.
+    fn in_hand(&self, seat: usize) -> bool {
+        self.hand_in_progress() && self.dealt[seat] && !self.game.players()[seat].folded
+    }
+
+    fn seat_mut(&mut self, seat: usize) -> Result<&mut DrawSeat, String> {
+        match self.seats.get_mut(seat) {
+            Some(Some(player)) => Ok(player),
+            _ => Err(format!("nobody is sitting in seat {}", seat + 1)),
+        }
+    }
+}
+
+impl TableGame for DrawTable {
+    fn hand_in_progress(&self) -> bool {
+        self.game.phase().is_some()
+    }
+
+    fn stacks(&self) -> Vec<u32> {
+        self.seats
+            .iter()
+            .map(|player| player.as_ref().map_or(0, |player| player.chips))
+            .collect()
+    }
+
+    fn hole_cards(&self, seat: usize) -> CardCollection {
+        match self.game.players().get(seat) {
+            Some(player) if self.dealt[seat] => player.cards.clone(),
+            _ => CardCollection(vec![]),
+        }
+    }
+
+    fn sit_down(&mut self, seat: usize, chips: u32) -> Result<(), String> {
+        match self.seats.get(seat) {
+            None => return Err(format!("there is no seat {}", seat + 1)),
+            Some(Some(_)) => return Err(format!("seat {} is taken", seat + 1)),
+            Some(None) => {}
+        }
+        if chips == 0 {
+            return Err("can't sit down without any chips".to_string());
+        }
+        self.seats[seat] = Some(DrawSeat {
+            chips,
+            sitting_out: false,
+            hands_sat_out: 0,
+        });
+
+        Ok(())
+    }
+
+    fn add_chips(&mut self, seat: usize, chips: u32) -> Result<(), String> {
+        if self.in_hand(seat) {
+            return Err("can't add chips in the middle of a hand".to_string());
+        }
+        let player = self.seat_mut(seat)?;
+        player.chips = player
+            .chips
+            .checked_add(chips)
+            .ok_or("that's more chips than a stack can hold")?;
+
+        Ok(())
+    }
+
+    fn leaving_chips(&self, seat: usize) -> Result<u32, String> {
+        let Some(Some(player)) = self.seats.get(seat) else {
+            return Err(format!("nobody is sitting in seat {}", seat + 1));
+        };
+        if self.in_hand(seat) {
+            return Err("can't leave in the middle of a hand".to_string());
+        }
+
+        Ok(player.chips)
+    }
+
+    fn stand_up(&mut self, seat: usize) -> Result<u32, String> {
+        let chips = self.leaving_chips(seat)?;
+        self.seats[seat] = None;
+        self.dealt[seat] = false;
+
+        Ok(chips)
+    }
+
+    fn sit_out(&mut self, seat: usize) -> Result<(), String> {
+        let player = self.seat_mut(seat)?;
+        if player.sitting_out {
+            return Err("already sitting out".to_string());
+        }
+        player.sitting_out = true;
+        player.hands_sat_out = 0;
+
+        Ok(())
+    }
+
+    fn sit_in(&mut self, seat: usize, _post_missed_blinds: bool) -> Result<(), String> {
+        let player = self.seat_mut(seat)?;
+        if !player.sitting_out {
+            return Err("already playing".to_string());
+        }
+        player.sitting_out = false;
+
+        Ok(())
+    }
+
+    fn start_hand(&mut self) -> Result<Vec<TableEvent>, String> {
+        if self.hand_in_progress() {
+            return Err("a hand is already being played".to_string());
+        }
+        let ready = |seat: &Option<DrawSeat>| {
+            seat.as_ref()
+                .is_some_and(|player| !player.sitting_out && player.chips > 0)
+        };
+        if self.seats.iter().filter(|seat| ready(seat)).count() < 2 {
+            return Err("need at least two players to start a hand".to_string());
+        }
+        self.hands_played += 1;
+        let mut events = vec![TableEvent::HandStarted {
+            hand: self.hands_played,
+        }];
+
+        let seats = self.seats.len();
+        let most_hands = self
+            .settings
+            .max_orbits_sat_out
+            .map(|orbits| orbits * seats as u32);
+        for seat in 0..seats {
+            let Some(player) = self.seats[seat].as_mut() else {
+                continue;
+            };
+            if !player.sitting_out {
+                continue;
+            }
+            player.hands_sat_out += 1;
+            if most_hands.is_some_and(|most| player.hands_sat_out >= most) {
+                let chips = player.chips;
+                self.seats[seat] = None;
+                events.push(TableEvent::Removed { seat, chips });
+            }
+        }
+
+        for seat in 0..seats {
+            self.dealt[seat] = ready(&self.seats[seat]);
+            let chips = match &self.seats[seat] {
+                Some(player) if self.dealt[seat] => player.chips,
+                _ => 0,
+            };
+            self.game.set_chips(seat, chips);
+        }
+        self.game.start_hand();
+        for seat in (0..seats).filter(|seat| self.dealt[*seat]) {
+            let amount = self.settings.small_blind.min(self.stacks()[seat]);
+            if amount > 0 {
+                events.push(TableEvent::Ante { seat, amount });
+            }
+        }
+        for seat in (0..seats).filter(|seat| self.dealt[*seat]) {
+            events.push(TableEvent::HoleCards {
+                seat,
+                cards: self.game.players()[seat].cards.clone(),
+            });
+        }
+
+        self.start_round(false);
+        self.sync_stacks();
+        self.progress(&mut events);
+
+        Ok(events)
+    }
+
+    fn decision(&self) -> Option<Decision> {
+        let round = self.round.as_ref()?;
+        let seat = round.to_act()?;
+        let players = self.game.players();
+
+        Some(Decision {
+            seat,
+            hole_cards: players[seat].cards.clone(),
+            board: CardCollection(vec![]),
+            street: None,
+            up_cards: vec![],
+            pot: self.pot(),
+            bet: round.bets().iter().max().copied().unwrap_or(0),
+            chips: round.stacks()[seat],
+            big_blind: self.settings.big_blind,
+            opponents: players.iter().filter(|player| !player.folded).count() - 1,
+            rules: HandRules::Standard,
+            legal: round.legal_actions()?,
+        })
+    }
+
+    fn to_act(&self) -> Option<usize> {
+        self.round.as_ref()?.to_act()
+    }
+
+    fn act(&mut self, action: Action) -> Result<Vec<TableEvent>, String> {
+        let Some(round) = self.round.as_mut() else {
+            return Err("there isn't a betting round being played".to_string());
+        };
+        let Some(seat) = round.to_act() else {
+            return Err("nobody is left to act".to_string());
+        };
+        round.act(action)?;
+        if action == Action::Fold {
+            self.game.fold(seat);
+        }
+        self.sync_stacks();
+
+        let mut events = vec![TableEvent::Action { seat, action }];
+        self.progress(&mut events);
+
+        Ok(events)
+    }
+
+    fn to_draw(&self) -> Option<usize> {
+        self.game.to_draw()
+    }
+
+    fn draw(&mut self, seat: usize, discards: Vec<usize>) -> Result<Vec<TableEvent>, String> {
+        if self.game.to_draw() != Some(seat) {
+            return Err("it isn't your turn to draw".to_string());
+        }
+        let Some(cards) = self.game.draw(seat, discards) else {
+            return Err("can't draw those cards".to_string());
+        };
+
+        let mut events = vec![TableEvent::Drew {
+            seat,
+            count: cards.0.len(),
+        }];
+        if !cards.0.is_empty() {
+            events.push(TableEvent::HoleCards { seat, cards });
+        }
+        self.progress(&mut events);
+
+        Ok(events)
+    }
+}
+
+/// Parses the cards a player chose to discard, given as the 1 based positions shown by
+/// `DrawGame::view` separated by spaces or commas, into indices of their hand. An empty input
+/// stands pat. Returns `None` if any position is invalid.
+pub fn parse_discards(input: &str) -> Option<Vec<usize>> {
+    let mut discards = vec![];
+    for position in input.split(|c: char| c == ',' || c.is_whitespace()) {
+        if position.is_empty() {
+            continue;
+        }
+        let position: usize = position.parse().ok()?;
+        if position == 0 || position > 5 {
+            return None;
+        }
+        discards.push(position - 1);
+    }
+
+    Some(discards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::betting::BettingStructure;
+    use crate::lobby::GameType;
+    use crate::timer::TimerSettings;
+
+    fn drawing(players: usize) -> DrawGame {
+        let mut game = DrawGame::new(vec![100; players], 1);
+        game.start_hand();
+        game.next_phase();
+        game
+    }
+
+    #[test]
+    fn players_draw_in_turn_from_left_of_the_button() {
+        let mut game = drawing(3);
+        assert_eq!(game.to_draw(), Some(1));
+        assert!(game.draw(0, vec![0]).is_none());
+        assert!(game.draw(2, vec![0]).is_none());
+        assert_eq!(game.draw(1, vec![0, 1]).map(|cards| cards.0.len()), Some(2));
+        assert!(game.draw(1, vec![]).is_none());
+        assert_eq!(game.to_draw(), Some(2));
+        game.fold(2);
+        assert_eq!(game.to_draw(), Some(0));
+        assert!(game.draw(0, vec![]).is_some());
+        assert_eq!(game.to_draw(), None);
+        assert!(game.next_phase() == Some(DrawPhase::FinalBetting));
+    }
+
+    #[test]
+    fn button_moves_after_each_hand() {
+        let mut game = drawing(3);
+        game.showdown();
+        game.start_hand();
+        game.next_phase();
+        assert_eq!(game.button(), 1);
+        assert_eq!(game.to_draw(), Some(2));
+    }
+
+    #[test]
+    fn discards_are_reshuffled_once_the_deck_runs_short() {
+        // eight hands leave 12 cards, so the third player to draw five needs the discards
+        let mut game = drawing(8);
+        for _ in 0..8 {
+            let player = game.to_draw().unwrap();
+            let before = game.players()[player].cards.clone();
+            let replacements = game.draw(player, vec![0, 1, 2, 3, 4]).unwrap();
+            assert_eq!(replacements.0.len(), 5);
+            // nobody is dealt back the cards they just threw away
+            assert!(replacements.0.iter().all(|card| !before.0.contains(card)));
+        }
+        let mut held: Vec<String> = game
+            .players()
+            .iter()
+            .flat_map(|player| player.cards.0.iter().map(|card| card.code()))
+            .collect();
+        held.sort();
+        held.dedup();
+        assert_eq!(held.len(), 40);
+    }
+
+    #[test]
+    fn draw_fails_when_even_the_discards_fall_short() {
+        // ten hands leave 2 cards and nothing has been discarded yet
+        let mut game = drawing(10);
+        assert!(game.draw(1, vec![0, 1, 2]).is_none());
+        assert_eq!(game.to_draw(), Some(1));
+        assert_eq!(game.draw(1, vec![0, 1]).map(|cards| cards.0.len()), Some(2));
+    }
+
+    fn table(players: usize) -> DrawTable {
+        let mut table = DrawTable::new(TableSettings {
+            game: GameType::Draw,
+            structure: BettingStructure::parse("fl", 1, 2).unwrap(),
+            small_blind: 1,
+            big_blind: 2,
+            seats: players,
+            broadcast_delay: None,
+            timer: TimerSettings::default(),
+            max_orbits_sat_out: Some(1),
+            max_runs: 1,
+            min_buy_in: 40,
+            max_buy_in: 200,
+        });
+        for seat in 0..players {
+            table.sit_down(seat, 100).unwrap();
+        }
+        table.seed_deck(30);
+        table
+    }
+
+    /// Checks or calls until the hand reaches the draw.
+    fn call_to_the_draw(table: &mut DrawTable, events: &mut Vec<TableEvent>) {
+        while let Some(decision) = table.decision() {
+            let action = if decision.legal.call == 0 {
+                Action::Check
+            } else {
+                Action::Call
+            };
+            events.extend(table.act(action).unwrap());
+        }
+    }
+
+    #[test]
+    fn hand_is_played_through_the_draw_to_a_showdown() {
+        let mut table = table(3);
+        let mut events = table.start_hand().unwrap();
+        let antes = events
+            .iter()
+            .filter(|event| matches!(event, TableEvent::Ante { amount: 1, .. }))
+            .count();
+        assert_eq!(antes, 3);
+        let decision = table.decision().unwrap();
+        assert_eq!(decision.seat, 1);
+        assert_eq!(decision.pot, 3);
+        assert_eq!(decision.legal.raise, Some((2, 2)));
+        assert_eq!(decision.hole_cards.0.len(), 5);
+
+        events.extend(table.act(Action::Raise(2)).unwrap());
+        call_to_the_draw(&mut table, &mut events);
+        assert_eq!(table.to_draw(), Some(1));
+        assert!(table.draw(2, vec![]).is_err());
+        let before = table.hole_cards(1);
+        let drew = table.draw(1, vec![0, 4]).unwrap();
+        assert!(matches!(drew[0], TableEvent::Drew { seat: 1, count: 2 }));
+        let TableEvent::HoleCards { seat: 1, cards } = &drew[1] else {
+            panic!("the replacements should follow the draw");
+        };
+        assert_eq!(cards.0.len(), 2);
+        let after = table.hole_cards(1);
+        assert!(after.0[..3] == before.0[1..4]);
+        events.extend(drew);
+        events.extend(table.draw(2, vec![]).unwrap());
+        events.extend(table.draw(0, vec![1]).unwrap());
+
+        // the bets after the draw are the big bet
+        let decision = table.decision().unwrap();
+        assert_eq!(decision.pot, 9);
+        assert_eq!(decision.legal.raise, Some((4, 4)));
+        call_to_the_draw(&mut table, &mut events);
+        assert!(!table.hand_in_progress());
+
+        let shown = events
+            .iter()
+            .filter(
+                |event| matches!(event, TableEvent::Showdown { cards, .. } if cards.0.len() == 5),
+            )
+            .count();
+        assert_eq!(shown, 3);
+        let won: u32 = events
+            .iter()
+            .filter_map(|event| match event {
+                TableEvent::PotWon { amount, .. } => Some(*amount),
+                _ => None,
+            })
+            .sum();
+        assert_eq!(won, 9);
+        assert_eq!(table.stacks().iter().sum::<u32>(), 300);
+    }
+
+    #[test]
+    fn hand_ends_without_a_draw_once_everyone_else_folds() {
+        let mut table = table(3);
+        let mut events = table.start_hand().unwrap();
+        events.extend(table.act(Action::Raise(2)).unwrap());
+        events.extend(table.act(Action::Fold).unwrap());
+        events.extend(table.act(Action::Fold).unwrap());
+        assert!(!table.hand_in_progress());
+        assert_eq!(table.to_draw(), None);
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, TableEvent::Drew { .. } | TableEvent::Showdown { .. })));
+        assert_eq!(table.stacks(), vec![99, 102, 99]);
+    }
+
+    #[test]
+    fn parses_discard_positions() {
+        assert_eq!(parse_discards("1 3,5"), Some(vec![0, 2, 4]));
+        assert_eq!(parse_discards(""), Some(vec![]));
+        assert_eq!(parse_discards("6"), None);
+        assert_eq!(parse_discards("0"), None);
+    }
+}
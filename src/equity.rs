@@ -0,0 +1,72 @@
+use crate::deck::*;
+use crate::hand::{score_hand, HandRules};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Returns the share of the pot the hole cards win on average against the given number of
+/// opponents holding random hands, estimated from `trials` deals of the opponents' cards and
+/// the rest of the board. A tie counts as an even share of the pot.
+pub fn monte_carlo_equity<R: Rng>(
+    hole: &CardCollection,
+    board: &CardCollection,
+    opponents: usize,
+    rules: HandRules,
+    trials: u32,
+    rng: &mut R,
+) -> f64 {
+    if opponents == 0 || trials == 0 {
+        return 1.0;
+    }
+    let mut deck = remaining_cards(rules, &[hole, board]);
+    let needed = 5usize.saturating_sub(board.0.len());
+    let dealt = needed + 2 * opponents;
+    if deck.len() < dealt {
+        return 0.0;
+    }
+
+    let mut won = 0.0;
+    let mut hand = Vec::with_capacity(7);
+    for _ in 0..trials {
+        let (cards, _) = deck.partial_shuffle(rng, dealt);
+        let (rest_of_board, opponents) = cards.split_at(needed);
+        // the hole cards go in the first two places and the board after them, so each
+        // opponent's hand only needs its first two cards swapped in
+        hand.clear();
+        hand.extend_from_slice(&hole.0);
+        hand.extend_from_slice(&board.0);
+        hand.extend_from_slice(rest_of_board);
+        let score = score_hand(&hand, rules);
+
+        let mut ties = 1;
+        let mut beaten = false;
+        for opponent in opponents.chunks(2) {
+            hand[..2].clone_from_slice(opponent);
+            let other = score_hand(&hand, rules);
+            if other > score {
+                beaten = true;
+                break;
+            }
+            if other == score {
+                ties += 1;
+            }
+        }
+        if !beaten {
+            won += 1.0 / ties as f64;
+        }
+    }
+
+    won / trials as f64
+}
+
+/// Returns the cards of the deck played under the rules that aren't among the given cards.
+pub fn remaining_cards(rules: HandRules, known: &[&CardCollection]) -> Vec<Card> {
+    let mut deck = rules.new_deck();
+    let all = deck
+        .pop_cards(deck.cards_left() as u8)
+        .unwrap_or(CardCollection(vec![]));
+
+    all.0
+        .into_iter()
+        .filter(|card| !known.iter().any(|cards| cards.0.contains(card)))
+        .collect()
+}
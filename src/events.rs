@@ -0,0 +1,338 @@
+use crate::betting::Action;
+use crate::deck::*;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Something that happened at a table, sent to the players and spectators.
+#[derive(Clone)]
+pub enum TableEvent {
+    HandStarted {
+        hand: u32,
+    },
+    HandFinished {
+        hand: u32,
+    },
+    /// A forced bet. Dead blinds go straight into the pot without counting towards the seat's
+    /// bet.
+    Blind {
+        seat: usize,
+        amount: u32,
+        dead: bool,
+    },
+    Ante {
+        seat: usize,
+        amount: u32,
+    },
+    /// The forced bet in stud from the seat showing the lowest up card, which counts towards
+    /// the seat's bet.
+    BringIn {
+        seat: usize,
+        amount: u32,
+    },
+    /// The blinds and ante went up, from the next hand on.
+    BlindsUp {
+        small_blind: u32,
+        big_blind: u32,
+        ante: u32,
+    },
+    /// The seat ran out of chips in a tournament and finished in the given place.
+    Eliminated {
+        seat: usize,
+        place: usize,
+    },
+    /// The seat's player was moved to another table to keep the tables balanced.
+    Moved {
+        seat: usize,
+        table: usize,
+        to_seat: usize,
+    },
+    /// A player moved here from another table and sat down with their chips.
+    Seated {
+        seat: usize,
+        chips: u32,
+    },
+    /// Every table now plays one hand at a time and waits for the others before the next.
+    HandForHand,
+    FinalTable,
+    /// The seat was removed from the table for sitting out too long, taking its chips with it.
+    Removed {
+        seat: usize,
+        chips: u32,
+    },
+    /// Cards dealt face down to a seat. Only that seat may see them before showdown.
+    HoleCards {
+        seat: usize,
+        cards: CardCollection,
+    },
+    /// What everyone else learns when a seat is dealt face down cards.
+    HiddenCards {
+        seat: usize,
+        count: usize,
+    },
+    /// Cards dealt face up to a seat, as in stud.
+    UpCards {
+        seat: usize,
+        cards: CardCollection,
+    },
+    Action {
+        seat: usize,
+        action: Action,
+    },
+    /// The seat threw away the given number of cards in the draw, standing pat with none. The
+    /// replacements follow as hole cards.
+    Drew {
+        seat: usize,
+        count: usize,
+    },
+    /// It is the seat's turn to act.
+    ToAct {
+        seat: usize,
+        pot: u32,
+    },
+    /// The time the seat to act has left, in seconds.
+    Countdown {
+        seat: usize,
+        action_left: u64,
+        time_bank_left: u64,
+    },
+    Board {
+        cards: CardCollection,
+    },
+    /// The players left in the hand are all in before the river, and each of them picks how
+    /// many times to run the rest of the board. It is run the fewest times anyone picks.
+    RunItOffer {
+        seats: Vec<usize>,
+        max_runs: u32,
+    },
+    RunsChosen {
+        seat: usize,
+        runs: u32,
+    },
+    /// The whole board of one of the runs, counting from 1, when the board is run more than
+    /// once.
+    Run {
+        run: u32,
+        cards: CardCollection,
+    },
+    Showdown {
+        seat: usize,
+        cards: CardCollection,
+    },
+    /// The seat threw its hand away at showdown without showing it.
+    Mucked {
+        seat: usize,
+    },
+    /// The board the last hand would have had if it had gone on to the river.
+    RabbitHunt {
+        cards: CardCollection,
+    },
+    PotWon {
+        seat: usize,
+        amount: u32,
+    },
+}
+
+impl TableEvent {
+    /// Returns the event as the recipient is allowed to see it. Face down cards are only ever
+    /// shown to the seat they were dealt to.
+    pub fn filter_for(&self, recipient: Recipient) -> TableEvent {
+        match self {
+            TableEvent::HoleCards { seat, cards } if recipient != Recipient::Seat(*seat) => {
+                TableEvent::HiddenCards {
+                    seat: *seat,
+                    count: cards.0.len(),
+                }
+            }
+            _ => self.clone(),
+        }
+    }
+}
+
+impl fmt::Display for TableEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TableEvent::HandStarted { hand } => write!(f, "Hand #{} started", hand),
+            TableEvent::HandFinished { hand } => write!(f, "Hand #{} is over", hand),
+            TableEvent::Blind { seat, amount, dead } => {
+                let kind = if *dead { "a dead blind" } else { "a blind" };
+                write!(f, "Seat {} posts {} of {}", seat + 1, kind, amount)
+            }
+            TableEvent::Ante { seat, amount } => {
+                write!(f, "Seat {} posts an ante of {}", seat + 1, amount)
+            }
+            TableEvent::BringIn { seat, amount } => {
+                write!(f, "Seat {} brings in for {}", seat + 1, amount)
+            }
+            TableEvent::BlindsUp {
+                small_blind,
+                big_blind,
+                ante,
+            } if *ante > 0 => write!(
+                f,
+                "The blinds are now {}/{} with a {} ante",
+                small_blind, big_blind, ante
+            ),
+            TableEvent::BlindsUp {
+                small_blind,
+                big_blind,
+                ..
+            } => write!(f, "The blinds are now {}/{}", small_blind, big_blind),
+            TableEvent::Eliminated { seat, place } => {
+                write!(f, "Seat {} finished in {} place", seat + 1, ordinal(*place))
+            }
+            TableEvent::Moved {
+                seat,
+                table,
+                to_seat,
+            } => write!(
+                f,
+                "Seat {} moves to table {}, seat {}",
+                seat + 1,
+                table + 1,
+                to_seat + 1
+            ),
+            TableEvent::Seated { seat, chips } => write!(
+                f,
+                "A player sits down in seat {} with {} chips",
+                seat + 1,
+                chips
+            ),
+            TableEvent::HandForHand => write!(f, "Hand-for-hand play has started"),
+            TableEvent::FinalTable => write!(f, "This is the final table"),
+            TableEvent::Removed { seat, chips } => write!(
+                f,
+                "Seat {} was removed for sitting out and left with {} chips",
+                seat + 1,
+                chips
+            ),
+            TableEvent::HoleCards { seat, cards } => {
+                write!(f, "Seat {} was dealt\n{}", seat + 1, cards)
+            }
+            TableEvent::HiddenCards { seat, count } => {
+                write!(f, "Seat {} was dealt {} cards face down", seat + 1, count)
+            }
+            TableEvent::UpCards { seat, cards } => {
+                write!(f, "Seat {} was dealt face up\n{}", seat + 1, cards)
+            }
+            TableEvent::Action { seat, action } => write!(f, "Seat {}: {}", seat + 1, action),
+            TableEvent::Drew { seat, count } if *count == 0 => {
+                write!(f, "Seat {} stands pat", seat + 1)
+            }
+            TableEvent::Drew { seat, count } => {
+                write!(f, "Seat {} draws {} cards", seat + 1, count)
+            }
+            TableEvent::ToAct { seat, pot } => {
+                write!(f, "Seat {} to act, the pot is {}", seat + 1, pot)
+            }
+            TableEvent::Countdown {
+                seat,
+                action_left,
+                time_bank_left,
+            } => write!(
+                f,
+                "Seat {} has {}s to act ({}s time bank)",
+                seat + 1,
+                action_left,
+                time_bank_left
+            ),
+            TableEvent::Board { cards } => write!(f, "Board\n{}", cards),
+            TableEvent::RunItOffer { seats, max_runs } => {
+                let seats: Vec<String> = seats.iter().map(|seat| (seat + 1).to_string()).collect();
+                write!(
+                    f,
+                    "Seats {} are all in, each choose to run the board up to {} times",
+                    seats.join(", "),
+                    max_runs
+                )
+            }
+            TableEvent::RunsChosen { seat, runs } if *runs == 1 => {
+                write!(f, "Seat {} wants to run it once", seat + 1)
+            }
+            TableEvent::RunsChosen { seat, runs } => {
+                write!(f, "Seat {} wants to run it {} times", seat + 1, runs)
+            }
+            TableEvent::Run { run, cards } => write!(f, "Run {}\n{}", run, cards),
+            TableEvent::Showdown { seat, cards } => {
+                write!(f, "Seat {} shows\n{}", seat + 1, cards)
+            }
+            TableEvent::Mucked { seat } => write!(f, "Seat {} mucks", seat + 1),
+            TableEvent::RabbitHunt { cards } => write!(f, "The rabbit hunt shows\n{}", cards),
+            TableEvent::PotWon { seat, amount } => {
+                write!(f, "Seat {} wins {} chips", seat + 1, amount)
+            }
+        }
+    }
+}
+
+/// Returns the place written out as in "1st" or "22nd".
+pub fn ordinal(place: usize) -> String {
+    let suffix = match (place % 10, place % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+
+    format!("{}{}", place, suffix)
+}
+
+/// Who an event is being sent to.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Recipient {
+    Seat(usize),
+    Spectator,
+}
+
+/// Decides what every recipient at a table gets to see. All events go through here so the
+/// filtering of face down cards can't be skipped.
+pub struct Broadcaster {
+    // when set, spectators see each hand this many hands late with every card shown
+    delay: Option<u32>,
+    // the events of the hands not yet shown to spectators, oldest first
+    delayed_hands: VecDeque<Vec<TableEvent>>,
+}
+
+impl Broadcaster {
+    /// Creates a broadcaster. A delay of `None` or 0 shows spectators each event live.
+    pub fn new(delay: Option<u32>) -> Self {
+        Broadcaster {
+            delay: delay.filter(|delay| *delay > 0),
+            delayed_hands: VecDeque::new(),
+        }
+    }
+
+    /// Publishes an event, returning what each of the given seats should receive and what the
+    /// spectators should receive. Spectators receive nothing live under a delay, and instead
+    /// get the whole hand from `delay` hands ago, unfiltered, once a new hand starts.
+    pub fn publish(
+        &mut self,
+        event: TableEvent,
+        seats: &[usize],
+    ) -> (Vec<(usize, TableEvent)>, Vec<TableEvent>) {
+        let to_seats = seats
+            .iter()
+            .map(|seat| (*seat, event.filter_for(Recipient::Seat(*seat))))
+            .collect();
+
+        let Some(delay) = self.delay else {
+            return (to_seats, vec![event.filter_for(Recipient::Spectator)]);
+        };
+
+        let mut to_spectators = vec![];
+        if let TableEvent::HandStarted { .. } = event {
+            self.delayed_hands.push_back(vec![]);
+            while self.delayed_hands.len() > delay as usize {
+                if let Some(hand) = self.delayed_hands.pop_front() {
+                    to_spectators.extend(hand);
+                }
+            }
+        }
+        match self.delayed_hands.back_mut() {
+            Some(hand) => hand.push(event),
+            None => self.delayed_hands.push_back(vec![event]),
+        }
+
+        (to_seats, to_spectators)
+    }
+}
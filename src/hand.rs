@@ -1,4 +1,5 @@
 use crate::deck::*;
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::fmt;
 
@@ -107,6 +108,164 @@ impl fmt::Display for Hand {
     }
 }
 
+/// The rule set used to rank high hands, picked per table.
+#[derive(Clone, Copy, PartialEq)]
+pub enum HandRules {
+    /// Regular rules played with the full 52 card deck.
+    Standard,
+    /// Short deck (6+) rules played with the 36 card deck. A flush beats a full house and
+    /// A-6-7-8-9 is a straight. Some tables also let a set beat a straight.
+    ShortDeck { trips_beat_straights: bool },
+}
+
+impl HandRules {
+    /// Creates a new deck with the cards these rules are played with.
+    pub fn new_deck(&self) -> Deck {
+        match *self {
+            HandRules::Standard => Deck::new(),
+            HandRules::ShortDeck { .. } => Deck::new_short_deck(),
+        }
+    }
+
+    /// Returns the position of the hand in the order hand types are ranked, 1 being the best.
+    fn hand_order(&self, hand: &Hand) -> u8 {
+        let short_deck = matches!(*self, HandRules::ShortDeck { .. });
+        let trips_beat_straights = matches!(
+            *self,
+            HandRules::ShortDeck {
+                trips_beat_straights: true
+            }
+        );
+        match hand {
+            Hand::RoyaleFlush => 1,
+            Hand::StraightFlush { .. } => 2,
+            Hand::FourOfAKind { .. } => 3,
+            Hand::FullHouse { .. } if short_deck => 5,
+            Hand::FullHouse { .. } => 4,
+            Hand::Flush { .. } if short_deck => 4,
+            Hand::Flush { .. } => 5,
+            Hand::Straight { .. } if trips_beat_straights => 7,
+            Hand::Straight { .. } => 6,
+            Hand::Set { .. } if trips_beat_straights => 6,
+            Hand::Set { .. } => 7,
+            Hand::TwoPair { .. } => 8,
+            Hand::Pair { .. } => 9,
+            Hand::HighCard { .. } => 10,
+        }
+    }
+
+    /// Returns the sorted values of the straight where the ace plays below the lowest card in
+    /// the deck.
+    fn low_straight(&self) -> [u8; 5] {
+        match *self {
+            HandRules::Standard => [2, 3, 4, 5, 14],
+            HandRules::ShortDeck { .. } => [6, 7, 8, 9, 14],
+        }
+    }
+}
+
+/// Scores the best 5 card hand among the cards, so that under the rules a higher score beats a
+/// lower one and equal scores tie. Unlike `get_best_hand` every kicker counts, and it works on
+/// bit masks of the values instead of trying each combination, which makes it quick enough for
+/// the millions of hands a simulation looks at.
+pub fn score_hand(cards: &[Card], rules: HandRules) -> u32 {
+    let mut counts = [0u8; 15];
+    let mut suits = [0u16; 4];
+    let mut values = 0u16;
+    for card in cards {
+        let value = card.value.value();
+        counts[value as usize] += 1;
+        suits[card.suit.clone() as usize] |= 1 << value;
+        values |= 1 << value;
+    }
+    let short_deck = matches!(rules, HandRules::ShortDeck { .. });
+    let trips_beat_straights = matches!(
+        rules,
+        HandRules::ShortDeck {
+            trips_beat_straights: true
+        }
+    );
+    // the strength of each kind of hand, higher being better
+    let (straight, set) = if trips_beat_straights { (3, 4) } else { (4, 3) };
+    let (flush, full_house) = if short_deck { (6, 5) } else { (5, 6) };
+    let low_straight = rules.low_straight();
+    let low_mask = low_straight
+        .iter()
+        .fold(0u16, |low, value| low | 1 << value);
+    let straight_high = |mask: u16| -> Option<u32> {
+        let high = (6..=14u32).rev().find(|high| {
+            let run = 0b11111 << (high - 4);
+            mask & run == run
+        });
+        match high {
+            Some(high) => Some(high),
+            None if mask & low_mask == low_mask => Some(low_straight[3] as u32),
+            None => None,
+        }
+    };
+    // packs the strength and up to 5 kickers, the values in `skip` left out, into the score
+    let score = |strength: u32, made: &[u32], mask: u16, skip: &[u32], take: usize| -> u32 {
+        let mut score = strength;
+        let mut kickers = 0;
+        for value in made {
+            score = score << 4 | value;
+            kickers += 1;
+        }
+        for value in (2..=14u32).rev() {
+            if kickers == made.len() + take {
+                break;
+            }
+            if mask & 1 << value != 0 && !skip.contains(&value) {
+                score = score << 4 | value;
+                kickers += 1;
+            }
+        }
+        score << (4 * (5 - kickers))
+    };
+
+    if let Some(suited) = suits.iter().find(|suited| suited.count_ones() >= 5) {
+        if let Some(high) = straight_high(*suited) {
+            return score(8, &[high], 0, &[], 0);
+        }
+    }
+    // the values held four, three and two times, highest first
+    let (mut quads, mut trips, mut pairs) = (vec![], vec![], vec![]);
+    for value in (2..=14u32).rev() {
+        match counts[value as usize] {
+            4 => quads.push(value),
+            3 => trips.push(value),
+            2 => pairs.push(value),
+            _ => {}
+        }
+    }
+
+    let mut best = score(0, &[], values, &[], 5);
+    if let Some(suited) = suits.iter().find(|suited| suited.count_ones() >= 5) {
+        best = best.max(score(flush, &[], *suited, &[], 5));
+    }
+    if let Some(quads) = quads.first() {
+        best = best.max(score(7, &[*quads], values, &[*quads], 1));
+    }
+    if let Some(three) = trips.first() {
+        let two = trips.get(1).into_iter().chain(pairs.first()).max();
+        if let Some(two) = two {
+            best = best.max(score(full_house, &[*three, *two], 0, &[], 0));
+        }
+        best = best.max(score(set, &[*three], values, &[*three], 2));
+    }
+    if let Some(high) = straight_high(values) {
+        best = best.max(score(straight, &[high], 0, &[], 0));
+    }
+    if let [first, second, ..] = pairs.as_slice() {
+        best = best.max(score(2, &[*first, *second], values, &[*first, *second], 1));
+    }
+    if let Some(pair) = pairs.first() {
+        best = best.max(score(1, &[*pair], values, &[*pair], 3));
+    }
+
+    best
+}
+
 // -----------------------
 // STABILITY NOTE
 // The functions below can definitely be improved. These functions were written quickly - the number
@@ -123,72 +282,149 @@ impl fmt::Display for Hand {
 /// Each of the input card collections must be seven cards, representing the 5 community cards and
 /// the 2 unique cards for that player.
 pub fn assign_hand_rankings(hands: Vec<CardCollection>) -> Vec<u8> {
-    // get the best possible hand for each player
-    let mut best_hands: Vec<Hand> = vec![];
-    for hand in hands {
-        best_hands.push(get_best_hand(hand));
-    }
+    assign_hand_rankings_with_rules(hands, HandRules::Standard)
+}
+
+/// Same as `assign_hand_rankings`, but ranks the hands under the given rule set.
+pub fn assign_hand_rankings_with_rules(hands: Vec<CardCollection>, rules: HandRules) -> Vec<u8> {
+    let scores = hands
+        .iter()
+        .map(|hand| score_hand(&hand.0, rules))
+        .collect();
 
-    // assign rankings
-    rank_hands(best_hands)
+    rank_scores(scores)
 }
 
 /// Given a reference to a card collection of 7 cards, representing the 5 community cards and the 2
 /// unique cards for a player, returns the best possible hand that can be constructed.
 pub fn get_best_hand(cards: CardCollection) -> Hand {
-    let mut hand_names: Vec<Hand> = Vec::new();
+    get_best_hand_with_rules(cards, HandRules::Standard)
+}
+
+/// Same as `get_best_hand`, but builds the hand under the given rule set.
+pub fn get_best_hand_with_rules(cards: CardCollection, rules: HandRules) -> Hand {
     // get the best hand for each combo
+    let mut hand_names: Vec<Hand> = Vec::new();
     for combo in generate_combinations(cards, CardCollection(vec![]), 0) {
-        let royal_flush = get_royale_flush(combo.clone());
-        if let Some(val) = royal_flush {
-            hand_names.push(val);
-            continue;
-        }
-        let straight_flush = get_straight_flush(combo.clone());
-        if let Some(val) = straight_flush {
-            hand_names.push(val);
-            continue;
-        }
-        let four_of_a_kind = get_four_of_a_kind(combo.clone());
-        if let Some(val) = four_of_a_kind {
-            hand_names.push(val);
-            continue;
-        }
-        let full_house = get_full_house(combo.clone());
-        if let Some(val) = full_house {
-            hand_names.push(val);
-            continue;
-        }
-        let flush = get_flush(combo.clone());
-        if let Some(val) = flush {
-            hand_names.push(val);
-            continue;
-        }
-        let straight = get_straight(combo.clone());
-        if let Some(val) = straight {
-            hand_names.push(val);
-            continue;
-        }
-        let set = get_set(combo.clone());
-        if let Some(val) = set {
-            hand_names.push(val);
-            continue;
-        }
-        let two_pair = get_two_pair(combo.clone());
-        if let Some(val) = two_pair {
-            hand_names.push(val);
-            continue;
-        }
-        let pair = get_pair(combo.clone());
-        if let Some(val) = pair {
-            hand_names.push(val);
-            continue;
+        hand_names.push(get_hand(combo, rules));
+    }
+
+    select_best_hand(hand_names, rules)
+}
+
+/// Given the 4 hole cards and 5 community cards of an Omaha player, returns the best possible
+/// hand that uses exactly 2 hole cards and 3 community cards.
+pub fn get_best_omaha_hand(hole: CardCollection, board: CardCollection) -> Hand {
+    let mut hand_names: Vec<Hand> = Vec::new();
+    for combo in generate_omaha_combinations(hole, board) {
+        hand_names.push(get_hand(combo, HandRules::Standard));
+    }
+
+    select_best_hand(hand_names, HandRules::Standard)
+}
+
+/// Given the hole cards of each Omaha player and the community cards, returns a vector of
+/// rankings in the same format as `assign_hand_rankings`.
+pub fn assign_omaha_hand_rankings(holes: Vec<CardCollection>, board: CardCollection) -> Vec<u8> {
+    let scores = holes
+        .into_iter()
+        .map(|hole| {
+            generate_omaha_combinations(hole, board.clone())
+                .iter()
+                .map(|combo| score_hand(&combo.0, HandRules::Standard))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    rank_scores(scores)
+}
+
+/// Given a card collection of exactly 5 cards, as held in draw games, returns the hand they
+/// make. Unlike `get_best_hand` there are no combinations to pick from.
+pub fn get_five_card_hand(cards: CardCollection) -> Hand {
+    if cards.0.len() != 5 {
+        panic!("expected 5 cards but saw {}", cards.0.len());
+    }
+
+    get_hand(cards, HandRules::Standard)
+}
+
+/// Given the 5 cards held by each player in a draw game, returns a vector of rankings in the
+/// same format as `assign_hand_rankings`.
+pub fn assign_five_card_hand_rankings(hands: Vec<CardCollection>) -> Vec<u8> {
+    for hand in &hands {
+        if hand.0.len() != 5 {
+            panic!("expected 5 cards but saw {}", hand.0.len());
         }
-        hand_names.push(get_high_card(combo.clone()));
     }
+    let scores = hands
+        .iter()
+        .map(|hand| score_hand(&hand.0, HandRules::Standard))
+        .collect();
+
+    rank_scores(scores)
+}
 
-    // rank all the hands and return the one with the highest ranking
-    let rankings = rank_hands(hand_names.clone());
+/// Returns the hand made by exactly 5 cards.
+fn get_hand(combo: CardCollection, rules: HandRules) -> Hand {
+    let royal_flush = get_royale_flush(combo.clone(), rules);
+    if let Some(val) = royal_flush {
+        return val;
+    }
+    let straight_flush = get_straight_flush(combo.clone(), rules);
+    if let Some(val) = straight_flush {
+        return val;
+    }
+    let four_of_a_kind = get_four_of_a_kind(combo.clone());
+    if let Some(val) = four_of_a_kind {
+        return val;
+    }
+    let full_house = get_full_house(combo.clone());
+    if let Some(val) = full_house {
+        return val;
+    }
+    let flush = get_flush(combo.clone());
+    if let Some(val) = flush {
+        return val;
+    }
+    let straight = get_straight(combo.clone(), rules);
+    if let Some(val) = straight {
+        return val;
+    }
+    let set = get_set(combo.clone());
+    if let Some(val) = set {
+        return val;
+    }
+    let two_pair = get_two_pair(combo.clone());
+    if let Some(val) = two_pair {
+        return val;
+    }
+    let pair = get_pair(combo.clone());
+    if let Some(val) = pair {
+        return val;
+    }
+    get_high_card(combo)
+}
+
+/// Turns the scores of some hands into rankings, 1 being the best. Equal scores share a ranking
+/// and the rankings after them carry on from the next number.
+fn rank_scores(scores: Vec<u32>) -> Vec<u8> {
+    let mut distinct = scores.clone();
+    distinct.sort_unstable_by(|a, b| b.cmp(a));
+    distinct.dedup();
+
+    scores
+        .iter()
+        .map(|score| distinct.iter().position(|other| other == score).unwrap() as u8 + 1)
+        .collect()
+}
+
+/// Ranks all the hands and returns the one with the highest ranking. Only the kind of hand is
+/// reliable here, as the tie breakers don't look at every kicker; rankings between players go
+/// through `rank_scores` instead.
+fn select_best_hand(hand_names: Vec<Hand>, rules: HandRules) -> Hand {
+    let rankings = rank_hands(hand_names.clone(), rules);
     for (index, ranking) in rankings.iter().enumerate() {
         if *ranking == 1 {
             return hand_names[index].clone();
@@ -200,9 +436,9 @@ pub fn get_best_hand(cards: CardCollection) -> Hand {
 
 /// Given a vector of hands, returns the rankings of each hand, where the value of some index
 /// in the returned vector corresponds to the ranking of that hand in the input vector. If two
-/// hands have the same strength according to the rules of texas holdem then they will have the
+/// hands have the same strength according to the given rules then they will have the
 /// same ranking in the returned vector.
-fn rank_hands(hands: Vec<Hand>) -> Vec<u8> {
+fn rank_hands(hands: Vec<Hand>, rules: HandRules) -> Vec<u8> {
     let order = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
     let mut rankings: Vec<u8> = vec![0; hands.len()];
     let mut rank_counter: u8 = 1;
@@ -210,70 +446,12 @@ fn rank_hands(hands: Vec<Hand>) -> Vec<u8> {
         let mut considered_hands = vec![];
         let mut considered_indices = vec![];
         for (index, hand) in hands.iter().enumerate() {
-            match hand {
-                Hand::RoyaleFlush => {
-                    if hand_type == 1 {
-                        considered_hands.push(hand.clone());
-                        considered_indices.push(index);
-                    }
-                }
-                Hand::StraightFlush { .. } => {
-                    if hand_type == 2 {
-                        considered_hands.push(hand.clone());
-                        considered_indices.push(index);
-                    }
-                }
-                Hand::FourOfAKind { .. } => {
-                    if hand_type == 3 {
-                        considered_hands.push(hand.clone());
-                        considered_indices.push(index);
-                    }
-                }
-                Hand::FullHouse { .. } => {
-                    if hand_type == 4 {
-                        considered_hands.push(hand.clone());
-                        considered_indices.push(index);
-                    }
-                }
-                Hand::Flush { .. } => {
-                    if hand_type == 5 {
-                        considered_hands.push(hand.clone());
-                        considered_indices.push(index);
-                    }
-                }
-                Hand::Straight { .. } => {
-                    if hand_type == 6 {
-                        considered_hands.push(hand.clone());
-                        considered_indices.push(index);
-                    }
-                }
-                Hand::Set { .. } => {
-                    if hand_type == 7 {
-                        considered_hands.push(hand.clone());
-                        considered_indices.push(index);
-                    }
-                }
-                Hand::TwoPair { .. } => {
-                    if hand_type == 8 {
-                        considered_hands.push(hand.clone());
-                        considered_indices.push(index);
-                    }
-                }
-                Hand::Pair { .. } => {
-                    if hand_type == 9 {
-                        considered_hands.push(hand.clone());
-                        considered_indices.push(index);
-                    }
-                }
-                Hand::HighCard { .. } => {
-                    if hand_type == 10 {
-                        considered_hands.push(hand.clone());
-                        considered_indices.push(index);
-                    }
-                }
-            };
+            if rules.hand_order(hand) == hand_type {
+                considered_hands.push(hand.clone());
+                considered_indices.push(index);
+            }
         }
-        if considered_hands.len() == 0 {
+        if considered_hands.is_empty() {
             continue;
         } else if considered_hands.len() == 1 {
             rankings[considered_indices[0]] = rank_counter;
@@ -309,9 +487,9 @@ fn tie_breaker(hands: Vec<Hand>) -> Vec<u8> {
             // Convert Vec<Hand> to Vec<StraightFlushHand>
             let straight_flush_hands: Vec<StraightFlushHand> = hands
                 .into_iter()
-                .filter_map(|hand| {
+                .map(|hand| {
                     if let Hand::StraightFlush(straight_flush_hand) = hand {
-                        Some(straight_flush_hand)
+                        straight_flush_hand
                     } else {
                         panic!("expected only straight flush hands, but saw something else");
                     }
@@ -324,9 +502,9 @@ fn tie_breaker(hands: Vec<Hand>) -> Vec<u8> {
             // Convert Vec<Hand> to Vec<FourOfAKindHand>
             let four_of_a_kind_hands: Vec<FourOfAKindHand> = hands
                 .into_iter()
-                .filter_map(|hand| {
+                .map(|hand| {
                     if let Hand::FourOfAKind(four_of_a_kind_hand) = hand {
-                        Some(four_of_a_kind_hand)
+                        four_of_a_kind_hand
                     } else {
                         panic!("expected only four of a kind hands, but saw something else");
                     }
@@ -339,9 +517,9 @@ fn tie_breaker(hands: Vec<Hand>) -> Vec<u8> {
             // Convert Vec<Hand> to Vec<FullHouseHand>
             let full_house_hands: Vec<FullHouseHand> = hands
                 .into_iter()
-                .filter_map(|hand| {
+                .map(|hand| {
                     if let Hand::FullHouse(full_house_hand) = hand {
-                        Some(full_house_hand)
+                        full_house_hand
                     } else {
                         panic!("expected only full house hands, but saw something else");
                     }
@@ -354,9 +532,9 @@ fn tie_breaker(hands: Vec<Hand>) -> Vec<u8> {
             // Convert Vec<Hand> to Vec<FlushHand>
             let flush_hands: Vec<FlushHand> = hands
                 .into_iter()
-                .filter_map(|hand| {
+                .map(|hand| {
                     if let Hand::Flush(flush_hand) = hand {
-                        Some(flush_hand)
+                        flush_hand
                     } else {
                         panic!("expected only flush hands, but saw something else");
                     }
@@ -369,9 +547,9 @@ fn tie_breaker(hands: Vec<Hand>) -> Vec<u8> {
             // Convert Vec<Hand> to Vec<StraightHand>
             let straight_hands: Vec<StraightHand> = hands
                 .into_iter()
-                .filter_map(|hand| {
+                .map(|hand| {
                     if let Hand::Straight(straight_hand) = hand {
-                        Some(straight_hand)
+                        straight_hand
                     } else {
                         panic!("expected only straight hands, but saw something else");
                     }
@@ -384,9 +562,9 @@ fn tie_breaker(hands: Vec<Hand>) -> Vec<u8> {
             // Convert Vec<Hand> to Vec<SetHand>
             let set_hands: Vec<SetHand> = hands
                 .into_iter()
-                .filter_map(|hand| {
+                .map(|hand| {
                     if let Hand::Set(set_hand) = hand {
-                        Some(set_hand)
+                        set_hand
                     } else {
                         panic!("expected only set hands, but saw something else");
                     }
@@ -399,9 +577,9 @@ fn tie_breaker(hands: Vec<Hand>) -> Vec<u8> {
             // Convert Vec<Hand> to Vec<TwoPairHand>
             let two_pair_hands: Vec<TwoPairHand> = hands
                 .into_iter()
-                .filter_map(|hand| {
+                .map(|hand| {
                     if let Hand::TwoPair(two_pair_hand) = hand {
-                        Some(two_pair_hand)
+                        two_pair_hand
                     } else {
                         panic!("expected only two pair hands, but saw something else");
                     }
@@ -414,9 +592,9 @@ fn tie_breaker(hands: Vec<Hand>) -> Vec<u8> {
             // Convert Vec<Hand> to Vec<PairHand>
             let pair_hands: Vec<PairHand> = hands
                 .into_iter()
-                .filter_map(|hand| {
+                .map(|hand| {
                     if let Hand::Pair(pair_hand) = hand {
-                        Some(pair_hand)
+                        pair_hand
                     } else {
                         panic!("expected only pair hands, but saw something else");
                     }
@@ -429,9 +607,9 @@ fn tie_breaker(hands: Vec<Hand>) -> Vec<u8> {
             // Convert Vec<Hand> to Vec<HighCardHand>
             let high_card_hands: Vec<HighCardHand> = hands
                 .into_iter()
-                .filter_map(|hand| {
+                .map(|hand| {
                     if let Hand::HighCard(high_card_hand) = hand {
-                        Some(high_card_hand)
+                        high_card_hand
                     } else {
                         panic!("expected only high card hands, but saw something else");
                     }
@@ -444,7 +622,7 @@ fn tie_breaker(hands: Vec<Hand>) -> Vec<u8> {
 }
 
 /// Returns the 21 combinations of 7 choose 5 cards.
-fn generate_combinations(
+pub(crate) fn generate_combinations(
     cards: CardCollection,
     cur_combo: CardCollection,
     index: usize,
@@ -473,6 +651,44 @@ fn generate_combinations(
     [added, skipped].concat()
 }
 
+/// Returns the 60 combinations of 2 of the 4 hole cards and 3 of the 5 community cards.
+pub(crate) fn generate_omaha_combinations(
+    hole: CardCollection,
+    board: CardCollection,
+) -> Vec<CardCollection> {
+    let mut combos = vec![];
+    for first in 0..hole.0.len() {
+        for second in first + 1..hole.0.len() {
+            for board_combo in generate_board_combinations(board.clone()) {
+                combos.push(CardCollection::concat(
+                    CardCollection(vec![hole.0[first].clone(), hole.0[second].clone()]),
+                    board_combo,
+                ));
+            }
+        }
+    }
+
+    combos
+}
+
+/// Returns the combinations of 3 of the community cards.
+fn generate_board_combinations(board: CardCollection) -> Vec<CardCollection> {
+    let mut combos = vec![];
+    for first in 0..board.0.len() {
+        for second in first + 1..board.0.len() {
+            for third in second + 1..board.0.len() {
+                combos.push(CardCollection(vec![
+                    board.0[first].clone(),
+                    board.0[second].clone(),
+                    board.0[third].clone(),
+                ]));
+            }
+        }
+    }
+
+    combos
+}
+
 fn is_flush(hand: CardCollection) -> bool {
     for i in 0..hand.0.len() {
         if hand.0[0].suit != hand.0[i].suit {
@@ -480,43 +696,27 @@ fn is_flush(hand: CardCollection) -> bool {
         }
     }
 
-    return true;
+    true
 }
 
-fn get_royale_flush(hand: CardCollection) -> Option<Hand> {
+fn get_royale_flush(hand: CardCollection, rules: HandRules) -> Option<Hand> {
     if !is_flush(hand.clone()) {
         return None;
     }
-    let mut sum = 0;
-    for card in hand.0 {
-        sum += card.value.value();
-    }
-    if sum == 50 {
+    if get_straight_high_card(hand, rules)? == CardValue::Ace.value() {
         return Some(Hand::RoyaleFlush);
     }
 
     None
 }
 
-fn get_straight_flush(hand: CardCollection) -> Option<Hand> {
+fn get_straight_flush(hand: CardCollection, rules: HandRules) -> Option<Hand> {
     if !is_flush(hand.clone()) {
         return None;
     }
 
-    let mut values = vec![];
-    for card in hand.0 {
-        values.push(card.value.value())
-    }
-    values.sort();
-
-    for index in 1..values.len() {
-        if values[index] - 1 != values[index - 1] {
-            return None;
-        }
-    }
-
     Some(Hand::StraightFlush(StraightFlushHand {
-        high_card: CardValue::new(values[values.len() - 1]),
+        high_card: CardValue::new(get_straight_high_card(hand, rules)?),
     }))
 }
 
@@ -529,7 +729,7 @@ fn get_four_of_a_kind(hand: CardCollection) -> Option<Hand> {
 
     for (four_val, four_count) in &value_map {
         if *four_count == 4 {
-            for (_one_val, one_count) in &value_map {
+            for one_count in value_map.values() {
                 if *one_count == 1 {
                     return Some(Hand::FourOfAKind(FourOfAKindHand {
                         value: CardValue::new(*four_val),
@@ -551,7 +751,7 @@ fn get_full_house(hand: CardCollection) -> Option<Hand> {
 
     for (three_val, three_count) in &value_map {
         if *three_count == 3 {
-            for (_two_val, two_count) in &value_map {
+            for two_count in value_map.values() {
                 if *two_count == 2 {
                     return Some(Hand::FullHouse(FullHouseHand {
                         three_value: CardValue::new(*three_val),
@@ -579,21 +779,30 @@ fn get_flush(hand: CardCollection) -> Option<Hand> {
     Some(Hand::Flush(FlushHand { high_card: max }))
 }
 
-fn get_straight(hand: CardCollection) -> Option<Hand> {
+fn get_straight(hand: CardCollection, rules: HandRules) -> Option<Hand> {
+    Some(Hand::Straight(StraightHand {
+        high_card: CardValue::new(get_straight_high_card(hand, rules)?),
+    }))
+}
+
+/// Returns the value of the highest card in the straight, if the cards make one. The ace can
+/// play either high or below the lowest card in the deck, in which case it isn't the high card.
+fn get_straight_high_card(hand: CardCollection, rules: HandRules) -> Option<u8> {
     let mut values = vec![];
     for card in hand.0 {
         values.push(card.value.value());
     }
     values.sort();
+    if values == rules.low_straight() {
+        return Some(values[values.len() - 2]);
+    }
     for index in 1..values.len() {
         if values[index] - 1 != values[index - 1] {
             return None;
         }
     }
 
-    Some(Hand::Straight(StraightHand {
-        high_card: CardValue::new(values[values.len() - 1]),
-    }))
+    Some(values[values.len() - 1])
 }
 
 fn get_set(hand: CardCollection) -> Option<Hand> {
@@ -603,18 +812,11 @@ fn get_set(hand: CardCollection) -> Option<Hand> {
         *count += 1
     }
 
-    let mut other_values = vec![];
     for (val, count) in &value_map {
         if *count == 3 {
-            for card in hand.0.clone() {
-                if card.value.value() != *val {
-                    other_values.push(card.value.value());
-                }
-                other_values.sort();
-                return Some(Hand::Set(SetHand {
-                    value: CardValue::new(*val),
-                }));
-            }
+            return Some(Hand::Set(SetHand {
+                value: CardValue::new(*val),
+            }));
         }
     }
 
@@ -706,7 +908,7 @@ fn straight_flush_tie_breaker(hands: Vec<StraightFlushHand>) -> Vec<u8> {
     let mut ret: Vec<u8> = vec![100; hands.len()];
     // sort hands by high card in descending order
     let mut hands_with_indices: Vec<_> = hands.iter().enumerate().collect();
-    hands_with_indices.sort_by(|a, b| b.1.high_card.value().cmp(&a.1.high_card.value()));
+    hands_with_indices.sort_by_key(|h| Reverse(h.1.high_card.value()));
 
     let mut rank = 1;
     for i in 0..hands_with_indices.len() - 1 {
@@ -727,11 +929,10 @@ fn four_of_a_kind_tie_breaker(hands: Vec<FourOfAKindHand>) -> Vec<u8> {
     let mut ret: Vec<u8> = vec![100; hands.len()];
     // sort hands by value of four of a kind in descending order
     let mut hands_with_indices: Vec<_> = hands.iter().enumerate().collect();
-    hands_with_indices.sort_by(|a, b| b.1.value.value().cmp(&a.1.value.value()));
+    hands_with_indices.sort_by_key(|h| Reverse(h.1.value.value()));
 
     let mut rank = 1;
-    for i in 0..hands_with_indices.len() - 1 {
-        let cur = hands_with_indices[i];
+    for cur in hands_with_indices.iter().take(hands_with_indices.len() - 1) {
         ret[cur.0] = rank;
         // it's impossible for two four of a kinds to have the same strength, so we don't
         // need to do the extra check here to see if we have to increase rank
@@ -746,11 +947,10 @@ fn full_house_tie_breaker(hands: Vec<FullHouseHand>) -> Vec<u8> {
     let mut ret: Vec<u8> = vec![100; hands.len()];
     // sort hands by value of set of full house in descending order
     let mut hands_with_indices: Vec<_> = hands.iter().enumerate().collect();
-    hands_with_indices.sort_by(|a, b| b.1.three_value.value().cmp(&a.1.three_value.value()));
+    hands_with_indices.sort_by_key(|h| Reverse(h.1.three_value.value()));
 
     let mut rank = 1;
-    for i in 0..hands_with_indices.len() - 1 {
-        let cur = hands_with_indices[i];
+    for cur in hands_with_indices.iter().take(hands_with_indices.len() - 1) {
         ret[cur.0] = rank;
         // it's impossible for two full houses to have the same strength, so we don't
         // need to do the extra check here to see if we have to increase rank
@@ -765,7 +965,7 @@ fn flush_tie_breaker(hands: Vec<FlushHand>) -> Vec<u8> {
     let mut ret: Vec<u8> = vec![100; hands.len()];
     // sort hands by high card in descending order
     let mut hands_with_indices: Vec<_> = hands.iter().enumerate().collect();
-    hands_with_indices.sort_by(|a, b| b.1.high_card.value().cmp(&a.1.high_card.value()));
+    hands_with_indices.sort_by_key(|h| Reverse(h.1.high_card.value()));
 
     let mut rank = 1;
     for i in 0..hands_with_indices.len() - 1 {
@@ -786,7 +986,7 @@ fn straight_tie_breaker(hands: Vec<StraightHand>) -> Vec<u8> {
     let mut ret: Vec<u8> = vec![100; hands.len()];
     // sort hands by high card in descending order
     let mut hands_with_indices: Vec<_> = hands.iter().enumerate().collect();
-    hands_with_indices.sort_by(|a, b| b.1.high_card.value().cmp(&a.1.high_card.value()));
+    hands_with_indices.sort_by_key(|h| Reverse(h.1.high_card.value()));
 
     let mut rank = 1;
     for i in 0..hands_with_indices.len() - 1 {
@@ -807,11 +1007,10 @@ fn set_tie_breaker(hands: Vec<SetHand>) -> Vec<u8> {
     let mut ret: Vec<u8> = vec![100; hands.len()];
     // sort hands by value of set of full house in descending order
     let mut hands_with_indices: Vec<_> = hands.iter().enumerate().collect();
-    hands_with_indices.sort_by(|a, b| b.1.value.value().cmp(&a.1.value.value()));
+    hands_with_indices.sort_by_key(|h| Reverse(h.1.value.value()));
 
     let mut rank = 1;
-    for i in 0..hands_with_indices.len() - 1 {
-        let cur = hands_with_indices[i];
+    for cur in hands_with_indices.iter().take(hands_with_indices.len() - 1) {
         ret[cur.0] = rank;
         // it's impossible for two sets to have the same strength, so we don't
         // need to do the extra check here to see if we have to increase rank
@@ -949,3 +1148,58 @@ fn high_card_tie_breaker(hands: Vec<HighCardHand>) -> Vec<u8> {
 
     ret
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(text: &str) -> CardCollection {
+        CardCollection(
+            text.split(' ')
+                .map(|code| Card::parse(code).unwrap())
+                .collect(),
+        )
+    }
+
+    fn rank(board: &str, holes: &[&str]) -> Vec<u8> {
+        let hands = holes
+            .iter()
+            .map(|hole| cards(&format!("{} {}", board, hole)))
+            .collect();
+        assign_hand_rankings(hands)
+    }
+
+    #[test]
+    fn kickers_decide_between_sets_on_the_board() {
+        let board = "7h 7d 7s Kc 2d";
+        assert_eq!(rank(board, &["Ah 3c", "Qd 4s"]), vec![1, 2]);
+        assert_eq!(rank(board, &["Qd 4s", "Ah 3c"]), vec![2, 1]);
+    }
+
+    #[test]
+    fn full_house_on_the_board_splits() {
+        assert_eq!(rank("7h 7d 7s Kc Kd", &["Ah 3c", "Qd 4s"]), vec![1, 1]);
+    }
+
+    #[test]
+    fn flushes_compare_every_card() {
+        assert_eq!(rank("Ah 5h 9h Jh 3c", &["Kh 4d", "2h Ks"]), vec![1, 2]);
+        assert_eq!(rank("Ah 5h 9h Jh 3c", &["2h Ks", "Kh 4d"]), vec![2, 1]);
+    }
+
+    #[test]
+    fn rankings_carry_on_after_ties() {
+        let board = "2c 5d 9h Js Kd";
+        assert_eq!(rank(board, &["Ah 3c", "As 3d", "Qd 4s"]), vec![1, 1, 2]);
+    }
+
+    #[test]
+    fn omaha_hands_use_two_hole_cards() {
+        // the four hearts in the second hand only make a flush with the board's three
+        let rankings = assign_omaha_hand_rankings(
+            vec![cards("Ac Ad 8s 9c"), cards("Kh Qh 2h 3h")],
+            cards("Ah 7h 4h Js 5c"),
+        );
+        assert_eq!(rankings, vec![2, 1]);
+    }
+}
@@ -0,0 +1,1157 @@
+use crate::deck::{Card, CardCollection};
+use crate::hand::{assign_omaha_hand_rankings, score_hand, HandRules};
+use crate::history::{days_from_civil, eastern_offset};
+use crate::holdem::Street;
+use crate::json::Json;
+use std::fmt;
+
+/// A player dealt into a hand history, or sitting out of it.
+#[derive(Clone)]
+pub struct HistoryPlayer {
+    pub seat: usize,
+    pub name: String,
+    /// The chips the player started the hand with.
+    pub chips: u32,
+    /// The player's face down cards, if they were shown or the history was written for them.
+    pub cards: Option<CardCollection>,
+    pub sitting_out: bool,
+}
+
+/// Something a player did in a hand history. Amounts are chips, or cents when the hand was
+/// played for money.
+#[derive(Clone, Copy, PartialEq)]
+pub enum HistoryAction {
+    Ante(u32),
+    SmallBlind(u32),
+    BigBlind(u32),
+    /// A missed small blind posted dead along with the big blind, as the total of both.
+    SmallAndBigBlinds(u32),
+    Fold,
+    Check,
+    Call(u32),
+    Bet(u32),
+    /// A raise by the first amount to a total bet of the second.
+    Raise(u32, u32),
+    /// The part of the player's bet nobody called, given back to them.
+    Returned(u32),
+    Show,
+    Muck,
+    Collected(u32),
+}
+
+impl HistoryAction {
+    /// Parses the action from its name and amounts as written in our JSON format.
+    fn parse(name: &str, amount: Option<u32>, to: Option<u32>) -> Option<Self> {
+        let action = match (name, amount, to) {
+            ("ante", Some(amount), _) => HistoryAction::Ante(amount),
+            ("small blind", Some(amount), _) => HistoryAction::SmallBlind(amount),
+            ("big blind", Some(amount), _) => HistoryAction::BigBlind(amount),
+            ("small and big blinds", Some(amount), _) => HistoryAction::SmallAndBigBlinds(amount),
+            ("fold", _, _) => HistoryAction::Fold,
+            ("check", _, _) => HistoryAction::Check,
+            ("call", Some(amount), _) => HistoryAction::Call(amount),
+            ("bet", Some(amount), _) => HistoryAction::Bet(amount),
+            ("raise", Some(amount), Some(to)) => HistoryAction::Raise(amount, to),
+            ("returned", Some(amount), _) => HistoryAction::Returned(amount),
+            ("show", _, _) => HistoryAction::Show,
+            ("muck", _, _) => HistoryAction::Muck,
+            ("collected", Some(amount), _) => HistoryAction::Collected(amount),
+            _ => return None,
+        };
+
+        Some(action)
+    }
+
+    /// Returns the action's name and amounts as written in our JSON format.
+    fn parts(&self) -> (&'static str, Option<u32>, Option<u32>) {
+        match *self {
+            HistoryAction::Ante(amount) => ("ante", Some(amount), None),
+            HistoryAction::SmallBlind(amount) => ("small blind", Some(amount), None),
+            HistoryAction::BigBlind(amount) => ("big blind", Some(amount), None),
+            HistoryAction::SmallAndBigBlinds(amount) => {
+                ("small and big blinds", Some(amount), None)
+            }
+            HistoryAction::Fold => ("fold", None, None),
+            HistoryAction::Check => ("check", None, None),
+            HistoryAction::Call(amount) => ("call", Some(amount), None),
+            HistoryAction::Bet(amount) => ("bet", Some(amount), None),
+            HistoryAction::Raise(amount, to) => ("raise", Some(amount), Some(to)),
+            HistoryAction::Returned(amount) => ("returned", Some(amount), None),
+            HistoryAction::Show => ("show", None, None),
+            HistoryAction::Muck => ("muck", None, None),
+            HistoryAction::Collected(amount) => ("collected", Some(amount), None),
+        }
+    }
+}
+
+/// An action in a hand history, in the order it happened.
+#[derive(Clone)]
+pub struct HistoryEntry {
+    pub street: Street,
+    /// The player who acted, as their place in the hand's players.
+    pub player: usize,
+    pub action: HistoryAction,
+    pub all_in: bool,
+}
+
+/// A hand read from a hand history, with its players, actions, boards and results.
+#[derive(Clone)]
+pub struct HandHistory {
+    pub id: u64,
+    /// The game as the history names it, as in "Hold'em No Limit".
+    pub game: String,
+    pub small_blind: u32,
+    pub big_blind: u32,
+    /// When the hand started, in seconds since the Unix epoch, if the time could be read.
+    pub started: Option<u64>,
+    pub table: String,
+    pub max_seats: usize,
+    pub button: usize,
+    pub players: Vec<HistoryPlayer>,
+    pub actions: Vec<HistoryEntry>,
+    /// The board, or each board when it was run more than once.
+    pub boards: Vec<CardCollection>,
+    pub total_pot: u32,
+    pub rake: u32,
+}
+
+impl HandHistory {
+    /// Parses a single hand written in the PokerStars hand history format. Lines the parser
+    /// doesn't know, like chat, are skipped.
+    pub fn parse_pokerstars(text: &str) -> Result<Self, String> {
+        Self::parse_pokerstars_lines(
+            text.lines()
+                .enumerate()
+                .map(|(number, line)| (number + 1, line)),
+        )
+    }
+
+    /// Parses a single hand in the PokerStars format from its lines, each along with its line
+    /// number.
+    fn parse_pokerstars_lines<'a>(
+        lines: impl Iterator<Item = (usize, &'a str)>,
+    ) -> Result<Self, String> {
+        let mut lines = lines
+            .map(|(number, line)| (number, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+        let Some((number, header)) = lines.next() else {
+            return Err("the hand history is empty".to_string());
+        };
+        let mut parser =
+            PokerStarsParser::new(header).map_err(|err| format!("line {}: {}", number, err))?;
+        for (number, line) in lines {
+            parser
+                .line(line)
+                .map_err(|err| format!("line {}: {}", number, err))?;
+        }
+
+        Ok(parser.history)
+    }
+
+    /// Parses the hand from our JSON format.
+    pub fn from_json(json: &Json) -> Result<Self, String> {
+        let mut players = vec![];
+        for player in json.array_field("players")? {
+            let cards = match player.get("cards") {
+                Some(Json::String(cards)) => Some(parse_cards(cards)?),
+                _ => None,
+            };
+            players.push(HistoryPlayer {
+                seat: seat_index(player.u64_field("seat")?)?,
+                name: player.str_field("name")?.to_string(),
+                chips: player.u32_field("chips")?,
+                cards,
+                sitting_out: player.get("sitting_out").and_then(Json::as_bool) == Some(true),
+            });
+        }
+
+        let mut actions = vec![];
+        for (number, action) in json.array_field("actions")?.iter().enumerate() {
+            let entry = entry_from_json(action, &players)
+                .map_err(|err| format!("action {}: {}", number + 1, err))?;
+            actions.push(entry);
+        }
+
+        let boards = json
+            .array_field("boards")?
+            .iter()
+            .map(|board| parse_cards(board.as_str().ok_or("the boards must be text")?))
+            .collect::<Result<_, _>>()?;
+
+        Ok(HandHistory {
+            id: json.u64_field("id")?,
+            game: json.str_field("game")?.to_string(),
+            small_blind: json.u32_field("small_blind")?,
+            big_blind: json.u32_field("big_blind")?,
+            started: json.get("started").and_then(Json::as_u64),
+            table: json.str_field("table")?.to_string(),
+            max_seats: json.u64_field("max_seats")? as usize,
+            button: seat_index(json.u64_field("button")?)?,
+            players,
+            actions,
+            boards,
+            total_pot: json.u32_field("total_pot")?,
+            rake: json.u32_field("rake")?,
+        })
+    }
+
+    /// Returns the hand in our JSON format. Seats count from 1 as they do at the table.
+    pub fn to_json(&self) -> Json {
+        let players = self
+            .players
+            .iter()
+            .map(|player| {
+                Json::object(vec![
+                    ("seat", (player.seat + 1).into()),
+                    ("name", player.name.as_str().into()),
+                    ("chips", player.chips.into()),
+                    ("cards", player.cards.as_ref().map(codes).into()),
+                    ("sitting_out", player.sitting_out.into()),
+                ])
+            })
+            .collect();
+        let actions = self
+            .actions
+            .iter()
+            .map(|entry| {
+                let (action, amount, to) = entry.action.parts();
+                let mut fields = vec![
+                    ("street", entry.street.to_string().into()),
+                    ("player", self.players[entry.player].name.as_str().into()),
+                    ("action", action.into()),
+                ];
+                if let Some(amount) = amount {
+                    fields.push(("amount", amount.into()));
+                }
+                if let Some(to) = to {
+                    fields.push(("to", to.into()));
+                }
+                if entry.all_in {
+                    fields.push(("all_in", true.into()));
+                }
+                Json::object(fields)
+            })
+            .collect();
+
+        Json::object(vec![
+            ("id", self.id.into()),
+            ("game", self.game.as_str().into()),
+            ("small_blind", self.small_blind.into()),
+            ("big_blind", self.big_blind.into()),
+            ("started", self.started.into()),
+            ("table", self.table.as_str().into()),
+            ("max_seats", self.max_seats.into()),
+            ("button", (self.button + 1).into()),
+            ("players", Json::Array(players)),
+            ("actions", Json::Array(actions)),
+            (
+                "boards",
+                self.boards.iter().map(codes).collect::<Vec<_>>().into(),
+            ),
+            ("total_pot", self.total_pot.into()),
+            ("rake", self.rake.into()),
+        ])
+    }
+
+    /// Returns the rules the hands are ranked under, going by the name of the game.
+    fn rules(&self) -> HandRules {
+        if self.game.starts_with("6+") {
+            HandRules::ShortDeck {
+                trips_beat_straights: false,
+            }
+        } else {
+            HandRules::Standard
+        }
+    }
+
+    fn is_omaha(&self) -> bool {
+        self.game.starts_with("Omaha")
+    }
+
+    /// Returns the chips each player won.
+    pub fn winnings(&self) -> Vec<u32> {
+        let mut winnings = vec![0; self.players.len()];
+        for entry in &self.actions {
+            if let HistoryAction::Collected(amount) = entry.action {
+                winnings[entry.player] += amount;
+            }
+        }
+
+        winnings
+    }
+
+    /// Checks the hand for things that can't have happened, like a card dealt twice, a player
+    /// betting chips they don't have, a pot that doesn't add up or the pot going to a player
+    /// whose shown hand was beaten. Returns a description of each problem found.
+    pub fn problems(&self) -> Vec<String> {
+        let (contributed, chip_problems) = self.replay_chips();
+        let mut problems = self.card_problems();
+        problems.extend(chip_problems);
+        problems.extend(self.showdown_problems(&contributed));
+
+        problems
+    }
+
+    fn card_problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+        let mut deck = self.rules().new_deck();
+        let in_deck = deck
+            .pop_cards(deck.cards_left() as u8)
+            .unwrap_or(CardCollection(vec![]));
+        let mut seen: Vec<Card> = vec![];
+        let mut check = |card: &Card, problems: &mut Vec<String>| {
+            if !in_deck.0.contains(card) {
+                problems.push(format!("the {} isn't in the deck", card.code()));
+            } else if seen.contains(card) {
+                problems.push(format!("the {} was dealt twice", card.code()));
+            } else {
+                seen.push(card.clone());
+            }
+        };
+
+        let hole_cards = if self.is_omaha() { 4 } else { 2 };
+        for player in &self.players {
+            let Some(cards) = &player.cards else {
+                continue;
+            };
+            if cards.0.len() != hole_cards {
+                problems.push(format!(
+                    "{} has {} hole cards instead of {}",
+                    player.name,
+                    cards.0.len(),
+                    hole_cards
+                ));
+            }
+            for card in &cards.0 {
+                check(card, &mut problems);
+            }
+        }
+        let first = self.boards.first().map_or(&[][..], |board| &board.0[..]);
+        for (run, board) in self.boards.iter().enumerate() {
+            if board.0.len() > 5 {
+                problems.push(format!("a board has {} cards", board.0.len()));
+            }
+            // the runs share the cards dealt before the board was run more than once
+            for (place, card) in board.0.iter().enumerate() {
+                if run == 0 || first.get(place) != Some(card) {
+                    check(card, &mut problems);
+                }
+            }
+        }
+
+        problems
+    }
+
+    /// Follows the chips through the hand, returning what each player put in the pot along
+    /// with the problems found on the way.
+    fn replay_chips(&self) -> (Vec<u32>, Vec<String>) {
+        let mut problems = vec![];
+        let players = self.players.len();
+        let mut stacks: Vec<u32> = self.players.iter().map(|player| player.chips).collect();
+        let mut bets: Vec<u32> = vec![0; players];
+        let mut contributed: Vec<u32> = vec![0; players];
+        let mut street = Street::Preflop;
+        for entry in &self.actions {
+            if entry.street != street {
+                street = entry.street;
+                bets = vec![0; players];
+            }
+            let player = entry.player;
+            let name = &self.players[player].name;
+            let current = bets.iter().copied().max().unwrap_or(0);
+            // the chips the action puts in, and how many of them count towards the player's bet
+            let (put, bet) = match entry.action {
+                HistoryAction::Ante(amount) => (amount, 0),
+                HistoryAction::SmallBlind(amount) | HistoryAction::BigBlind(amount) => {
+                    (amount, amount)
+                }
+                HistoryAction::SmallAndBigBlinds(amount) => (amount, amount.min(self.big_blind)),
+                HistoryAction::Call(amount) => {
+                    let owed = current.saturating_sub(bets[player]).min(stacks[player]);
+                    if amount != owed {
+                        problems.push(format!(
+                            "{} calls {} but had {} to call",
+                            name, amount, owed
+                        ));
+                    }
+                    (amount, amount)
+                }
+                HistoryAction::Bet(amount) => {
+                    if current > 0 {
+                        problems.push(format!("{} bets into a bet of {}", name, current));
+                    }
+                    let put = amount.saturating_sub(bets[player]);
+                    (put, put)
+                }
+                HistoryAction::Raise(by, to) => {
+                    if to.checked_sub(current) != Some(by) {
+                        problems.push(format!(
+                            "{} raises by {} to {} but the bet was {}",
+                            name, by, to, current
+                        ));
+                    }
+                    let put = to.saturating_sub(bets[player]);
+                    (put, put)
+                }
+                HistoryAction::Check => {
+                    if bets[player] < current {
+                        problems.push(format!("{} checks facing a bet of {}", name, current));
+                    }
+                    (0, 0)
+                }
+                HistoryAction::Returned(amount) => {
+                    let others = (0..players)
+                        .filter(|other| *other != player)
+                        .map(|other| bets[other])
+                        .max()
+                        .unwrap_or(0);
+                    let uncalled = bets[player].saturating_sub(others);
+                    if amount != uncalled {
+                        problems.push(format!(
+                            "{} was given back {} but {} of their bet wasn't called",
+                            name, amount, uncalled
+                        ));
+                    }
+                    let amount = amount.min(bets[player]);
+                    bets[player] -= amount;
+                    contributed[player] -= amount;
+                    stacks[player] += amount;
+                    continue;
+                }
+                HistoryAction::Fold
+                | HistoryAction::Show
+                | HistoryAction::Muck
+                | HistoryAction::Collected(_) => continue,
+            };
+            if put > stacks[player] {
+                problems.push(format!(
+                    "{} puts in {} but only has {} left",
+                    name, put, stacks[player]
+                ));
+            }
+            let put = put.min(stacks[player]);
+            stacks[player] -= put;
+            contributed[player] += put;
+            bets[player] += bet.min(put);
+            if entry.all_in != (stacks[player] == 0) {
+                problems.push(match entry.all_in {
+                    true => format!("{} is said to be all in with {} left", name, stacks[player]),
+                    false => format!("{} is all in but isn't said to be", name),
+                });
+            }
+        }
+
+        let pot: u32 = contributed.iter().sum();
+        if pot != self.total_pot {
+            problems.push(format!(
+                "the total pot is {} but the bets add up to {}",
+                self.total_pot, pot
+            ));
+        }
+        let collected: u32 = self.winnings().iter().sum();
+        if collected + self.rake != self.total_pot {
+            problems.push(format!(
+                "the total pot is {} but {} was collected and {} raked",
+                self.total_pot, collected, self.rake
+            ));
+        }
+
+        (contributed, problems)
+    }
+
+    /// Checks that no player who showed a hand won with it while another shown hand beat it
+    /// and was in every pot it was. Hands split high and low and boards run more than once
+    /// aren't checked.
+    fn showdown_problems(&self, contributed: &[u32]) -> Vec<String> {
+        let board = match self.boards.as_slice() {
+            [board] if board.0.len() == 5 => board,
+            _ => return vec![],
+        };
+        if self.game.contains("Hi/Lo") {
+            return vec![];
+        }
+        let hole_cards = if self.is_omaha() { 4 } else { 2 };
+        let shown: Vec<usize> = (0..self.players.len())
+            .filter(|player| {
+                self.actions
+                    .iter()
+                    .any(|entry| entry.player == *player && entry.action == HistoryAction::Show)
+            })
+            .filter(|player| {
+                self.players[*player]
+                    .cards
+                    .as_ref()
+                    .is_some_and(|cards| cards.0.len() == hole_cards)
+            })
+            .collect();
+        if shown.len() < 2 {
+            return vec![];
+        }
+        let holes: Vec<CardCollection> = shown
+            .iter()
+            .filter_map(|player| self.players[*player].cards.clone())
+            .collect();
+        // how strong each shown hand is, higher beating lower
+        let strengths: Vec<u32> = if self.is_omaha() {
+            assign_omaha_hand_rankings(holes, board.clone())
+                .into_iter()
+                .map(|rank| u32::from(u8::MAX - rank))
+                .collect()
+        } else {
+            holes
+                .into_iter()
+                .map(|hole| {
+                    score_hand(&CardCollection::concat(hole, board.clone()).0, self.rules())
+                })
+                .collect()
+        };
+
+        let winnings = self.winnings();
+        let mut problems = vec![];
+        for (place, winner) in shown.iter().enumerate() {
+            if winnings[*winner] == 0 {
+                continue;
+            }
+            let beaten_by = shown.iter().enumerate().find(|(other_place, other)| {
+                strengths[*other_place] > strengths[place]
+                    && contributed[**other] >= contributed[*winner]
+            });
+            if let Some((_, other)) = beaten_by {
+                problems.push(format!(
+                    "{} won {} but {}'s hand beats theirs",
+                    self.players[*winner].name, winnings[*winner], self.players[*other].name
+                ));
+            }
+        }
+
+        problems
+    }
+}
+
+impl fmt::Display for HandHistory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Hand #{}: {} {}/{} at {}, {} players, pot {}",
+            self.id,
+            self.game,
+            self.small_blind,
+            self.big_blind,
+            self.table,
+            self.players.len(),
+            self.total_pot
+        )?;
+        for board in &self.boards {
+            write!(f, "\nBoard {}", codes(board))?;
+        }
+        for (player, won) in self.players.iter().zip(self.winnings()) {
+            if won > 0 {
+                write!(f, "\n{} won {}", player.name, won)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses every hand in the text, which is either hand histories in the PokerStars format one
+/// after the other, or hands in our JSON format one per line or in a list.
+pub fn parse_hand_histories(text: &str) -> Result<Vec<HandHistory>, String> {
+    let trimmed = text.trim_start();
+    if trimmed.starts_with('[') {
+        let json = Json::parse(text)?;
+        let hands = json.as_array().ok_or("expected a list of hands")?;
+        return hands
+            .iter()
+            .enumerate()
+            .map(|(number, hand)| {
+                HandHistory::from_json(hand).map_err(|err| format!("hand {}: {}", number + 1, err))
+            })
+            .collect();
+    }
+    if trimmed.starts_with('{') {
+        return text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(number, line)| {
+                Json::parse(line)
+                    .and_then(|json| HandHistory::from_json(&json))
+                    .map_err(|err| format!("line {}: {}", number + 1, err))
+            })
+            .collect();
+    }
+
+    // each hand starts with its header, so the file is split up before every header
+    let mut hands: Vec<Vec<(usize, &str)>> = vec![];
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim_start_matches('\u{feff}');
+        if line.starts_with("PokerStars ") || hands.is_empty() {
+            hands.push(vec![]);
+        }
+        if let Some(hand) = hands.last_mut() {
+            hand.push((number + 1, line));
+        }
+    }
+    hands
+        .into_iter()
+        .filter(|hand| hand.iter().any(|(_, line)| !line.trim().is_empty()))
+        .map(|hand| HandHistory::parse_pokerstars_lines(hand.into_iter()))
+        .collect()
+}
+
+/// Where the parser is in a PokerStars hand history.
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Seats,
+    Hand,
+    Summary,
+}
+
+struct PokerStarsParser {
+    history: HandHistory,
+    section: Section,
+    street: Street,
+    // whether amounts are written in dollars and cents, which are read as cents
+    cents: bool,
+}
+
+impl PokerStarsParser {
+    /// Starts a hand from the header line, as in
+    /// "PokerStars Hand #1: Hold'em No Limit (1/2) - 2024/01/31 8:05:00 ET".
+    fn new(header: &str) -> Result<Self, String> {
+        let header = header.trim_start_matches('\u{feff}');
+        let (site, rest) = header
+            .split_once('#')
+            .ok_or("expected a PokerStars hand header")?;
+        if !site.starts_with("PokerStars") {
+            return Err("expected a PokerStars hand header".to_string());
+        }
+        let (id, rest) = rest.split_once(':').ok_or("the hand number is missing")?;
+        let id = id
+            .trim()
+            .parse()
+            .map_err(|_| format!("{} isn't a hand number", id))?;
+        let (description, time) = rest
+            .rsplit_once(" - ")
+            .ok_or("the time the hand was played is missing")?;
+
+        // the stakes are the last thing in brackets, as in "($0.01/$0.02 USD)"
+        let open = description.rfind('(').ok_or("the stakes are missing")?;
+        let stakes = description[open + 1..].trim_end_matches(')');
+        let stakes = stakes.split_whitespace().next().unwrap_or_default();
+        let cents = stakes.contains(['$', '€', '£']);
+        let (small_blind, big_blind) = stakes
+            .split_once('/')
+            .and_then(|(small, big)| Some((amount(small, cents)?, amount(big, cents)?)))
+            .ok_or(format!("{} aren't stakes", stakes))?;
+
+        // tournaments name themselves and the level around the game
+        let description = description[..open].trim();
+        let description = description.split(" - ").next().unwrap_or(description);
+        let start = ["6+ Hold'em", "Hold'em", "Omaha"]
+            .iter()
+            .find_map(|game| description.find(game))
+            .ok_or("only hold'em and omaha hands can be read")?;
+
+        Ok(PokerStarsParser {
+            history: HandHistory {
+                id,
+                game: description[start..].trim().to_string(),
+                small_blind,
+                big_blind,
+                started: parse_time(time),
+                table: String::new(),
+                max_seats: 0,
+                button: 0,
+                players: vec![],
+                actions: vec![],
+                boards: vec![],
+                total_pot: 0,
+                rake: 0,
+            },
+            section: Section::Seats,
+            street: Street::Preflop,
+            cents,
+        })
+    }
+
+    fn amount(&self, text: &str) -> Result<u32, String> {
+        amount(text, self.cents).ok_or(format!("{} isn't an amount", text))
+    }
+
+    fn line(&mut self, line: &str) -> Result<(), String> {
+        if let Some(rest) = line.strip_prefix("*** ") {
+            return self.marker(rest.trim_end_matches(" ***"), line);
+        }
+        if let Some(rest) = line.strip_prefix("Table '") {
+            return self.table(rest);
+        }
+        match self.section {
+            Section::Seats if line.starts_with("Seat ") => return self.seat(line),
+            Section::Summary => return self.summary(line),
+            _ => {}
+        }
+        if let Some(rest) = line.strip_prefix("Dealt to ") {
+            let Some((player, cards)) = self.find_player(rest) else {
+                return Ok(());
+            };
+            if let Some(cards) = bracketed(cards).last() {
+                self.history.players[player].cards = Some(parse_cards(cards)?);
+            }
+            return Ok(());
+        }
+        if let Some(rest) = line.strip_prefix("Uncalled bet (") {
+            let (amount, name) = rest
+                .split_once(") returned to ")
+                .ok_or("expected who the bet was returned to")?;
+            let amount = self.amount(amount)?;
+            let player = self
+                .player_named(name)
+                .ok_or(format!("there's no player called {}", name))?;
+            self.push(player, HistoryAction::Returned(amount), false);
+            return Ok(());
+        }
+        let Some((player, rest)) = self.find_player(line) else {
+            return Ok(());
+        };
+        if let Some(rest) = rest.strip_prefix(" collected ") {
+            let amount = self.amount(rest.split_whitespace().next().unwrap_or_default())?;
+            self.push(player, HistoryAction::Collected(amount), false);
+            return Ok(());
+        }
+        let Some(rest) = rest.strip_prefix(": ") else {
+            return Ok(());
+        };
+        self.action(player, rest)
+    }
+
+    /// Reads the line between "***"s that starts each part of the hand.
+    fn marker(&mut self, marker: &str, line: &str) -> Result<(), String> {
+        let (name, cards) = marker.split_once(" *** ").unwrap_or((marker, ""));
+        let (run, name) = match name.split_once(' ') {
+            Some(("FIRST", name)) => (0, name),
+            Some(("SECOND", name)) => (1, name),
+            Some(("THIRD", name)) => (2, name),
+            _ => (0, name),
+        };
+        let street = match name {
+            "HOLE CARDS" => {
+                self.section = Section::Hand;
+                self.street = Street::Preflop;
+                return Ok(());
+            }
+            "SUMMARY" => {
+                self.section = Section::Summary;
+                return Ok(());
+            }
+            "FLOP" => Street::Flop,
+            "TURN" => Street::Turn,
+            "RIVER" => Street::River,
+            "SHOW DOWN" => return Ok(()),
+            _ if name.contains("STREET") || name.contains("DRAW") => {
+                return Err("only hold'em and omaha hands can be read".to_string())
+            }
+            _ => return Ok(()),
+        };
+        let mut board = vec![];
+        for cards in bracketed(cards) {
+            board.extend(parse_cards(cards)?.0);
+        }
+        if board.len() != street.board_cards() {
+            return Err(format!(
+                "expected {} cards on the {} in '{}'",
+                street.board_cards(),
+                street,
+                line
+            ));
+        }
+        self.section = Section::Hand;
+        self.street = street;
+        let boards = &mut self.history.boards;
+        while boards.len() <= run {
+            boards.push(CardCollection(vec![]));
+        }
+        boards[run] = CardCollection(board);
+
+        Ok(())
+    }
+
+    /// Reads "Table 'name' 6-max Seat #3 is the button".
+    fn table(&mut self, rest: &str) -> Result<(), String> {
+        let (name, rest) = rest
+            .rsplit_once("' ")
+            .ok_or("the table's name isn't closed")?;
+        self.history.table = name.to_string();
+        for word in rest.split_whitespace() {
+            if let Some(seats) = word.strip_suffix("-max") {
+                self.history.max_seats = seats
+                    .parse()
+                    .map_err(|_| format!("{} isn't a number of seats", seats))?;
+            }
+            if let Some(seat) = word.strip_prefix('#') {
+                self.history.button = seat
+                    .parse()
+                    .map_err(|_| format!("{} isn't a seat", seat))
+                    .and_then(seat_index)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads "Seat 1: name (100 in chips)", which may go on to say the player is sitting out.
+    fn seat(&mut self, line: &str) -> Result<(), String> {
+        let rest = line.trim_start_matches("Seat ");
+        let (seat, rest) = rest.split_once(": ").ok_or("expected the seat's number")?;
+        let seat = seat
+            .parse()
+            .map_err(|_| format!("{} isn't a seat", seat))
+            .and_then(seat_index)?;
+        let in_chips = rest
+            .find(" in chips")
+            .ok_or("the player's chips are missing")?;
+        let open = rest[..in_chips]
+            .rfind(" (")
+            .ok_or("the player's chips are missing")?;
+        let chips = self.amount(&rest[open + 2..in_chips])?;
+        self.history.players.push(HistoryPlayer {
+            seat,
+            name: rest[..open].to_string(),
+            chips,
+            cards: None,
+            sitting_out: rest.ends_with("is sitting out") || rest.contains("out of hand"),
+        });
+        self.history.max_seats = self.history.max_seats.max(seat + 1);
+
+        Ok(())
+    }
+
+    /// Reads the pot, the boards and the cards players showed or mucked from the summary.
+    fn summary(&mut self, line: &str) -> Result<(), String> {
+        if let Some(rest) = line.strip_prefix("Total pot ") {
+            let pot = rest.split_whitespace().next().unwrap_or_default();
+            self.history.total_pot = self.amount(pot)?;
+            if let Some((_, rake)) = rest.split_once("| Rake ") {
+                let rake = rake.split_whitespace().next().unwrap_or_default();
+                self.history.rake = self.amount(rake)?;
+            }
+            return Ok(());
+        }
+        if let Some((run, cards)) = line.split_once("Board ") {
+            let run = match run.trim() {
+                "" | "FIRST" => 0,
+                "SECOND" => 1,
+                "THIRD" => 2,
+                _ => return Ok(()),
+            };
+            let board = match bracketed(cards).first() {
+                Some(cards) => parse_cards(cards)?,
+                None => return Ok(()),
+            };
+            let boards = &mut self.history.boards;
+            while boards.len() <= run {
+                boards.push(CardCollection(vec![]));
+            }
+            if boards[run].0.is_empty() {
+                boards[run] = board;
+            }
+            return Ok(());
+        }
+        // seat lines like "Seat 2: bob (big blind) showed [Ah Kd] and won (12)" can be the only
+        // place mucked cards are shown
+        let Some(rest) = line.strip_prefix("Seat ") else {
+            return Ok(());
+        };
+        let Some((_, rest)) = rest.split_once(": ") else {
+            return Ok(());
+        };
+        let Some((player, rest)) = self.find_player(rest) else {
+            return Ok(());
+        };
+        if !(rest.contains(" showed [") || rest.contains(" mucked [")) {
+            return Ok(());
+        }
+        if let Some(cards) = bracketed(rest).first() {
+            let cards = parse_cards(cards)?;
+            self.history.players[player].cards.get_or_insert(cards);
+        }
+
+        Ok(())
+    }
+
+    /// Reads what the player did from the rest of their line, as in "raises 4 to 6". Things
+    /// that don't change the hand, like sitting out, are skipped.
+    fn action(&mut self, player: usize, rest: &str) -> Result<(), String> {
+        let (rest, all_in) = match rest.strip_suffix(" and is all-in") {
+            Some(rest) => (rest, true),
+            None => (rest, false),
+        };
+        let words: Vec<&str> = rest.split_whitespace().collect();
+        let action = match words.as_slice() {
+            ["posts", "small", "&", "big", "blinds", amount] => {
+                HistoryAction::SmallAndBigBlinds(self.amount(amount)?)
+            }
+            ["posts", "small", "blind", amount] => HistoryAction::SmallBlind(self.amount(amount)?),
+            ["posts", "big", "blind", amount] => HistoryAction::BigBlind(self.amount(amount)?),
+            ["posts", "the", "ante", amount] => HistoryAction::Ante(self.amount(amount)?),
+            ["folds", ..] => HistoryAction::Fold,
+            ["checks"] => HistoryAction::Check,
+            ["calls", amount] => HistoryAction::Call(self.amount(amount)?),
+            ["bets", amount] => HistoryAction::Bet(self.amount(amount)?),
+            ["raises", by, "to", to] => HistoryAction::Raise(self.amount(by)?, self.amount(to)?),
+            ["shows", ..] | ["mucks", ..] => {
+                if let Some(cards) = bracketed(rest).first() {
+                    self.history.players[player].cards = Some(parse_cards(cards)?);
+                }
+                match words[0] {
+                    "shows" => HistoryAction::Show,
+                    _ => HistoryAction::Muck,
+                }
+            }
+            _ => return Ok(()),
+        };
+        self.push(player, action, all_in);
+
+        Ok(())
+    }
+
+    fn push(&mut self, player: usize, action: HistoryAction, all_in: bool) {
+        self.history.actions.push(HistoryEntry {
+            street: self.street,
+            player,
+            action,
+            all_in,
+        });
+    }
+
+    fn player_named(&self, name: &str) -> Option<usize> {
+        self.history
+            .players
+            .iter()
+            .position(|player| player.name == name)
+    }
+
+    /// Returns the player whose name the text starts with, along with the rest of the text.
+    /// The longest name wins when one name starts another.
+    fn find_player<'a>(&self, text: &'a str) -> Option<(usize, &'a str)> {
+        self.history
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(_, player)| text.starts_with(&player.name))
+            .max_by_key(|(_, player)| player.name.len())
+            .map(|(place, player)| (place, &text[player.name.len()..]))
+    }
+}
+
+/// Parses an action of our JSON format, whose player is named among the players.
+fn entry_from_json(json: &Json, players: &[HistoryPlayer]) -> Result<HistoryEntry, String> {
+    let street = json.str_field("street")?;
+    let street = Street::parse(street).ok_or(format!("{} isn't a street", street))?;
+    let name = json.str_field("player")?;
+    let player = players
+        .iter()
+        .position(|player| player.name == name)
+        .ok_or(format!("there's no player called {}", name))?;
+    let amount = |key| match json.get(key) {
+        Some(_) => json.u32_field(key).map(Some),
+        None => Ok(None),
+    };
+    let kind = json.str_field("action")?;
+    let action = HistoryAction::parse(kind, amount("amount")?, amount("to")?).ok_or(format!(
+        "{} isn't an action or is missing its amounts",
+        kind
+    ))?;
+
+    Ok(HistoryEntry {
+        street,
+        player,
+        action,
+        all_in: json.get("all_in").and_then(Json::as_bool) == Some(true),
+    })
+}
+
+/// Turns a seat counting from 1 into one counting from 0.
+fn seat_index(seat: u64) -> Result<usize, String> {
+    match seat {
+        0 => Err("seats count from 1".to_string()),
+        seat => Ok(seat as usize - 1),
+    }
+}
+
+/// Parses an amount of chips, or of money as cents, as in "150" or "$1.50".
+fn amount(text: &str, cents: bool) -> Option<u32> {
+    let text = text.trim_start_matches(['$', '€', '£']).replace(',', "");
+    if !cents {
+        return text.parse().ok();
+    }
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+    let whole: u32 = whole.parse().ok()?;
+    let fraction: u32 = match fraction.len() {
+        0 => 0,
+        1 => fraction.parse::<u32>().ok()? * 10,
+        2 => fraction.parse().ok()?,
+        _ => return None,
+    };
+
+    whole.checked_mul(100)?.checked_add(fraction)
+}
+
+/// Returns the text inside each pair of square brackets, as in the "Ah Kd" of "[Ah Kd]".
+fn bracketed(text: &str) -> Vec<&str> {
+    text.split('[')
+        .skip(1)
+        .filter_map(|part| part.split_once(']').map(|(inside, _)| inside))
+        .collect()
+}
+
+fn parse_cards(text: &str) -> Result<CardCollection, String> {
+    let cards = text
+        .split_whitespace()
+        .map(|code| Card::parse(code).ok_or(format!("{} isn't a card", code)))
+        .collect::<Result<_, _>>()?;
+
+    Ok(CardCollection(cards))
+}
+
+/// Returns the cards written as in "Ah Kd".
+fn codes(cards: &CardCollection) -> String {
+    let codes: Vec<String> = cards.0.iter().map(Card::code).collect();
+    codes.join(" ")
+}
+
+/// Reads the time a hand was played, as in "2024/01/31 8:05:00 ET", into seconds since the
+/// Unix epoch. Times in other zones are read from the Eastern time PokerStars adds after them
+/// in brackets, and the time is `None` if the zone isn't known.
+fn parse_time(text: &str) -> Option<u64> {
+    let text = bracketed(text).first().copied().unwrap_or(text);
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let [date, time, zone] = words.as_slice() else {
+        return None;
+    };
+    let date: Vec<&str> = date.split('/').collect();
+    let time: Vec<&str> = time.split(':').collect();
+    let ([year, month, day], [hours, minutes, seconds]) = (date.as_slice(), time.as_slice()) else {
+        return None;
+    };
+    let days = days_from_civil(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+    let local = days * 86400
+        + hours.parse::<i64>().ok()? * 3600
+        + minutes.parse::<i64>().ok()? * 60
+        + seconds.parse::<i64>().ok()?;
+    let utc = match *zone {
+        "UTC" | "GMT" => local,
+        // the offset is found from the time in winter, which is right outside the hour the
+        // clocks go back
+        "ET" => {
+            let winter = local + 5 * 3600;
+            local + eastern_offset(winter)
+        }
+        _ => return None,
+    };
+
+    u64::try_from(utc).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HAND: &str = "\
+PokerStars Hand #100000007: Hold'em No Limit (1/2) - 2024/03/01 12:30:00 ET
+Table 'Table 1' 3-max Seat #1 is the button
+Seat 1: alice (100 in chips)
+Seat 2: bob (150 in chips)
+Seat 3: carol (200 in chips)
+bob: posts small blind 1
+carol: posts big blind 2
+*** HOLE CARDS ***
+Dealt to alice [5h Ad]
+alice: raises 4 to 6
+bob: folds
+carol: calls 4
+*** FLOP *** [5d Jc 7d]
+carol: checks
+alice: bets 8
+carol: calls 8
+*** TURN *** [5d Jc 7d] [2h]
+carol: checks
+alice: checks
+*** RIVER *** [5d Jc 7d 2h] [9c]
+carol: checks
+alice: checks
+*** SHOW DOWN ***
+carol: shows [Td Kd] (high card)
+alice: shows [5h Ad] (a pair)
+alice collected 29 from pot
+*** SUMMARY ***
+Total pot 29 | Rake 0
+Board [5d Jc 7d 2h 9c]
+Seat 1: alice (button) showed [5h Ad] and won (29) with a pair
+Seat 2: bob (small blind) folded before Flop
+Seat 3: carol (big blind) showed [Td Kd] and lost with high card";
+
+    fn problems_with(from: &str, to: &str) -> Vec<String> {
+        let text = HAND.replace(from, to);
+        HandHistory::parse_pokerstars(&text).unwrap().problems()
+    }
+
+    #[test]
+    fn reads_a_pokerstars_hand() {
+        let hand = HandHistory::parse_pokerstars(HAND).unwrap();
+        assert_eq!(hand.id, 100000007);
+        assert_eq!(hand.game, "Hold'em No Limit");
+        assert_eq!((hand.small_blind, hand.big_blind), (1, 2));
+        assert_eq!(hand.button, 0);
+        assert_eq!(hand.players.len(), 3);
+        assert_eq!(hand.total_pot, 29);
+        assert_eq!(hand.winnings(), vec![29, 0, 0]);
+        assert_eq!(codes(&hand.boards[0]), "5d Jc 7d 2h 9c");
+        assert!(hand.problems().is_empty(), "{:?}", hand.problems());
+    }
+
+    #[test]
+    fn finds_a_card_dealt_twice() {
+        let problems = problems_with("5h Ad", "5d Ad");
+        assert_eq!(problems, vec!["the 5d was dealt twice"]);
+    }
+
+    #[test]
+    fn finds_bets_that_do_not_add_up() {
+        let problems = problems_with("carol: calls 4", "carol: calls 5");
+        assert!(problems.contains(&"carol calls 5 but had 4 to call".to_string()));
+        assert!(problems.contains(&"the total pot is 29 but the bets add up to 30".to_string()));
+
+        let problems = problems_with("Total pot 29", "Total pot 31");
+        assert!(problems.contains(&"the total pot is 31 but the bets add up to 29".to_string()));
+        assert!(
+            problems.contains(&"the total pot is 31 but 29 was collected and 0 raked".to_string())
+        );
+
+        let problems = problems_with(
+            "carol: checks\nalice: bets 8",
+            "carol: checks\nalice: checks",
+        );
+        assert!(problems.contains(&"carol calls 8 but had 0 to call".to_string()));
+    }
+
+    #[test]
+    fn finds_the_pot_going_to_a_beaten_hand() {
+        let problems = problems_with("alice collected", "carol collected");
+        assert_eq!(problems, vec!["carol won 29 but alice's hand beats theirs"]);
+    }
+
+    #[test]
+    fn reports_the_line_a_hand_cannot_be_read_at() {
+        assert!(HandHistory::parse_pokerstars("\n\n").is_err());
+        let err = HandHistory::parse_pokerstars("Full Tilt Poker Game #1: ...").err();
+        assert_eq!(
+            err.as_deref(),
+            Some("line 1: expected a PokerStars hand header")
+        );
+        let text = HAND.replace("(1/2)", "(1/two)");
+        let err = HandHistory::parse_pokerstars(&text).err();
+        assert_eq!(err.as_deref(), Some("line 1: 1/two aren't stakes"));
+    }
+}
@@ -0,0 +1,661 @@
+use crate::betting::{Action, BettingStructure};
+use crate::deck::{Card, CardCollection};
+use crate::events::TableEvent;
+use crate::holdem::{HoldemGame, SeatStatus};
+use crate::json::Json;
+use crate::lobby::{GameType, TableSettings};
+use crate::timer::TimerSettings;
+
+/// A seat as it stood when a hand started, with everything about it that decides how the hand
+/// is dealt.
+#[derive(Clone)]
+pub struct LoggedSeat {
+    pub chips: u32,
+    pub status: SeatStatus,
+    pub missed_small_blind: bool,
+    pub missed_big_blind: bool,
+    pub muck_losing_hands: bool,
+    pub orbits_sat_out: u32,
+}
+
+/// The log of a hand of hold'em: how the table stood when it started, the seed its deck was
+/// shuffled from, and every event of the hand, which takes in every action and every card
+/// dealt. A table set up from the log deals the same hand again, so the log can be checked by
+/// replaying it.
+#[derive(Clone)]
+pub struct HandLog {
+    pub settings: TableSettings,
+    pub ante: u32,
+    pub hand: u32,
+    pub seed: u64,
+    /// Where the button was before the hand moved it on.
+    pub button: Option<usize>,
+    pub seats: Vec<Option<LoggedSeat>>,
+    pub events: Vec<TableEvent>,
+}
+
+impl HandLog {
+    /// Writes the log as JSON Lines: a line for the hand, one for each seated player and then
+    /// one for each event. Seats count from 1.
+    pub fn to_json_lines(&self) -> String {
+        let mut fields = vec![
+            ("type", "hand".into()),
+            ("hand", self.hand.into()),
+            ("seed", self.seed.into()),
+            ("game", self.settings.game.name().into()),
+            ("limit", self.settings.structure.name().into()),
+        ];
+        if let BettingStructure::FixedLimit { raise_cap, .. } = self.settings.structure {
+            fields.push(("raise_cap", u32::from(raise_cap).into()));
+        }
+        fields.extend([
+            ("small_blind", self.settings.small_blind.into()),
+            ("big_blind", self.settings.big_blind.into()),
+            ("ante", self.ante.into()),
+            ("seats", self.settings.seats.into()),
+            ("max_runs", self.settings.max_runs.into()),
+            (
+                "max_orbits_sat_out",
+                self.settings.max_orbits_sat_out.into(),
+            ),
+            ("button", self.button.map(|seat| seat + 1).into()),
+        ]);
+        let mut lines = vec![Json::object(fields)];
+
+        for (seat, player) in self.seats.iter().enumerate() {
+            let Some(player) = player else {
+                continue;
+            };
+            lines.push(Json::object(vec![
+                ("type", "seat".into()),
+                ("seat", (seat + 1).into()),
+                ("chips", player.chips.into()),
+                ("status", player.status.to_string().into()),
+                ("missed_small_blind", player.missed_small_blind.into()),
+                ("missed_big_blind", player.missed_big_blind.into()),
+                ("muck_losing_hands", player.muck_losing_hands.into()),
+                ("orbits_sat_out", player.orbits_sat_out.into()),
+            ]));
+        }
+        lines.extend(self.events.iter().map(event_to_json));
+
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+    /// Reads every hand in a JSON Lines log, each starting at its hand line.
+    pub fn parse_json_lines(text: &str) -> Result<Vec<Self>, String> {
+        let mut logs: Vec<HandLog> = vec![];
+        for (number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            read_line(&mut logs, line).map_err(|err| format!("line {}: {}", number + 1, err))?;
+        }
+
+        Ok(logs)
+    }
+
+    /// Deals the hand again from the seed and plays the logged actions, checking that every
+    /// event comes out the same as in the log. `step` is called with each event as it is
+    /// replayed. Returns an error at the first event that differs.
+    pub fn replay(&self, mut step: impl FnMut(&TableEvent)) -> Result<(), String> {
+        let mut game = HoldemGame::from_log(self)?;
+        let mut replayed = game.start_hand()?;
+        let mut logged = self.events.iter().enumerate().peekable();
+        loop {
+            for event in replayed {
+                let replayed = event_to_json(&event);
+                let Some((number, expected)) = logged.next() else {
+                    return Err(format!(
+                        "replaying went on to {} after the log ended",
+                        replayed
+                    ));
+                };
+                let expected = event_to_json(expected);
+                if replayed != expected {
+                    return Err(format!(
+                        "event {}: the log has {} but replaying gave {}",
+                        number + 1,
+                        expected,
+                        replayed
+                    ));
+                }
+                step(&event);
+            }
+
+            // every event the table doesn't make by itself is something a player did
+            let Some((number, next)) = logged.peek() else {
+                return Ok(());
+            };
+            let number = *number;
+            let played = match next {
+                TableEvent::Action { action, .. } => game.act(*action),
+                TableEvent::RunsChosen { seat, runs } => game.choose_runs(*seat, *runs),
+                TableEvent::Showdown { seat, .. } if game.street().is_none() => {
+                    game.show_cards(*seat)
+                }
+                next => Err(format!(
+                    "the log has {} where a player should have acted",
+                    event_to_json(next)
+                )),
+            };
+            replayed = played.map_err(|err| format!("event {}: {}", number + 1, err))?;
+        }
+    }
+}
+
+/// Reads a line of a log, which either starts a new hand or adds to the last one.
+fn read_line(logs: &mut Vec<HandLog>, line: &str) -> Result<(), String> {
+    let json = Json::parse(line)?;
+    let kind = json.str_field("type")?;
+    if kind == "hand" {
+        logs.push(hand_from_json(&json)?);
+        return Ok(());
+    }
+    let Some(log) = logs.last_mut() else {
+        return Err("the log has to start with a hand".to_string());
+    };
+    if kind == "seat" {
+        let seat = seat_field(&json, "seat")?;
+        let Some(slot) = log.seats.get_mut(seat) else {
+            return Err(format!("there is no seat {}", seat + 1));
+        };
+        *slot = Some(seat_from_json(&json)?);
+    } else {
+        log.events.push(event_from_json(&json)?);
+    }
+
+    Ok(())
+}
+
+/// Reads the hand line that starts a hand, leaving every seat empty.
+fn hand_from_json(json: &Json) -> Result<HandLog, String> {
+    let game = json.str_field("game")?;
+    let game = GameType::parse(game).ok_or(format!("{} isn't a game", game))?;
+    let small_blind = json.u32_field("small_blind")?;
+    let big_blind = json.u32_field("big_blind")?;
+    let limit = json.str_field("limit")?;
+    let mut structure = BettingStructure::parse(limit, small_blind, big_blind)
+        .ok_or(format!("{} isn't a betting limit", limit))?;
+    if let BettingStructure::FixedLimit { raise_cap, .. } = &mut structure {
+        *raise_cap = u8::try_from(json.u32_field("raise_cap")?)
+            .map_err(|_| "the raise_cap is too big".to_string())?;
+    }
+    let seats = json.u32_field("seats")? as usize;
+    if seats < 2 {
+        return Err("a table needs at least two seats".to_string());
+    }
+    let max_orbits_sat_out = match json.field("max_orbits_sat_out")? {
+        Json::Null => None,
+        _ => Some(json.u32_field("max_orbits_sat_out")?),
+    };
+    let button = match json.field("button")? {
+        Json::Null => None,
+        _ => Some(seat_field(json, "button")?),
+    };
+
+    Ok(HandLog {
+        settings: TableSettings {
+            game,
+            structure,
+            small_blind,
+            big_blind,
+            seats,
+            broadcast_delay: None,
+            timer: TimerSettings::default(),
+            max_orbits_sat_out,
+            max_runs: json.u32_field("max_runs")?,
+            min_buy_in: 0,
+            max_buy_in: u32::MAX,
+        },
+        ante: json.u32_field("ante")?,
+        hand: json.u32_field("hand")?,
+        seed: json.u64_field("seed")?,
+        button,
+        seats: vec![None; seats],
+        events: vec![],
+    })
+}
+
+fn seat_from_json(json: &Json) -> Result<LoggedSeat, String> {
+    let status = json.str_field("status")?;
+
+    Ok(LoggedSeat {
+        chips: json.u32_field("chips")?,
+        status: SeatStatus::parse(status).ok_or(format!("{} isn't a seat status", status))?,
+        missed_small_blind: json.bool_field("missed_small_blind")?,
+        missed_big_blind: json.bool_field("missed_big_blind")?,
+        muck_losing_hands: json.bool_field("muck_losing_hands")?,
+        orbits_sat_out: json.u32_field("orbits_sat_out")?,
+    })
+}
+
+/// Writes the event as a JSON object with its type and fields.
+pub fn event_to_json(event: &TableEvent) -> Json {
+    let seat = |seat: &usize| -> Json { (seat + 1).into() };
+    let fields: Vec<(&str, Json)> = match event {
+        TableEvent::HandStarted { hand } => {
+            vec![("type", "hand started".into()), ("hand", (*hand).into())]
+        }
+        TableEvent::HandFinished { hand } => {
+            vec![("type", "hand finished".into()), ("hand", (*hand).into())]
+        }
+        TableEvent::Blind {
+            seat: blind,
+            amount,
+            dead,
+        } => vec![
+            ("type", "blind".into()),
+            ("seat", seat(blind)),
+            ("amount", (*amount).into()),
+            ("dead", (*dead).into()),
+        ],
+        TableEvent::Ante { seat: ante, amount } => vec![
+            ("type", "ante".into()),
+            ("seat", seat(ante)),
+            ("amount", (*amount).into()),
+        ],
+        TableEvent::BringIn {
+            seat: bring_in,
+            amount,
+        } => vec![
+            ("type", "bring in".into()),
+            ("seat", seat(bring_in)),
+            ("amount", (*amount).into()),
+        ],
+        TableEvent::BlindsUp {
+            small_blind,
+            big_blind,
+            ante,
+        } => vec![
+            ("type", "blinds up".into()),
+            ("small_blind", (*small_blind).into()),
+            ("big_blind", (*big_blind).into()),
+            ("ante", (*ante).into()),
+        ],
+        TableEvent::Eliminated {
+            seat: eliminated,
+            place,
+        } => vec![
+            ("type", "eliminated".into()),
+            ("seat", seat(eliminated)),
+            ("place", (*place).into()),
+        ],
+        TableEvent::Moved {
+            seat: moved,
+            table,
+            to_seat,
+        } => vec![
+            ("type", "moved".into()),
+            ("seat", seat(moved)),
+            ("table", (table + 1).into()),
+            ("to_seat", seat(to_seat)),
+        ],
+        TableEvent::Seated {
+            seat: seated,
+            chips,
+        } => vec![
+            ("type", "seated".into()),
+            ("seat", seat(seated)),
+            ("chips", (*chips).into()),
+        ],
+        TableEvent::HandForHand => vec![("type", "hand for hand".into())],
+        TableEvent::FinalTable => vec![("type", "final table".into())],
+        TableEvent::Removed {
+            seat: removed,
+            chips,
+        } => vec![
+            ("type", "removed".into()),
+            ("seat", seat(removed)),
+            ("chips", (*chips).into()),
+        ],
+        TableEvent::HoleCards { seat: dealt, cards } => vec![
+            ("type", "hole cards".into()),
+            ("seat", seat(dealt)),
+            ("cards", cards_to_json(cards)),
+        ],
+        TableEvent::HiddenCards { seat: dealt, count } => vec![
+            ("type", "hidden cards".into()),
+            ("seat", seat(dealt)),
+            ("count", (*count).into()),
+        ],
+        TableEvent::UpCards { seat: dealt, cards } => vec![
+            ("type", "up cards".into()),
+            ("seat", seat(dealt)),
+            ("cards", cards_to_json(cards)),
+        ],
+        TableEvent::Action {
+            seat: acting,
+            action,
+        } => {
+            let mut fields = vec![("type", "action".into()), ("seat", seat(acting))];
+            match action {
+                Action::Fold => fields.push(("action", "fold".into())),
+                Action::Check => fields.push(("action", "check".into())),
+                Action::Call => fields.push(("action", "call".into())),
+                Action::Raise(to) => {
+                    fields.push(("action", "raise".into()));
+                    fields.push(("to", (*to).into()));
+                }
+            }
+            fields
+        }
+        TableEvent::Drew {
+            seat: drawing,
+            count,
+        } => vec![
+            ("type", "drew".into()),
+            ("seat", seat(drawing)),
+            ("count", (*count).into()),
+        ],
+        TableEvent::ToAct { seat: acting, pot } => vec![
+            ("type", "to act".into()),
+            ("seat", seat(acting)),
+            ("pot", (*pot).into()),
+        ],
+        TableEvent::Countdown {
+            seat: acting,
+            action_left,
+            time_bank_left,
+        } => vec![
+            ("type", "countdown".into()),
+            ("seat", seat(acting)),
+            ("action_left", (*action_left).into()),
+            ("time_bank_left", (*time_bank_left).into()),
+        ],
+        TableEvent::Board { cards } => {
+            vec![("type", "board".into()), ("cards", cards_to_json(cards))]
+        }
+        TableEvent::RunItOffer { seats, max_runs } => vec![
+            ("type", "run it offer".into()),
+            ("seats", Json::Array(seats.iter().map(seat).collect())),
+            ("max_runs", (*max_runs).into()),
+        ],
+        TableEvent::RunsChosen {
+            seat: choosing,
+            runs,
+        } => vec![
+            ("type", "runs chosen".into()),
+            ("seat", seat(choosing)),
+            ("runs", (*runs).into()),
+        ],
+        TableEvent::Run { run, cards } => vec![
+            ("type", "run".into()),
+            ("run", (*run).into()),
+            ("cards", cards_to_json(cards)),
+        ],
+        TableEvent::Showdown {
+            seat: showing,
+            cards,
+        } => vec![
+            ("type", "showdown".into()),
+            ("seat", seat(showing)),
+            ("cards", cards_to_json(cards)),
+        ],
+        TableEvent::Mucked { seat: mucking } => {
+            vec![("type", "mucked".into()), ("seat", seat(mucking))]
+        }
+        TableEvent::RabbitHunt { cards } => vec![
+            ("type", "rabbit hunt".into()),
+            ("cards", cards_to_json(cards)),
+        ],
+        TableEvent::PotWon {
+            seat: winner,
+            amount,
+        } => vec![
+            ("type", "pot won".into()),
+            ("seat", seat(winner)),
+            ("amount", (*amount).into()),
+        ],
+    };
+
+    Json::object(fields)
+}
+
+/// Reads an event written by `event_to_json`.
+pub fn event_from_json(json: &Json) -> Result<TableEvent, String> {
+    let event = match json.str_field("type")? {
+        "hand started" => TableEvent::HandStarted {
+            hand: json.u32_field("hand")?,
+        },
+        "hand finished" => TableEvent::HandFinished {
+            hand: json.u32_field("hand")?,
+        },
+        "blind" => TableEvent::Blind {
+            seat: seat_field(json, "seat")?,
+            amount: json.u32_field("amount")?,
+            dead: json.bool_field("dead")?,
+        },
+        "ante" => TableEvent::Ante {
+            seat: seat_field(json, "seat")?,
+            amount: json.u32_field("amount")?,
+        },
+        "bring in" => TableEvent::BringIn {
+            seat: seat_field(json, "seat")?,
+            amount: json.u32_field("amount")?,
+        },
+        "blinds up" => TableEvent::BlindsUp {
+            small_blind: json.u32_field("small_blind")?,
+            big_blind: json.u32_field("big_blind")?,
+            ante: json.u32_field("ante")?,
+        },
+        "eliminated" => TableEvent::Eliminated {
+            seat: seat_field(json, "seat")?,
+            place: json.u64_field("place")? as usize,
+        },
+        "moved" => TableEvent::Moved {
+            seat: seat_field(json, "seat")?,
+            table: seat_field(json, "table")?,
+            to_seat: seat_field(json, "to_seat")?,
+        },
+        "seated" => TableEvent::Seated {
+            seat: seat_field(json, "seat")?,
+            chips: json.u32_field("chips")?,
+        },
+        "hand for hand" => TableEvent::HandForHand,
+        "final table" => TableEvent::FinalTable,
+        "removed" => TableEvent::Removed {
+            seat: seat_field(json, "seat")?,
+            chips: json.u32_field("chips")?,
+        },
+        "hole cards" => TableEvent::HoleCards {
+            seat: seat_field(json, "seat")?,
+            cards: cards_field(json, "cards")?,
+        },
+        "hidden cards" => TableEvent::HiddenCards {
+            seat: seat_field(json, "seat")?,
+            count: json.u64_field("count")? as usize,
+        },
+        "up cards" => TableEvent::UpCards {
+            seat: seat_field(json, "seat")?,
+            cards: cards_field(json, "cards")?,
+        },
+        "action" => {
+            let action = match json.str_field("action")? {
+                "fold" => Action::Fold,
+                "check" => Action::Check,
+                "call" => Action::Call,
+                "raise" => Action::Raise(json.u32_field("to")?),
+                action => return Err(format!("{} isn't an action", action)),
+            };
+            TableEvent::Action {
+                seat: seat_field(json, "seat")?,
+                action,
+            }
+        }
+        "drew" => TableEvent::Drew {
+            seat: seat_field(json, "seat")?,
+            count: json.u64_field("count")? as usize,
+        },
+        "to act" => TableEvent::ToAct {
+            seat: seat_field(json, "seat")?,
+            pot: json.u32_field("pot")?,
+        },
+        "countdown" => TableEvent::Countdown {
+            seat: seat_field(json, "seat")?,
+            action_left: json.u64_field("action_left")?,
+            time_bank_left: json.u64_field("time_bank_left")?,
+        },
+        "board" => TableEvent::Board {
+            cards: cards_field(json, "cards")?,
+        },
+        "run it offer" => TableEvent::RunItOffer {
+            seats: json
+                .array_field("seats")?
+                .iter()
+                .map(|seat| match seat.as_u64() {
+                    Some(seat) if seat > 0 => Ok(seat as usize - 1),
+                    _ => Err("the seats must count from 1".to_string()),
+                })
+                .collect::<Result<_, _>>()?,
+            max_runs: json.u32_field("max_runs")?,
+        },
+        "runs chosen" => TableEvent::RunsChosen {
+            seat: seat_field(json, "seat")?,
+            runs: json.u32_field("runs")?,
+        },
+        "run" => TableEvent::Run {
+            run: json.u32_field("run")?,
+            cards: cards_field(json, "cards")?,
+        },
+        "showdown" => TableEvent::Showdown {
+            seat: seat_field(json, "seat")?,
+            cards: cards_field(json, "cards")?,
+        },
+        "mucked" => TableEvent::Mucked {
+            seat: seat_field(json, "seat")?,
+        },
+        "rabbit hunt" => TableEvent::RabbitHunt {
+            cards: cards_field(json, "cards")?,
+        },
+        "pot won" => TableEvent::PotWon {
+            seat: seat_field(json, "seat")?,
+            amount: json.u32_field("amount")?,
+        },
+        kind => return Err(format!("{} isn't an event", kind)),
+    };
+
+    Ok(event)
+}
+
+fn cards_to_json(cards: &CardCollection) -> Json {
+    cards
+        .0
+        .iter()
+        .map(Card::code)
+        .collect::<Vec<String>>()
+        .into()
+}
+
+fn cards_field(json: &Json, key: &str) -> Result<CardCollection, String> {
+    let cards = json
+        .array_field(key)?
+        .iter()
+        .map(|card| {
+            card.as_str()
+                .and_then(Card::parse)
+                .ok_or(format!("the {} must be cards like \"As\"", key))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(CardCollection(cards))
+}
+
+/// Returns the object's field as a seat counting from 0, from the seat counting from 1 it holds.
+fn seat_field(json: &Json, key: &str) -> Result<usize, String> {
+    match json.u64_field(key)? {
+        0 => Err(format!("the {} must count from 1", key)),
+        seat => Ok(seat as usize - 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays a logged hand of 1/2 no limit with three players to a showdown.
+    fn logged_hand() -> HandLog {
+        let mut game = HoldemGame::new(TableSettings {
+            game: GameType::Holdem,
+            structure: BettingStructure::NoLimit { big_blind: 2 },
+            small_blind: 1,
+            big_blind: 2,
+            seats: 3,
+            broadcast_delay: None,
+            timer: TimerSettings::default(),
+            max_orbits_sat_out: None,
+            max_runs: 1,
+            min_buy_in: 40,
+            max_buy_in: 200,
+        });
+        for seat in 0..3 {
+            game.sit_down(seat, 100).unwrap();
+        }
+        game.log_hands();
+        game.seed_deck(5);
+        game.start_hand().unwrap();
+        for action in [Action::Raise(6), Action::Fold, Action::Call] {
+            game.act(action).unwrap();
+        }
+        while game.street().is_some() {
+            game.act(Action::Check).unwrap();
+        }
+
+        game.hand_log().unwrap().clone()
+    }
+
+    #[test]
+    fn a_written_log_reads_back_and_replays() {
+        let log = logged_hand();
+        let logs = HandLog::parse_json_lines(&log.to_json_lines()).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].to_json_lines(), log.to_json_lines());
+
+        let mut replayed = 0;
+        logs[0].replay(|_| replayed += 1).unwrap();
+        assert_eq!(replayed, log.events.len());
+    }
+
+    #[test]
+    fn replaying_finds_the_first_event_that_differs() {
+        let mut log = logged_hand();
+        let board = log
+            .events
+            .iter()
+            .position(|event| matches!(event, TableEvent::Board { .. }))
+            .unwrap();
+        log.events[board] = TableEvent::Board {
+            cards: CardCollection(vec![Card::parse("2c").unwrap(); 3]),
+        };
+        let err = log.replay(|_| {}).unwrap_err();
+        assert!(
+            err.starts_with(&format!("event {}: the log has", board + 1)),
+            "{}",
+            err
+        );
+
+        // a different seed deals different cards
+        let mut log = logged_hand();
+        log.seed += 1;
+        assert!(log.replay(|_| {}).is_err());
+
+        // and a log cut short is caught once replaying runs past its end
+        let mut log = logged_hand();
+        log.events.pop();
+        assert!(log.replay(|_| {}).is_err());
+    }
+
+    #[test]
+    fn reading_reports_the_line_of_a_bad_log() {
+        let err = HandLog::parse_json_lines("{\"type\": \"seat\"}").err();
+        assert_eq!(
+            err.as_deref(),
+            Some("line 1: the log has to start with a hand")
+        );
+        let mut text = logged_hand().to_json_lines();
+        text.push_str("not json\n");
+        let lines = text.lines().count();
+        let err = HandLog::parse_json_lines(&text).err().unwrap_or_default();
+        assert!(err.starts_with(&format!("line {}:", lines)), "{}", err);
+    }
+}
@@ -0,0 +1,762 @@
+use crate::betting::{Action, BettingStructure};
+use crate::deck::{Card, CardCollection};
+use crate::events::{ordinal, Recipient, TableEvent};
+use crate::hand::{get_best_hand_with_rules, get_best_omaha_hand, Hand};
+use crate::lobby::{GameType, TableSettings};
+
+/// A hand played at a table, with everything needed to write it out as a hand history.
+#[derive(Clone)]
+pub struct PlayedHand {
+    pub table: usize,
+    pub hand: u32,
+    pub settings: TableSettings,
+    /// When the hand started, in seconds since the Unix epoch.
+    pub started: u64,
+    /// The player sitting in each seat when the hand started and the chips they had.
+    pub seats: Vec<Option<(usize, u32)>>,
+    /// Every event of the hand, from `HandStarted` to `HandFinished`.
+    pub events: Vec<TableEvent>,
+}
+
+impl PlayedHand {
+    /// Returns the number the hand goes by in hand histories, which is unique across the tables
+    /// as long as no table plays more than 100 million hands.
+    pub fn id(&self) -> u64 {
+        self.table as u64 * 100_000_000 + self.hand as u64
+    }
+
+    /// Returns the seat the player was sitting in when the hand started.
+    pub fn seat_of(&self, player: usize) -> Option<usize> {
+        self.seats
+            .iter()
+            .position(|seat| seat.is_some_and(|(sitting, _)| sitting == player))
+    }
+
+    /// Returns the hand as the recipient is allowed to see it.
+    pub fn filter_for(&self, recipient: Recipient) -> PlayedHand {
+        PlayedHand {
+            events: self
+                .events
+                .iter()
+                .map(|event| event.filter_for(recipient))
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Writes the hand out in the PokerStars hand history format, naming the players with
+    /// `name`. Every face down card left in the events is written out, so filter the hand for
+    /// whoever it is for first.
+    pub fn to_pokerstars(&self, name: impl Fn(usize) -> String) -> String {
+        let names = self
+            .seats
+            .iter()
+            .map(|seat| seat.map_or_else(String::new, |(player, _)| name(player)))
+            .collect();
+        let mut writer = HistoryWriter::new(self, names);
+        for event in &self.events {
+            writer.event(event);
+        }
+
+        writer.finish()
+    }
+}
+
+/// Follows the events of a hand, keeping track of the chips, and writes out the lines of its
+/// hand history.
+struct HistoryWriter<'a> {
+    hand: &'a PlayedHand,
+    names: Vec<String>,
+    lines: Vec<String>,
+    // the seats dealt into the hand
+    dealt: Vec<bool>,
+    button: Option<usize>,
+    small_blind: Option<usize>,
+    big_blind: Option<usize>,
+    stacks: Vec<u32>,
+    // the chips each seat has in front of it in the current betting round
+    bets: Vec<u32>,
+    // the chips each seat has put in the pot, without the bets returned to it
+    contributed: Vec<u32>,
+    // the bets returned to each seat that it hasn't been paid back yet
+    returned: Vec<u32>,
+    collected: Vec<u32>,
+    // the dead small blind each seat posted, written out with the seat's live blind
+    dead_blinds: Vec<u32>,
+    live_blinds: usize,
+    // where each seat folded, and whether it put in any chips but an ante before it did
+    folded: Vec<Option<&'static str>>,
+    voluntary: Vec<bool>,
+    shown: Vec<Option<CardCollection>>,
+    mucked: Vec<bool>,
+    board: Vec<Card>,
+    runs: Vec<Vec<Card>>,
+    dealing: bool,
+    // whether anyone has bet since cards were last dealt, so that the next cards dealt start a
+    // new street, and the streets dealt that way
+    betting: bool,
+    streets: usize,
+    showdown: bool,
+}
+
+impl<'a> HistoryWriter<'a> {
+    fn new(hand: &'a PlayedHand, names: Vec<String>) -> Self {
+        let seats = hand.seats.len();
+        let mut dealt = vec![false; seats];
+        let mut live_blinds = vec![];
+        for event in &hand.events {
+            match event {
+                TableEvent::HoleCards { seat, .. } | TableEvent::HiddenCards { seat, .. } => {
+                    dealt[*seat] = true
+                }
+                TableEvent::Blind {
+                    seat, dead: false, ..
+                } => live_blinds.push(*seat),
+                _ => {}
+            }
+        }
+        let small_blind = live_blinds.first().copied();
+        // heads-up the button posts the small blind, and otherwise it sits just before it
+        let button = small_blind.map(|small_blind| {
+            if dealt.iter().filter(|dealt| **dealt).count() == 2 {
+                return small_blind;
+            }
+            (1..seats)
+                .map(|offset| (small_blind + seats - offset) % seats)
+                .find(|seat| dealt[*seat])
+                .unwrap_or(small_blind)
+        });
+
+        HistoryWriter {
+            hand,
+            names,
+            lines: vec![],
+            dealt,
+            button,
+            small_blind,
+            big_blind: live_blinds.get(1).copied(),
+            stacks: hand
+                .seats
+                .iter()
+                .map(|seat| seat.map_or(0, |(_, chips)| chips))
+                .collect(),
+            bets: vec![0; seats],
+            contributed: vec![0; seats],
+            returned: vec![0; seats],
+            collected: vec![0; seats],
+            dead_blinds: vec![0; seats],
+            live_blinds: 0,
+            folded: vec![None; seats],
+            voluntary: vec![false; seats],
+            shown: vec![None; seats],
+            mucked: vec![false; seats],
+            board: vec![],
+            runs: vec![],
+            dealing: false,
+            betting: false,
+            streets: 0,
+            showdown: false,
+        }
+    }
+
+    fn event(&mut self, event: &TableEvent) {
+        match event {
+            TableEvent::HandStarted { .. } => self.header(),
+            TableEvent::Ante { seat, amount } => {
+                self.put(*seat, *amount, false);
+                let line = format!("posts the ante {}", amount);
+                self.act(*seat, line);
+            }
+            TableEvent::Blind {
+                seat,
+                amount,
+                dead: true,
+            } => {
+                self.put(*seat, *amount, false);
+                self.dead_blinds[*seat] += amount;
+                self.voluntary[*seat] = true;
+            }
+            TableEvent::Blind { seat, amount, .. } => {
+                self.put(*seat, *amount, true);
+                self.voluntary[*seat] = true;
+                let dead = std::mem::take(&mut self.dead_blinds[*seat]);
+                let line = match self.live_blinds {
+                    _ if dead > 0 => format!("posts small & big blinds {}", dead + amount),
+                    0 => format!("posts small blind {}", amount),
+                    _ => format!("posts big blind {}", amount),
+                };
+                self.live_blinds += 1;
+                self.act(*seat, line);
+            }
+            TableEvent::BringIn { seat, amount } => {
+                self.put(*seat, *amount, true);
+                self.betting = true;
+                self.act(*seat, format!("brings in for {}", amount));
+            }
+            TableEvent::HoleCards { seat, cards } | TableEvent::UpCards { seat, cards } => {
+                self.deal();
+                // cards dealt after a betting round in stud start the next street
+                if std::mem::take(&mut self.betting) {
+                    self.close_round();
+                    self.streets += 1;
+                    self.lines
+                        .push(format!("*** {} STREET ***", ordinal(self.streets + 3)));
+                }
+                self.lines.push(format!(
+                    "Dealt to {} {}",
+                    self.names[*seat],
+                    codes(&cards.0)
+                ));
+            }
+            TableEvent::HiddenCards { .. } => self.deal(),
+            TableEvent::Action { seat, action } => {
+                self.betting = true;
+                self.action(*seat, *action);
+            }
+            TableEvent::Drew { seat, count } => {
+                if std::mem::take(&mut self.betting) {
+                    self.close_round();
+                    self.lines.push("*** FIRST DRAW ***".to_string());
+                }
+                let line = match count {
+                    0 => "stands pat".to_string(),
+                    count => format!("discards {} cards", count),
+                };
+                self.act(*seat, line);
+            }
+            TableEvent::Board { cards } => {
+                self.betting = false;
+                self.close_round();
+                self.write_streets("", &cards.0);
+                self.board = cards.0.clone();
+            }
+            TableEvent::RunItOffer { .. } => self.close_round(),
+            TableEvent::Run { run, cards } => {
+                self.close_round();
+                let prefix = format!("{} ", run_name(*run));
+                self.write_streets(&prefix, &cards.0);
+                self.runs.push(cards.0.clone());
+            }
+            TableEvent::Showdown { seat, cards } => {
+                self.start_showdown();
+                let line = match self.describe(cards) {
+                    Some(hand) => format!("shows {} ({})", codes(&cards.0), hand),
+                    None => format!("shows {}", codes(&cards.0)),
+                };
+                self.act(*seat, line);
+                self.shown[*seat] = Some(cards.clone());
+            }
+            TableEvent::Mucked { seat } => {
+                self.start_showdown();
+                self.act(*seat, "mucks hand".to_string());
+                self.mucked[*seat] = true;
+            }
+            TableEvent::PotWon { seat, amount } => {
+                self.close_round();
+                let returned = std::mem::take(&mut self.returned[*seat]);
+                let amount = amount.saturating_sub(returned);
+                if amount > 0 {
+                    self.collected[*seat] += amount;
+                    self.lines.push(format!(
+                        "{} collected {} from pot",
+                        self.names[*seat], amount
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn header(&mut self) {
+        let settings = &self.hand.settings;
+        let game = match settings.game {
+            GameType::Holdem => "Hold'em",
+            GameType::ShortDeck => "6+ Hold'em",
+            GameType::OmahaHiLo => "Omaha Hi/Lo",
+            GameType::Stud => "7 Card Stud",
+            GameType::StudHiLo => "7 Card Stud Hi/Lo",
+            GameType::Draw => "5 Card Draw",
+        };
+        let (limit, stakes) = match settings.structure {
+            BettingStructure::NoLimit { .. } => {
+                ("No Limit", (settings.small_blind, settings.big_blind))
+            }
+            BettingStructure::PotLimit { .. } => {
+                ("Pot Limit", (settings.small_blind, settings.big_blind))
+            }
+            BettingStructure::FixedLimit {
+                small_bet, big_bet, ..
+            } => ("Limit", (small_bet, big_bet)),
+            BettingStructure::SpreadLimit { min_bet, max_bet } => {
+                ("Spread Limit", (min_bet, max_bet))
+            }
+        };
+        self.lines.push(format!(
+            "PokerStars Hand #{}: {} {} ({}/{}) - {} ET",
+            self.hand.id(),
+            game,
+            limit,
+            stakes.0,
+            stakes.1,
+            format_time(eastern_time(self.hand.started))
+        ));
+        self.lines.push(format!(
+            "Table 'Table {}' {}-max Seat #{} is the button",
+            self.hand.table,
+            self.hand.seats.len(),
+            self.button.map_or(1, |button| button + 1)
+        ));
+        for (seat, sitting) in self.hand.seats.iter().enumerate() {
+            let Some((_, chips)) = sitting else {
+                continue;
+            };
+            let sitting_out = if self.dealt[seat] {
+                ""
+            } else {
+                " is sitting out"
+            };
+            self.lines.push(format!(
+                "Seat {}: {} ({} in chips){}",
+                seat + 1,
+                self.names[seat],
+                chips,
+                sitting_out
+            ));
+        }
+    }
+
+    /// Writes the line for something the seat did, as in "alice: checks", adding when it put
+    /// the seat all in.
+    fn act(&mut self, seat: usize, line: String) {
+        let all_in = match line.as_str() {
+            "checks" | "folds" | "mucks hand" => false,
+            line if line.starts_with("shows") => false,
+            _ => self.stacks[seat] == 0,
+        };
+        let all_in = if all_in { " and is all-in" } else { "" };
+        self.lines
+            .push(format!("{}: {}{}", self.names[seat], line, all_in));
+    }
+
+    /// Moves chips from the seat's stack into the pot, counting them towards its bet in the
+    /// betting round if asked to.
+    fn put(&mut self, seat: usize, amount: u32, bet: bool) {
+        let amount = amount.min(self.stacks[seat]);
+        self.stacks[seat] -= amount;
+        self.contributed[seat] += amount;
+        if bet {
+            self.bets[seat] += amount;
+        }
+    }
+
+    fn action(&mut self, seat: usize, action: Action) {
+        let current = self.bets.iter().copied().max().unwrap_or(0);
+        let line = match action {
+            Action::Fold => {
+                self.folded[seat] = Some(self.street_name());
+                "folds".to_string()
+            }
+            Action::Check => "checks".to_string(),
+            Action::Call => {
+                let amount = (current - self.bets[seat]).min(self.stacks[seat]);
+                self.put(seat, amount, true);
+                self.voluntary[seat] = true;
+                format!("calls {}", amount)
+            }
+            Action::Raise(total) => {
+                let amount = total.saturating_sub(self.bets[seat]).min(self.stacks[seat]);
+                self.put(seat, amount, true);
+                self.voluntary[seat] = true;
+                let total = self.bets[seat];
+                match current {
+                    0 => format!("bets {}", total),
+                    _ if total > current => format!("raises {} to {}", total - current, total),
+                    _ => format!("calls {}", amount),
+                }
+            }
+        };
+        self.act(seat, line);
+    }
+
+    /// Writes the line before the hole cards, along with any dead blinds not already written out
+    /// with a live one.
+    fn deal(&mut self) {
+        if self.dealing {
+            return;
+        }
+        self.dealing = true;
+        for seat in 0..self.dead_blinds.len() {
+            let dead = std::mem::take(&mut self.dead_blinds[seat]);
+            if dead > 0 {
+                self.act(seat, format!("posts small blind {}", dead));
+            }
+        }
+        self.lines.push("*** HOLE CARDS ***".to_string());
+    }
+
+    /// Ends the betting round, returning the part of the biggest bet nobody called.
+    fn close_round(&mut self) {
+        let mut order: Vec<usize> = (0..self.bets.len()).collect();
+        order.sort_by_key(|seat| std::cmp::Reverse(self.bets[*seat]));
+        if let [top, second, ..] = order[..] {
+            let uncalled = self.bets[top] - self.bets[second];
+            if uncalled > 0 {
+                self.stacks[top] += uncalled;
+                self.contributed[top] -= uncalled;
+                self.returned[top] += uncalled;
+                self.lines.push(format!(
+                    "Uncalled bet ({}) returned to {}",
+                    uncalled, self.names[top]
+                ));
+            }
+        }
+        self.bets.iter_mut().for_each(|bet| *bet = 0);
+    }
+
+    fn start_showdown(&mut self) {
+        self.close_round();
+        if !self.showdown {
+            self.showdown = true;
+            self.lines.push("*** SHOW DOWN ***".to_string());
+        }
+    }
+
+    /// Writes the lines of the streets dealt to make up the board, as in
+    /// "*** TURN *** [Ah Kd 2c] [7s]".
+    fn write_streets(&mut self, prefix: &str, board: &[Card]) {
+        for (street, end) in [("FLOP", 3), ("TURN", 4), ("RIVER", 5)] {
+            if self.board.len() >= end || board.len() < end {
+                continue;
+            }
+            let cards = match end {
+                3 => codes(&board[..3]),
+                _ => format!(
+                    "{} {}",
+                    codes(&board[..end - 1]),
+                    codes(&board[end - 1..end])
+                ),
+            };
+            self.lines
+                .push(format!("*** {}{} *** {}", prefix, street, cards));
+        }
+    }
+
+    /// Returns where the hand is, as a player's fold is described in the summary.
+    fn street_name(&self) -> &'static str {
+        match self.board.len() {
+            0 => "before Flop",
+            3 => "on the Flop",
+            4 => "on the Turn",
+            _ => "on the River",
+        }
+    }
+
+    /// Describes the best hand the hole cards make with the board, or the board of the first
+    /// run when it was run more than once.
+    fn describe(&self, hole: &CardCollection) -> Option<&'static str> {
+        let board = self.runs.first().unwrap_or(&self.board);
+        if board.len() != 5 {
+            return None;
+        }
+        let board = CardCollection(board.clone());
+        let hand = match self.hand.settings.game {
+            GameType::OmahaHiLo => get_best_omaha_hand(hole.clone(), board),
+            game => get_best_hand_with_rules(
+                CardCollection::concat(hole.clone(), board),
+                game.hand_rules(),
+            ),
+        };
+
+        Some(match hand {
+            Hand::HighCard(_) => "high card",
+            Hand::Pair(_) => "a pair",
+            Hand::TwoPair(_) => "two pair",
+            Hand::Set(_) => "three of a kind",
+            Hand::Straight(_) => "a straight",
+            Hand::Flush(_) => "a flush",
+            Hand::FullHouse(_) => "a full house",
+            Hand::FourOfAKind(_) => "four of a kind",
+            Hand::StraightFlush(_) => "a straight flush",
+            Hand::RoyaleFlush => "a royal flush",
+        })
+    }
+
+    fn finish(mut self) -> String {
+        self.close_round();
+        self.lines.push("*** SUMMARY ***".to_string());
+        self.lines.push(format!(
+            "Total pot {} | Rake 0",
+            self.contributed.iter().sum::<u32>()
+        ));
+        if self.runs.is_empty() && !self.board.is_empty() {
+            self.lines.push(format!("Board {}", codes(&self.board)));
+        }
+        for (run, board) in self.runs.iter().enumerate() {
+            let line = format!("{} Board {}", run_name(run as u32 + 1), codes(board));
+            self.lines.push(line);
+        }
+
+        for seat in 0..self.hand.seats.len() {
+            if !self.dealt[seat] {
+                continue;
+            }
+            let mut positions = String::new();
+            for (position, name) in [
+                (self.button, "button"),
+                (self.small_blind, "small blind"),
+                (self.big_blind, "big blind"),
+            ] {
+                if position == Some(seat) {
+                    positions.push_str(&format!(" ({})", name));
+                }
+            }
+            let description = self.shown[seat]
+                .as_ref()
+                .map(|cards| (cards, self.describe(cards)));
+            let result = match (self.folded[seat], description) {
+                (Some(street), _) if !self.voluntary[seat] && street == "before Flop" => {
+                    format!("folded {} (didn't bet)", street)
+                }
+                (Some(street), _) => format!("folded {}", street),
+                (None, Some((cards, hand))) => {
+                    let with = hand.map_or(String::new(), |hand| format!(" with {}", hand));
+                    match self.collected[seat] {
+                        0 => format!("showed {} and lost{}", codes(&cards.0), with),
+                        won => format!("showed {} and won ({}){}", codes(&cards.0), won, with),
+                    }
+                }
+                _ if self.collected[seat] > 0 => format!("collected ({})", self.collected[seat]),
+                _ => "mucked".to_string(),
+            };
+            self.lines.push(format!(
+                "Seat {}: {}{} {}",
+                seat + 1,
+                self.names[seat],
+                positions,
+                result
+            ));
+        }
+
+        self.lines.join("\n")
+    }
+}
+
+/// Returns the cards written as in "[Ah Kd]".
+fn codes(cards: &[Card]) -> String {
+    let codes: Vec<String> = cards.iter().map(|card| card.code()).collect();
+    format!("[{}]", codes.join(" "))
+}
+
+/// Returns the name a run of the board goes by, as in "FIRST".
+fn run_name(run: u32) -> String {
+    match run {
+        1 => "FIRST".to_string(),
+        2 => "SECOND".to_string(),
+        3 => "THIRD".to_string(),
+        run => ordinal(run as usize).to_uppercase(),
+    }
+}
+
+/// Returns the number of days from 1970/01/01 to the date.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Howard Hinnant's algorithm, with years starting in March so leap days come last
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+/// Returns the date the given number of days from 1970/01/01 falls on, as the year, month and
+/// day.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+/// Returns the number of seconds US Eastern time is behind UTC at the moment, which is 4 hours
+/// from 2am on the second Sunday in March to 2am on the first Sunday in November and 5 hours
+/// the rest of the year.
+pub fn eastern_offset(utc: i64) -> i64 {
+    let (year, _, _) = civil_from_days(utc.div_euclid(86400));
+    // the first Sunday on or after the date, where 1970/01/01 was a Thursday
+    let sunday = |month, day| {
+        let days = days_from_civil(year, month, day);
+        days + (3 - days).rem_euclid(7)
+    };
+    let summer_starts = (sunday(3, 8) * 86400) + 7 * 3600;
+    let summer_ends = (sunday(11, 1) * 86400) + 6 * 3600;
+    if (summer_starts..summer_ends).contains(&utc) {
+        4 * 3600
+    } else {
+        5 * 3600
+    }
+}
+
+/// Returns the Unix time shifted to US Eastern time, the time zone PokerStars writes hands in.
+fn eastern_time(utc: u64) -> i64 {
+    utc as i64 - eastern_offset(utc as i64)
+}
+
+/// Returns the time written as in "2024/01/31 8:05:00".
+fn format_time(time: i64) -> String {
+    let (year, month, day) = civil_from_days(time.div_euclid(86400));
+    let seconds = time.rem_euclid(86400);
+    format!(
+        "{}/{:02}/{:02} {}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::holdem::HoldemGame;
+    use crate::timer::TimerSettings;
+
+    /// Plays a seeded hand of 1/2 no limit with three players through the game engine, taking
+    /// the actions in order, and returns it as the table would keep it.
+    fn played_hand(seed: u64, actions: &[Action]) -> PlayedHand {
+        let settings = TableSettings {
+            game: GameType::Holdem,
+            structure: BettingStructure::NoLimit { big_blind: 2 },
+            small_blind: 1,
+            big_blind: 2,
+            seats: 3,
+            broadcast_delay: None,
+            timer: TimerSettings::default(),
+            max_orbits_sat_out: None,
+            max_runs: 1,
+            min_buy_in: 40,
+            max_buy_in: 200,
+        };
+        let mut game = HoldemGame::new(settings);
+        for seat in 0..3 {
+            game.sit_down(seat, 100 + seat as u32 * 50).unwrap();
+        }
+        game.seed_deck(seed);
+        let seats = (0..3).map(|seat| Some((seat + 10, 100 + seat as u32 * 50)));
+        let mut events = game.start_hand().unwrap();
+        for action in actions {
+            events.extend(game.act(*action).unwrap());
+        }
+        assert!(game.street().is_none());
+
+        PlayedHand {
+            table: 1,
+            hand: 7,
+            settings,
+            // 2024-03-01 17:30:00 UTC
+            started: 1_709_314_200,
+            seats: seats.collect(),
+            events,
+        }
+    }
+
+    fn name(player: usize) -> String {
+        ["alice", "bob", "carol"][player - 10].to_string()
+    }
+
+    #[test]
+    fn writes_a_showdown_like_pokerstars() {
+        let hand = played_hand(
+            1,
+            &[
+                Action::Raise(6),
+                Action::Fold,
+                Action::Call,
+                Action::Check,
+                Action::Raise(8),
+                Action::Call,
+                Action::Check,
+                Action::Check,
+                Action::Check,
+                Action::Check,
+            ],
+        );
+        let expected = "\
+PokerStars Hand #100000007: Hold'em No Limit (1/2) - 2024/03/01 12:30:00 ET
+Table 'Table 1' 3-max Seat #1 is the button
+Seat 1: alice (100 in chips)
+Seat 2: bob (150 in chips)
+Seat 3: carol (200 in chips)
+bob: posts small blind 1
+carol: posts big blind 2
+*** HOLE CARDS ***
+Dealt to alice [5h Ad]
+alice: raises 4 to 6
+bob: folds
+carol: calls 4
+*** FLOP *** [5d Jc 7d]
+carol: checks
+alice: bets 8
+carol: calls 8
+*** TURN *** [5d Jc 7d] [2h]
+carol: checks
+alice: checks
+*** RIVER *** [5d Jc 7d 2h] [9c]
+carol: checks
+alice: checks
+*** SHOW DOWN ***
+carol: shows [Td Kd] (high card)
+alice: shows [5h Ad] (a pair)
+alice collected 29 from pot
+*** SUMMARY ***
+Total pot 29 | Rake 0
+Board [5d Jc 7d 2h 9c]
+Seat 1: alice (button) showed [5h Ad] and won (29) with a pair
+Seat 2: bob (small blind) folded before Flop
+Seat 3: carol (big blind) showed [Td Kd] and lost with high card";
+        let written = hand.filter_for(Recipient::Seat(0)).to_pokerstars(name);
+        assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn writes_a_hand_won_without_a_showdown_like_pokerstars() {
+        let hand = played_hand(1, &[Action::Raise(6), Action::Fold, Action::Fold]);
+        let expected = "\
+PokerStars Hand #100000007: Hold'em No Limit (1/2) - 2024/03/01 12:30:00 ET
+Table 'Table 1' 3-max Seat #1 is the button
+Seat 1: alice (100 in chips)
+Seat 2: bob (150 in chips)
+Seat 3: carol (200 in chips)
+bob: posts small blind 1
+carol: posts big blind 2
+*** HOLE CARDS ***
+Dealt to bob [2c 6s]
+alice: raises 4 to 6
+bob: folds
+carol: folds
+Uncalled bet (4) returned to alice
+alice collected 5 from pot
+*** SUMMARY ***
+Total pot 5 | Rake 0
+Seat 1: alice (button) collected (5)
+Seat 2: bob (small blind) folded before Flop
+Seat 3: carol (big blind) folded before Flop";
+        let written = hand.filter_for(Recipient::Seat(1)).to_pokerstars(name);
+        assert_eq!(written, expected);
+    }
+}
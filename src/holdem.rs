@@ -0,0 +1,1464 @@
+use crate::betting::{Action, BettingRound, LegalActions};
+use crate::deck::*;
+use crate::events::TableEvent;
+use crate::hand::{assign_hand_rankings_with_rules, assign_omaha_hand_rankings, HandRules};
+use crate::hand_log::{HandLog, LoggedSeat};
+use crate::lobby::{TableGame, TableSettings};
+use crate::lowball::{assign_omaha_low_hand_rankings, LowballRules};
+use crate::showdown::{build_pots, resolve_omaha_hi_lo, run_pots, split_runs, Pot};
+use crate::strategy::Decision;
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Street {
+    Preflop,
+    Flop,
+    Turn,
+    River,
+}
+
+impl Street {
+    /// Parses the street's name.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "preflop" => Some(Street::Preflop),
+            "flop" => Some(Street::Flop),
+            "turn" => Some(Street::Turn),
+            "river" => Some(Street::River),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of cards on the board during the street.
+    pub fn board_cards(&self) -> usize {
+        match *self {
+            Street::Preflop => 0,
+            Street::Flop => 3,
+            Street::Turn => 4,
+            Street::River => 5,
+        }
+    }
+}
+
+impl fmt::Display for Street {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Street::Preflop => write!(f, "preflop"),
+            Street::Flop => write!(f, "flop"),
+            Street::Turn => write!(f, "turn"),
+            Street::River => write!(f, "river"),
+        }
+    }
+}
+
+/// Whether a seated player is being dealt in.
+#[derive(Clone, Copy, PartialEq)]
+pub enum SeatStatus {
+    Playing,
+    /// Keeping their seat without being dealt in. The blinds that pass them are missed.
+    SittingOut,
+    /// Back from sitting out, and waiting for the big blind to reach them instead of posting the
+    /// blinds they missed.
+    WaitingForBigBlind,
+}
+
+impl SeatStatus {
+    /// Parses the status's name.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "playing" => Some(SeatStatus::Playing),
+            "sitting out" => Some(SeatStatus::SittingOut),
+            "waiting for the big blind" => Some(SeatStatus::WaitingForBigBlind),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SeatStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SeatStatus::Playing => write!(f, "playing"),
+            SeatStatus::SittingOut => write!(f, "sitting out"),
+            SeatStatus::WaitingForBigBlind => write!(f, "waiting for the big blind"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct HoldemPlayer {
+    pub chips: u32,
+    pub hole_cards: CardCollection,
+    pub status: SeatStatus,
+    pub missed_small_blind: bool,
+    pub missed_big_blind: bool,
+    /// Whether the player throws away hands at showdown that can't win anything rather than
+    /// showing them.
+    pub muck_losing_hands: bool,
+    // the number of times the big blind has passed the player while they sat out
+    orbits_sat_out: u32,
+    // whether the player was dealt into the hand in progress and hasn't folded
+    in_hand: bool,
+    // the chips the player put in over the hand, not counting the current betting round
+    contributed: u32,
+}
+
+impl HoldemPlayer {
+    fn new(chips: u32) -> Self {
+        HoldemPlayer {
+            chips,
+            hole_cards: CardCollection(vec![]),
+            status: SeatStatus::Playing,
+            missed_small_blind: false,
+            missed_big_blind: false,
+            muck_losing_hands: true,
+            orbits_sat_out: 0,
+            in_hand: false,
+            contributed: 0,
+        }
+    }
+
+    pub fn in_hand(&self) -> bool {
+        self.in_hand
+    }
+
+    fn can_play(&self, or_waiting: bool) -> bool {
+        self.chips > 0
+            && (self.status == SeatStatus::Playing
+                || (or_waiting && self.status == SeatStatus::WaitingForBigBlind))
+    }
+}
+
+/// A Texas Hold'em table, played with the deck and hand rules of the table's game. The button
+/// moves to the next player being dealt in every hand, and the blinds follow it. Omaha hi-lo
+/// tables deal four hole cards instead, and split each pot between the best high hand and the
+/// best eight-or-better low, each made from exactly two hole cards and three from the board.
+///
+/// Players sitting out miss the small blind when it passes them and the big blind when it
+/// passes them, which also counts as an orbit sat out. On sitting back in they either post what
+/// they missed, a dead small blind and a live big blind, or wait for the big blind to reach
+/// them. Players who end up in the blinds when they return owe nothing more.
+///
+/// When everyone left in the hand is all in before the river, the table can let them run the
+/// rest of the board several times from the same deck, and each pot is split evenly between
+/// the runs.
+///
+/// At showdown the last player to bet or raise on the river shows first, or the first player
+/// to the left of the button if nobody did, and the others follow round the table. Players
+/// who can't win anything against the hands already shown may muck, unless someone is all in,
+/// in which case every hand is shown.
+///
+/// A table logging its hands reseeds the deck before every hand and keeps a log of the hand,
+/// which a table set up from the log can deal again exactly.
+pub struct HoldemGame {
+    settings: TableSettings,
+    rules: HandRules,
+    deck: Deck,
+    seats: Vec<Option<HoldemPlayer>>,
+    button: Option<usize>,
+    board: CardCollection,
+    street: Option<Street>,
+    round: Option<BettingRound>,
+    // the seats all in and how many runs each of them picked, while they are picking
+    run_choices: Option<Vec<(usize, Option<u32>)>>,
+    // the last seat to bet or raise on the current street
+    last_aggressor: Option<usize>,
+    // the seat that won the last hand without a showdown, until they show or the next hand
+    won_uncontested: Option<usize>,
+    // the ante every player dealt in posts, on top of the blinds
+    ante: u32,
+    hands_played: u32,
+    // whether hands are logged, and the log of the hand in progress or the last hand
+    logging: bool,
+    log: Option<HandLog>,
+}
+
+impl HoldemGame {
+    pub fn new(settings: TableSettings) -> Self {
+        HoldemGame {
+            settings,
+            rules: settings.game.hand_rules(),
+            deck: settings.game.new_deck(),
+            seats: vec![None; settings.seats],
+            button: None,
+            board: CardCollection(vec![]),
+            street: None,
+            round: None,
+            run_choices: None,
+            last_aggressor: None,
+            won_uncontested: None,
+            ante: 0,
+            hands_played: 0,
+            logging: false,
+            log: None,
+        }
+    }
+
+    /// Sets up a table the way the log says it stood before its hand, with the deck seeded
+    /// from the log's seed, so that starting a hand deals the logged hand again.
+    pub fn from_log(log: &HandLog) -> Result<Self, String> {
+        if log.seats.len() != log.settings.seats {
+            return Err(format!(
+                "the table has {} seats but the log has {}",
+                log.settings.seats,
+                log.seats.len()
+            ));
+        }
+        let mut game = HoldemGame::new(log.settings);
+        game.ante = log.ante;
+        game.button = log.button;
+        game.hands_played = log.hand.saturating_sub(1);
+        game.deck.seed(log.seed);
+        game.seats = log
+            .seats
+            .iter()
+            .map(|seat| {
+                seat.as_ref().map(|seat| HoldemPlayer {
+                    status: seat.status,
+                    missed_small_blind: seat.missed_small_blind,
+                    missed_big_blind: seat.missed_big_blind,
+                    muck_losing_hands: seat.muck_losing_hands,
+                    orbits_sat_out: seat.orbits_sat_out,
+                    ..HoldemPlayer::new(seat.chips)
+                })
+            })
+            .collect();
+
+        Ok(game)
+    }
+
+    /// Changes the blinds and ante from the next hand on, as when the blinds go up in a
+    /// tournament.
+    pub fn set_stakes(&mut self, small_blind: u32, big_blind: u32, ante: u32) {
+        self.settings.small_blind = small_blind;
+        self.settings.big_blind = big_blind;
+        self.settings.structure = self.settings.structure.with_stakes(small_blind, big_blind);
+        self.ante = ante;
+    }
+
+    /// Seeds the deck, so tables seeded the same way deal the same cards to the same seats.
+    pub fn seed_deck(&mut self, seed: u64) {
+        self.deck.seed(seed);
+    }
+
+    /// Logs every hand from the next one on.
+    pub fn log_hands(&mut self) {
+        self.logging = true;
+    }
+
+    /// Returns the log of the hand in progress, or of the last hand between hands, if hands
+    /// are being logged.
+    pub fn hand_log(&self) -> Option<&HandLog> {
+        self.log.as_ref()
+    }
+
+    pub fn settings(&self) -> &TableSettings {
+        &self.settings
+    }
+
+    pub fn ante(&self) -> u32 {
+        self.ante
+    }
+
+    /// Returns the number of players sitting at the table.
+    pub fn players_seated(&self) -> usize {
+        self.players().count()
+    }
+
+    /// Returns the first empty seat, if the table isn't full.
+    pub fn open_seat(&self) -> Option<usize> {
+        self.seats.iter().position(|seat| seat.is_none())
+    }
+
+    pub fn hands_played(&self) -> u32 {
+        self.hands_played
+    }
+
+    pub fn seats(&self) -> &Vec<Option<HoldemPlayer>> {
+        &self.seats
+    }
+
+    pub fn button(&self) -> Option<usize> {
+        self.button
+    }
+
+    pub fn board(&self) -> &CardCollection {
+        &self.board
+    }
+
+    /// Returns the street being played, or `None` between hands.
+    pub fn street(&self) -> Option<Street> {
+        self.street
+    }
+
+    /// Returns the total number of chips in the pot, including the current round's bets.
+    pub fn pot(&self) -> u32 {
+        let contributed: u32 = self.players().map(|(_, p)| p.contributed).sum();
+        let bets: u32 = match &self.round {
+            Some(round) => round.bets().iter().sum(),
+            None => 0,
+        };
+
+        contributed + bets
+    }
+
+    /// Returns the seat whose turn it is, if a hand is being played.
+    pub fn to_act(&self) -> Option<usize> {
+        self.round.as_ref()?.to_act()
+    }
+
+    pub fn legal_actions(&self) -> Option<LegalActions> {
+        self.round.as_ref()?.legal_actions()
+    }
+
+    /// Returns what the player to act gets to see, if the hand is waiting on an action.
+    pub fn decision(&self) -> Option<Decision> {
+        let round = self.round.as_ref()?;
+        let seat = round.to_act()?;
+        let player = self.seats[seat].as_ref()?;
+
+        Some(Decision {
+            seat,
+            hole_cards: player.hole_cards.clone(),
+            board: self.board.clone(),
+            street: self.street,
+            up_cards: vec![],
+            pot: self.pot(),
+            bet: round.bets().iter().max().copied().unwrap_or(0),
+            chips: player.chips,
+            big_blind: self.settings.big_blind,
+            opponents: self
+                .players()
+                .filter(|(other, p)| *other != seat && p.in_hand)
+                .count(),
+            rules: self.rules,
+            legal: round.legal_actions()?,
+        })
+    }
+
+    /// Returns the seats that still have to pick how many times to run the board, while the
+    /// players all in are picking.
+    pub fn awaiting_runs(&self) -> Vec<usize> {
+        match &self.run_choices {
+            Some(choices) => choices
+                .iter()
+                .filter(|(_, runs)| runs.is_none())
+                .map(|(seat, _)| *seat)
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Returns the most times the rest of the board can be run, which the cards left in the
+    /// deck may limit.
+    pub fn max_runs(&self) -> u32 {
+        let needed = 5usize.saturating_sub(self.board.0.len()).max(1);
+        let runs = (self.deck.cards_left() / needed) as u32;
+
+        runs.clamp(1, self.settings.max_runs.max(1))
+    }
+
+    /// Records how many times an all in player wants to run the board. Once everyone has picked,
+    /// the board is run the fewest times picked and the hand is settled.
+    pub fn choose_runs(&mut self, seat: usize, runs: u32) -> Result<Vec<TableEvent>, String> {
+        let max_runs = self.max_runs();
+        let Some(choices) = self.run_choices.as_mut() else {
+            return Err("nobody is picking how many times to run the board".to_string());
+        };
+        let Some((_, choice)) = choices.iter_mut().find(|(choosing, _)| *choosing == seat) else {
+            return Err(format!("seat {} isn't all in", seat + 1));
+        };
+        if choice.is_some() {
+            return Err("already picked how many times to run it".to_string());
+        }
+        if runs == 0 || runs > max_runs {
+            return Err(format!(
+                "can run the board between 1 and {} times",
+                max_runs
+            ));
+        }
+        *choice = Some(runs);
+
+        let mut events = vec![TableEvent::RunsChosen { seat, runs }];
+        if choices.iter().any(|(_, runs)| runs.is_none()) {
+            self.record(&events);
+            return Ok(events);
+        }
+        let runs = choices
+            .iter()
+            .filter_map(|(_, runs)| *runs)
+            .min()
+            .unwrap_or(1);
+        self.run_choices = None;
+        self.run_out(runs, &mut events);
+        self.record(&events);
+
+        Ok(events)
+    }
+
+    /// Seats a player with the given chips. They are dealt in from the next hand.
+    pub fn sit_down(&mut self, seat: usize, chips: u32) -> Result<(), String> {
+        match self.seats.get(seat) {
+            None => return Err(format!("there is no seat {}", seat + 1)),
+            Some(Some(_)) => return Err(format!("seat {} is taken", seat + 1)),
+            Some(None) => {}
+        }
+        if chips == 0 {
+            return Err("can't sit down without any chips".to_string());
+        }
+        self.seats[seat] = Some(HoldemPlayer::new(chips));
+
+        Ok(())
+    }
+
+    /// Adds chips to the player's stack, such as when they top up. Players can't add chips
+    /// while they are in a hand.
+    pub fn add_chips(&mut self, seat: usize, chips: u32) -> Result<(), String> {
+        let in_hand = self.player(seat)?.in_hand && self.street.is_some();
+        if in_hand {
+            return Err("can't add chips in the middle of a hand".to_string());
+        }
+        let player = self.player_mut(seat)?;
+        player.chips = player
+            .chips
+            .checked_add(chips)
+            .ok_or("that's more chips than a stack can hold")?;
+
+        Ok(())
+    }
+
+    /// Returns the chips the player would stand up with, or why they can't stand up yet.
+    pub fn leaving_chips(&self, seat: usize) -> Result<u32, String> {
+        let player = self.player(seat)?;
+        if player.in_hand && self.street.is_some() {
+            return Err("can't leave in the middle of a hand".to_string());
+        }
+
+        Ok(player.chips)
+    }
+
+    /// Removes the player from their seat and returns their chips. Players can't leave while
+    /// they are still in a hand.
+    pub fn stand_up(&mut self, seat: usize) -> Result<u32, String> {
+        let chips = self.leaving_chips(seat)?;
+        self.seats[seat] = None;
+        if self.won_uncontested == Some(seat) {
+            self.won_uncontested = None;
+        }
+
+        Ok(chips)
+    }
+
+    /// Sets whether the player mucks hands that can't win anything at showdown.
+    pub fn set_muck_losing_hands(&mut self, seat: usize, muck: bool) -> Result<(), String> {
+        self.player_mut(seat)?.muck_losing_hands = muck;
+
+        Ok(())
+    }
+
+    /// Shows the hole cards of the player who won the last hand without a showdown, if they
+    /// choose to.
+    pub fn show_cards(&mut self, seat: usize) -> Result<Vec<TableEvent>, String> {
+        if self.street.is_some() {
+            return Err("can only show once the hand is over".to_string());
+        }
+        if self.won_uncontested != Some(seat) {
+            return Err(
+                "only the player who won the last hand without a showdown can show".to_string(),
+            );
+        }
+        let cards = self.player(seat)?.hole_cards.clone();
+        self.won_uncontested = None;
+        let events = vec![TableEvent::Showdown { seat, cards }];
+        self.record(&events);
+
+        Ok(events)
+    }
+
+    /// Deals the rest of the board the last hand would have had if it hadn't ended early,
+    /// without taking the cards from the deck, so nothing about the game changes.
+    pub fn rabbit_hunt(&self) -> Result<Vec<TableEvent>, String> {
+        if self.street.is_some() {
+            return Err("can only rabbit hunt once the hand is over".to_string());
+        }
+        if self.hands_played == 0 {
+            return Err("no hand has been played yet".to_string());
+        }
+        let needed = 5usize.saturating_sub(self.board.0.len());
+        if needed == 0 {
+            return Err("the whole board was dealt".to_string());
+        }
+        let Some(cards) = self.deck.peek_cards(needed as u8) else {
+            return Err("there aren't enough cards left to rabbit hunt".to_string());
+        };
+
+        Ok(vec![TableEvent::RabbitHunt {
+            cards: CardCollection::concat(self.board.clone(), cards),
+        }])
+    }
+
+    /// Sits the player out from the next hand on. A player in the hand in progress still plays
+    /// it out.
+    pub fn sit_out(&mut self, seat: usize) -> Result<(), String> {
+        let player = self.player_mut(seat)?;
+        if player.status == SeatStatus::SittingOut {
+            return Err("already sitting out".to_string());
+        }
+        player.status = SeatStatus::SittingOut;
+        player.orbits_sat_out = 0;
+
+        Ok(())
+    }
+
+    /// Sits the player back in. A player who missed blinds either posts them in the next hand
+    /// they are dealt into, or waits for the big blind to reach them.
+    pub fn sit_in(&mut self, seat: usize, post_missed_blinds: bool) -> Result<(), String> {
+        let player = self.player_mut(seat)?;
+        if player.status == SeatStatus::Playing {
+            return Err("already playing".to_string());
+        }
+        let missed = player.missed_small_blind || player.missed_big_blind;
+        player.status = if missed && !post_missed_blinds {
+            SeatStatus::WaitingForBigBlind
+        } else {
+            SeatStatus::Playing
+        };
+
+        Ok(())
+    }
+
+    /// Moves the button, removes players who sat out too long, collects the blinds and deals the
+    /// hole cards. Returns the events of the start of the hand, or an error if a hand is already
+    /// being played or there aren't two players to deal in.
+    pub fn start_hand(&mut self) -> Result<Vec<TableEvent>, String> {
+        if self.street.is_some() {
+            return Err("a hand is already being played".to_string());
+        }
+        let Some((button, small_blind, big_blind)) = self.next_positions() else {
+            return Err("need at least two players to start a hand".to_string());
+        };
+        if self.logging {
+            let seed = self.deck.next_seed();
+            self.deck.seed(seed);
+            self.log = Some(self.start_log(seed));
+        }
+
+        self.button = Some(button);
+        self.hands_played += 1;
+        self.last_aggressor = None;
+        self.won_uncontested = None;
+        let mut events = vec![TableEvent::HandStarted {
+            hand: self.hands_played,
+        }];
+        events.extend(self.miss_blinds(button, small_blind, big_blind));
+
+        for seat in 0..self.seats.len() {
+            let in_blinds = seat == small_blind || seat == big_blind;
+            if let Some(player) = self.seats[seat].as_mut() {
+                player.in_hand = player.can_play(false) || seat == big_blind;
+                player.hole_cards = CardCollection(vec![]);
+                player.contributed = 0;
+                if in_blinds {
+                    player.status = SeatStatus::Playing;
+                    player.missed_small_blind = false;
+                    player.missed_big_blind = false;
+                }
+            }
+        }
+
+        // antes and dead blinds go in the pot before the betting starts
+        let mut dead = 0;
+        for seat in 0..self.seats.len() {
+            let Some(player) = self.seats[seat].as_mut() else {
+                continue;
+            };
+            if !player.in_hand || self.ante == 0 {
+                continue;
+            }
+            let amount = self.ante.min(player.chips);
+            player.chips -= amount;
+            player.contributed += amount;
+            dead += amount;
+            events.push(TableEvent::Ante { seat, amount });
+        }
+        for seat in 0..self.seats.len() {
+            let Some(player) = self.seats[seat].as_mut() else {
+                continue;
+            };
+            if !player.in_hand || !player.missed_small_blind {
+                continue;
+            }
+            let amount = self.settings.small_blind.min(player.chips);
+            player.chips -= amount;
+            player.contributed += amount;
+            player.missed_small_blind = false;
+            dead += amount;
+            events.push(TableEvent::Blind {
+                seat,
+                amount,
+                dead: true,
+            });
+        }
+
+        let (stacks, folded) = self.round_stacks();
+        let mut round = BettingRound::new(
+            self.settings.structure,
+            stacks,
+            folded,
+            dead,
+            false,
+            big_blind + 1,
+        );
+        let mut blinds = vec![
+            (small_blind, self.settings.small_blind),
+            (big_blind, self.settings.big_blind),
+        ];
+        for (seat, player) in self.players() {
+            if player.in_hand && player.missed_big_blind {
+                blinds.push((seat, self.settings.big_blind));
+            }
+        }
+        for (seat, amount) in blinds {
+            let amount = round.post_blind(seat, amount);
+            events.push(TableEvent::Blind {
+                seat,
+                amount,
+                dead: false,
+            });
+        }
+        self.round = Some(round);
+        self.sync_stacks();
+
+        self.deck.shuffle();
+        self.board = CardCollection(vec![]);
+        self.street = Some(Street::Preflop);
+        for seat in 0..self.seats.len() {
+            let dealt = match &self.seats[seat] {
+                Some(player) => player.in_hand,
+                None => false,
+            };
+            if !dealt {
+                continue;
+            }
+            let cards = self.pop_cards(self.settings.game.hole_cards());
+            if let Some(player) = self.seats[seat].as_mut() {
+                player.hole_cards = cards.clone();
+                player.missed_big_blind = false;
+            }
+            events.push(TableEvent::HoleCards { seat, cards });
+        }
+
+        self.progress(&mut events);
+        self.record(&events);
+
+        Ok(events)
+    }
+
+    /// Takes the action for the player to act and plays the hand on as far as it can go without
+    /// another action, returning everything that happened. An illegal action changes nothing.
+    pub fn act(&mut self, action: Action) -> Result<Vec<TableEvent>, String> {
+        let Some(round) = self.round.as_mut() else {
+            return Err("there isn't a hand being played".to_string());
+        };
+        let Some(seat) = round.to_act() else {
+            return Err("nobody is left to act".to_string());
+        };
+        round.act(action)?;
+        match action {
+            Action::Fold => {
+                if let Some(player) = self.seats[seat].as_mut() {
+                    player.in_hand = false;
+                }
+            }
+            Action::Raise(_) => self.last_aggressor = Some(seat),
+            _ => {}
+        }
+        self.sync_stacks();
+
+        let mut events = vec![TableEvent::Action { seat, action }];
+        self.progress(&mut events);
+        self.record(&events);
+
+        Ok(events)
+    }
+
+    /// Plays the hand on until someone has to act, dealing the next streets as betting rounds
+    /// complete and settling the pots once the hand is over.
+    fn progress(&mut self, events: &mut Vec<TableEvent>) {
+        loop {
+            let Some(round) = &self.round else {
+                return;
+            };
+            if let Some(seat) = round.to_act() {
+                events.push(TableEvent::ToAct {
+                    seat,
+                    pot: self.pot(),
+                });
+                return;
+            }
+            let bets = round.bets().clone();
+            for (seat, bet) in bets.iter().enumerate() {
+                if let Some(player) = self.seats[seat].as_mut() {
+                    player.contributed += bet;
+                }
+            }
+            self.round = None;
+
+            let in_hand = self.players().filter(|(_, p)| p.in_hand).count();
+            if in_hand > 1 && self.street != Some(Street::River) && self.offer_runs(events) {
+                return;
+            }
+            let (street, cards) = match self.street {
+                _ if in_hand <= 1 => return self.showdown(events),
+                Some(Street::Preflop) => (Street::Flop, 3),
+                Some(Street::Flop) => (Street::Turn, 1),
+                Some(Street::Turn) => (Street::River, 1),
+                _ => return self.showdown(events),
+            };
+            let cards = self.pop_cards(cards);
+            self.board = CardCollection::concat(self.board.clone(), cards);
+            self.street = Some(street);
+            events.push(TableEvent::Board {
+                cards: self.board.clone(),
+            });
+
+            let (stacks, folded) = self.round_stacks();
+            let button = self.button.unwrap_or(0);
+            self.last_aggressor = None;
+            self.round = Some(BettingRound::new(
+                self.settings.structure,
+                stacks,
+                folded,
+                self.pot(),
+                street != Street::Flop,
+                button + 1,
+            ));
+        }
+    }
+
+    /// Lets the players left in the hand pick how many times to run the board if none of them
+    /// can bet any more and the table allows more than one run. Returns whether they were asked.
+    fn offer_runs(&mut self, events: &mut Vec<TableEvent>) -> bool {
+        let can_bet = self
+            .players()
+            .filter(|(_, p)| p.in_hand && p.chips > 0)
+            .count();
+        let max_runs = self.max_runs();
+        if can_bet > 1 || max_runs <= 1 {
+            return false;
+        }
+        let seats: Vec<usize> = self
+            .players()
+            .filter(|(_, p)| p.in_hand)
+            .map(|(seat, _)| seat)
+            .collect();
+        self.run_choices = Some(seats.iter().map(|seat| (*seat, None)).collect());
+        events.push(TableEvent::RunItOffer { seats, max_runs });
+
+        true
+    }
+
+    /// Deals the rest of the board the given number of times, each run from the cards left
+    /// after the last, and settles the hand.
+    fn run_out(&mut self, runs: u32, events: &mut Vec<TableEvent>) {
+        let needed = 5 - self.board.0.len() as u8;
+        let mut boards = vec![];
+        for run in 1..=runs {
+            let cards = self.pop_cards(needed);
+            let board = CardCollection::concat(self.board.clone(), cards);
+            if runs == 1 {
+                events.push(TableEvent::Board {
+                    cards: board.clone(),
+                });
+            } else {
+                events.push(TableEvent::Run {
+                    run,
+                    cards: board.clone(),
+                });
+            }
+            boards.push(board);
+        }
+        self.board = boards[0].clone();
+        self.street = Some(Street::River);
+        self.settle(boards, events);
+    }
+
+    /// Settles the hand on the board dealt.
+    fn showdown(&mut self, events: &mut Vec<TableEvent>) {
+        self.settle(vec![self.board.clone()], events);
+    }
+
+    /// Splits the pots between the players left in the hand and ends it, splitting each pot
+    /// evenly between the runs of the board, and in hi-lo games each run's share between high
+    /// and low. Odd chips go to the players closest to the left of the button.
+    fn settle(&mut self, boards: Vec<CardCollection>, events: &mut Vec<TableEvent>) {
+        let seats = self.seats.len();
+        let button = self.button.unwrap_or(0);
+        // the seats in the order odd chips are handed out
+        let order: Vec<usize> = (1..=seats)
+            .map(|offset| (button + offset) % seats)
+            .collect();
+
+        let mut contributions = vec![];
+        let mut folded = vec![];
+        for seat in &order {
+            match &self.seats[*seat] {
+                Some(player) => {
+                    contributions.push(player.contributed);
+                    folded.push(!player.in_hand);
+                }
+                None => {
+                    contributions.push(0);
+                    folded.push(true);
+                }
+            }
+        }
+        let pots = build_pots(&contributions, &folded);
+
+        let active: Vec<usize> = (0..order.len()).filter(|index| !folded[*index]).collect();
+        let hi_lo = self.settings.game.is_hi_lo();
+        let winnings = if active.len() == 1 {
+            let mut rankings = vec![u8::MAX; order.len()];
+            rankings[active[0]] = 1;
+            self.won_uncontested = Some(order[active[0]]);
+            split_runs(pots, vec![rankings])
+        } else if hi_lo {
+            let holes: Vec<CardCollection> = order
+                .iter()
+                .map(|seat| match &self.seats[*seat] {
+                    Some(player) => player.hole_cards.clone(),
+                    None => CardCollection(vec![]),
+                })
+                .collect();
+            // the high and low rankings of every run, where hands without a low rank last
+            let mut rankings = vec![];
+            for board in &boards {
+                rankings.push(assign_omaha_hand_rankings(holes.clone(), board.clone()));
+                let low = assign_omaha_low_hand_rankings(
+                    holes.clone(),
+                    board.clone(),
+                    LowballRules::EightOrBetter,
+                );
+                rankings.push(low.into_iter().map(|low| low.unwrap_or(u8::MAX)).collect());
+            }
+            self.show_hands(&order, &active, &pots, &rankings, events);
+
+            let runs = boards.len() as u32;
+            let mut winnings = vec![0; order.len()];
+            for (run, board) in boards.into_iter().enumerate() {
+                let pots = run_pots(&pots, runs, run as u32);
+                for (total, payout) in
+                    winnings
+                        .iter_mut()
+                        .zip(resolve_omaha_hi_lo(holes.clone(), board, pots))
+                {
+                    *total += payout.total();
+                }
+            }
+            winnings
+        } else {
+            let mut run_rankings = vec![];
+            for board in boards {
+                let mut rankings = vec![u8::MAX; order.len()];
+                let hands = active
+                    .iter()
+                    .filter_map(|index| self.seats[order[*index]].as_ref())
+                    .map(|player| CardCollection::concat(player.hole_cards.clone(), board.clone()))
+                    .collect();
+                for (index, ranking) in active
+                    .iter()
+                    .zip(assign_hand_rankings_with_rules(hands, self.rules))
+                {
+                    rankings[*index] = ranking;
+                }
+                run_rankings.push(rankings);
+            }
+            self.show_hands(&order, &active, &pots, &run_rankings, events);
+            split_runs(pots, run_rankings)
+        };
+
+        for (index, amount) in winnings.into_iter().enumerate() {
+            let seat = order[index];
+            if let Some(player) = self.seats[seat].as_mut() {
+                player.chips += amount;
+                player.contributed = 0;
+                player.in_hand = false;
+                if amount > 0 {
+                    events.push(TableEvent::PotWon { seat, amount });
+                }
+            }
+        }
+        self.street = None;
+        events.push(TableEvent::HandFinished {
+            hand: self.hands_played,
+        });
+    }
+
+    /// Shows or mucks the hands of the players left at showdown, in showdown order. `order`
+    /// holds the seats as they are indexed in `active`, `pots` and `rankings`, which has the
+    /// rankings of each way a pot can be won, such as each run or the low half of a pot, with
+    /// `u8::MAX` for hands that can't win that way.
+    fn show_hands(
+        &self,
+        order: &[usize],
+        active: &[usize],
+        pots: &[Pot],
+        rankings: &[Vec<u8>],
+        events: &mut Vec<TableEvent>,
+    ) {
+        let all_in = active.iter().any(|index| {
+            self.seats[order[*index]]
+                .as_ref()
+                .is_some_and(|player| player.chips == 0)
+        });
+        let first = self
+            .last_aggressor
+            .and_then(|seat| active.iter().position(|index| order[*index] == seat))
+            .unwrap_or(0);
+
+        let mut shown: Vec<usize> = vec![];
+        for offset in 0..active.len() {
+            let index = active[(first + offset) % active.len()];
+            let seat = order[index];
+            let Some(player) = &self.seats[seat] else {
+                continue;
+            };
+            // a hand can win a pot if nothing shown that is eligible for it beats it
+            let can_win = pots
+                .iter()
+                .filter(|pot| pot.eligible.contains(&index))
+                .any(|pot| {
+                    rankings.iter().any(|rankings| {
+                        rankings[index] != u8::MAX
+                            && pot
+                                .eligible
+                                .iter()
+                                .filter(|other| shown.contains(other))
+                                .all(|other| rankings[index] <= rankings[*other])
+                    })
+                });
+            if all_in || can_win || !player.muck_losing_hands {
+                events.push(TableEvent::Showdown {
+                    seat,
+                    cards: player.hole_cards.clone(),
+                });
+                shown.push(index);
+            } else {
+                events.push(TableEvent::Mucked { seat });
+            }
+        }
+    }
+
+    /// Marks the blinds missed by the players sitting out between the button and the big
+    /// blind, and removes the players who have now sat out too many orbits.
+    fn miss_blinds(
+        &mut self,
+        button: usize,
+        small_blind: usize,
+        big_blind: usize,
+    ) -> Vec<TableEvent> {
+        let seats = self.seats.len();
+        let mut events = vec![];
+        let mut passed_small_blind = small_blind == button;
+        for offset in 1..seats {
+            let seat = (button + offset) % seats;
+            if seat == big_blind {
+                break;
+            }
+            if seat == small_blind {
+                passed_small_blind = true;
+                continue;
+            }
+            let Some(player) = self.seats[seat].as_mut() else {
+                continue;
+            };
+            if player.status != SeatStatus::SittingOut {
+                continue;
+            }
+            if !passed_small_blind {
+                player.missed_small_blind = true;
+                continue;
+            }
+            player.missed_big_blind = true;
+            player.orbits_sat_out += 1;
+            let too_long = self
+                .settings
+                .max_orbits_sat_out
+                .is_some_and(|max| player.orbits_sat_out >= max);
+            if too_long {
+                let chips = player.chips;
+                self.seats[seat] = None;
+                events.push(TableEvent::Removed { seat, chips });
+            }
+        }
+
+        events
+    }
+
+    /// Returns the stacks and folds to start a betting round with, where seats not in the hand
+    /// count as folded.
+    fn round_stacks(&self) -> (Vec<u32>, Vec<bool>) {
+        self.seats
+            .iter()
+            .map(|seat| match seat {
+                Some(player) if player.in_hand => (player.chips, false),
+                _ => (0, true),
+            })
+            .unzip()
+    }
+
+    /// Copies the stacks of the players still in the hand back from the betting round. Folded
+    /// players count as having no chips in the rounds after they fold, so their stacks are
+    /// left alone.
+    fn sync_stacks(&mut self) {
+        let Some(round) = &self.round else {
+            return;
+        };
+        for (seat, stack) in round.stacks().iter().enumerate() {
+            if let Some(player) = self.seats[seat].as_mut() {
+                if player.in_hand {
+                    player.chips = *stack;
+                }
+            }
+        }
+    }
+
+    /// Returns the seat that will post the big blind in the next hand, if there are enough
+    /// players to deal one.
+    pub fn next_big_blind(&self) -> Option<usize> {
+        self.next_positions().map(|(_, _, big_blind)| big_blind)
+    }
+
+    /// Returns the button, small blind and big blind seats for the next hand, or `None` if
+    /// there aren't two players to deal in.
+    fn next_positions(&self) -> Option<(usize, usize, usize)> {
+        let from = self.button.map_or(0, |button| button + 1);
+        let button = self.next_seat(from, false)?;
+        let small_blind = self.next_seat(button + 1, false).unwrap_or(button);
+        let big_blind = self.next_seat(small_blind + 1, true)?;
+        let dealt_in = self.players().filter(|(_, p)| p.can_play(false)).count()
+            + usize::from(!self.seat_can_play(big_blind, false));
+        if dealt_in < 2 {
+            return None;
+        }
+        // heads-up the button posts the small blind
+        if dealt_in == 2 && small_blind != button {
+            return Some((button, button, small_blind));
+        }
+
+        Some((button, small_blind, big_blind))
+    }
+
+    /// Returns the first seat from the given one onwards with a player who can be dealt in,
+    /// counting players waiting for the big blind if asked to.
+    fn next_seat(&self, from: usize, or_waiting: bool) -> Option<usize> {
+        let seats = self.seats.len();
+        (0..seats)
+            .map(|offset| (from + offset) % seats)
+            .find(|seat| self.seat_can_play(*seat, or_waiting))
+    }
+
+    fn seat_can_play(&self, seat: usize, or_waiting: bool) -> bool {
+        match &self.seats[seat] {
+            Some(player) => player.can_play(or_waiting),
+            None => false,
+        }
+    }
+
+    fn players(&self) -> impl Iterator<Item = (usize, &HoldemPlayer)> {
+        self.seats
+            .iter()
+            .enumerate()
+            .filter_map(|(seat, player)| player.as_ref().map(|player| (seat, player)))
+    }
+
+    fn player(&self, seat: usize) -> Result<&HoldemPlayer, String> {
+        match self.seats.get(seat) {
+            Some(Some(player)) => Ok(player),
+            _ => Err(format!("nobody is sitting in seat {}", seat + 1)),
+        }
+    }
+
+    fn player_mut(&mut self, seat: usize) -> Result<&mut HoldemPlayer, String> {
+        match self.seats.get_mut(seat) {
+            Some(Some(player)) => Ok(player),
+            _ => Err(format!("nobody is sitting in seat {}", seat + 1)),
+        }
+    }
+
+    /// Starts the log of a hand about to be dealt from a deck seeded with the seed.
+    fn start_log(&self, seed: u64) -> HandLog {
+        let seats = self
+            .seats
+            .iter()
+            .map(|seat| {
+                seat.as_ref().map(|player| LoggedSeat {
+                    chips: player.chips,
+                    status: player.status,
+                    missed_small_blind: player.missed_small_blind,
+                    missed_big_blind: player.missed_big_blind,
+                    muck_losing_hands: player.muck_losing_hands,
+                    orbits_sat_out: player.orbits_sat_out,
+                })
+            })
+            .collect();
+
+        HandLog {
+            settings: self.settings,
+            ante: self.ante,
+            hand: self.hands_played + 1,
+            seed,
+            button: self.button,
+            seats,
+            events: vec![],
+        }
+    }
+
+    /// Adds the events to the log of the hand, if hands are being logged.
+    fn record(&mut self, events: &[TableEvent]) {
+        if let Some(log) = self.log.as_mut() {
+            log.events.extend(events.iter().cloned());
+        }
+    }
+
+    fn pop_cards(&mut self, num: u8) -> CardCollection {
+        match self.deck.pop_cards(num) {
+            Some(cards) => cards,
+            None => panic!("ran out of cards while dealing holdem"),
+        }
+    }
+}
+
+impl TableGame for HoldemGame {
+    fn hand_in_progress(&self) -> bool {
+        self.street.is_some()
+    }
+
+    fn stacks(&self) -> Vec<u32> {
+        self.seats
+            .iter()
+            .map(|player| player.as_ref().map_or(0, |player| player.chips))
+            .collect()
+    }
+
+    fn hole_cards(&self, seat: usize) -> CardCollection {
+        match self.seats.get(seat) {
+            Some(Some(player)) => player.hole_cards.clone(),
+            _ => CardCollection(vec![]),
+        }
+    }
+
+    fn sit_down(&mut self, seat: usize, chips: u32) -> Result<(), String> {
+        HoldemGame::sit_down(self, seat, chips)
+    }
+
+    fn add_chips(&mut self, seat: usize, chips: u32) -> Result<(), String> {
+        HoldemGame::add_chips(self, seat, chips)
+    }
+
+    fn leaving_chips(&self, seat: usize) -> Result<u32, String> {
+        HoldemGame::leaving_chips(self, seat)
+    }
+
+    fn stand_up(&mut self, seat: usize) -> Result<u32, String> {
+        HoldemGame::stand_up(self, seat)
+    }
+
+    fn sit_out(&mut self, seat: usize) -> Result<(), String> {
+        HoldemGame::sit_out(self, seat)
+    }
+
+    fn sit_in(&mut self, seat: usize, post_missed_blinds: bool) -> Result<(), String> {
+        HoldemGame::sit_in(self, seat, post_missed_blinds)
+    }
+
+    fn start_hand(&mut self) -> Result<Vec<TableEvent>, String> {
+        HoldemGame::start_hand(self)
+    }
+
+    fn decision(&self) -> Option<Decision> {
+        HoldemGame::decision(self)
+    }
+
+    fn to_act(&self) -> Option<usize> {
+        HoldemGame::to_act(self)
+    }
+
+    fn act(&mut self, action: Action) -> Result<Vec<TableEvent>, String> {
+        HoldemGame::act(self, action)
+    }
+
+    fn awaiting_runs(&self) -> Vec<usize> {
+        HoldemGame::awaiting_runs(self)
+    }
+
+    fn max_runs(&self) -> u32 {
+        HoldemGame::max_runs(self)
+    }
+
+    fn choose_runs(&mut self, seat: usize, runs: u32) -> Result<Vec<TableEvent>, String> {
+        HoldemGame::choose_runs(self, seat, runs)
+    }
+
+    fn set_muck_losing_hands(&mut self, seat: usize, muck: bool) -> Result<(), String> {
+        HoldemGame::set_muck_losing_hands(self, seat, muck)
+    }
+
+    fn show_cards(&mut self, seat: usize) -> Result<Vec<TableEvent>, String> {
+        HoldemGame::show_cards(self, seat)
+    }
+
+    fn rabbit_hunt(&self) -> Result<Vec<TableEvent>, String> {
+        HoldemGame::rabbit_hunt(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::betting::BettingStructure;
+    use crate::lobby::GameType;
+    use crate::timer::TimerSettings;
+
+    fn settings(game: GameType, seats: usize) -> TableSettings {
+        TableSettings {
+            game,
+            structure: BettingStructure::NoLimit { big_blind: 2 },
+            small_blind: 1,
+            big_blind: 2,
+            seats,
+            broadcast_delay: None,
+            timer: TimerSettings::default(),
+            max_orbits_sat_out: Some(2),
+            max_runs: 1,
+            min_buy_in: 40,
+            max_buy_in: 200,
+        }
+    }
+
+    /// Seats a player with 100 chips in each of the seats.
+    fn table(game: GameType, seats: usize) -> HoldemGame {
+        let mut table = HoldemGame::new(settings(game, seats));
+        for seat in 0..seats {
+            table.sit_down(seat, 100).unwrap();
+        }
+        table
+    }
+
+    /// Checks or calls every decision until the hand is over.
+    fn check_down(game: &mut HoldemGame) -> Vec<TableEvent> {
+        let mut events = vec![];
+        while let Some(legal) = game.legal_actions() {
+            let action = if legal.call == 0 {
+                Action::Check
+            } else {
+                Action::Call
+            };
+            events.extend(game.act(action).unwrap());
+        }
+        events
+    }
+
+    #[test]
+    fn omaha_hi_lo_splits_the_pot_between_high_and_low() {
+        let mut game = table(GameType::OmahaHiLo, 3);
+        game.seed_deck(27);
+        let mut events = game.start_hand().unwrap();
+        events.extend(check_down(&mut game));
+
+        let mut dealt = 0;
+        let mut won = vec![];
+        for event in &events {
+            match event {
+                TableEvent::HoleCards { cards, .. } => {
+                    assert_eq!(cards.0.len(), 4);
+                    dealt += 1;
+                }
+                TableEvent::PotWon { seat, amount } => won.push((*seat, *amount)),
+                _ => (),
+            }
+        }
+        assert_eq!(dealt, 3);
+        // the second seat's straight takes the high half and ties the first seat's six low,
+        // keeping the odd chip of the low half
+        assert_eq!(won, vec![(1, 5), (0, 1)]);
+        let chips: u32 = game
+            .seats()
+            .iter()
+            .flatten()
+            .map(|player| player.chips)
+            .sum();
+        assert_eq!(chips, 300);
+    }
+
+    /// Plays a hand, checking it down, and returns its events.
+    fn play_hand(game: &mut HoldemGame) -> Vec<TableEvent> {
+        let mut events = game.start_hand().unwrap();
+        events.extend(check_down(game));
+        events
+    }
+
+    fn blinds(events: &[TableEvent], seat: usize) -> Vec<(u32, bool)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                TableEvent::Blind {
+                    seat: posted,
+                    amount,
+                    dead,
+                } if *posted == seat => Some((*amount, *dead)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn player(game: &HoldemGame, seat: usize) -> &HoldemPlayer {
+        game.seats()[seat].as_ref().unwrap()
+    }
+
+    #[test]
+    fn players_sitting_out_miss_the_blinds_that_pass_them() {
+        let mut game = table(GameType::Holdem, 4);
+        game.sit_out(1).unwrap();
+        assert!(game.sit_out(1).is_err());
+
+        // the button is seat 0 and the small blind skips seat 1 for seat 2
+        let events = play_hand(&mut game);
+        assert!(player(&game, 1).hole_cards.0.is_empty());
+        assert!(blinds(&events, 1).is_empty());
+        assert_eq!(blinds(&events, 2), vec![(1, false)]);
+        assert!(player(&game, 1).missed_small_blind);
+        assert!(!player(&game, 1).missed_big_blind);
+
+        // two hands on the blinds are seats 0 and 2, passing seat 1 again
+        play_hand(&mut game);
+        play_hand(&mut game);
+        assert!(player(&game, 1).missed_big_blind);
+        assert_eq!(player(&game, 1).chips, 100);
+    }
+
+    /// Sits seat 1 out of a four seat table until it has missed both blinds.
+    fn missed_both_blinds() -> HoldemGame {
+        let mut game = table(GameType::Holdem, 4);
+        game.sit_out(1).unwrap();
+        for _ in 0..3 {
+            play_hand(&mut game);
+        }
+        game
+    }
+
+    #[test]
+    fn players_back_in_wait_for_the_big_blind_unless_they_post() {
+        let mut waiting = missed_both_blinds();
+        let mut posting = missed_both_blinds();
+
+        waiting.sit_in(1, false).unwrap();
+        assert!(player(&waiting, 1).status == SeatStatus::WaitingForBigBlind);
+        for _ in 0..2 {
+            let events = play_hand(&mut waiting);
+            assert!(!events
+                .iter()
+                .any(|event| matches!(event, TableEvent::HoleCards { seat: 1, .. })));
+        }
+        // the big blind reaches seat 1, which posts it live without any dead blind
+        let events = play_hand(&mut waiting);
+        assert_eq!(blinds(&events, 1), vec![(2, false)]);
+        assert!(player(&waiting, 1).status == SeatStatus::Playing);
+        assert!(!player(&waiting, 1).missed_small_blind);
+
+        // posting deals the player straight back in on the button with a dead small blind and
+        // a live big one
+        play_hand(&mut posting);
+        posting.sit_in(1, true).unwrap();
+        assert!(player(&posting, 1).status == SeatStatus::Playing);
+        let events = posting.start_hand().unwrap();
+        assert_eq!(blinds(&events, 1), vec![(1, true), (2, false)]);
+        assert_eq!(player(&posting, 1).hole_cards.0.len(), 2);
+        assert!(!player(&posting, 1).missed_small_blind);
+        assert!(!player(&posting, 1).missed_big_blind);
+    }
+
+    #[test]
+    fn players_sitting_out_are_removed_after_the_most_orbits() {
+        let mut game = table(GameType::Holdem, 4);
+        game.sit_out(1).unwrap();
+        let mut removed = vec![];
+        for hand in 1..=8 {
+            for event in play_hand(&mut game) {
+                if let TableEvent::Removed { seat, chips } = event {
+                    removed.push((hand, seat, chips));
+                }
+            }
+        }
+        // the big blind passes seat 1 every third hand from the third, and two orbits is the
+        // most the table allows
+        assert_eq!(removed, vec![(6, 1, 100)]);
+        assert!(game.seats()[1].is_none());
+        let chips: u32 = game
+            .seats()
+            .iter()
+            .flatten()
+            .map(|player| player.chips)
+            .sum();
+        assert_eq!(chips, 300);
+    }
+
+    /// Checks and calls down to the river, where the first player to act bets 10 and everyone
+    /// calls, returning the showdown's events.
+    fn bet_the_river(muck_losing_hands: bool, seed: u64) -> Vec<TableEvent> {
+        let mut game = table(GameType::Holdem, 3);
+        for seat in 0..3 {
+            game.set_muck_losing_hands(seat, muck_losing_hands).unwrap();
+        }
+        game.seed_deck(seed);
+        let mut events = game.start_hand().unwrap();
+        let mut bet = false;
+        while let Some(legal) = game.legal_actions() {
+            let action = match game.street() {
+                Some(Street::River) if !bet => Action::Raise(10),
+                _ if legal.call == 0 => Action::Check,
+                _ => Action::Call,
+            };
+            bet |= game.street() == Some(Street::River);
+            events.extend(game.act(action).unwrap());
+        }
+        events
+    }
+
+    /// Returns the seats in the order they showed or mucked, with whether they showed.
+    fn showdown_order(events: &[TableEvent]) -> Vec<(usize, bool)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                TableEvent::Showdown { seat, .. } => Some((*seat, true)),
+                TableEvent::Mucked { seat } => Some((*seat, false)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn river_bettor_shows_first_and_losing_hands_are_mucked() {
+        // the small blind bets the river, so it shows first and the button last, and the
+        // others can't beat it
+        let mucking = bet_the_river(true, 0);
+        assert_eq!(
+            showdown_order(&mucking),
+            vec![(1, true), (2, false), (0, false)]
+        );
+        let won: Vec<usize> = mucking
+            .iter()
+            .filter_map(|event| match event {
+                TableEvent::PotWon { seat, .. } => Some(*seat),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(won, vec![1]);
+
+        // players who don't muck show in the same order, even when they can't win
+        let showing = bet_the_river(false, 0);
+        assert_eq!(
+            showdown_order(&showing),
+            vec![(1, true), (2, true), (0, true)]
+        );
+    }
+}
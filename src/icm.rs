@@ -0,0 +1,196 @@
+use rand::Rng;
+use std::collections::HashMap;
+
+/// The most finishing positions the exact calculation walks through before `icm_equity` falls
+/// back to Monte Carlo.
+const EXACT_LIMIT: f64 = 2_000_000.0;
+/// The number of finishing orders `icm_equity` samples when it falls back to Monte Carlo.
+const MONTE_CARLO_TRIALS: u32 = 200_000;
+
+/// Returns each player's share of the prize money under the Independent Chip Model, in the same
+/// units as the payouts. `payouts` holds the prizes for 1st, 2nd and so on. The exact model is
+/// used when it is cheap enough, and a Monte Carlo approximation otherwise.
+pub fn icm_equity(stacks: &[u32], payouts: &[u32]) -> Vec<f64> {
+    let players = stacks.iter().filter(|stack| **stack > 0).count();
+    let places = payouts.len().min(players);
+    // the exact model visits every set of players that could fill the paid places
+    let mut positions = 0.0;
+    let mut sets = 1.0;
+    for place in 0..places {
+        sets = sets * (players - place) as f64 / (place + 1) as f64;
+        positions += sets;
+    }
+
+    if positions <= EXACT_LIMIT {
+        exact_icm_equity(stacks, payouts)
+    } else {
+        monte_carlo_icm_equity(stacks, payouts, MONTE_CARLO_TRIALS, &mut rand::thread_rng())
+    }
+}
+
+/// Returns each player's ICM equity worked out exactly. Under the model a player finishes
+/// first with the share of the chips they hold, and each following place is handed out the
+/// same way among the players left. The work grows with the number of ways the paid places
+/// can be filled, so this is only practical for small fields or few paid places.
+pub fn exact_icm_equity(stacks: &[u32], payouts: &[u32]) -> Vec<f64> {
+    let total: u64 = stacks.iter().map(|stack| *stack as u64).sum();
+    let mut equity = vec![0.0; stacks.len()];
+    if total == 0 {
+        return equity;
+    }
+    let players: Vec<usize> = (0..stacks.len()).filter(|p| stacks[*p] > 0).collect();
+
+    // the chance that exactly the players in each set took the places handed out so far,
+    // along with the chips those players hold
+    let mut finished: HashMap<Vec<usize>, (f64, u64)> = HashMap::new();
+    finished.insert(vec![], (1.0, 0));
+    for payout in payouts.iter().take(players.len()) {
+        let mut next: HashMap<Vec<usize>, (f64, u64)> = HashMap::new();
+        for (placed, (chance, placed_chips)) in finished {
+            let chips_left = (total - placed_chips) as f64;
+            for player in &players {
+                if placed.contains(player) {
+                    continue;
+                }
+                let stack = stacks[*player] as u64;
+                let takes_place = chance * stack as f64 / chips_left;
+                equity[*player] += takes_place * *payout as f64;
+
+                let mut set = placed.clone();
+                set.push(*player);
+                set.sort();
+                let entry = next.entry(set).or_insert((0.0, placed_chips + stack));
+                entry.0 += takes_place;
+            }
+        }
+        finished = next;
+    }
+
+    equity
+}
+
+/// Returns each player's ICM equity estimated from the given number of sampled finishing
+/// orders. Each order is drawn the way the model hands out places, so the estimate converges
+/// on the exact equity as the trials grow.
+pub fn monte_carlo_icm_equity<R: Rng>(
+    stacks: &[u32],
+    payouts: &[u32],
+    trials: u32,
+    rng: &mut R,
+) -> Vec<f64> {
+    let mut equity = vec![0.0; stacks.len()];
+    let players: Vec<usize> = (0..stacks.len()).filter(|p| stacks[*p] > 0).collect();
+    if players.is_empty() || trials == 0 {
+        return equity;
+    }
+
+    let mut order: Vec<(f64, usize)> = Vec::with_capacity(players.len());
+    for _ in 0..trials {
+        // racing exponential clocks that tick at the rate of each stack picks the winner of
+        // each place with the chance the model gives it
+        order.clear();
+        for player in &players {
+            let clock = -(1.0 - rng.gen::<f64>()).ln() / stacks[*player] as f64;
+            order.push((clock, *player));
+        }
+        order.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for ((_, player), payout) in order.iter().zip(payouts) {
+            equity[*player] += *payout as f64;
+        }
+    }
+
+    equity
+        .into_iter()
+        .map(|total| total / trials as f64)
+        .collect()
+}
+
+/// Returns each player's share of the prize money if it were split by chip count alone.
+pub fn chip_equity(stacks: &[u32], payouts: &[u32]) -> Vec<f64> {
+    let total: u64 = stacks.iter().map(|stack| *stack as u64).sum();
+    let pool: u64 = payouts.iter().map(|payout| *payout as u64).sum();
+    if total == 0 {
+        return vec![0.0; stacks.len()];
+    }
+
+    stacks
+        .iter()
+        .map(|stack| *stack as f64 * pool as f64 / total as f64)
+        .collect()
+}
+
+/// Rounds the equities down to whole amounts that add up to the prize pool, handing what
+/// rounding left over to the players who lost the most to it.
+pub fn round_equity(equity: &[f64], payouts: &[u32]) -> Vec<u32> {
+    let pool: u32 = payouts.iter().sum();
+    let mut amounts: Vec<u32> = equity.iter().map(|equity| *equity as u32).collect();
+    let mut by_remainder: Vec<usize> = (0..equity.len()).collect();
+    by_remainder.sort_by(|a, b| {
+        let a = equity[*a] - equity[*a].floor();
+        let b = equity[*b] - equity[*b].floor();
+        b.total_cmp(&a)
+    });
+    let left_over = pool.saturating_sub(amounts.iter().sum());
+    for player in by_remainder.into_iter().take(left_over as usize) {
+        amounts[player] += 1;
+    }
+
+    amounts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn assert_close(equity: &[f64], expected: &[f64], tolerance: f64) {
+        assert_eq!(equity.len(), expected.len());
+        for (equity, expected) in equity.iter().zip(expected) {
+            assert!((equity - expected).abs() < tolerance, "{:?}", equity);
+        }
+    }
+
+    #[test]
+    fn exact_equity_of_three_players() {
+        let equity = exact_icm_equity(&[5000, 3000, 2000], &[50, 30, 20]);
+        assert_close(&equity, &[38.392857, 32.75, 28.857143], 1e-5);
+    }
+
+    #[test]
+    fn heads_up_equity_splits_the_difference_by_chips() {
+        // second place is locked up, and first is won with the share of the chips
+        let equity = icm_equity(&[3000, 1000], &[70, 30]);
+        assert_close(&equity, &[60.0, 40.0], 1e-9);
+    }
+
+    #[test]
+    fn busted_players_win_nothing_and_unfilled_places_are_not_paid() {
+        let equity = icm_equity(&[0, 4000, 0, 1000], &[50, 30, 20]);
+        assert_close(&equity, &[0.0, 46.0, 0.0, 34.0], 1e-9);
+        assert_close(&icm_equity(&[0, 0], &[50, 30]), &[0.0, 0.0], 1e-9);
+    }
+
+    #[test]
+    fn monte_carlo_equity_converges_on_the_exact_equity() {
+        let stacks = [5000, 3000, 2000, 1000];
+        let payouts = [50, 30, 20];
+        let exact = exact_icm_equity(&stacks, &payouts);
+        let mut rng = StdRng::seed_from_u64(3);
+        let estimate = monte_carlo_icm_equity(&stacks, &payouts, 200_000, &mut rng);
+        assert_close(&estimate, &exact, 0.3);
+    }
+
+    #[test]
+    fn chip_equity_shares_the_pool_by_stack() {
+        let equity = chip_equity(&[5000, 3000, 2000], &[50, 30, 20]);
+        assert_close(&equity, &[50.0, 30.0, 20.0], 1e-9);
+    }
+
+    #[test]
+    fn rounding_hands_the_left_over_to_the_biggest_remainders() {
+        let rounded = round_equity(&[38.392857, 32.75, 28.857143], &[50, 30, 20]);
+        assert_eq!(rounded, vec![38, 33, 29]);
+        assert_eq!(rounded.iter().sum::<u32>(), 100);
+    }
+}
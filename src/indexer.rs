@@ -0,0 +1,426 @@
+use crate::deck::*;
+use crate::holdem::Street;
+use std::collections::HashMap;
+
+/// The number of card values in a suit.
+const RANKS: usize = 13;
+/// The number of suits.
+const SUITS: usize = 4;
+/// The suits in the order a canonical hand hands them out.
+const SUIT_ORDER: [CardSuit; SUITS] = [
+    CardSuit::Heart,
+    CardSuit::Diamond,
+    CardSuit::Spade,
+    CardSuit::Club,
+];
+
+/// A way the cards of a hand can be spread over the suits, with the suits sorted by how many
+/// cards they have in each round, most first.
+struct Configuration {
+    // the cards of each suit in each round
+    shapes: Vec<Vec<usize>>,
+    // the first index of the hands with this configuration
+    offset: u64,
+    // the suits with the same shape, as where they start and how many there are, along with
+    // the number of ways one suit of that shape can hold its cards
+    groups: Vec<(usize, usize, u64)>,
+}
+
+/// Maps hands to indexes that are the same for every hand with the same cards but the suits
+/// swapped around, so AsKs and AhKh share an index. The indexes of a street run from 0 to one
+/// less than its `size` without any gaps, which makes them a compact way of keying tables of
+/// hands. The cards are dealt in rounds, and the order of the cards within a round doesn't
+/// matter.
+pub struct HandIndexer {
+    rounds: Vec<usize>,
+    configurations: Vec<Configuration>,
+    // the place of each configuration by its flattened shapes
+    lookup: HashMap<Vec<usize>, usize>,
+    size: u64,
+}
+
+impl HandIndexer {
+    /// Creates an indexer for hands dealt in rounds of the given number of cards.
+    pub fn new(rounds: &[usize]) -> Self {
+        let shapes = suit_shapes(rounds);
+        let mut configurations = vec![];
+        let mut lookup = HashMap::new();
+        let mut size = 0;
+        let mut chosen = vec![];
+        for_each_configuration(&shapes, rounds, &mut chosen, &mut |shapes| {
+            let mut groups = vec![];
+            let mut config_size = 1;
+            let mut start = 0;
+            while start < SUITS {
+                let count = shapes[start..]
+                    .iter()
+                    .take_while(|shape| **shape == shapes[start])
+                    .count();
+                let ways = suit_ways(&shapes[start]);
+                config_size *= multichoose(ways, count as u64);
+                groups.push((start, count, ways));
+                start += count;
+            }
+            lookup.insert(shapes.concat(), configurations.len());
+            configurations.push(Configuration {
+                shapes: shapes.to_vec(),
+                offset: size,
+                groups,
+            });
+            size += config_size;
+        });
+
+        HandIndexer {
+            rounds: rounds.to_vec(),
+            configurations,
+            lookup,
+            size,
+        }
+    }
+
+    /// Creates an indexer for the hold'em hands of the street, with the hole cards, flop, turn
+    /// and river each dealt in their own round.
+    pub fn holdem(street: Street) -> Self {
+        let rounds = match street {
+            Street::Preflop => vec![2],
+            Street::Flop => vec![2, 3],
+            Street::Turn => vec![2, 3, 1],
+            Street::River => vec![2, 3, 1, 1],
+        };
+
+        Self::new(&rounds)
+    }
+
+    /// Returns the number of hands that aren't the same as each other with the suits swapped.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the index of the hand, with the hole cards dealt in the first round and the
+    /// board in the others, or `None` if it has the wrong number of cards or a card twice.
+    pub fn index(&self, hole: &CardCollection, board: &CardCollection) -> Option<u64> {
+        let cards: Vec<&Card> = hole.0.iter().chain(&board.0).collect();
+        if cards.len() != self.rounds.iter().sum::<usize>() {
+            return None;
+        }
+        // the values each suit has in each round, as bits
+        let mut masks = vec![vec![0u16; self.rounds.len()]; SUITS];
+        let mut seen = [0u16; SUITS];
+        let mut dealt = 0;
+        for (round, count) in self.rounds.iter().enumerate() {
+            for card in &cards[dealt..dealt + count] {
+                let suit = card.suit.clone() as usize;
+                let bit = 1 << (card.value.value() - 2);
+                if seen[suit] & bit != 0 {
+                    return None;
+                }
+                seen[suit] |= bit;
+                masks[suit][round] |= bit;
+            }
+            dealt += count;
+        }
+
+        let mut suits: Vec<(Vec<usize>, u64)> = masks
+            .iter()
+            .map(|masks| {
+                let shape = masks
+                    .iter()
+                    .map(|mask| mask.count_ones() as usize)
+                    .collect();
+                let index = suit_index(masks);
+                (shape, index)
+            })
+            .collect();
+        suits.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        let key: Vec<usize> = suits.iter().flat_map(|(shape, _)| shape.clone()).collect();
+        let configuration = &self.configurations[*self.lookup.get(&key)?];
+
+        let mut index = 0;
+        let mut scale = 1;
+        for (start, count, ways) in &configuration.groups {
+            let group: Vec<u64> = suits[*start..start + count]
+                .iter()
+                .map(|(_, index)| *index)
+                .collect();
+            index += scale * multiset_rank(&group);
+            scale *= multichoose(*ways, *count as u64);
+        }
+
+        Some(configuration.offset + index)
+    }
+
+    /// Returns the hole cards and board of a hand with the index, the suits given out in a set
+    /// order, or `None` if the index is too big.
+    pub fn unindex(&self, index: u64) -> Option<(CardCollection, CardCollection)> {
+        if index >= self.size {
+            return None;
+        }
+        let place = self
+            .configurations
+            .partition_point(|configuration| configuration.offset <= index)
+            - 1;
+        let configuration = &self.configurations[place];
+
+        let mut left = index - configuration.offset;
+        let mut rounds: Vec<Vec<Card>> = vec![vec![]; self.rounds.len()];
+        for (start, count, ways) in &configuration.groups {
+            let size = multichoose(*ways, *count as u64);
+            let group = multiset_unrank(left % size, *count);
+            left /= size;
+            for (offset, suit_index) in group.into_iter().enumerate() {
+                let suit = start + offset;
+                let masks = suit_unindex(suit_index, &configuration.shapes[suit]);
+                for (round, mask) in masks.into_iter().enumerate() {
+                    for value in 0..RANKS {
+                        if mask & 1 << value != 0 {
+                            rounds[round].push(Card {
+                                suit: SUIT_ORDER[suit].clone(),
+                                value: CardValue::new(value as u8 + 2),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        // each round's cards go highest first, the way hands are written
+        for cards in rounds.iter_mut() {
+            cards.sort_by_key(|card| std::cmp::Reverse(card.value.value()));
+        }
+        let mut rounds = rounds.into_iter();
+        let hole = rounds.next().unwrap_or_default();
+        let board = rounds.flatten().collect();
+        Some((CardCollection(hole), CardCollection(board)))
+    }
+}
+
+/// Returns every way one suit can hold cards in the rounds, as the number of its cards in each.
+fn suit_shapes(rounds: &[usize]) -> Vec<Vec<usize>> {
+    let mut shapes = vec![vec![]];
+    for round in rounds {
+        shapes = shapes
+            .into_iter()
+            .flat_map(|shape: Vec<usize>| {
+                let used: usize = shape.iter().sum();
+                (0..=(*round).min(RANKS - used)).map(move |count| {
+                    let mut shape = shape.clone();
+                    shape.push(count);
+                    shape
+                })
+            })
+            .collect();
+    }
+
+    shapes
+}
+
+/// Calls `found` with the shapes of the suits of every configuration, the suits sorted most
+/// cards first, whose cards add up to the size of each round.
+fn for_each_configuration(
+    shapes: &[Vec<usize>],
+    rounds: &[usize],
+    chosen: &mut Vec<Vec<usize>>,
+    found: &mut impl FnMut(&[Vec<usize>]),
+) {
+    if chosen.len() == SUITS {
+        let complete = (0..rounds.len())
+            .all(|round| chosen.iter().map(|shape| shape[round]).sum::<usize>() == rounds[round]);
+        if complete {
+            found(chosen);
+        }
+        return;
+    }
+    for shape in shapes.iter().rev() {
+        if chosen.last().is_some_and(|last| shape > last) {
+            continue;
+        }
+        let fits = (0..rounds.len()).all(|round| {
+            chosen.iter().map(|shape| shape[round]).sum::<usize>() + shape[round] <= rounds[round]
+        });
+        if fits {
+            chosen.push(shape.clone());
+            for_each_configuration(shapes, rounds, chosen, found);
+            chosen.pop();
+        }
+    }
+}
+
+/// Returns the number of ways one suit can hold the shape's cards.
+fn suit_ways(shape: &[usize]) -> u64 {
+    let mut left = RANKS as u64;
+    let mut ways = 1;
+    for count in shape {
+        ways *= binomial(left, *count as u64);
+        left -= *count as u64;
+    }
+
+    ways
+}
+
+/// Returns the index of one suit's cards among the ways of holding a suit of the same shape.
+/// Each round's values are numbered among the values the earlier rounds didn't use.
+fn suit_index(masks: &[u16]) -> u64 {
+    let mut used = 0u16;
+    let mut index = 0;
+    let mut scale = 1;
+    for mask in masks {
+        let free = RANKS as u64 - used.count_ones() as u64;
+        let mut rank = 0;
+        let mut position = 0;
+        let mut chosen = 0;
+        for value in 0..RANKS {
+            if used & 1 << value != 0 {
+                continue;
+            }
+            if mask & 1 << value != 0 {
+                chosen += 1;
+                rank += binomial(position, chosen);
+            }
+            position += 1;
+        }
+        index += scale * rank;
+        scale *= binomial(free, mask.count_ones() as u64);
+        used |= mask;
+    }
+
+    index
+}
+
+/// Returns the values one suit holds in each round for its index among the ways of holding the
+/// shape.
+fn suit_unindex(mut index: u64, shape: &[usize]) -> Vec<u16> {
+    let mut used = 0u16;
+    let mut masks = vec![];
+    for count in shape {
+        let free: Vec<usize> = (0..RANKS).filter(|value| used & 1 << value == 0).collect();
+        let ways = binomial(free.len() as u64, *count as u64);
+        let positions = subset_unrank(index % ways, *count);
+        index /= ways;
+        let mask = positions
+            .into_iter()
+            .fold(0u16, |mask, position| mask | 1 << free[position]);
+        used |= mask;
+        masks.push(mask);
+    }
+
+    masks
+}
+
+/// Returns the rank of a sorted multiset of numbers among the multisets of the same size.
+fn multiset_rank(sorted: &[u64]) -> u64 {
+    sorted
+        .iter()
+        .enumerate()
+        .map(|(place, value)| binomial(value + place as u64, place as u64 + 1))
+        .sum()
+}
+
+/// Returns the sorted multiset of `size` numbers with the rank.
+fn multiset_unrank(rank: u64, size: usize) -> Vec<u64> {
+    subset_unrank(rank, size)
+        .into_iter()
+        .enumerate()
+        .map(|(place, position)| (position - place) as u64)
+        .collect()
+}
+
+/// Returns the sorted positions of the subset of `size` with the rank in colexicographic
+/// order.
+fn subset_unrank(mut rank: u64, size: usize) -> Vec<usize> {
+    let mut positions = vec![0; size];
+    for chosen in (1..=size).rev() {
+        // the highest position whose subsets fit in what's left of the rank, found by doubling
+        // a step past it and then halving the step back down
+        let fits = |position: usize| binomial(position as u64, chosen as u64) <= rank;
+        let mut position = chosen - 1;
+        let mut step = 1;
+        while fits(position + step) {
+            position += step;
+            step *= 2;
+        }
+        while step > 1 {
+            step /= 2;
+            if fits(position + step) {
+                position += step;
+            }
+        }
+        rank -= binomial(position as u64, chosen as u64);
+        positions[chosen - 1] = position;
+    }
+
+    positions
+}
+
+/// Returns the number of ways of picking `k` of `n` things.
+fn binomial(n: u64, k: u64) -> u64 {
+    if k > n {
+        return 0;
+    }
+    let k = k.min(n - k);
+    (0..k).fold(1, |ways, i| ways * (n - i) / (i + 1))
+}
+
+/// Returns the number of ways of picking `k` of `n` things when the same one can be picked
+/// more than once.
+fn multichoose(n: u64, k: u64) -> u64 {
+    if k == 0 {
+        return 1;
+    }
+    binomial(n + k - 1, k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(codes: &str) -> CardCollection {
+        CardCollection(codes.split(' ').filter_map(Card::parse).collect())
+    }
+
+    /// Checks that every `step`th index comes back from the hand it unindexes to.
+    fn assert_round_trips(indexer: &HandIndexer, step: u64) {
+        for index in (0..indexer.size()).step_by(step as usize) {
+            let (hole, board) = indexer.unindex(index).unwrap();
+            assert_eq!(indexer.index(&hole, &board), Some(index));
+        }
+        let (hole, board) = indexer.unindex(indexer.size() - 1).unwrap();
+        assert_eq!(indexer.index(&hole, &board), Some(indexer.size() - 1));
+        assert!(indexer.unindex(indexer.size()).is_none());
+    }
+
+    #[test]
+    fn holdem_streets_have_the_known_number_of_hands() {
+        assert_eq!(HandIndexer::holdem(Street::Preflop).size(), 169);
+        assert_eq!(HandIndexer::holdem(Street::Flop).size(), 1_286_792);
+        assert_eq!(HandIndexer::holdem(Street::Turn).size(), 55_190_538);
+        assert_eq!(HandIndexer::holdem(Street::River).size(), 2_428_287_420);
+    }
+
+    #[test]
+    fn every_preflop_hand_round_trips() {
+        assert_round_trips(&HandIndexer::holdem(Street::Preflop), 1);
+    }
+
+    #[test]
+    fn hands_on_later_streets_round_trip() {
+        assert_round_trips(&HandIndexer::holdem(Street::Flop), 997);
+        assert_round_trips(&HandIndexer::holdem(Street::River), 9_999_991);
+    }
+
+    #[test]
+    fn hands_with_the_suits_swapped_share_an_index() {
+        let indexer = HandIndexer::holdem(Street::Flop);
+        let index = indexer.index(&cards("As Ks"), &cards("Qs 7h 2d"));
+        assert!(index.is_some());
+        assert_eq!(indexer.index(&cards("Kh Ah"), &cards("2c 7d Qh")), index);
+        // the flush draw is gone when the board card's suit doesn't match
+        assert_ne!(indexer.index(&cards("As Ks"), &cards("Qh 7s 2d")), index);
+    }
+
+    #[test]
+    fn hands_with_the_wrong_cards_have_no_index() {
+        let indexer = HandIndexer::holdem(Street::Flop);
+        assert_eq!(indexer.index(&cards("As Ks"), &cards("Qs 7h")), None);
+        assert_eq!(indexer.index(&cards("As Ks"), &cards("As 7h 2d")), None);
+    }
+}
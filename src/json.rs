@@ -0,0 +1,405 @@
+use std::fmt;
+
+/// A JSON value, as read from and written to our JSON hand histories and logs. Numbers keep the
+/// text they were written with, so big numbers like seeds come back exactly.
+#[derive(Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    /// The fields of an object, in the order they were written.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses a JSON value that takes up the whole of the text.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parser = JsonParser {
+            chars: text.chars().collect(),
+            position: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("the end of the text"));
+        }
+
+        Ok(value)
+    }
+
+    /// Creates an object with the fields.
+    pub fn object(fields: Vec<(&str, Json)>) -> Self {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// Returns the value of the object's field, if it is an object with the field.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields
+                .iter()
+                .find(|(field, _)| field == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Json::Number(number) => number.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Returns the object's field, or an error naming it if it is missing.
+    pub fn field(&self, key: &str) -> Result<&Json, String> {
+        self.get(key).ok_or(format!("the {} is missing", key))
+    }
+
+    /// Returns the object's field as text, or an error naming it if it isn't.
+    pub fn str_field(&self, key: &str) -> Result<&str, String> {
+        self.field(key)?
+            .as_str()
+            .ok_or(format!("the {} must be text", key))
+    }
+
+    /// Returns the object's field as a whole number, or an error naming it if it isn't.
+    pub fn u64_field(&self, key: &str) -> Result<u64, String> {
+        self.field(key)?
+            .as_u64()
+            .ok_or(format!("the {} must be a whole number", key))
+    }
+
+    /// Returns the object's field as a number of chips, or an error naming it if it isn't.
+    pub fn u32_field(&self, key: &str) -> Result<u32, String> {
+        u32::try_from(self.u64_field(key)?).map_err(|_| format!("the {} is too big", key))
+    }
+
+    /// Returns the object's field as true or false, or an error naming it if it isn't.
+    pub fn bool_field(&self, key: &str) -> Result<bool, String> {
+        self.field(key)?
+            .as_bool()
+            .ok_or(format!("the {} must be true or false", key))
+    }
+
+    /// Returns the object's field as an array, or an error naming it if it isn't.
+    pub fn array_field(&self, key: &str) -> Result<&Vec<Json>, String> {
+        self.field(key)?
+            .as_array()
+            .ok_or(format!("the {} must be a list", key))
+    }
+}
+
+impl From<&str> for Json {
+    fn from(text: &str) -> Self {
+        Json::String(text.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(text: String) -> Self {
+        Json::String(text)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl From<u64> for Json {
+    fn from(number: u64) -> Self {
+        Json::Number(number.to_string())
+    }
+}
+
+impl From<u32> for Json {
+    fn from(number: u32) -> Self {
+        Json::Number(number.to_string())
+    }
+}
+
+impl From<usize> for Json {
+    fn from(number: usize) -> Self {
+        Json::Number(number.to_string())
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(values: Vec<T>) -> Self {
+        Json::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Writes the value on one line, without any spaces.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(number) => write!(f, "{}", number),
+            Json::String(text) => write_string(f, text),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Writes the text in quotes, escaping the characters JSON doesn't allow in a string.
+fn write_string(f: &mut fmt::Formatter, text: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in text.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl JsonParser {
+    fn error(&self, expected: &str) -> String {
+        match self.chars.get(self.position) {
+            Some(found) => format!(
+                "expected {} but found '{}' at character {}",
+                expected,
+                found,
+                self.position + 1
+            ),
+            None => format!("expected {} but the text ended", expected),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|c| c.is_whitespace())
+        {
+            self.position += 1;
+        }
+    }
+
+    /// Moves past the character if it is next, returning whether it was.
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        if self.chars.get(self.position) == Some(&expected) {
+            self.position += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.chars.get(self.position) {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Json::String),
+            Some('t') => self.word("true", Json::Bool(true)),
+            Some('f') => self.word("false", Json::Bool(false)),
+            Some('n') => self.word("null", Json::Null),
+            Some(c) if *c == '-' || c.is_ascii_digit() => self.number(),
+            _ => Err(self.error("a value")),
+        }
+    }
+
+    fn word(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        let end = self.position + word.len();
+        if self
+            .chars
+            .get(self.position..end)
+            .is_some_and(|found| found.iter().copied().eq(word.chars()))
+        {
+            self.position = end;
+            return Ok(value);
+        }
+
+        Err(self.error(word))
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        while self
+            .chars
+            .get(self.position)
+            .is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(*c))
+        {
+            self.position += 1;
+        }
+        let number: String = self.chars[start..self.position].iter().collect();
+        if number.parse::<f64>().is_err() {
+            self.position = start;
+            return Err(self.error("a number"));
+        }
+
+        Ok(Json::Number(number))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if !self.eat('"') {
+            return Err(self.error("a string"));
+        }
+        let mut text = String::new();
+        loop {
+            let Some(c) = self.chars.get(self.position).copied() else {
+                return Err(self.error("the end of the string"));
+            };
+            self.position += 1;
+            match c {
+                '"' => return Ok(text),
+                '\\' => {
+                    let Some(escaped) = self.chars.get(self.position).copied() else {
+                        return Err(self.error("an escaped character"));
+                    };
+                    self.position += 1;
+                    match escaped {
+                        '"' | '\\' | '/' => text.push(escaped),
+                        'b' => text.push('\u{8}'),
+                        'f' => text.push('\u{c}'),
+                        'n' => text.push('\n'),
+                        'r' => text.push('\r'),
+                        't' => text.push('\t'),
+                        'u' => text.push(self.unicode_escape()?),
+                        _ => {
+                            self.position -= 1;
+                            return Err(self.error("an escaped character"));
+                        }
+                    }
+                }
+                c => text.push(c),
+            }
+        }
+    }
+
+    /// Reads the hex digits of a \u escape, along with the second half of a surrogate pair.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let first = self.hex()?;
+        let code = if (0xd800..0xdc00).contains(&first) {
+            if !(self.eat('\\') && self.eat('u')) {
+                return Err(self.error("the second half of a surrogate pair"));
+            }
+            let second = self.hex()?;
+            0x10000 + ((first - 0xd800) << 10) + (second.wrapping_sub(0xdc00) & 0x3ff)
+        } else {
+            first
+        };
+
+        char::from_u32(code).ok_or(self.error("a character code"))
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        let end = self.position + 4;
+        let digits: String = match self.chars.get(self.position..end) {
+            Some(digits) => digits.iter().collect(),
+            None => return Err(self.error("four hex digits")),
+        };
+        let code = u32::from_str_radix(&digits, 16).map_err(|_| self.error("four hex digits"))?;
+        self.position = end;
+
+        Ok(code)
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.eat('[');
+        let mut values = vec![];
+        if self.eat(']') {
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            if self.eat(']') {
+                return Ok(Json::Array(values));
+            }
+            if !self.eat(',') {
+                return Err(self.error("',' or ']'"));
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.eat('{');
+        let mut fields = vec![];
+        if self.eat('}') {
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            if !self.eat(':') {
+                return Err(self.error("':'"));
+            }
+            fields.push((key, self.value()?));
+            if self.eat('}') {
+                return Ok(Json::Object(fields));
+            }
+            if !self.eat(',') {
+                return Err(self.error("',' or '}'"));
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+pub mod abstraction;
+pub mod bankroll;
+pub mod betting;
+pub mod bots;
+pub mod cfr;
+pub mod cfr_games;
+pub mod client;
+pub mod deck;
+pub mod draw;
+pub mod equity;
+pub mod events;
+pub mod hand;
+pub mod hand_history;
+pub mod hand_log;
+pub mod history;
+pub mod holdem;
+pub mod icm;
+pub mod indexer;
+pub mod json;
+pub mod lobby;
+pub mod lowball;
+pub mod server;
+pub mod session;
+pub mod showdown;
+pub mod simulate;
+pub mod strategy;
+pub mod stud;
+pub mod timer;
+pub mod tournament;
@@ -0,0 +1,1440 @@
+use crate::betting::{Action, BettingStructure, LegalActions};
+use crate::deck::{CardCollection, Deck};
+use crate::draw::DrawTable;
+use crate::events::{Broadcaster, Recipient, TableEvent};
+use crate::hand::HandRules;
+use crate::history::PlayedHand;
+use crate::holdem::HoldemGame;
+use crate::strategy::{Decision, Strategy};
+use crate::stud::StudTable;
+use crate::timer::{ActionTimer, ManualClock, TimerSettings};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The number of finished hands each table keeps for its hand histories.
+const HANDS_KEPT: usize = 100;
+/// How long a table waits after a hand before dealing the next, so players can see how it
+/// ended.
+pub const NEXT_HAND_DELAY: Duration = Duration::from_secs(3);
+/// How often the table tells everyone how long the player it is waiting on has left.
+pub const COUNTDOWN_EVERY: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GameType {
+    Holdem,
+    ShortDeck,
+    OmahaHiLo,
+    Stud,
+    StudHiLo,
+    Draw,
+}
+
+impl GameType {
+    /// Parses the name a client uses for the game type.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "holdem" => Some(GameType::Holdem),
+            "shortdeck" => Some(GameType::ShortDeck),
+            "omaha8" => Some(GameType::OmahaHiLo),
+            "stud" => Some(GameType::Stud),
+            "stud8" => Some(GameType::StudHiLo),
+            "draw" => Some(GameType::Draw),
+            _ => None,
+        }
+    }
+
+    /// Returns the name a client uses for the game type, which `parse` reads.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            GameType::Holdem => "holdem",
+            GameType::ShortDeck => "shortdeck",
+            GameType::OmahaHiLo => "omaha8",
+            GameType::Stud => "stud",
+            GameType::StudHiLo => "stud8",
+            GameType::Draw => "draw",
+        }
+    }
+
+    /// Returns the rules high hands are ranked under in this game.
+    pub fn hand_rules(&self) -> HandRules {
+        match *self {
+            GameType::ShortDeck => HandRules::ShortDeck {
+                trips_beat_straights: false,
+            },
+            _ => HandRules::Standard,
+        }
+    }
+
+    /// Returns the number of cards each player is dealt face down at the start of a hand.
+    pub fn hole_cards(&self) -> u8 {
+        match *self {
+            GameType::OmahaHiLo => 4,
+            GameType::Draw => 5,
+            _ => 2,
+        }
+    }
+
+    /// Returns whether pots are split between the best high hand and the best eight-or-better
+    /// low hand.
+    pub fn is_hi_lo(&self) -> bool {
+        matches!(*self, GameType::OmahaHiLo | GameType::StudHiLo)
+    }
+
+    /// Creates a new deck with the cards this game is played with.
+    pub fn new_deck(&self) -> Deck {
+        self.hand_rules().new_deck()
+    }
+}
+
+impl fmt::Display for GameType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GameType::Holdem => write!(f, "Texas Holdem"),
+            GameType::ShortDeck => write!(f, "Short Deck Holdem"),
+            GameType::OmahaHiLo => write!(f, "Omaha Hi-Lo"),
+            GameType::Stud => write!(f, "Seven-Card Stud"),
+            GameType::StudHiLo => write!(f, "Seven-Card Stud Hi-Lo"),
+            GameType::Draw => write!(f, "Five-Card Draw"),
+        }
+    }
+}
+
+/// The settings a table is created with.
+#[derive(Clone, Copy)]
+pub struct TableSettings {
+    pub game: GameType,
+    pub structure: BettingStructure,
+    pub small_blind: u32,
+    pub big_blind: u32,
+    pub seats: usize,
+    /// When set, spectators watch each hand this many hands late with every card shown.
+    pub broadcast_delay: Option<u32>,
+    pub timer: TimerSettings,
+    /// When set, players are removed from the table after sitting out this many orbits.
+    pub max_orbits_sat_out: Option<u32>,
+    /// The most times the board may be run when the players are all in before the river. 1
+    /// always runs it once.
+    pub max_runs: u32,
+    /// The fewest and most chips a player may sit down with.
+    pub min_buy_in: u32,
+    pub max_buy_in: u32,
+}
+
+impl fmt::Display for TableSettings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let limit = match self.structure {
+            BettingStructure::NoLimit { .. } => "No Limit",
+            BettingStructure::PotLimit { .. } => "Pot Limit",
+            BettingStructure::FixedLimit { .. } => "Fixed Limit",
+            BettingStructure::SpreadLimit { .. } => "Spread Limit",
+        };
+        write!(
+            f,
+            "{} {} {}/{}",
+            limit, self.game, self.small_blind, self.big_blind
+        )
+    }
+}
+
+/// The game a table deals its hands on, which holds the chips in front of each seat and plays
+/// out each hand as the players act.
+pub trait TableGame {
+    /// Returns whether a hand is being played.
+    fn hand_in_progress(&self) -> bool;
+
+    /// Returns the chips in front of each seat, 0 for empty seats.
+    fn stacks(&self) -> Vec<u32>;
+
+    /// Returns the cards dealt face down to the seat in the hand in progress, or the last hand
+    /// between hands.
+    fn hole_cards(&self, seat: usize) -> CardCollection;
+
+    /// Seats a player with the given chips. They are dealt in from the next hand.
+    fn sit_down(&mut self, seat: usize, chips: u32) -> Result<(), String>;
+
+    /// Adds chips to the player's stack. Players can't add chips while they are in a hand.
+    fn add_chips(&mut self, seat: usize, chips: u32) -> Result<(), String>;
+
+    /// Returns the chips the player would stand up with, or why they can't stand up yet.
+    fn leaving_chips(&self, seat: usize) -> Result<u32, String>;
+
+    /// Removes the player from their seat and returns their chips.
+    fn stand_up(&mut self, seat: usize) -> Result<u32, String>;
+
+    /// Sits the player out from the next hand on.
+    fn sit_out(&mut self, seat: usize) -> Result<(), String>;
+
+    /// Sits the player back in, posting any blinds they missed if asked to.
+    fn sit_in(&mut self, seat: usize, post_missed_blinds: bool) -> Result<(), String>;
+
+    /// Deals a new hand, returning the events of its start, or an error if a hand is already
+    /// being played or there aren't two players to deal in.
+    fn start_hand(&mut self) -> Result<Vec<TableEvent>, String>;
+
+    /// Returns what the player to act gets to see, if the hand is waiting on an action.
+    fn decision(&self) -> Option<Decision>;
+
+    /// Returns the seat whose turn it is, if a hand is being played.
+    fn to_act(&self) -> Option<usize>;
+
+    /// Takes the action for the player to act and plays the hand on as far as it can go,
+    /// returning everything that happened. An illegal action changes nothing.
+    fn act(&mut self, action: Action) -> Result<Vec<TableEvent>, String>;
+
+    /// Returns the seats that still have to pick how many times to run the board.
+    fn awaiting_runs(&self) -> Vec<usize> {
+        vec![]
+    }
+
+    /// Returns the most times the rest of the board can be run.
+    fn max_runs(&self) -> u32 {
+        1
+    }
+
+    /// Records how many times an all in player wants to run the board.
+    fn choose_runs(&mut self, _seat: usize, _runs: u32) -> Result<Vec<TableEvent>, String> {
+        Err("the board can't be run more than once in this game".to_string())
+    }
+
+    /// Returns the seat whose turn it is to draw, in games with a draw.
+    fn to_draw(&self) -> Option<usize> {
+        None
+    }
+
+    /// Replaces the cards the seat discards, at indices of their hand, and plays the hand on,
+    /// returning everything that happened. Discarding nothing stands pat.
+    fn draw(&mut self, _seat: usize, _discards: Vec<usize>) -> Result<Vec<TableEvent>, String> {
+        Err("there is no draw in this game".to_string())
+    }
+
+    /// Sets whether the player mucks hands that can't win anything at showdown.
+    fn set_muck_losing_hands(&mut self, _seat: usize, _muck: bool) -> Result<(), String> {
+        Err("every hand left at showdown is shown in this game".to_string())
+    }
+
+    /// Shows the hole cards of the player who won the last hand without a showdown.
+    fn show_cards(&mut self, _seat: usize) -> Result<Vec<TableEvent>, String> {
+        Err("hands can't be shown after they are over in this game".to_string())
+    }
+
+    /// Deals the rest of the board the last hand would have had, without changing the game.
+    fn rabbit_hunt(&self) -> Result<Vec<TableEvent>, String> {
+        Err("there is no board to rabbit hunt in this game".to_string())
+    }
+}
+
+/// A table in the lobby. Each table deals its hands on its own game, which holds the chips in
+/// front of each seat.
+pub struct Table {
+    pub id: usize,
+    pub settings: TableSettings,
+    // the player sitting in each seat, and what makes their decisions
+    seats: Vec<Option<usize>>,
+    players: Vec<Option<Box<dyn Strategy + Send>>>,
+    // the seats asked for a decision since the hand last moved on
+    asked: Vec<usize>,
+    game: Box<dyn TableGame + Send>,
+    // when the last hand finished, if one has
+    hand_finished_at: Option<Instant>,
+    // the players watching without a seat
+    spectators: Vec<usize>,
+    broadcaster: Broadcaster,
+    // the turn timer runs on the time `play` was last given, so the whole table keeps the
+    // caller's time
+    clock: Arc<ManualClock>,
+    timer: ActionTimer<Arc<ManualClock>>,
+    // when the countdown for the turn in progress was last sent, if it has been
+    countdown_sent: Option<Instant>,
+    // the players the game removed whose chips haven't been handed back yet
+    removed: Vec<(usize, u32)>,
+    // every event of the hand in progress, unfiltered
+    current_hand: Vec<TableEvent>,
+    // when the hand in progress started, and who sat where with how many chips
+    hand_started: u64,
+    starting_seats: Vec<Option<(usize, u32)>>,
+    // the number of the last hand started
+    last_hand: u32,
+    // the last hands finished at the table, oldest first
+    played_hands: VecDeque<PlayedHand>,
+}
+
+impl Table {
+    pub fn seats(&self) -> &Vec<Option<usize>> {
+        &self.seats
+    }
+
+    /// Returns the chips in front of each seat, 0 for empty seats.
+    pub fn stacks(&self) -> Vec<u32> {
+        self.game.stacks()
+    }
+
+    pub fn game(&self) -> &dyn TableGame {
+        self.game.as_ref()
+    }
+
+    pub fn hand_in_progress(&self) -> bool {
+        self.game.hand_in_progress()
+    }
+
+    /// Adds chips to the seat's stack between hands, up to the maximum buy-in. `pay` is called
+    /// to pay for the chips once the top-up is known to be allowed, and nothing changes if it
+    /// fails.
+    pub fn top_up(
+        &mut self,
+        seat: usize,
+        chips: u32,
+        pay: impl FnOnce() -> Result<(), String>,
+    ) -> Result<(), String> {
+        if self.hand_in_progress() {
+            return Err("can only top up between hands".to_string());
+        }
+        if self.stacks()[seat] + chips > self.settings.max_buy_in {
+            return Err(format!(
+                "can only top up to {} chips",
+                self.settings.max_buy_in
+            ));
+        }
+        pay()?;
+
+        self.game.add_chips(seat, chips)
+    }
+
+    /// Buys the seat back in after it lost all its chips, paying for them with `pay` as for
+    /// `top_up`.
+    pub fn rebuy(
+        &mut self,
+        seat: usize,
+        chips: u32,
+        pay: impl FnOnce() -> Result<(), String>,
+    ) -> Result<(), String> {
+        if self.hand_in_progress() {
+            return Err("can only rebuy between hands".to_string());
+        }
+        if self.stacks()[seat] > 0 {
+            return Err("can only rebuy once you're out of chips, top up instead".to_string());
+        }
+        self.check_buy_in(chips)?;
+        pay()?;
+
+        self.game.add_chips(seat, chips)
+    }
+
+    fn check_buy_in(&self, chips: u32) -> Result<(), String> {
+        if chips < self.settings.min_buy_in || chips > self.settings.max_buy_in {
+            return Err(format!(
+                "the buy-in at table {} is between {} and {} chips",
+                self.id, self.settings.min_buy_in, self.settings.max_buy_in
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn timer(&mut self) -> &mut ActionTimer<Arc<ManualClock>> {
+        &mut self.timer
+    }
+
+    /// Returns the countdown for the player whose turn it is, if anyone is on the clock.
+    pub fn countdown(&self) -> Option<TableEvent> {
+        let (seat, action_left, time_bank_left) = self.timer.remaining()?;
+
+        Some(TableEvent::Countdown {
+            seat,
+            action_left: action_left.as_secs(),
+            time_bank_left: time_bank_left.as_secs(),
+        })
+    }
+
+    /// Returns the seat to act and the action to take for them if they have run out of time,
+    /// which is to check if they can and fold otherwise.
+    pub fn timed_out_action(&mut self, legal: &LegalActions) -> Option<(usize, Action)> {
+        let seat = self.timer.check_timeout()?;
+
+        Some((seat, legal.default_action()))
+    }
+
+    pub fn spectators(&self) -> &Vec<usize> {
+        &self.spectators
+    }
+
+    /// Sits the seat out from the next hand on. Players who sit out too long are removed.
+    pub fn sit_out(&mut self, seat: usize) -> Result<(), String> {
+        self.game.sit_out(seat)
+    }
+
+    /// Sets whether the seat mucks hands that can't win anything at showdown.
+    pub fn set_muck_losing_hands(&mut self, seat: usize, muck: bool) -> Result<(), String> {
+        self.game.set_muck_losing_hands(seat, muck)
+    }
+
+    /// Shows the cards of the seat that won the last hand without a showdown, returning what
+    /// each player and spectator should be sent.
+    pub fn show_cards(&mut self, seat: usize) -> Result<Vec<(usize, TableEvent)>, String> {
+        let events = self.game.show_cards(seat)?;
+
+        Ok(events
+            .into_iter()
+            .flat_map(|event| self.publish(event))
+            .collect())
+    }
+
+    /// Deals the rest of the last hand's board for everyone to see, returning what each player
+    /// and spectator should be sent.
+    pub fn rabbit_hunt(&mut self) -> Result<Vec<(usize, TableEvent)>, String> {
+        let events = self.game.rabbit_hunt()?;
+
+        Ok(events
+            .into_iter()
+            .flat_map(|event| self.publish(event))
+            .collect())
+    }
+
+    /// Sits the seat back in. A player who missed blinds posts them in the next hand if asked
+    /// to, and otherwise waits for the big blind.
+    pub fn sit_in(&mut self, seat: usize, post_missed_blinds: bool) -> Result<(), String> {
+        self.game.sit_in(seat, post_missed_blinds)
+    }
+
+    pub fn seats_filled(&self) -> usize {
+        self.seats.iter().filter(|seat| seat.is_some()).count()
+    }
+
+    /// Plays the hand on as far as it can go without waiting on anyone, dealing a new hand once
+    /// two players can be dealt in and `NEXT_HAND_DELAY` has passed since the last. Each seat's
+    /// strategy is polled for its decisions, and the table stops to wait when one isn't ready.
+    /// `auto` is asked first for each decision, given the player and what they can do, and
+    /// picks the action for players the table shouldn't wait on. Players who run out of time
+    /// get the default action, or run the board once, and while the table waits everyone is
+    /// sent the countdown every `COUNTDOWN_EVERY`.
+    /// Every event goes out through `publish`, and the versions each player should be sent are
+    /// handed to `deliver` as they happen, so they reach the players before anything a strategy
+    /// sends them when asked to decide. Returns the players removed for sitting out too long
+    /// with the chips they left with, which the caller has to cash out. Turns are timed against
+    /// `now` too, so the table only moves on when it is played with a later time.
+    pub fn play(
+        &mut self,
+        now: Instant,
+        mut auto: impl FnMut(usize, &LegalActions) -> Option<Action>,
+        mut deliver: impl FnMut(usize, TableEvent),
+    ) -> Vec<(usize, u32)> {
+        self.clock.set(now);
+        loop {
+            if !self.hand_in_progress() {
+                let resting = self
+                    .hand_finished_at
+                    .is_some_and(|finished| now < finished + NEXT_HAND_DELAY);
+                if resting {
+                    break;
+                }
+                let starting_seats = self.seat_stacks();
+                let Ok(events) = self.game.start_hand() else {
+                    break;
+                };
+                self.starting_seats = starting_seats;
+                for (player, event) in self.publish_all(events, now) {
+                    deliver(player, event);
+                }
+                continue;
+            }
+
+            if let Some(decision) = self.game.decision() {
+                let seat = decision.seat;
+                let first = !self.asked.contains(&seat);
+                if first {
+                    self.asked.push(seat);
+                    self.start_turn(seat);
+                }
+                let timed_out = self
+                    .timed_out_action(&decision.legal)
+                    .map(|(_, action)| action);
+                let automatic = timed_out
+                    .or_else(|| self.seats[seat].and_then(|player| auto(player, &decision.legal)));
+                let action = match (automatic, self.players[seat].as_mut()) {
+                    (Some(action), _) => Some(action),
+                    (None, Some(player)) => player.poll_decide(&decision, first),
+                    (None, None) => Some(decision.legal.default_action()),
+                };
+                let Some(action) = action else {
+                    break;
+                };
+                self.asked.clear();
+                self.timer.end_turn();
+                let events = match self.game.act(action) {
+                    Ok(events) => events,
+                    Err(_) => match self.game.act(decision.legal.default_action()) {
+                        Ok(events) => events,
+                        Err(_) => break,
+                    },
+                };
+                for (player, event) in self.publish_all(events, now) {
+                    deliver(player, event);
+                }
+                continue;
+            }
+
+            if let Some(seat) = self.game.to_draw() {
+                let first = !self.asked.contains(&seat);
+                if first {
+                    self.asked.push(seat);
+                    self.start_turn(seat);
+                }
+                // players who run out of time stand pat
+                let timed_out = self.timer.check_timeout().map(|_| vec![]);
+                let discards = match (timed_out, self.players[seat].as_mut()) {
+                    (Some(discards), _) => Some(discards),
+                    (None, Some(player)) => {
+                        player.poll_discards(&self.game.hole_cards(seat), first)
+                    }
+                    (None, None) => Some(vec![]),
+                };
+                let Some(discards) = discards else {
+                    break;
+                };
+                self.asked.clear();
+                self.timer.end_turn();
+                let events = match self.game.draw(seat, discards) {
+                    Ok(events) => events,
+                    Err(_) => match self.game.draw(seat, vec![]) {
+                        Ok(events) => events,
+                        Err(_) => break,
+                    },
+                };
+                for (player, event) in self.publish_all(events, now) {
+                    deliver(player, event);
+                }
+                continue;
+            }
+
+            // the players all in pick how many times to run the board in any order, and are
+            // timed one at a time
+            let max_runs = self.game.max_runs();
+            let awaiting = self.game.awaiting_runs();
+            let timed = awaiting.first().copied();
+            if let Some(seat) = timed {
+                if self.timer.remaining().map(|(on_clock, _, _)| on_clock) != Some(seat) {
+                    self.start_turn(seat);
+                }
+            }
+            let mut chosen = self.timer.check_timeout().map(|seat| (seat, 1));
+            for seat in awaiting.into_iter().filter(|_| chosen.is_none()) {
+                let first = !self.asked.contains(&seat);
+                if first {
+                    self.asked.push(seat);
+                }
+                let runs = match self.players[seat].as_mut() {
+                    Some(player) => player.poll_runs(max_runs, first),
+                    None => Some(1),
+                };
+                if let Some(runs) = runs {
+                    chosen = Some((seat, runs));
+                    break;
+                }
+            }
+            let Some((seat, runs)) = chosen else {
+                break;
+            };
+            self.asked.retain(|asked| *asked != seat);
+            if timed == Some(seat) {
+                self.timer.end_turn();
+            }
+            let events = match self.game.choose_runs(seat, runs) {
+                Ok(events) => events,
+                Err(_) => match self.game.choose_runs(seat, 1) {
+                    Ok(events) => events,
+                    Err(_) => break,
+                },
+            };
+            for (player, event) in self.publish_all(events, now) {
+                deliver(player, event);
+            }
+        }
+
+        let due = self
+            .countdown_sent
+            .is_none_or(|sent| now >= sent + COUNTDOWN_EVERY);
+        if let Some(countdown) = self.countdown().filter(|_| due) {
+            self.countdown_sent = Some(now);
+            for (player, event) in self.announce(countdown) {
+                deliver(player, event);
+            }
+        }
+
+        std::mem::take(&mut self.removed)
+    }
+
+    fn start_turn(&mut self, seat: usize) {
+        self.timer.start_turn(seat);
+        self.countdown_sent = None;
+    }
+
+    /// Returns who should be sent an event that isn't part of the hand, the players sitting
+    /// at the table and the spectators watching it live.
+    fn announce(&self, event: TableEvent) -> Vec<(usize, TableEvent)> {
+        let mut deliveries: Vec<(usize, TableEvent)> = self
+            .seats
+            .iter()
+            .flatten()
+            .map(|player| (*player, event.clone()))
+            .collect();
+        if self.settings.broadcast_delay.unwrap_or(0) == 0 {
+            for spectator in &self.spectators {
+                deliveries.push((*spectator, event.clone()));
+            }
+        }
+
+        deliveries
+    }
+
+    /// Publishes the events of the table's game in order. Players removed by the game lose
+    /// their seat once they have been told.
+    fn publish_all(&mut self, events: Vec<TableEvent>, now: Instant) -> Vec<(usize, TableEvent)> {
+        let mut deliveries = vec![];
+        for event in events {
+            let removed = match event {
+                TableEvent::Removed { seat, chips } => Some((seat, chips)),
+                _ => None,
+            };
+            if let TableEvent::HandFinished { .. } = event {
+                self.hand_finished_at = Some(now);
+                self.timer.hand_finished();
+            }
+            deliveries.extend(self.publish(event));
+            if let Some((seat, chips)) = removed {
+                if let Some(player) = self.seats[seat].take() {
+                    self.removed.push((player, chips));
+                }
+                self.players[seat] = None;
+            }
+        }
+
+        deliveries
+    }
+
+    /// Returns who sits in each seat with how many chips.
+    fn seat_stacks(&self) -> Vec<Option<(usize, u32)>> {
+        self.seats
+            .iter()
+            .zip(self.stacks())
+            .map(|(seat, chips)| seat.map(|player| (player, chips)))
+            .collect()
+    }
+
+    /// Publishes an event at the table and returns the version of it each player sitting at or
+    /// watching the table should be sent, keyed by player.
+    pub fn publish(&mut self, event: TableEvent) -> Vec<(usize, TableEvent)> {
+        if let TableEvent::HandStarted { hand } = event {
+            self.current_hand.clear();
+            self.last_hand = hand;
+            self.hand_started = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs());
+        }
+        self.current_hand.push(event.clone());
+        if let TableEvent::HandFinished { hand } = event {
+            // the seats are taken so a hand that finishes twice is only kept once
+            let seats = std::mem::take(&mut self.starting_seats);
+            if !seats.is_empty() {
+                if self.played_hands.len() == HANDS_KEPT {
+                    self.played_hands.pop_front();
+                }
+                self.played_hands.push_back(PlayedHand {
+                    table: self.id,
+                    hand,
+                    settings: self.settings,
+                    started: self.hand_started,
+                    seats,
+                    events: self.current_hand.clone(),
+                });
+            }
+        }
+
+        let occupied: Vec<usize> = (0..self.seats.len())
+            .filter(|seat| self.seats[*seat].is_some())
+            .collect();
+        let (to_seats, to_spectators) = self.broadcaster.publish(event, &occupied);
+
+        let mut deliveries = vec![];
+        for (seat, event) in to_seats {
+            if let Some(player) = self.seats[seat] {
+                deliveries.push((player, event));
+            }
+        }
+        for event in to_spectators {
+            for spectator in &self.spectators {
+                deliveries.push((*spectator, event.clone()));
+            }
+        }
+
+        deliveries
+    }
+
+    /// Returns the hand in progress as the player sitting at the table is allowed to see it,
+    /// so a player who reconnects can catch up on their cards, the board, the pot and whose
+    /// turn it is.
+    pub fn snapshot(&self, player: usize) -> Vec<TableEvent> {
+        let Some(seat) = self.seats.iter().position(|seat| *seat == Some(player)) else {
+            return vec![];
+        };
+
+        self.current_hand
+            .iter()
+            .map(|event| event.filter_for(Recipient::Seat(seat)))
+            .collect()
+    }
+
+    /// Returns up to the last `count` hands finished at the table as the player is allowed to
+    /// see them, oldest first. Players see the hands they were dealt into with their own cards,
+    /// and the rest as a spectator would, so under a broadcast delay only the hands spectators
+    /// have already been shown.
+    pub fn played_hands(&self, player: usize, count: usize) -> Vec<PlayedHand> {
+        let delay = self.settings.broadcast_delay.unwrap_or(0);
+        let visible: Vec<PlayedHand> = self
+            .played_hands
+            .iter()
+            .filter_map(|hand| match hand.seat_of(player) {
+                Some(seat) => Some(hand.filter_for(Recipient::Seat(seat))),
+                None if delay == 0 => Some(hand.filter_for(Recipient::Spectator)),
+                None if hand.hand + delay <= self.last_hand => Some(hand.clone()),
+                None => None,
+            })
+            .collect();
+
+        visible[visible.len().saturating_sub(count)..].to_vec()
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Table {}: {} ({}/{} seats filled, buy-in {}-{})",
+            self.id,
+            self.settings,
+            self.seats_filled(),
+            self.settings.seats,
+            self.settings.min_buy_in,
+            self.settings.max_buy_in
+        )
+    }
+}
+
+/// The tables hosted by the server. Players are referred to by the id the server gave their
+/// connection, and can sit at one table at a time.
+pub struct Lobby {
+    tables: Vec<Table>,
+    next_table_id: usize,
+}
+
+impl Lobby {
+    pub fn new() -> Self {
+        Lobby {
+            tables: vec![],
+            next_table_id: 1,
+        }
+    }
+
+    pub fn tables(&self) -> &Vec<Table> {
+        &self.tables
+    }
+
+    pub fn tables_mut(&mut self) -> &mut Vec<Table> {
+        &mut self.tables
+    }
+
+    pub fn table(&mut self, table: usize) -> Option<&mut Table> {
+        self.tables.iter_mut().find(|t| t.id == table)
+    }
+
+    /// Creates a new empty table and returns its id.
+    pub fn create_table(&mut self, settings: TableSettings) -> Result<usize, String> {
+        if settings.seats < 2 || settings.seats > 10 {
+            return Err("a table must have between 2 and 10 seats".to_string());
+        }
+        if settings.small_blind > settings.big_blind {
+            return Err("the small blind can't be bigger than the big blind".to_string());
+        }
+        if settings.min_buy_in == 0 || settings.min_buy_in > settings.max_buy_in {
+            return Err("the minimum buy-in must be above 0 and at most the maximum".to_string());
+        }
+        let clock = Arc::new(ManualClock::new());
+        let game: Box<dyn TableGame + Send> = match settings.game {
+            GameType::Holdem | GameType::ShortDeck | GameType::OmahaHiLo => {
+                Box::new(HoldemGame::new(settings))
+            }
+            GameType::Stud | GameType::StudHiLo => Box::new(StudTable::new(settings)),
+            GameType::Draw => Box::new(DrawTable::new(settings)),
+        };
+        let id = self.next_table_id;
+        self.next_table_id += 1;
+        self.tables.push(Table {
+            id,
+            settings,
+            seats: vec![None; settings.seats],
+            players: (0..settings.seats).map(|_| None).collect(),
+            asked: vec![],
+            game,
+            hand_finished_at: None,
+            countdown_sent: None,
+            removed: vec![],
+            spectators: vec![],
+            broadcaster: Broadcaster::new(settings.broadcast_delay),
+            clock: clock.clone(),
+            timer: ActionTimer::new(clock, settings.timer, settings.seats),
+            current_hand: vec![],
+            hand_started: 0,
+            starting_seats: vec![],
+            last_hand: 0,
+            played_hands: VecDeque::new(),
+        });
+
+        Ok(id)
+    }
+
+    /// Returns the table and seat the player is sitting in, if any.
+    pub fn seat_of(&self, player: usize) -> Option<(usize, usize)> {
+        for table in &self.tables {
+            for (seat, occupant) in table.seats.iter().enumerate() {
+                if *occupant == Some(player) {
+                    return Some((table.id, seat));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the table the player is watching, if any.
+    pub fn watching(&self, player: usize) -> Option<usize> {
+        self.tables
+            .iter()
+            .find(|table| table.spectators.contains(&player))
+            .map(|table| table.id)
+    }
+
+    /// Starts watching a table, which stops the player watching any other table.
+    pub fn watch(&mut self, player: usize, table: usize) -> Result<(), String> {
+        if let Some((current, _)) = self.seat_of(player) {
+            return Err(format!("already sitting at table {}", current));
+        }
+        if self.table(table).is_none() {
+            return Err(format!("there is no table {}", table));
+        }
+        self.stop_watching(player);
+        if let Some(table) = self.table(table) {
+            table.spectators.push(player);
+        }
+
+        Ok(())
+    }
+
+    /// Stops the player watching their table, returning the table they were watching.
+    pub fn stop_watching(&mut self, player: usize) -> Option<usize> {
+        let table = self.watching(player)?;
+        self.table(table)?
+            .spectators
+            .retain(|spectator| *spectator != player);
+
+        Some(table)
+    }
+
+    /// Sits the player in the first open seat at the table with the chips they bought in for,
+    /// with `strategy` making their decisions, and returns the seat. `pay` is called to pay for
+    /// the chips once the player is known to be able to sit down, and nothing changes if it
+    /// fails.
+    pub fn join(
+        &mut self,
+        player: usize,
+        table: usize,
+        chips: u32,
+        strategy: Box<dyn Strategy + Send>,
+        pay: impl FnOnce() -> Result<(), String>,
+    ) -> Result<usize, String> {
+        if let Some((current, _)) = self.seat_of(player) {
+            return Err(format!("already sitting at table {}", current));
+        }
+        let Some(found) = self.table(table) else {
+            return Err(format!("there is no table {}", table));
+        };
+        found.check_buy_in(chips)?;
+        if found.seats.iter().all(|seat| seat.is_some()) {
+            return Err(format!("table {} is full", table));
+        }
+        pay()?;
+
+        self.sit(player, table, chips, strategy)
+            .map_err(|(err, _)| err)
+    }
+
+    /// Sits the player down, handing the strategy back if they can't.
+    fn sit(
+        &mut self,
+        player: usize,
+        table: usize,
+        chips: u32,
+        strategy: Box<dyn Strategy + Send>,
+    ) -> Result<usize, (String, Box<dyn Strategy + Send>)> {
+        if let Some((current, _)) = self.seat_of(player) {
+            return Err((format!("already sitting at table {}", current), strategy));
+        }
+        let Some(table) = self.table(table) else {
+            return Err((format!("there is no table {}", table), strategy));
+        };
+        let Some(seat) = table.seats.iter().position(|seat| seat.is_none()) else {
+            return Err((format!("table {} is full", table.id), strategy));
+        };
+        if let Err(err) = table.game.sit_down(seat, chips) {
+            return Err((err, strategy));
+        }
+        table.seats[seat] = Some(player);
+        table.players[seat] = Some(strategy);
+        table.timer.reset_seat(seat);
+        table.spectators.retain(|spectator| *spectator != player);
+
+        Ok(seat)
+    }
+
+    /// Removes the player from their table, returning the table they left and the chips they
+    /// took with them. Players can't leave a hand they are still in. `cash_out` is called with
+    /// the table and chips once the player is known to be able to leave, and they keep their
+    /// seat if it fails.
+    pub fn leave(
+        &mut self,
+        player: usize,
+        cash_out: impl FnOnce(usize, u32) -> Result<(), String>,
+    ) -> Result<(usize, u32), String> {
+        let Some((table, seat)) = self.seat_of(player) else {
+            return Err("you aren't sitting at a table".to_string());
+        };
+        let Some(found) = self.table(table) else {
+            return Err(format!("there is no table {}", table));
+        };
+        cash_out(table, found.game.leaving_chips(seat)?)?;
+        let chips = found.game.stand_up(seat)?;
+        found.seats[seat] = None;
+        found.players[seat] = None;
+
+        Ok((table, chips))
+    }
+
+    /// Sits the player back in at their table, posting any blinds they missed if asked to.
+    pub fn sit_in(&mut self, player: usize, post_missed_blinds: bool) -> Result<(), String> {
+        let Some((table, seat)) = self.seat_of(player) else {
+            return Err("you aren't sitting at a table".to_string());
+        };
+        let Some(found) = self.table(table) else {
+            return Err(format!("there is no table {}", table));
+        };
+        found.sit_in(seat, post_missed_blinds)
+    }
+
+    /// Moves the player and their chips to another table, keeping their current seat if the
+    /// new table is full. Players can't move while their table is playing a hand.
+    pub fn move_player(&mut self, player: usize, table: usize) -> Result<usize, String> {
+        let Some((current, seat)) = self.seat_of(player) else {
+            return Err("you aren't sitting at a table".to_string());
+        };
+        if current == table {
+            return Err(format!("already sitting at table {}", table));
+        }
+        if self.table(current).is_some_and(|t| t.hand_in_progress()) {
+            return Err("can only move between hands".to_string());
+        }
+        let chips = self.table(current).map_or(0, |old| old.stacks()[seat]);
+        let Some(target) = self.table(table) else {
+            return Err(format!("there is no table {}", table));
+        };
+        target.check_buy_in(chips)?;
+        let Some(old) = self.table(current) else {
+            return Err(format!("there is no table {}", current));
+        };
+        // the strategy is only taken once the player is out of their seat, and every way the
+        // move can fail from there puts both back
+        let chips = old.game.stand_up(seat)?;
+        old.seats[seat] = None;
+        let Some(strategy) = old.players[seat].take() else {
+            old.seats[seat] = Some(player);
+            old.game.sit_down(seat, chips)?;
+            return Err("you aren't sitting at a table".to_string());
+        };
+        match self.sit(player, table, chips, strategy) {
+            Ok(new_seat) => Ok(new_seat),
+            Err((err, strategy)) => {
+                if let Some(old) = self.table(current) {
+                    old.seats[seat] = Some(player);
+                    old.players[seat] = Some(strategy);
+                    old.game.sit_down(seat, chips)?;
+                }
+                Err(err)
+            }
+        }
+    }
+}
+
+impl Default for Lobby {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::CallingStation;
+    use crate::deck::CardCollection;
+    use crate::strategy::Decision;
+    use std::sync::{Arc, Mutex};
+
+    fn settings(broadcast_delay: Option<u32>) -> TableSettings {
+        TableSettings {
+            game: GameType::Holdem,
+            structure: BettingStructure::NoLimit { big_blind: 2 },
+            small_blind: 1,
+            big_blind: 2,
+            seats: 6,
+            broadcast_delay,
+            timer: TimerSettings::default(),
+            max_orbits_sat_out: Some(3),
+            max_runs: 1,
+            min_buy_in: 40,
+            max_buy_in: 200,
+        }
+    }
+
+    /// Never decides, like a player who hasn't answered yet, and keeps whether each time it
+    /// was asked was the first that turn.
+    struct Waiting(Arc<Mutex<Vec<bool>>>);
+
+    impl Strategy for Waiting {
+        fn decide(&mut self, decision: &Decision) -> Action {
+            decision.legal.default_action()
+        }
+
+        fn poll_decide(&mut self, _: &Decision, first: bool) -> Option<Action> {
+            self.0.lock().unwrap().push(first);
+            None
+        }
+    }
+
+    fn waiting() -> Box<dyn Strategy + Send> {
+        Box::new(Waiting(Arc::default()))
+    }
+
+    /// Checks or calls every decision, which plays the hand to a showdown.
+    fn call_down() -> Box<dyn Strategy + Send> {
+        Box::new(CallingStation)
+    }
+
+    /// Calls down and throws away the first two cards in the draw.
+    struct Discarding;
+
+    impl Strategy for Discarding {
+        fn decide(&mut self, decision: &Decision) -> Action {
+            CallingStation.decide(decision)
+        }
+
+        fn choose_discards(&mut self, _hand: &CardCollection) -> Vec<usize> {
+            vec![0, 1]
+        }
+    }
+
+    /// Sets up a table with two players and a spectator, players 1 and 2 and 3.
+    fn table(
+        broadcast_delay: Option<u32>,
+        strategy: fn() -> Box<dyn Strategy + Send>,
+    ) -> (Lobby, usize) {
+        let mut lobby = Lobby::new();
+        let table = lobby.create_table(settings(broadcast_delay)).unwrap();
+        lobby.join(1, table, 100, strategy(), || Ok(())).unwrap();
+        lobby.join(2, table, 100, strategy(), || Ok(())).unwrap();
+        lobby.watch(3, table).unwrap();
+        (lobby, table)
+    }
+
+    /// Plays the table on, keeping what each player is sent.
+    fn play(table: &mut Table, now: Instant) -> Vec<(usize, TableEvent)> {
+        let mut deliveries = vec![];
+        table.play(
+            now,
+            |_, _| None,
+            |player, event| deliveries.push((player, event)),
+        );
+        deliveries
+    }
+
+    fn hole_cards_for(deliveries: &[(usize, TableEvent)], player: usize) -> Vec<CardCollection> {
+        deliveries
+            .iter()
+            .filter(|(to, _)| *to == player)
+            .filter_map(|(_, event)| match event {
+                TableEvent::HoleCards { cards, .. } => Some(cards.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn spectators_never_see_hole_cards_before_showdown() {
+        let (mut lobby, table) = table(None, call_down);
+        let found = lobby.table(table).unwrap();
+        let deliveries = play(found, Instant::now());
+        assert!(!found.hand_in_progress());
+
+        // each player is sent their own cards and nobody else's
+        assert_eq!(hole_cards_for(&deliveries, 1).len(), 1);
+        assert_eq!(hole_cards_for(&deliveries, 2).len(), 1);
+        assert!(hole_cards_for(&deliveries, 1)[0].0 != hole_cards_for(&deliveries, 2)[0].0);
+        let to_spectator: Vec<&TableEvent> = deliveries
+            .iter()
+            .filter(|(to, _)| *to == 3)
+            .map(|(_, event)| event)
+            .collect();
+        let showdown = to_spectator
+            .iter()
+            .position(|event| matches!(event, TableEvent::Showdown { .. }))
+            .unwrap();
+        assert!(to_spectator
+            .iter()
+            .all(|event| !matches!(event, TableEvent::HoleCards { .. })));
+        assert!(to_spectator[..showdown]
+            .iter()
+            .any(|event| matches!(event, TableEvent::HiddenCards { .. })));
+    }
+
+    #[test]
+    fn everyone_sees_the_up_cards_at_a_stud_table() {
+        let mut lobby = Lobby::new();
+        let mut stud = settings(None);
+        stud.game = GameType::Stud;
+        stud.structure = BettingStructure::parse("fl", 1, 2).unwrap();
+        let table = lobby.create_table(stud).unwrap();
+        lobby.join(1, table, 100, call_down(), || Ok(())).unwrap();
+        lobby.join(2, table, 100, call_down(), || Ok(())).unwrap();
+        lobby.watch(3, table).unwrap();
+        let found = lobby.table(table).unwrap();
+        let deliveries = play(found, Instant::now());
+        assert!(!found.hand_in_progress());
+
+        // two players are each dealt an up card on third through sixth street
+        for player in 1..=3 {
+            let up_cards = deliveries
+                .iter()
+                .filter(|(to, event)| *to == player && matches!(event, TableEvent::UpCards { .. }))
+                .count();
+            assert_eq!(up_cards, 8);
+        }
+        assert_eq!(hole_cards_for(&deliveries, 1).len(), 2);
+        assert!(hole_cards_for(&deliveries, 3).is_empty());
+        let history = found.played_hands(1, 1)[0].to_pokerstars(|player| player.to_string());
+        assert!(history.contains("brings in for 1"));
+        assert!(history.contains("*** 7th STREET ***"));
+    }
+
+    #[test]
+    fn only_the_drawing_player_sees_their_replacements() {
+        let mut lobby = Lobby::new();
+        let mut draw = settings(None);
+        draw.game = GameType::Draw;
+        draw.structure = BettingStructure::parse("fl", 1, 2).unwrap();
+        let table = lobby.create_table(draw).unwrap();
+        lobby
+            .join(1, table, 100, Box::new(Discarding), || Ok(()))
+            .unwrap();
+        lobby.join(2, table, 100, call_down(), || Ok(())).unwrap();
+        lobby.watch(3, table).unwrap();
+        let found = lobby.table(table).unwrap();
+        let deliveries = play(found, Instant::now());
+        assert!(!found.hand_in_progress());
+
+        let dealt: Vec<usize> = hole_cards_for(&deliveries, 1)
+            .iter()
+            .map(|cards| cards.0.len())
+            .collect();
+        assert_eq!(dealt, vec![5, 2]);
+        assert_eq!(hole_cards_for(&deliveries, 2).len(), 1);
+        for player in 2..=3 {
+            assert!(deliveries.iter().any(|(to, event)| *to == player
+                && matches!(event, TableEvent::HiddenCards { seat: 0, count: 2 })));
+        }
+        let history = found.played_hands(1, 1)[0].to_pokerstars(|player| player.to_string());
+        assert!(history.contains("*** FIRST DRAW ***"));
+        assert!(history.contains("1: discards 2 cards"));
+        assert!(history.contains("2: stands pat"));
+    }
+
+    #[test]
+    fn delayed_spectators_see_nothing_of_the_hand_in_progress() {
+        let (mut lobby, table) = table(Some(1), waiting);
+        let found = lobby.table(table).unwrap();
+        let deliveries = play(found, Instant::now());
+        assert!(found.hand_in_progress());
+        assert!(deliveries.iter().all(|(to, _)| *to != 3));
+        assert!(!hole_cards_for(&deliveries, 1).is_empty());
+    }
+
+    #[test]
+    fn snapshot_shows_only_the_players_own_cards() {
+        let (mut lobby, table) = table(None, waiting);
+        let found = lobby.table(table).unwrap();
+        play(found, Instant::now());
+        let snapshot = found.snapshot(1);
+        let own = found.game().hole_cards(0);
+        let shown: Vec<&TableEvent> = snapshot
+            .iter()
+            .filter(|event| matches!(event, TableEvent::HoleCards { .. }))
+            .collect();
+        assert!(
+            matches!(shown.as_slice(), [TableEvent::HoleCards { seat: 0, cards }] if cards.0 == own.0)
+        );
+        assert!(found.snapshot(3).is_empty());
+    }
+
+    #[test]
+    fn next_hand_waits_for_the_delay() {
+        let (mut lobby, table) = table(None, call_down);
+        let found = lobby.table(table).unwrap();
+        let now = Instant::now();
+        play(found, now);
+        assert!(play(found, now + Duration::from_secs(1)).is_empty());
+        assert!(!play(found, now + NEXT_HAND_DELAY).is_empty());
+    }
+
+    #[test]
+    fn table_waits_for_a_strategy_that_has_not_decided() {
+        let asked = Arc::new(Mutex::new(vec![]));
+        let mut lobby = Lobby::new();
+        let table = lobby.create_table(settings(None)).unwrap();
+        let player = Box::new(Waiting(asked.clone()));
+        lobby.join(1, table, 100, player, || Ok(())).unwrap();
+        lobby.join(2, table, 100, call_down(), || Ok(())).unwrap();
+        let found = lobby.table(table).unwrap();
+        let now = Instant::now();
+        play(found, now);
+        play(found, now);
+        assert!(found.hand_in_progress());
+        assert_eq!(found.game().to_act(), Some(0));
+        assert_eq!(*asked.lock().unwrap(), vec![true, false]);
+    }
+
+    #[test]
+    fn auto_acts_for_players_the_table_does_not_wait_on() {
+        let (mut lobby, table) = table(None, waiting);
+        let found = lobby.table(table).unwrap();
+        let mut automatic = vec![];
+        found.play(
+            Instant::now(),
+            |player, legal| {
+                automatic.push(player);
+                Some(legal.default_action())
+            },
+            |_, _| {},
+        );
+        // the first player to act folds and the hand is over without asking anyone
+        assert!(!found.hand_in_progress());
+        assert_eq!(automatic, vec![1]);
+    }
+
+    #[test]
+    fn shown_cards_and_the_rabbit_hunt_reach_everyone_without_changing_the_pot() {
+        let (mut lobby, table) = table(None, waiting);
+        let found = lobby.table(table).unwrap();
+        // the button folds its small blind, so the big blind wins without a showdown
+        found.play(
+            Instant::now(),
+            |_, legal| Some(legal.default_action()),
+            |_, _| {},
+        );
+        assert!(!found.hand_in_progress());
+        let stacks = found.stacks();
+        assert_eq!(stacks[..2], [99, 101]);
+
+        let hunted = found.rabbit_hunt().unwrap();
+        assert!(found.rabbit_hunt().is_ok());
+        assert_eq!(
+            hunted.iter().map(|(to, _)| *to).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert!(hunted.iter().all(
+            |(_, event)| matches!(event, TableEvent::RabbitHunt { cards } if cards.0.len() == 5)
+        ));
+        assert!(found.stacks() == stacks);
+
+        assert!(found.show_cards(0).is_err());
+        let own = found.game().hole_cards(1);
+        let shown = found.show_cards(1).unwrap();
+        assert_eq!(shown.len(), 3);
+        assert!(shown.iter().all(|(_, event)| matches!(
+            event,
+            TableEvent::Showdown { seat: 1, cards } if cards.0 == own.0
+        )));
+        assert!(found.show_cards(1).is_err());
+        assert!(found.stacks() == stacks);
+    }
+
+    #[test]
+    fn everyone_is_sent_the_countdown_while_the_table_waits() {
+        let (mut lobby, table) = table(None, waiting);
+        let found = lobby.table(table).unwrap();
+        let now = Instant::now();
+        let countdowns = |deliveries: &[(usize, TableEvent)]| -> Vec<usize> {
+            deliveries
+                .iter()
+                .filter(|(_, event)| matches!(event, TableEvent::Countdown { seat: 0, .. }))
+                .map(|(player, _)| *player)
+                .collect()
+        };
+        assert_eq!(countdowns(&play(found, now)), vec![1, 2, 3]);
+        assert!(countdowns(&play(found, now + Duration::from_secs(1))).is_empty());
+        assert_eq!(
+            countdowns(&play(found, now + COUNTDOWN_EVERY)),
+            vec![1, 2, 3]
+        );
+        // the countdown isn't part of the hand
+        assert!(found
+            .snapshot(1)
+            .iter()
+            .all(|event| !matches!(event, TableEvent::Countdown { .. })));
+    }
+
+    #[test]
+    fn players_out_of_time_get_the_default_action() {
+        let mut lobby = Lobby::new();
+        let mut out_of_time = settings(None);
+        out_of_time.timer.action_time = Duration::from_secs(30);
+        out_of_time.timer.time_bank = Duration::from_secs(10);
+        let table = lobby.create_table(out_of_time).unwrap();
+        lobby.join(1, table, 100, waiting(), || Ok(())).unwrap();
+        lobby.join(2, table, 100, waiting(), || Ok(())).unwrap();
+        let found = lobby.table(table).unwrap();
+        let now = Instant::now();
+        play(found, now);
+        play(found, now + Duration::from_secs(39));
+        assert!(found.hand_in_progress());
+        assert!(matches!(
+            found.countdown(),
+            Some(TableEvent::Countdown {
+                seat: 0,
+                action_left: 0,
+                time_bank_left: 1,
+            })
+        ));
+
+        let deliveries = play(found, now + Duration::from_secs(40));
+        assert!(!found.hand_in_progress());
+        assert!(deliveries.iter().any(|(_, event)| matches!(
+            event,
+            TableEvent::Action {
+                action: Action::Fold,
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn chips_of_removed_players_are_handed_back() {
+        let (mut lobby, table) = table(None, call_down);
+        lobby.join(4, table, 100, call_down(), || Ok(())).unwrap();
+        let found = lobby.table(table).unwrap();
+        found.sit_out(2).unwrap();
+        let mut now = Instant::now();
+        let mut removed = vec![];
+        for _ in 0..20 {
+            removed.extend(found.play(now, |_, _| None, |_, _| {}));
+            now += NEXT_HAND_DELAY;
+        }
+        assert_eq!(removed, vec![(4, 100)]);
+        assert_eq!(found.seats()[2], None);
+        assert_eq!(found.stacks().iter().sum::<u32>(), 200);
+    }
+
+    #[test]
+    fn moving_checks_the_buy_in_of_the_new_table() {
+        let mut lobby = Lobby::new();
+        let first = lobby.create_table(settings(None)).unwrap();
+        let mut small = settings(None);
+        small.max_buy_in = 80;
+        let second = lobby.create_table(small).unwrap();
+        lobby.join(1, first, 100, call_down(), || Ok(())).unwrap();
+        assert!(lobby.move_player(1, second).is_err());
+        assert_eq!(lobby.seat_of(1), Some((first, 0)));
+        assert_eq!(lobby.table(first).unwrap().stacks()[0], 100);
+    }
+
+    #[test]
+    fn leaving_keeps_the_seat_if_cashing_out_fails() {
+        let (mut lobby, table) = table(None, call_down);
+        let refused = lobby.leave(1, |_, _| Err("no".to_string()));
+        assert_eq!(refused, Err("no".to_string()));
+        assert_eq!(lobby.seat_of(1), Some((table, 0)));
+
+        let mut cashed_out = None;
+        let left = lobby.leave(1, |table, chips| {
+            cashed_out = Some((table, chips));
+            Ok(())
+        });
+        assert_eq!(left, Ok((table, 100)));
+        assert_eq!(cashed_out, Some((table, 100)));
+        assert_eq!(lobby.seat_of(1), None);
+    }
+
+    #[test]
+    fn hands_played_are_kept_for_the_history() {
+        let (mut lobby, table) = table(None, call_down);
+        let found = lobby.table(table).unwrap();
+        play(found, Instant::now());
+        let hands = found.played_hands(1, 10);
+        assert_eq!(hands.len(), 1);
+        assert_eq!(hands[0].seats[..2], [Some((1, 100)), Some((2, 100))]);
+        assert!(matches!(
+            hands[0].events.first(),
+            Some(TableEvent::HandStarted { hand: 1 })
+        ));
+        assert!(matches!(
+            hands[0].events.last(),
+            Some(TableEvent::HandFinished { hand: 1 })
+        ));
+        assert!(hands[0]
+            .to_pokerstars(|player| player.to_string())
+            .contains("*** SUMMARY ***"));
+    }
+
+    #[test]
+    fn moving_takes_the_strategy_along() {
+        let mut lobby = Lobby::new();
+        let first = lobby.create_table(settings(None)).unwrap();
+        let second = lobby.create_table(settings(None)).unwrap();
+        lobby.join(1, first, 100, call_down(), || Ok(())).unwrap();
+        lobby.join(2, second, 100, call_down(), || Ok(())).unwrap();
+        lobby.move_player(1, second).unwrap();
+        let found = lobby.table(second).unwrap();
+        play(found, Instant::now());
+        // both strategies call the hand down without anyone waiting
+        assert!(!found.hand_in_progress());
+        assert_eq!(found.stacks().iter().sum::<u32>(), 200);
+    }
+
+    #[test]
+    fn failed_move_keeps_the_seat_and_strategy() {
+        let mut lobby = Lobby::new();
+        let first = lobby.create_table(settings(None)).unwrap();
+        let mut heads_up = settings(None);
+        heads_up.seats = 2;
+        let second = lobby.create_table(heads_up).unwrap();
+        lobby.join(1, first, 100, call_down(), || Ok(())).unwrap();
+        lobby.join(2, first, 100, call_down(), || Ok(())).unwrap();
+        lobby.join(3, second, 100, call_down(), || Ok(())).unwrap();
+        lobby.join(4, second, 100, call_down(), || Ok(())).unwrap();
+
+        let err = lobby.move_player(1, second).unwrap_err();
+        assert_eq!(err, format!("table {} is full", second));
+        assert_eq!(lobby.seat_of(1), Some((first, 0)));
+        let found = lobby.table(first).unwrap();
+        assert!(found.players[0].is_some());
+        assert_eq!(found.stacks()[0], 100);
+        // the strategy still plays the seat, so the hand is called down without waiting
+        play(found, Instant::now());
+        assert!(!found.hand_in_progress());
+        assert_eq!(found.stacks().iter().sum::<u32>(), 200);
+    }
+}
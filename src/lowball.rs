@@ -0,0 +1,293 @@
+use crate::deck::*;
+use crate::hand::{generate_combinations, generate_omaha_combinations};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The rule set used to score a low hand.
+#[derive(Clone, Copy, PartialEq)]
+pub enum LowballRules {
+    /// Aces are low and straights and flushes are ignored, so A-2-3-4-5 is the best hand.
+    AceToFive,
+    /// Aces are high and straights and flushes count against the hand, so 2-3-4-5-7 is the
+    /// best hand.
+    DeuceToSeven,
+    /// Ace-to-five rules, but only five unpaired cards of eight or lower qualify.
+    EightOrBetter,
+}
+
+#[derive(Clone)]
+pub struct LowHand {
+    // how badly the hand is made, 0 being five unpaired cards that are neither a straight
+    // nor a flush (when those count), with each made hand ranked above the last
+    category: u8,
+    // the values of the cards ordered from most to least significant, with aces as 1 when
+    // they play low
+    values: Vec<u8>,
+}
+
+impl fmt::Display for LowHand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let values: Vec<String> = self
+            .values
+            .iter()
+            .map(|val| match *val {
+                1 | 14 => "A".to_string(),
+                11 => "J".to_string(),
+                12 => "Q".to_string(),
+                13 => "K".to_string(),
+                _ => val.to_string(),
+            })
+            .collect();
+        write!(f, "{} low", values.join("-"))
+    }
+}
+
+/// Given a vector of card collections (representing the hands of the players) and the lowball
+/// rules in play, returns a vector of rankings where the ranking in a particular index corresponds
+/// to the card collection at that index in the input. A ranking of 1 is the best low hand, and
+/// two hands of the same strength get the same ranking. Under eight-or-better rules a hand that
+/// doesn't qualify for low gets `None`. Each of the input card collections must have at least
+/// 5 cards.
+pub fn assign_low_hand_rankings(
+    hands: Vec<CardCollection>,
+    rules: LowballRules,
+) -> Vec<Option<u8>> {
+    // get the best possible low hand for each player
+    let mut best_hands: Vec<Option<LowHand>> = vec![];
+    for hand in hands {
+        best_hands.push(get_best_low_hand(hand, rules));
+    }
+
+    // assign rankings
+    rank_low_hands(best_hands)
+}
+
+/// Given a card collection of at least 5 cards, returns the best possible low hand that can be
+/// constructed under the given rules, or `None` if no 5 card combination qualifies.
+pub fn get_best_low_hand(cards: CardCollection, rules: LowballRules) -> Option<LowHand> {
+    select_best_low_hand(
+        generate_combinations(cards, CardCollection(vec![]), 0),
+        rules,
+    )
+}
+
+/// Given the 4 hole cards and 5 community cards of an Omaha player, returns the best possible
+/// low hand that uses exactly 2 hole cards and 3 community cards.
+pub fn get_best_omaha_low_hand(
+    hole: CardCollection,
+    board: CardCollection,
+    rules: LowballRules,
+) -> Option<LowHand> {
+    select_best_low_hand(generate_omaha_combinations(hole, board), rules)
+}
+
+/// Given the hole cards of each Omaha player and the community cards, returns a vector of
+/// low rankings in the same format as `assign_low_hand_rankings`.
+pub fn assign_omaha_low_hand_rankings(
+    holes: Vec<CardCollection>,
+    board: CardCollection,
+    rules: LowballRules,
+) -> Vec<Option<u8>> {
+    let mut best_hands: Vec<Option<LowHand>> = vec![];
+    for hole in holes {
+        best_hands.push(get_best_omaha_low_hand(hole, board.clone(), rules));
+    }
+
+    rank_low_hands(best_hands)
+}
+
+/// Given a vector of optional low hands, returns the rankings of each hand, where the value of
+/// some index in the returned vector corresponds to the ranking of that hand in the input vector.
+/// Hands of equal strength share a ranking and missing hands stay unranked.
+pub(crate) fn rank_low_hands(hands: Vec<Option<LowHand>>) -> Vec<Option<u8>> {
+    let mut rankings: Vec<Option<u8>> = vec![None; hands.len()];
+    // sort the qualifying hands from best to worst
+    let mut hands_with_indices: Vec<(usize, &LowHand)> = hands
+        .iter()
+        .enumerate()
+        .filter_map(|(index, hand)| hand.as_ref().map(|hand| (index, hand)))
+        .collect();
+    hands_with_indices.sort_by(|a, b| compare_low_hands(a.1, b.1));
+
+    let mut rank = 1;
+    for (i, cur) in hands_with_indices.iter().enumerate() {
+        // only increase the rank if the previous hand is not of equal strength
+        if i > 0 && compare_low_hands(hands_with_indices[i - 1].1, cur.1) != Ordering::Equal {
+            rank += 1;
+        }
+        rankings[cur.0] = Some(rank);
+    }
+
+    rankings
+}
+
+/// Returns the best low hand made by any of the 5 card combinations, if any qualify.
+fn select_best_low_hand(combos: Vec<CardCollection>, rules: LowballRules) -> Option<LowHand> {
+    let mut best: Option<LowHand> = None;
+    for combo in combos {
+        let Some(low_hand) = get_low_hand(combo, rules) else {
+            continue;
+        };
+        best = match best {
+            Some(cur) if compare_low_hands(&cur, &low_hand) != Ordering::Greater => Some(cur),
+            _ => Some(low_hand),
+        };
+    }
+
+    best
+}
+
+/// Orders two low hands so that the better low hand compares as less.
+pub(crate) fn compare_low_hands(a: &LowHand, b: &LowHand) -> Ordering {
+    a.category
+        .cmp(&b.category)
+        .then_with(|| a.values.cmp(&b.values))
+}
+
+/// Scores exactly 5 cards as a low hand, returning `None` if they don't qualify.
+fn get_low_hand(hand: CardCollection, rules: LowballRules) -> Option<LowHand> {
+    let ace_low = rules != LowballRules::DeuceToSeven;
+    let mut value_map = HashMap::new();
+    for card in &hand.0 {
+        let val = match card.value {
+            CardValue::Ace if ace_low => 1,
+            _ => card.value.value(),
+        };
+        let count = value_map.entry(val).or_insert(0u8);
+        *count += 1
+    }
+
+    // order values by how many times they appear, then by value, which mirrors the order
+    // the high hand tie breakers compare values in
+    let mut groups: Vec<(u8, u8)> = value_map.into_iter().collect();
+    groups.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| b.0.cmp(&a.0)));
+    let mut values = vec![];
+    for (val, count) in &groups {
+        for _ in 0..*count {
+            values.push(*val);
+        }
+    }
+
+    let counts: Vec<u8> = groups.iter().map(|group| group.1).collect();
+    let paired_category = match counts.as_slice() {
+        [1, 1, 1, 1, 1] => 0,
+        [2, 1, 1, 1] => 1,
+        [2, 2, 1] => 2,
+        [3, 1, 1] => 3,
+        [3, 2] => 6,
+        _ => 7,
+    };
+
+    let category = match rules {
+        LowballRules::AceToFive => paired_category,
+        LowballRules::EightOrBetter => {
+            if paired_category != 0 || values[0] > 8 {
+                return None;
+            }
+            paired_category
+        }
+        LowballRules::DeuceToSeven => {
+            let straight = paired_category == 0 && values[0] - values[4] == 4;
+            let flush = hand.0.iter().all(|card| card.suit == hand.0[0].suit);
+            match (straight, flush) {
+                (true, true) => 8,
+                (false, true) => 5,
+                (true, false) => 4,
+                (false, false) => paired_category,
+            }
+        }
+    };
+
+    Some(LowHand { category, values })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(codes: &str) -> CardCollection {
+        CardCollection(codes.split(' ').filter_map(Card::parse).collect())
+    }
+
+    fn rank(hands: &[&str], rules: LowballRules) -> Vec<Option<u8>> {
+        assign_low_hand_rankings(hands.iter().map(|hand| cards(hand)).collect(), rules)
+    }
+
+    #[test]
+    fn ace_to_five_plays_aces_low_and_ignores_straights_and_flushes() {
+        let rankings = rank(
+            &[
+                "2h 3h 4h 5h 6h",
+                "Ah 2h 3h 4h 5h",
+                "7c 6d 4h 3s 2c",
+                "7c 6d 5h 4s 2c",
+                "Ac Ad 2h 3s 4c",
+                "Kc Qd Jh Ts 9c",
+            ],
+            LowballRules::AceToFive,
+        );
+        assert_eq!(
+            rankings,
+            vec![Some(2), Some(1), Some(3), Some(4), Some(6), Some(5)]
+        );
+    }
+
+    #[test]
+    fn deuce_to_seven_plays_aces_high_and_counts_straights_and_flushes() {
+        let rankings = rank(
+            &[
+                "2c 3d 4h 5s 7c",
+                "2c 3d 4h 5s 6c",
+                "Ac 2d 3h 4s 5c",
+                "2h 3h 4h 5h 7h",
+                "8c 6d 5h 4s 2c",
+                "2c 2d 3h 4s 5c",
+            ],
+            LowballRules::DeuceToSeven,
+        );
+        assert_eq!(
+            rankings,
+            vec![Some(1), Some(5), Some(3), Some(6), Some(2), Some(4)]
+        );
+    }
+
+    #[test]
+    fn eight_or_better_needs_five_unpaired_cards_of_eight_or_lower() {
+        let rankings = rank(
+            &[
+                "Ah 2c 3d 8s 9h Kd Kc",
+                "Ah 2c 3d 8s 7h Kd Kc",
+                "Ah 2c 3d 4s 4h Kd Kc",
+                "As 2d 3c 7h 8d Qs Qc",
+            ],
+            LowballRules::EightOrBetter,
+        );
+        assert_eq!(rankings, vec![None, Some(1), None, Some(1)]);
+        let best = get_best_low_hand(cards("Ah 2c 3d 8s 7h 4d Kc"), LowballRules::EightOrBetter);
+        assert_eq!(best.unwrap().to_string(), "7-4-3-2-A low");
+    }
+
+    #[test]
+    fn omaha_low_uses_exactly_two_hole_cards() {
+        let board = cards("3c 4d 5h Kc Qd");
+        let rankings = assign_omaha_low_hand_rankings(
+            vec![
+                cards("Ah 2c Ks Kd"),
+                cards("Ah 2c 3s 4h"),
+                cards("6h 7c 8d 9s"),
+            ],
+            board,
+            LowballRules::EightOrBetter,
+        );
+        assert_eq!(rankings, vec![Some(1), Some(1), Some(2)]);
+
+        let one_low_card = cards("5c Kd Qh Js Tc");
+        let low = get_best_omaha_low_hand(
+            cards("Ah 2c 3s 4h"),
+            one_low_card,
+            LowballRules::EightOrBetter,
+        );
+        assert!(low.is_none());
+    }
+}
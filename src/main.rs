@@ -1,5 +1,23 @@
 use clap::{Parser, Subcommand};
-use dotenv;
+use poker_rust::abstraction::{Abstraction, AbstractionConfig, Feature};
+use poker_rust::betting::BettingStructure;
+use poker_rust::bots::BotKind;
+use poker_rust::cfr::{self, Algorithm};
+use poker_rust::cfr_games::{parse_range, LimitGame, RiverSubgame};
+use poker_rust::deck::{Card, CardCollection};
+use poker_rust::events::TableEvent;
+use poker_rust::hand_history::parse_hand_histories;
+use poker_rust::hand_log::HandLog;
+use poker_rust::holdem::Street;
+use poker_rust::indexer::HandIndexer;
+use poker_rust::simulate::{self, SimulationConfig};
+use poker_rust::{client, icm, server, tournament};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::fs;
+use std::io;
+use std::thread;
+use std::time::Duration;
 
 #[derive(Subcommand)]
 enum Command {
@@ -8,6 +26,13 @@ enum Command {
         /// Sets the port to bind to
         #[clap(long, short = 'p')]
         port: u32,
+        /// Sets how many seconds to wait for a disconnected player before checking or folding
+        /// for them
+        #[clap(long, default_value_t = 30)]
+        disconnect_timeout: u64,
+        /// Sets the file the ledger of every player's bankroll is kept in
+        #[clap(long, default_value = "ledger.tsv")]
+        ledger: String,
     },
     /// Starts the client
     Client {
@@ -18,6 +43,154 @@ enum Command {
         #[clap(long, short = 'p')]
         port: u32,
     },
+    /// Plays a tournament in this terminal, with the players taking turns at the keyboard
+    Tournament {
+        /// Sets the tournament config file with the blind levels and payouts
+        #[clap(long, short = 'c')]
+        config: String,
+        /// Adds a bot to the tournament, one of random, station, tag or equity. Repeat it to add
+        /// more bots
+        #[clap(long = "bot")]
+        bots: Vec<String>,
+        /// Sets a file to add a log of every hand to, which replay can deal again
+        #[clap(long)]
+        log: Option<String>,
+        /// The names of the players
+        players: Vec<String>,
+    },
+    /// Plays bots against each other without a server and reports how each of them did
+    Simulate {
+        /// Sets the bots playing, one per seat, each one of random, station, tag or equity
+        #[clap(long, short = 'b', num_args = 2.., required = true)]
+        bots: Vec<String>,
+        /// Sets the betting limit, one of nl, pl, fl or sl
+        #[clap(long, default_value = "nl")]
+        limit: String,
+        /// Sets the number of hands to play
+        #[clap(long, default_value_t = 1_000_000)]
+        hands: u64,
+        /// Sets the stack each bot starts every hand with, in big blinds
+        #[clap(long, default_value_t = 100)]
+        stack: u32,
+        /// Deals every hand once for each seating of the bots, cancelling out the luck of the
+        /// cards
+        #[clap(long)]
+        duplicate: bool,
+        /// Sets the seed the cards are shuffled from, picked at random if not given
+        #[clap(long)]
+        seed: Option<u64>,
+        /// Sets the number of threads to play on, one per core if not given
+        #[clap(long)]
+        threads: Option<usize>,
+    },
+    /// Solves a small poker game by counterfactual regret minimization and prints the strategy
+    Solve {
+        /// Sets the game to solve, one of kuhn, leduc or river
+        game: String,
+        /// Sets the algorithm, one of vanilla, cfr+ or mccfr
+        #[clap(long, short = 'a', default_value = "cfr+")]
+        algorithm: String,
+        /// Sets the number of iterations, by default 10000 for kuhn, 1000 for leduc and 200 for
+        /// river
+        #[clap(long, short = 'n')]
+        iterations: Option<u64>,
+        /// Sets the river's board
+        #[clap(long, default_value = "Ks 9h 4c 2d 2s")]
+        board: String,
+        /// Sets the range of the player first to act on the river
+        #[clap(long, default_value = "KK 99 44 AK KQ KJ K5s QJ JT T8s 87s 65s")]
+        first_range: String,
+        /// Sets the range of the player last to act on the river
+        #[clap(long, default_value = "AA QQ JJ TT 99 AK KQ KJ A5s 76s")]
+        second_range: String,
+        /// Sets the chips in the pot before the river
+        #[clap(long, default_value_t = 100)]
+        pot: u32,
+        /// Sets the chips each player has left to bet on the river
+        #[clap(long, default_value_t = 200)]
+        stack: u32,
+        /// Sets the bet sizes allowed on the river besides going all in, as fractions of the pot
+        #[clap(long, num_args = 1.., default_values_t = [0.5, 1.0])]
+        bet_sizes: Vec<f64>,
+        /// Sets the most bets and raises allowed on the river
+        #[clap(long, default_value_t = 2)]
+        max_raises: usize,
+        /// Sets the seed Monte Carlo CFR samples from, picked at random if not given
+        #[clap(long)]
+        seed: Option<u64>,
+    },
+    /// Groups similar hold'em hands into buckets on each street and saves them to a file
+    Abstraction {
+        /// Sets the file the abstraction is saved to
+        #[clap(long, short = 'o', default_value = "abstraction.txt")]
+        output: String,
+        /// Sets the number of buckets preflop, on the flop, on the turn and on the river
+        #[clap(long, num_args = 4, default_values_t = [8, 50, 50, 50])]
+        buckets: Vec<usize>,
+        /// Sets what hands are compared by before the river, strength or distribution
+        #[clap(long, default_value = "distribution")]
+        feature: String,
+        /// Sets the number of bins in the hand strength distributions
+        #[clap(long, default_value_t = 10)]
+        bins: usize,
+        /// Sets the number of random hands clustered on each street after the flop
+        #[clap(long, default_value_t = 2000)]
+        samples: usize,
+        /// Sets the number of runouts of the board each hand is sampled over
+        #[clap(long, default_value_t = 32)]
+        trials: u32,
+        /// Sets the most rounds of k-means run on each street
+        #[clap(long, default_value_t = 100)]
+        iterations: usize,
+        /// Sets the seed the hands are sampled from, picked at random if not given
+        #[clap(long)]
+        seed: Option<u64>,
+    },
+    /// Prints the index a hold'em hand shares with the hands that are the same but for their
+    /// suits, or the hand with an index
+    Index {
+        /// Sets the hole cards followed by the board, as in "As Ks Qh Jh 2c"
+        cards: Vec<String>,
+        /// Prints the hand with this index instead
+        #[clap(long, requires = "street")]
+        index: Option<u64>,
+        /// Sets the street of the index, one of preflop, flop, turn or river
+        #[clap(long)]
+        street: Option<String>,
+    },
+    /// Reads hand histories in the PokerStars format or our JSON format and checks them for
+    /// anything that couldn't have happened
+    Import {
+        /// Sets the file to read the hands from
+        file: String,
+        /// Writes the hands out in our JSON format, one per line, instead of checking them
+        #[clap(long)]
+        json: bool,
+    },
+    /// Deals the hands in a hand log again, checking every event against the log, and shows them
+    /// street by street
+    Replay {
+        /// Sets the hand log to read
+        file: String,
+        /// Replays only this hand, counting the hands in the log from 1
+        #[clap(long)]
+        hand: Option<usize>,
+        /// Waits for enter after every action and street
+        #[clap(long)]
+        step: bool,
+    },
+    /// Works out each player's share of the prize money under the Independent Chip Model
+    Icm {
+        /// Sets the chip stacks of the players left
+        #[clap(long, short = 's', num_args = 1.., required = true)]
+        stacks: Vec<u32>,
+        /// Sets the prizes still to be won, starting with 1st place
+        #[clap(long, short = 'p', num_args = 1.., required = true)]
+        payouts: Vec<u32>,
+        /// Estimates the equity from this many sampled finishes instead of working it out exactly
+        #[clap(long)]
+        trials: Option<u32>,
+    },
 }
 /// Texas Holdem' in the terminal
 #[derive(Parser)]
@@ -33,11 +206,337 @@ fn main() {
 
     // start the application
     match Cli::parse().command {
-        Command::Server { port } => {
+        Command::Server {
+            port,
+            disconnect_timeout,
+            ledger,
+        } => {
             println!("Binding the server to port {}", port);
+            let disconnect_timeout = Duration::from_secs(disconnect_timeout);
+            if let Err(err) = server::run(port, disconnect_timeout, &ledger) {
+                eprintln!("The server stopped: {}", err);
+            }
         }
         Command::Client { ip_address, port } => {
             println!("Connecting the client to {}:{}", ip_address, port);
+            if let Err(err) = client::run(&ip_address, port) {
+                eprintln!("The client stopped: {}", err);
+            }
+        }
+        Command::Tournament {
+            config,
+            bots,
+            log,
+            players,
+        } => {
+            if let Err(err) = tournament::run(&config, players, bots, log.as_deref()) {
+                eprintln!("The tournament stopped: {}", err);
+            }
+        }
+        Command::Simulate {
+            bots,
+            limit,
+            hands,
+            stack,
+            duplicate,
+            seed,
+            threads,
+        } => {
+            let bots: Option<Vec<BotKind>> = bots.iter().map(|bot| BotKind::parse(bot)).collect();
+            let Some(bots) = bots else {
+                eprintln!("The bots must be random, station, tag or equity");
+                return;
+            };
+            let Some(structure) = BettingStructure::parse(&limit, 0, 0) else {
+                eprintln!("{} isn't a betting limit", limit);
+                return;
+            };
+            let threads = threads.unwrap_or_else(|| {
+                thread::available_parallelism().map_or(1, |threads| threads.get())
+            });
+            let config = SimulationConfig {
+                bots,
+                structure,
+                hands,
+                stack,
+                duplicate,
+                seed: seed.unwrap_or_else(rand::random),
+                threads,
+            };
+            if let Err(err) = simulate::run(&config) {
+                eprintln!("The simulation stopped: {}", err);
+            }
+        }
+        Command::Solve {
+            game,
+            algorithm,
+            iterations,
+            board,
+            first_range,
+            second_range,
+            pot,
+            stack,
+            bet_sizes,
+            max_raises,
+            seed,
+        } => {
+            let Some(algorithm) = Algorithm::parse(&algorithm) else {
+                eprintln!("The algorithm must be vanilla, cfr+ or mccfr");
+                return;
+            };
+            let seed = seed.unwrap_or_else(rand::random);
+            match game.as_str() {
+                "kuhn" => {
+                    let iterations = iterations.unwrap_or(10_000);
+                    cfr::run(LimitGame::kuhn(), algorithm, iterations, seed);
+                }
+                "leduc" => {
+                    let iterations = iterations.unwrap_or(1000);
+                    cfr::run(LimitGame::leduc(), algorithm, iterations, seed);
+                }
+                "river" => {
+                    let river = river_subgame(
+                        &board,
+                        [&first_range, &second_range],
+                        pot,
+                        stack,
+                        bet_sizes,
+                        max_raises,
+                    );
+                    match river {
+                        Ok(river) => cfr::run(river, algorithm, iterations.unwrap_or(200), seed),
+                        Err(err) => eprintln!("The river can't be solved: {}", err),
+                    }
+                }
+                _ => eprintln!("The game must be kuhn, leduc or river"),
+            }
+        }
+        Command::Abstraction {
+            output,
+            buckets,
+            feature,
+            bins,
+            samples,
+            trials,
+            iterations,
+            seed,
+        } => {
+            let Some(feature) = Feature::parse(&feature, bins) else {
+                eprintln!("The feature must be strength or distribution");
+                return;
+            };
+            let config = AbstractionConfig {
+                buckets: [buckets[0], buckets[1], buckets[2], buckets[3]],
+                feature,
+                samples,
+                trials,
+                iterations,
+            };
+            let mut rng = StdRng::seed_from_u64(seed.unwrap_or_else(rand::random));
+            let abstraction = match Abstraction::build(&config, &mut rng) {
+                Ok(abstraction) => abstraction,
+                Err(err) => {
+                    eprintln!("The abstraction couldn't be built: {}", err);
+                    return;
+                }
+            };
+            for street in [Street::Preflop, Street::Flop, Street::Turn, Street::River] {
+                let strengths: Vec<String> = abstraction
+                    .strengths(street)
+                    .iter()
+                    .map(|strength| format!("{:.2}", strength))
+                    .collect();
+                println!("{:<8} {}", street, strengths.join(" "));
+            }
+            match abstraction.save(&output) {
+                Ok(()) => println!("Saved the abstraction to {}", output),
+                Err(err) => eprintln!("The abstraction couldn't be saved: {}", err),
+            }
+        }
+        Command::Index {
+            cards,
+            index,
+            street,
+        } => {
+            if let Err(err) = print_index(&cards, index, street.as_deref()) {
+                eprintln!("The hand couldn't be indexed: {}", err);
+            }
+        }
+        Command::Import { file, json } => {
+            if let Err(err) = import_hands(&file, json) {
+                eprintln!("The hands couldn't be read: {}", err);
+            }
+        }
+        Command::Replay { file, hand, step } => {
+            if let Err(err) = replay_hands(&file, hand, step) {
+                eprintln!("The hands couldn't be replayed: {}", err);
+            }
+        }
+        Command::Icm {
+            stacks,
+            payouts,
+            trials,
+        } => {
+            let equity = match trials {
+                Some(trials) => {
+                    icm::monte_carlo_icm_equity(&stacks, &payouts, trials, &mut rand::thread_rng())
+                }
+                None => icm::icm_equity(&stacks, &payouts),
+            };
+            let chips = icm::chip_equity(&stacks, &payouts);
+            println!("Player  Stack  ICM equity  Chip equity");
+            for (player, stack) in stacks.iter().enumerate() {
+                println!(
+                    "{:>6}  {:>5}  {:>10.2}  {:>11.2}",
+                    player + 1,
+                    stack,
+                    equity[player],
+                    chips[player]
+                );
+            }
         }
     }
 }
+
+/// Sets up the river subgame from the command line's board and ranges.
+fn river_subgame(
+    board: &str,
+    ranges: [&str; 2],
+    pot: u32,
+    stack: u32,
+    bet_sizes: Vec<f64>,
+    max_raises: usize,
+) -> Result<RiverSubgame, String> {
+    let cards: Option<Vec<Card>> = board.split_whitespace().map(Card::parse).collect();
+    let board = CardCollection(cards.ok_or(format!("{} isn't a board", board))?);
+    let ranges = [
+        parse_range(ranges[0], &board)?,
+        parse_range(ranges[1], &board)?,
+    ];
+
+    RiverSubgame::new(board, ranges, pot, stack, bet_sizes, max_raises)
+}
+
+/// Prints the street and index of the hand, or the hand with the index on the street.
+fn print_index(cards: &[String], index: Option<u64>, street: Option<&str>) -> Result<(), String> {
+    let codes = |cards: &CardCollection| -> String {
+        let codes: Vec<String> = cards.0.iter().map(Card::code).collect();
+        codes.join(" ")
+    };
+    if let Some(index) = index {
+        let street = street.unwrap_or_default();
+        let street = Street::parse(street).ok_or(format!("{} isn't a street", street))?;
+        let indexer = HandIndexer::holdem(street);
+        let (hole, board) = indexer.unindex(index).ok_or(format!(
+            "the {} only has {} hands",
+            street,
+            indexer.size()
+        ))?;
+        println!("{}  {}", codes(&hole), codes(&board));
+        return Ok(());
+    }
+
+    let cards: Option<Vec<Card>> = cards.iter().map(|card| Card::parse(card)).collect();
+    let mut cards = cards.ok_or("the cards must be written like As or Td")?;
+    let board = CardCollection(cards.split_off(2.min(cards.len())));
+    let hole = CardCollection(cards);
+    let street = [Street::Preflop, Street::Flop, Street::Turn, Street::River]
+        .into_iter()
+        .find(|street| street.board_cards() == board.0.len())
+        .ok_or("the board must have 0, 3, 4 or 5 cards")?;
+    let indexer = HandIndexer::holdem(street);
+    let index = indexer
+        .index(&hole, &board)
+        .ok_or("the hand needs two hole cards and no card twice")?;
+    println!("{} index {} of {}", street, index, indexer.size());
+
+    Ok(())
+}
+
+/// Prints each hand in the file along with the problems found in it, or writes the hands out
+/// in our JSON format.
+fn import_hands(file: &str, json: bool) -> Result<(), String> {
+    let text = fs::read_to_string(file).map_err(|err| format!("{}: {}", file, err))?;
+    let hands = parse_hand_histories(&text)?;
+    if json {
+        for hand in &hands {
+            println!("{}", hand.to_json());
+        }
+        return Ok(());
+    }
+
+    let mut flagged = 0;
+    for hand in &hands {
+        println!("{}", hand);
+        let problems = hand.problems();
+        if !problems.is_empty() {
+            flagged += 1;
+        }
+        for problem in problems {
+            println!("  Problem: {}", problem);
+        }
+        println!();
+    }
+    println!("Read {} hands, {} with problems", hands.len(), flagged);
+
+    Ok(())
+}
+
+/// Replays each hand in the log, or just the one asked for, printing every event as it comes
+/// with a heading for each street. Stepping waits for enter after every action and street.
+fn replay_hands(file: &str, hand: Option<usize>, step: bool) -> Result<(), String> {
+    let text = fs::read_to_string(file).map_err(|err| format!("{}: {}", file, err))?;
+    let logs = HandLog::parse_json_lines(&text)?;
+    let logs = match hand {
+        Some(hand) => {
+            let log = hand
+                .checked_sub(1)
+                .and_then(|place| logs.get(place))
+                .ok_or(format!("the log only has {} hands", logs.len()))?;
+            vec![log.clone()]
+        }
+        None => logs,
+    };
+
+    for log in &logs {
+        println!(
+            "Hand #{}: {} with seed {}",
+            log.hand, log.settings, log.seed
+        );
+        for (seat, player) in log.seats.iter().enumerate() {
+            if let Some(player) = player {
+                println!(
+                    "Seat {}: {} chips, {}",
+                    seat + 1,
+                    player.chips,
+                    player.status
+                );
+            }
+        }
+        let replayed = log.replay(|event| {
+            let street = match event {
+                TableEvent::Board { cards } => match cards.0.len() {
+                    3 => Some("FLOP"),
+                    4 => Some("TURN"),
+                    _ => Some("RIVER"),
+                },
+                TableEvent::Run { .. } => Some("RUN"),
+                _ => None,
+            };
+            if let Some(street) = street {
+                println!("*** {} ***", street);
+            }
+            println!("{}", event);
+            let pause = street.is_some() || matches!(event, TableEvent::Action { .. });
+            if step && pause {
+                let _ = io::stdin().read_line(&mut String::new());
+            }
+        });
+        match replayed {
+            Ok(()) => println!("Hand #{} matches its log\n", log.hand),
+            Err(err) => return Err(format!("hand #{} doesn't match its log: {}", log.hand, err)),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,1047 @@
+use crate::bankroll::{Bank, Movement};
+use crate::betting::{Action, BettingStructure, LegalActions};
+use crate::bots::BotKind;
+use crate::deck::CardCollection;
+use crate::draw::{numbered_hand, parse_discards};
+use crate::events::TableEvent;
+use crate::lobby::{GameType, Lobby, TableSettings};
+use crate::session::SessionStore;
+use crate::strategy::{Decision, Strategy};
+use crate::timer::TimerSettings;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the dealer looks over the tables for hands to deal and turns to take.
+const DEALER_TICK: Duration = Duration::from_millis(200);
+
+const HELP: &str = "Commands:
+  login <name> <password>                       pick the name your bankroll is kept under,
+                                                the first login with a name sets its password
+  balance                                       show your bankroll
+  deposit <chips>                               add money you brought to your bankroll
+  withdraw <chips>                              take money out of your bankroll
+  list                                          list the tables
+  create <game> <limit> <small> <big> <seats> [delay]
+                                                create a table, spectators see hands
+                                                [delay] hands late if given
+  join <table> <chips>                          sit down at a table, buying in for <chips>
+  topup <chips>                                 add chips to your stack between hands
+  rebuy <chips>                                 buy back in once you're out of chips
+  watch <table>                                 watch a table without sitting down
+  unwatch                                       stop watching your table
+  move <table>                                  move to another table with your chips
+  addbot <table> <bot>                          fill an empty seat with a bot, staking it
+                                                the table's max buy-in from your bankroll
+  removebot <table> <seat>                      take a bot off a table, its chips go back to
+                                                whoever staked it
+  fold | check | call | bet <total> | raise <total>
+                                                act when it's your turn, bets and raises
+                                                are your total bet for the round
+  runs <count>                                  pick how many times to run the board when
+                                                everyone is all in
+  draw [positions]                              discard the cards at [positions] in the
+                                                draw, or stand pat without any
+  show                                          show your cards after winning a hand
+                                                without a showdown
+  muck on|off                                   muck hands that can't win at showdown, or
+                                                show every hand
+  rabbit                                        see the rest of the board the last hand
+                                                would have dealt
+  sitout                                        sit out from the next hand, players who
+                                                sit out too long are cashed out
+  sitin [post]                                  play again, posting the blinds you missed
+                                                with post or else waiting for the big blind
+  leave                                         leave your table and cash out
+  resume <token>                                get your seat back after reconnecting
+  history [count]                               show the last [count] hands at your table
+                                                in PokerStars format
+  ledger                                        show how much everyone is up or down
+Games: holdem, shortdeck, omaha8, stud, stud8, draw
+Limits: nl, pl, fl, sl (for spread limit the stakes are the min and max bet)
+Bots: random, station, tag, equity";
+
+/// The lines waiting to be written to a player's connection. Resuming a session points the
+/// player's outbox at the new connection, so anything holding it keeps reaching them.
+#[derive(Clone)]
+pub struct Outbox(Arc<Mutex<Sender<String>>>);
+
+impl Outbox {
+    pub fn new(connection: Sender<String>) -> Self {
+        Outbox(Arc::new(Mutex::new(connection)))
+    }
+
+    /// Queues the line for the connection. Lines for a closed connection are dropped.
+    pub fn send(&self, line: String) {
+        if let Ok(connection) = self.0.lock() {
+            let _ = connection.send(line);
+        }
+    }
+
+    fn redirect(&self, connection: Sender<String>) {
+        if let Ok(mut current) = self.0.lock() {
+            *current = connection;
+        }
+    }
+}
+
+/// What a connected player sends to the seat they play from.
+pub enum RemoteInput {
+    Action(Action),
+    Runs(u32),
+    Discards(Vec<usize>),
+}
+
+/// Plays a seat for a connected player, passing on what they send once the table asks for it
+/// and telling them when it's their turn.
+pub struct RemotePlayer {
+    inputs: Receiver<RemoteInput>,
+    outbox: Outbox,
+}
+
+impl RemotePlayer {
+    pub fn new(inputs: Receiver<RemoteInput>, outbox: Outbox) -> Self {
+        RemotePlayer { inputs, outbox }
+    }
+
+    /// Throws away anything sent for an earlier turn.
+    fn drain(&self) {
+        while self.inputs.try_recv().is_ok() {}
+    }
+}
+
+impl Strategy for RemotePlayer {
+    fn decide(&mut self, decision: &Decision) -> Action {
+        match self.inputs.recv() {
+            Ok(RemoteInput::Action(action)) => action,
+            _ => decision.legal.default_action(),
+        }
+    }
+
+    fn choose_runs(&mut self, _max_runs: u32) -> u32 {
+        match self.inputs.recv() {
+            Ok(RemoteInput::Runs(runs)) => runs,
+            _ => 1,
+        }
+    }
+
+    fn choose_discards(&mut self, _hand: &CardCollection) -> Vec<usize> {
+        match self.inputs.recv() {
+            Ok(RemoteInput::Discards(discards)) => discards,
+            _ => vec![],
+        }
+    }
+
+    fn poll_decide(&mut self, decision: &Decision, first: bool) -> Option<Action> {
+        if first {
+            self.drain();
+            self.outbox
+                .send(format!("It's your turn, {}", decision.describe()));
+        }
+        match self.inputs.try_recv() {
+            Ok(RemoteInput::Action(action)) => Some(action),
+            _ => None,
+        }
+    }
+
+    fn poll_runs(&mut self, max_runs: u32, first: bool) -> Option<u32> {
+        if first {
+            self.drain();
+            self.outbox.send(format!(
+                "Everyone is all in, pick how many times to run the board with runs <count>, up to {}",
+                max_runs
+            ));
+        }
+        match self.inputs.try_recv() {
+            Ok(RemoteInput::Runs(runs)) => Some(runs),
+            _ => None,
+        }
+    }
+
+    fn poll_discards(&mut self, hand: &CardCollection, first: bool) -> Option<Vec<usize>> {
+        if first {
+            self.drain();
+            self.outbox.send(format!(
+                "It's your turn to draw, discard with draw <positions> or stand pat with draw\n{}",
+                numbered_hand(hand)
+            ));
+        }
+        match self.inputs.try_recv() {
+            Ok(RemoteInput::Discards(discards)) => Some(discards),
+            _ => None,
+        }
+    }
+}
+
+/// Everything the server shares between the connections.
+pub struct ServerState {
+    pub lobby: Lobby,
+    pub sessions: SessionStore,
+    pub bank: Bank,
+    // the name each player logged in with
+    names: HashMap<usize, String>,
+    // where the lines for each connected player go
+    outboxes: HashMap<usize, Outbox>,
+    // where to send what each seated player does
+    inputs: HashMap<usize, Sender<RemoteInput>>,
+    // the bots sitting at tables by the player number they sit under, with the name of the
+    // player who staked them
+    bots: HashMap<usize, String>,
+    // the player number the next bot sits under, counting down so they never meet a connection's
+    next_bot: usize,
+}
+
+impl ServerState {
+    pub fn new(disconnect_timeout: Duration, bank: Bank) -> Self {
+        ServerState {
+            lobby: Lobby::new(),
+            sessions: SessionStore::new(disconnect_timeout),
+            bank,
+            names: HashMap::new(),
+            outboxes: HashMap::new(),
+            inputs: HashMap::new(),
+            bots: HashMap::new(),
+            next_bot: usize::MAX,
+        }
+    }
+
+    /// Sends the player's lines to the connection from now on.
+    pub fn connect(&mut self, player: usize, connection: Sender<String>) {
+        self.outboxes.insert(player, Outbox::new(connection));
+    }
+
+    /// Plays every table on as far as it can go, dealing new hands and taking the actions the
+    /// players and bots have picked, and sends each player what they get to see of it.
+    pub fn play_tables(&mut self, now: Instant) {
+        let outboxes = &self.outboxes;
+        let sessions = &self.sessions;
+        let mut removed = vec![];
+        for table in self.lobby.tables_mut() {
+            let id = table.id;
+            let players = table.play(
+                now,
+                |player, legal| auto_action(sessions, player, legal, now),
+                |player, event| {
+                    if let Some(outbox) = outboxes.get(&player) {
+                        outbox.send(event.to_string());
+                    }
+                },
+            );
+            removed.extend(
+                players
+                    .into_iter()
+                    .map(|(player, chips)| (player, id, chips)),
+            );
+        }
+        for (player, table, chips) in removed {
+            self.inputs.remove(&player);
+            self.sessions.remove(player);
+            let Some(name) = self.account(player) else {
+                log::error!("nobody to cash out {} chips to for {}", chips, player);
+                continue;
+            };
+            self.bots.remove(&player);
+            if let Ok(balance) = cash_out(&mut self.bank, &name, table, chips) {
+                if let Some(outbox) = self.outboxes.get(&player) {
+                    outbox.send(format!(
+                        "Cashed out {} chips from table {}, your bankroll is {}",
+                        chips, table, balance
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Sends each player the events they were handed.
+    fn deliver(&self, deliveries: Vec<(usize, TableEvent)>) {
+        for (player, event) in deliveries {
+            if let Some(outbox) = self.outboxes.get(&player) {
+                outbox.send(event.to_string());
+            }
+        }
+    }
+
+    /// Passes what the player sent on to their seat and plays their table on.
+    fn send_input(&mut self, player: usize, input: RemoteInput) -> Result<(), String> {
+        let Some(inputs) = self.inputs.get(&player) else {
+            return Err("You aren't sitting at a table".to_string());
+        };
+        inputs
+            .send(input)
+            .map_err(|_| "You aren't sitting at a table".to_string())?;
+        self.play_tables(Instant::now());
+
+        Ok(())
+    }
+
+    /// Takes the player off their table and puts their chips back in their bankroll, returning
+    /// the table they left, the chips they cashed out and their new balance.
+    fn leave_table(&mut self, player: usize) -> Result<(usize, u32, u32), String> {
+        let Some(name) = self.account(player) else {
+            return Err("you aren't logged in".to_string());
+        };
+        let bank = &mut self.bank;
+        let mut balance = 0;
+        let (table, chips) = self.lobby.leave(player, |table, chips| {
+            balance = cash_out(bank, &name, table, chips)?;
+            Ok(())
+        })?;
+        self.inputs.remove(&player);
+        self.bots.remove(&player);
+
+        Ok((table, chips, balance))
+    }
+
+    /// Returns the name of the bankroll the player's chips belong to, which for a bot is the
+    /// player who staked it.
+    fn account(&self, player: usize) -> Option<String> {
+        self.bots
+            .get(&player)
+            .or_else(|| self.names.get(&player))
+            .cloned()
+    }
+
+    /// Cleans up after a connection closes. Players with a session keep their seat so they can
+    /// resume it, and everyone else is removed from the lobby.
+    fn drop_connection(&mut self, connection: usize, player: usize, now: Instant) {
+        if self.sessions.disconnect(connection, now).is_some() {
+            return;
+        }
+        // the session was resumed on another connection
+        if self.sessions.has_session(player) {
+            return;
+        }
+        let _ = self.leave_table(player);
+        self.lobby.stop_watching(player);
+        self.names.remove(&player);
+        self.outboxes.remove(&player);
+    }
+}
+
+/// Binds the server to the port and serves every client that connects on its own thread. The
+/// bankrolls are kept in the ledger file at `ledger`.
+pub fn run(port: u32, disconnect_timeout: Duration, ledger: &str) -> io::Result<()> {
+    let bank = Bank::open(ledger).map_err(io::Error::other)?;
+    let listener = TcpListener::bind(format!("0.0.0.0:{}", port))?;
+    let state = Arc::new(Mutex::new(ServerState::new(disconnect_timeout, bank)));
+    let dealer = Arc::clone(&state);
+    thread::spawn(move || loop {
+        thread::sleep(DEALER_TICK);
+        lock(&dealer).play_tables(Instant::now());
+    });
+
+    let mut next_connection = 1;
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                log::warn!("failed to accept a connection: {}", err);
+                continue;
+            }
+        };
+        let connection = next_connection;
+        next_connection += 1;
+        let state = Arc::clone(&state);
+        thread::spawn(move || {
+            // players start out as the connection they arrived on, until they resume a session
+            let mut player = connection;
+            log::info!("player {} connected", player);
+            if let Err(err) = handle_client(stream, connection, &mut player, &state) {
+                log::warn!("lost the connection to player {}: {}", player, err);
+            }
+            lock(&state).drop_connection(connection, player, Instant::now());
+            log::info!("player {} disconnected", player);
+        });
+    }
+
+    Ok(())
+}
+
+/// Reads commands from the client one line at a time and sends back the response to each.
+/// Everything sent to the client goes through one writer thread, so the responses and what
+/// happens at the tables arrive whole and in order.
+fn handle_client(
+    stream: TcpStream,
+    connection: usize,
+    player: &mut usize,
+    state: &Arc<Mutex<ServerState>>,
+) -> io::Result<()> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream.try_clone()?;
+    let (sender, lines) = mpsc::channel::<String>();
+    thread::spawn(move || {
+        for line in lines {
+            if writeln!(writer, "{}", line).is_err() {
+                break;
+            }
+        }
+    });
+    lock(state).connect(*player, sender.clone());
+    let _ = sender.send(format!("Welcome, you are player {}\n{}", player, HELP));
+
+    for line in reader.lines() {
+        let response = handle_command(&mut lock(state), connection, player, &sender, &line?);
+        if !response.is_empty() {
+            let _ = sender.send(response);
+        }
+    }
+    let _ = stream.shutdown(Shutdown::Both);
+
+    Ok(())
+}
+
+/// Runs a single command for the player and returns the text to send back, which is empty for
+/// actions since everyone at the table is sent those. Resuming a session switches the
+/// connection, whose lines go to `sender`, over to the session's player.
+pub fn handle_command(
+    state: &mut ServerState,
+    connection: usize,
+    player: &mut usize,
+    sender: &Sender<String>,
+    line: &str,
+) -> String {
+    let lobby = &mut state.lobby;
+    let bank = &mut state.bank;
+    let name = state.names.get(player).cloned();
+    let args: Vec<&str> = line.split_whitespace().collect();
+    match args.as_slice() {
+        ["help"] => HELP.to_string(),
+        ["login", new_name, password] => {
+            if !new_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return "Names can only have letters, numbers, - and _".to_string();
+            }
+            if lobby.seat_of(*player).is_some() {
+                return "You can't change your name while sitting at a table".to_string();
+            }
+            let taken = state
+                .names
+                .iter()
+                .any(|(other, taken)| other != player && taken == new_name);
+            if taken {
+                return format!("Someone is already playing as {}", new_name);
+            }
+            if let Err(err) = state.sessions.log_in(new_name, password) {
+                return err;
+            }
+            state.names.insert(*player, new_name.to_string());
+            format!(
+                "Logged in as {}, your bankroll is {}",
+                new_name,
+                bank.balance(new_name)
+            )
+        }
+        ["login", ..] => "Log in with login <name> <password>".to_string(),
+        ["balance"] => match name {
+            Some(name) => format!("Your bankroll is {}", bank.balance(&name)),
+            None => "Log in first with login <name> <password>".to_string(),
+        },
+        [verb @ ("deposit" | "withdraw"), chips] => {
+            let Some(name) = name else {
+                return "Log in first with login <name> <password>".to_string();
+            };
+            let Ok(chips) = chips.parse() else {
+                return format!("{} isn't a number of chips", chips);
+            };
+            let movement = if *verb == "deposit" {
+                Movement::Deposit
+            } else {
+                Movement::Withdrawal
+            };
+            match bank.record(&name, movement, None, chips) {
+                Ok(balance) => format!("Your bankroll is now {}", balance),
+                Err(err) => err,
+            }
+        }
+        ["ledger"] => {
+            let settlement = bank.settlement();
+            if settlement.is_empty() {
+                return "The ledger is empty".to_string();
+            }
+            let lines: Vec<String> = settlement
+                .into_iter()
+                .map(|(name, net)| match net {
+                    net if net > 0 => format!("{} is up {}", name, net),
+                    net if net < 0 => format!("{} is down {}", name, -net),
+                    _ => format!("{} is even", name),
+                })
+                .collect();
+            lines.join("\n")
+        }
+        ["list"] => {
+            if lobby.tables().is_empty() {
+                return "There are no tables yet".to_string();
+            }
+            let tables: Vec<String> = lobby.tables().iter().map(|t| t.to_string()).collect();
+            tables.join("\n")
+        }
+        ["create", game, limit, small, big, seats, rest @ ..] if rest.len() <= 1 => {
+            let settings = match parse_settings(game, limit, small, big, seats, rest.first()) {
+                Ok(settings) => settings,
+                Err(err) => return err,
+            };
+            match lobby.create_table(settings) {
+                Ok(table) => format!("Created table {}", table),
+                Err(err) => err,
+            }
+        }
+        ["join", table, chips] => {
+            let Some(name) = name else {
+                return "Log in first with login <name> <password>".to_string();
+            };
+            let (Ok(table), Ok(chips)) = (table.parse(), chips.parse()) else {
+                return "The table and chips must be numbers".to_string();
+            };
+            let pay = || {
+                bank.record(&name, Movement::BuyIn, Some(table), chips)
+                    .map(|_| ())
+            };
+            let (inputs, received) = mpsc::channel();
+            let outbox = state
+                .outboxes
+                .entry(*player)
+                .or_insert_with(|| Outbox::new(sender.clone()))
+                .clone();
+            let remote = Box::new(RemotePlayer::new(received, outbox));
+            match lobby.join(*player, table, chips, remote, pay) {
+                Ok(seat) => {
+                    state.inputs.insert(*player, inputs);
+                    format!(
+                    "Sat down at table {} in seat {} with {} chips\nYour session token is {}, use resume <token> to get your seat back if you lose your connection",
+                    table,
+                    seat + 1,
+                    chips,
+                    state.sessions.issue(*player, connection)
+                )
+                }
+                Err(err) => err,
+            }
+        }
+        [verb @ ("topup" | "rebuy"), chips] => {
+            let (Some(name), Some((table, seat))) = (name, lobby.seat_of(*player)) else {
+                return "You aren't sitting at a table".to_string();
+            };
+            let Ok(chips) = chips.parse() else {
+                return format!("{} isn't a number of chips", chips);
+            };
+            let Some(found) = lobby.table(table) else {
+                return format!("there is no table {}", table);
+            };
+            let movement = if *verb == "topup" {
+                Movement::TopUp
+            } else {
+                Movement::Rebuy
+            };
+            let pay = || bank.record(&name, movement, Some(table), chips).map(|_| ());
+            let result = if movement == Movement::TopUp {
+                found.top_up(seat, chips, pay)
+            } else {
+                found.rebuy(seat, chips, pay)
+            };
+            match result {
+                Ok(()) => format!("Your stack is now {}", found.stacks()[seat]),
+                Err(err) => err,
+            }
+        }
+        ["move", table] => match table.parse() {
+            Ok(table) => match lobby.move_player(*player, table) {
+                Ok(seat) => format!("Moved to table {} in seat {}", table, seat + 1),
+                Err(err) => err,
+            },
+            Err(_) => format!("{} isn't a table number", table),
+        },
+        ["addbot", table, kind] => {
+            let Some(name) = name else {
+                return "Log in first with login <name> <password>".to_string();
+            };
+            let Ok(table) = table.parse() else {
+                return format!("{} isn't a table number", table);
+            };
+            let Some(kind) = BotKind::parse(kind) else {
+                return format!("{} isn't a bot", kind);
+            };
+            let Some(found) = lobby.table(table) else {
+                return format!("there is no table {}", table);
+            };
+            if !kind.plays(found.settings.game) {
+                return format!("The {} bot can't play {}", kind, found.settings.game);
+            }
+            // bots buy in for as much as the table allows, staked by whoever adds them
+            let chips = found.settings.max_buy_in;
+            let bot = state.next_bot;
+            let pay = || {
+                bank.record(&name, Movement::BuyIn, Some(table), chips)
+                    .map(|_| ())
+            };
+            match lobby.join(bot, table, chips, kind.create(), pay) {
+                Ok(seat) => {
+                    state.next_bot -= 1;
+                    state.bots.insert(bot, name);
+                    format!(
+                        "Staked a bot ({}) at table {} in seat {} with {} chips",
+                        kind,
+                        table,
+                        seat + 1,
+                        chips
+                    )
+                }
+                Err(err) => err,
+            }
+        }
+        ["removebot", table, seat] => {
+            let (Ok(table), Ok(seat)) = (table.parse::<usize>(), seat.parse::<usize>()) else {
+                return "The table and seat must be numbers".to_string();
+            };
+            let Some(found) = lobby.table(table) else {
+                return format!("there is no table {}", table);
+            };
+            let sitting = seat
+                .checked_sub(1)
+                .and_then(|seat| found.seats().get(seat).copied().flatten());
+            let Some(bot) = sitting.filter(|sitting| state.bots.contains_key(sitting)) else {
+                return format!("There isn't a bot in seat {} at table {}", seat, table);
+            };
+            let backer = state.bots[&bot].clone();
+            match state.leave_table(bot) {
+                Ok((_, chips, _)) => format!(
+                    "Took the bot in seat {} off table {} and cashed out {} chips to {}",
+                    seat, table, chips, backer
+                ),
+                Err(err) => err,
+            }
+        }
+        ["watch", table] => match table.parse() {
+            Ok(table) => match lobby.watch(*player, table) {
+                Ok(()) => format!("Watching table {}", table),
+                Err(err) => err,
+            },
+            Err(_) => format!("{} isn't a table number", table),
+        },
+        ["unwatch"] => match lobby.stop_watching(*player) {
+            Some(table) => format!("Stopped watching table {}", table),
+            None => "You aren't watching a table".to_string(),
+        },
+        ["sitout"] => {
+            let Some((table, seat)) = lobby.seat_of(*player) else {
+                return "You aren't sitting at a table".to_string();
+            };
+            let Some(found) = lobby.table(table) else {
+                return format!("there is no table {}", table);
+            };
+            match found.sit_out(seat) {
+                Ok(()) => "You'll sit out from the next hand".to_string(),
+                Err(err) => err,
+            }
+        }
+        ["sitin", rest @ ..] if matches!(rest, [] | ["post"]) => {
+            if lobby.seat_of(*player).is_none() {
+                return "You aren't sitting at a table".to_string();
+            }
+            let post_missed_blinds = !rest.is_empty();
+            match lobby.sit_in(*player, post_missed_blinds) {
+                Ok(()) => "You're back in".to_string(),
+                Err(err) => err,
+            }
+        }
+        ["leave"] => {
+            if lobby.seat_of(*player).is_none() {
+                return "You aren't sitting at a table".to_string();
+            }
+            match state.leave_table(*player) {
+                Ok((table, chips, balance)) => {
+                    state.sessions.remove(*player);
+                    format!(
+                        "Left table {} and cashed out {} chips, your bankroll is {}",
+                        table, chips, balance
+                    )
+                }
+                Err(err) => err,
+            }
+        }
+        ["resume", token] => {
+            let resumed = match state.sessions.resume(token, connection) {
+                Ok(resumed) => resumed,
+                Err(err) => return err,
+            };
+            state.outboxes.remove(player);
+            match state.outboxes.get(&resumed) {
+                Some(outbox) => outbox.redirect(sender.clone()),
+                None => {
+                    state.outboxes.insert(resumed, Outbox::new(sender.clone()));
+                }
+            }
+            *player = resumed;
+            let mut response = format!("Resumed as player {}", resumed);
+            if let Some((table, _)) = lobby.seat_of(resumed) {
+                response.push_str(&format!(", sitting at table {}", table));
+                if let Some(table) = lobby.table(table) {
+                    for event in table.snapshot(resumed) {
+                        response.push_str(&format!("\n{}", event));
+                    }
+                }
+            }
+            response
+        }
+        ["history", rest @ ..] if rest.len() <= 1 => {
+            let count = match rest.first().map(|count| count.parse()) {
+                Some(Ok(count)) => count,
+                Some(Err(_)) => return "The number of hands must be a number".to_string(),
+                None => 1,
+            };
+            let table = match lobby.seat_of(*player) {
+                Some((table, _)) => Some(table),
+                None => lobby.watching(*player),
+            };
+            let Some(found) = table.and_then(|table| lobby.table(table)) else {
+                return "You aren't sitting at or watching a table".to_string();
+            };
+            let hands = found.played_hands(*player, count);
+            if hands.is_empty() {
+                return "There are no hands to show yet".to_string();
+            }
+            let histories: Vec<String> = hands
+                .iter()
+                .map(|hand| hand.to_pokerstars(|player| player_name(&state.names, player)))
+                .collect();
+            histories.join("\n\n")
+        }
+        ["show"] => {
+            let Some((table, seat)) = lobby.seat_of(*player) else {
+                return "You aren't sitting at a table".to_string();
+            };
+            let Some(found) = lobby.table(table) else {
+                return format!("there is no table {}", table);
+            };
+            match found.show_cards(seat) {
+                Ok(deliveries) => {
+                    state.deliver(deliveries);
+                    String::new()
+                }
+                Err(err) => err,
+            }
+        }
+        ["muck", setting @ ("on" | "off")] => {
+            let Some((table, seat)) = lobby.seat_of(*player) else {
+                return "You aren't sitting at a table".to_string();
+            };
+            let Some(found) = lobby.table(table) else {
+                return format!("there is no table {}", table);
+            };
+            let muck = *setting == "on";
+            match found.set_muck_losing_hands(seat, muck) {
+                Ok(()) if muck => "You'll muck hands that can't win at showdown".to_string(),
+                Ok(()) => "You'll show every hand you take to showdown".to_string(),
+                Err(err) => err,
+            }
+        }
+        ["rabbit"] => {
+            let table = match lobby.seat_of(*player) {
+                Some((table, _)) => Some(table),
+                None => lobby.watching(*player),
+            };
+            let Some(found) = table.and_then(|table| lobby.table(table)) else {
+                return "You aren't sitting at or watching a table".to_string();
+            };
+            match found.rabbit_hunt() {
+                Ok(deliveries) => {
+                    state.deliver(deliveries);
+                    String::new()
+                }
+                Err(err) => err,
+            }
+        }
+        ["runs", runs] => {
+            let Some((table, seat)) = lobby.seat_of(*player) else {
+                return "You aren't sitting at a table".to_string();
+            };
+            let Ok(runs) = runs.parse() else {
+                return format!("{} isn't a number of runs", runs);
+            };
+            let Some(found) = lobby.table(table) else {
+                return format!("there is no table {}", table);
+            };
+            if !found.game().awaiting_runs().contains(&seat) {
+                return "You aren't picking how many times to run the board".to_string();
+            }
+            let max_runs = found.game().max_runs();
+            if runs == 0 || runs > max_runs {
+                return format!("You can run the board 1 to {} times", max_runs);
+            }
+            match state.send_input(*player, RemoteInput::Runs(runs)) {
+                Ok(()) => String::new(),
+                Err(err) => err,
+            }
+        }
+        ["draw", rest @ ..] => {
+            let Some((table, seat)) = lobby.seat_of(*player) else {
+                return "You aren't sitting at a table".to_string();
+            };
+            let Some(discards) = parse_discards(&rest.join(" ")) else {
+                return "Type the positions of the cards to discard, from 1 to 5".to_string();
+            };
+            let Some(found) = lobby.table(table) else {
+                return format!("there is no table {}", table);
+            };
+            if found.game().to_draw() != Some(seat) {
+                return "It isn't your turn to draw".to_string();
+            }
+            match state.send_input(*player, RemoteInput::Discards(discards)) {
+                Ok(()) => String::new(),
+                Err(err) => err,
+            }
+        }
+        _ => match Action::parse(line) {
+            Some(action) => {
+                let Some((table, seat)) = lobby.seat_of(*player) else {
+                    return "You aren't sitting at a table".to_string();
+                };
+                let Some(found) = lobby.table(table) else {
+                    return format!("there is no table {}", table);
+                };
+                let Some(decision) = found.game().decision().filter(|d| d.seat == seat) else {
+                    return "It isn't your turn".to_string();
+                };
+                if let Err(err) = decision.legal.check(action) {
+                    return err;
+                }
+                match state.send_input(*player, RemoteInput::Action(action)) {
+                    Ok(()) => String::new(),
+                    Err(err) => err,
+                }
+            }
+            None => format!("Unknown command '{}', type help for a list", line.trim()),
+        },
+    }
+}
+
+/// Puts the chips taken off the table back in the named bankroll, returning the new balance.
+fn cash_out(bank: &mut Bank, name: &str, table: usize, chips: u32) -> Result<u32, String> {
+    bank.record(name, Movement::CashOut, Some(table), chips)
+        .map_err(|err| {
+            log::error!("couldn't cash out {} chips for {}: {}", chips, name, err);
+            "couldn't cash out your chips".to_string()
+        })
+}
+
+/// Returns the action to take for the player if they have been disconnected for longer than the
+/// disconnect timeout, or `None` if the table should wait for them.
+fn auto_action(
+    sessions: &SessionStore,
+    player: usize,
+    legal: &LegalActions,
+    now: Instant,
+) -> Option<Action> {
+    if sessions.is_away(player, now) {
+        return Some(legal.default_action());
+    }
+
+    None
+}
+
+/// Returns the name the player goes by in hand histories. Bots and players who haven't logged
+/// in are named after their player number.
+fn player_name(names: &HashMap<usize, String>, player: usize) -> String {
+    match names.get(&player) {
+        Some(name) => name.clone(),
+        None if player > usize::MAX / 2 => format!("bot{}", usize::MAX - player + 1),
+        None => format!("player{}", player),
+    }
+}
+
+fn parse_settings(
+    game: &str,
+    limit: &str,
+    small: &str,
+    big: &str,
+    seats: &str,
+    delay: Option<&&str>,
+) -> Result<TableSettings, String> {
+    let game = match GameType::parse(game) {
+        Some(game) => game,
+        None => return Err(format!("{} isn't a game", game)),
+    };
+    let (Ok(small_blind), Ok(big_blind), Ok(seats)) = (small.parse(), big.parse(), seats.parse())
+    else {
+        return Err("the stakes and seats must be numbers".to_string());
+    };
+    let Some(structure) = BettingStructure::parse(limit, small_blind, big_blind) else {
+        return Err(format!("{} isn't a betting limit", limit));
+    };
+    let broadcast_delay = match delay.map(|delay| delay.parse()) {
+        Some(Ok(delay)) => Some(delay),
+        Some(Err(_)) => return Err("the broadcast delay must be a number of hands".to_string()),
+        None => None,
+    };
+
+    Ok(TableSettings {
+        game,
+        structure,
+        small_blind,
+        big_blind,
+        seats,
+        broadcast_delay,
+        timer: TimerSettings::default(),
+        max_orbits_sat_out: Some(3),
+        max_runs: 3,
+        min_buy_in: big_blind * 20,
+        max_buy_in: big_blind * 100,
+    })
+}
+
+/// Locks the server state, carrying on with the data even if another client's thread panicked.
+fn lock(state: &Arc<Mutex<ServerState>>) -> std::sync::MutexGuard<'_, ServerState> {
+    match state.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lobby::NEXT_HAND_DELAY;
+
+    /// Runs the commands as player 1 on connection 1 and returns the last response.
+    fn run(state: &mut ServerState, lines: &[&str]) -> String {
+        let (sender, _lines) = mpsc::channel();
+        let mut player = 1;
+        let mut response = String::new();
+        for line in lines {
+            response = handle_command(state, 1, &mut player, &sender, line);
+        }
+        response
+    }
+
+    #[test]
+    fn every_chip_staked_comes_back_to_the_ledger() {
+        let mut state = ServerState::new(Duration::from_secs(30), Bank::new());
+        run(
+            &mut state,
+            &[
+                "login alice secret",
+                "deposit 1000",
+                "create holdem nl 1 2 6",
+                "join 1 100",
+                "sitout",
+                "addbot 1 station",
+                "addbot 1 station",
+            ],
+        );
+        assert_eq!(state.bank.balance("alice"), 500);
+
+        // alice is removed for sitting out while the bots play
+        let mut now = Instant::now();
+        for _ in 0..20 {
+            state.play_tables(now);
+            now += NEXT_HAND_DELAY;
+        }
+        assert_eq!(state.lobby.seat_of(1), None);
+        assert_eq!(state.bank.balance("alice"), 600);
+
+        run(&mut state, &["removebot 1 2", "removebot 1 3"]);
+        assert_eq!(state.lobby.tables()[0].seats_filled(), 0);
+        assert_eq!(state.bank.balance("alice"), 1000);
+        assert_eq!(state.bank.settlement(), vec![("alice".to_string(), 0)]);
+    }
+
+    #[test]
+    fn bots_need_someone_to_stake_them() {
+        let mut state = ServerState::new(Duration::from_secs(30), Bank::new());
+        let response = run(
+            &mut state,
+            &[
+                "login alice secret",
+                "deposit 100",
+                "create holdem nl 1 2 6",
+                "addbot 1 station",
+            ],
+        );
+        assert_eq!(response, "your bankroll is only 100");
+        assert_eq!(state.lobby.tables()[0].seats_filled(), 0);
+    }
+
+    #[test]
+    fn players_keep_their_seat_if_they_cannot_be_cashed_out() {
+        let mut state = ServerState::new(Duration::from_secs(30), Bank::new());
+        let max = u32::MAX.to_string();
+        run(
+            &mut state,
+            &[
+                "login alice secret",
+                &format!("deposit {}", max),
+                "create holdem nl 1 2 6",
+                "join 1 100",
+                "deposit 100",
+            ],
+        );
+        assert_eq!(run(&mut state, &["leave"]), "couldn't cash out your chips");
+        assert_eq!(state.lobby.seat_of(1), Some((1, 0)));
+        assert_eq!(state.bank.balance("alice"), u32::MAX);
+    }
+
+    #[test]
+    fn bankrolls_need_the_password_of_the_name() {
+        let mut state = ServerState::new(Duration::from_secs(30), Bank::new());
+        run(&mut state, &["login alice secret", "deposit 100"]);
+        state.drop_connection(1, 1, Instant::now());
+
+        // someone else connecting can't take alice's name without her password
+        let (sender, _lines) = mpsc::channel();
+        let mut player = 2;
+        let mut command = |line| handle_command(&mut state, 2, &mut player, &sender, line);
+        assert_eq!(
+            command("login alice"),
+            "Log in with login <name> <password>"
+        );
+        assert_eq!(command("login alice guess"), "Wrong password for alice");
+        assert_eq!(
+            command("withdraw 100"),
+            "Log in first with login <name> <password>"
+        );
+        assert_eq!(
+            command("login alice secret"),
+            "Logged in as alice, your bankroll is 100"
+        );
+    }
+
+    #[test]
+    fn players_draw_on_their_turn_with_the_draw_command() {
+        let mut state = ServerState::new(Duration::from_secs(30), Bank::new());
+        run(
+            &mut state,
+            &[
+                "login alice secret",
+                "deposit 1000",
+                "create draw fl 1 2 2",
+                "join 1 100",
+                "addbot 1 station",
+            ],
+        );
+        let now = Instant::now();
+        state.play_tables(now);
+        assert_eq!(run(&mut state, &["draw 1"]), "It isn't your turn to draw");
+        assert_eq!(run(&mut state, &["check"]), "");
+        state.play_tables(now);
+
+        // the bot left of the button stood pat, and now it's alice's turn
+        let table = &state.lobby.tables()[0];
+        assert_eq!(table.game().to_draw(), Some(0));
+        let before = table.game().hole_cards(0);
+        assert_eq!(
+            run(&mut state, &["draw 6"]),
+            "Type the positions of the cards to discard, from 1 to 5"
+        );
+        assert_eq!(run(&mut state, &["draw 1 2"]), "");
+        state.play_tables(now);
+        let table = &state.lobby.tables()[0];
+        assert_eq!(table.game().to_draw(), None);
+        let after = table.game().hole_cards(0);
+        assert!(after.0[..3] == before.0[2..]);
+    }
+}
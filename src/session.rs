@@ -0,0 +1,191 @@
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+struct Session {
+    player: usize,
+    // the connection currently speaking for the player
+    connection: usize,
+    // when the player's connection dropped, if it has
+    disconnected_at: Option<Instant>,
+}
+
+// a password kept as a digest of it with a random salt
+struct Credentials {
+    salt: String,
+    digest: u64,
+}
+
+/// The sessions of players sitting at tables. A session outlives the connection it was issued
+/// on, so a player whose connection drops can reconnect with their token and keep their seat.
+///
+/// The store also keeps the password of every name logged in under, so only whoever first
+/// logged in with a name can play from its bankroll.
+pub struct SessionStore {
+    sessions: HashMap<String, Session>,
+    passwords: HashMap<String, Credentials>,
+    // how long a disconnected player is waited for before acting for them
+    disconnect_timeout: Duration,
+}
+
+impl SessionStore {
+    pub fn new(disconnect_timeout: Duration) -> Self {
+        SessionStore {
+            sessions: HashMap::new(),
+            passwords: HashMap::new(),
+            disconnect_timeout,
+        }
+    }
+
+    /// Returns the token of the player's session, issuing a new one if they don't have one.
+    pub fn issue(&mut self, player: usize, connection: usize) -> String {
+        if let Some(token) = self.token_of(player) {
+            return token;
+        }
+        let token = random_string(24);
+        self.sessions.insert(
+            token.clone(),
+            Session {
+                player,
+                connection,
+                disconnected_at: None,
+            },
+        );
+
+        token
+    }
+
+    /// Checks the password for the name. The first login under a name sets its password, and
+    /// every login after has to give the same one.
+    pub fn log_in(&mut self, name: &str, password: &str) -> Result<(), String> {
+        match self.passwords.get(name) {
+            Some(credentials) if digest(&credentials.salt, password) != credentials.digest => {
+                Err(format!("Wrong password for {}", name))
+            }
+            Some(_) => Ok(()),
+            None => {
+                let salt = random_string(16);
+                let digest = digest(&salt, password);
+                self.passwords
+                    .insert(name.to_string(), Credentials { salt, digest });
+                Ok(())
+            }
+        }
+    }
+
+    /// Hands the session over to a new connection and returns the player it belongs to. A
+    /// session can't be taken over while its connection is still open.
+    pub fn resume(&mut self, token: &str, connection: usize) -> Result<usize, String> {
+        let Some(session) = self.sessions.get_mut(token) else {
+            return Err("That session token isn't known".to_string());
+        };
+        if session.connection != connection && session.disconnected_at.is_none() {
+            return Err("That session is still connected".to_string());
+        }
+        session.connection = connection;
+        session.disconnected_at = None;
+
+        Ok(session.player)
+    }
+
+    /// Marks the session speaking through the connection as disconnected and returns its player,
+    /// or `None` if the connection has no session.
+    pub fn disconnect(&mut self, connection: usize, now: Instant) -> Option<usize> {
+        let session = self
+            .sessions
+            .values_mut()
+            .find(|session| session.connection == connection)?;
+        session.disconnected_at = Some(now);
+
+        Some(session.player)
+    }
+
+    /// Ends the player's session, such as when they leave their table.
+    pub fn remove(&mut self, player: usize) {
+        self.sessions.retain(|_, session| session.player != player);
+    }
+
+    /// Returns whether the player has been disconnected for longer than the disconnect timeout,
+    /// in which case the table checks or folds for them until they return.
+    pub fn is_away(&self, player: usize, now: Instant) -> bool {
+        self.sessions.values().any(|session| {
+            session.player == player
+                && session
+                    .disconnected_at
+                    .is_some_and(|at| now.duration_since(at) >= self.disconnect_timeout)
+        })
+    }
+
+    pub fn has_session(&self, player: usize) -> bool {
+        self.token_of(player).is_some()
+    }
+
+    fn token_of(&self, player: usize) -> Option<String> {
+        self.sessions
+            .iter()
+            .find(|(_, session)| session.player == player)
+            .map(|(token, _)| token.clone())
+    }
+}
+
+fn random_string(length: usize) -> String {
+    thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
+
+fn digest(salt: &str, password: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    salt.hash(&mut hasher);
+    password.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resume_is_refused_while_the_old_connection_is_open() {
+        let mut sessions = SessionStore::new(Duration::from_secs(30));
+        let token = sessions.issue(1, 10);
+        assert!(sessions.resume(&token, 11).is_err());
+        assert_eq!(sessions.resume(&token, 10), Ok(1));
+
+        let now = Instant::now();
+        assert_eq!(sessions.disconnect(10, now), Some(1));
+        assert_eq!(sessions.resume(&token, 11), Ok(1));
+        // the new connection now speaks for the player
+        assert_eq!(sessions.disconnect(10, now), None);
+        assert!(sessions.resume(&token, 12).is_err());
+        assert!(sessions.resume("unknown", 12).is_err());
+    }
+
+    #[test]
+    fn names_keep_the_password_they_were_first_logged_in_with() {
+        let mut sessions = SessionStore::new(Duration::from_secs(30));
+        assert!(sessions.log_in("alice", "secret").is_ok());
+        assert!(sessions.log_in("alice", "secret").is_ok());
+        assert!(sessions.log_in("alice", "guess").is_err());
+        assert!(sessions.log_in("bob", "guess").is_ok());
+    }
+
+    #[test]
+    fn players_are_away_once_the_timeout_passes() {
+        let mut sessions = SessionStore::new(Duration::from_secs(30));
+        let token = sessions.issue(1, 10);
+        let now = Instant::now();
+        assert!(!sessions.is_away(1, now));
+        sessions.disconnect(10, now);
+        assert!(!sessions.is_away(1, now + Duration::from_secs(29)));
+        assert!(sessions.is_away(1, now + Duration::from_secs(30)));
+
+        sessions.resume(&token, 11).unwrap();
+        assert!(!sessions.is_away(1, now + Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,320 @@
+use crate::deck::*;
+use crate::hand::{assign_hand_rankings, assign_omaha_hand_rankings};
+use crate::lowball::{assign_low_hand_rankings, assign_omaha_low_hand_rankings, LowballRules};
+
+/// A pot of chips along with the players that are eligible to win it. Players are referred
+/// to by their index in the showdown.
+#[derive(Clone)]
+pub struct Pot {
+    pub amount: u32,
+    pub eligible: Vec<usize>,
+}
+
+/// The chips a player won from the high and low halves of all the pots.
+#[derive(Clone, Default)]
+pub struct HiLoPayout {
+    pub high: u32,
+    pub low: u32,
+}
+
+impl HiLoPayout {
+    pub fn total(&self) -> u32 {
+        self.high + self.low
+    }
+}
+
+/// Given how many chips each player put in over the hand and which players folded, returns the
+/// main pot followed by any side pots. Folded players' chips stay in the pots, but they aren't
+/// eligible to win any of them.
+pub fn build_pots(contributions: &[u32], folded: &[bool]) -> Vec<Pot> {
+    let mut levels: Vec<u32> = contributions
+        .iter()
+        .enumerate()
+        .filter(|(player, amount)| !folded[*player] && **amount > 0)
+        .map(|(_, amount)| *amount)
+        .collect();
+    levels.sort();
+    levels.dedup();
+
+    let mut pots: Vec<Pot> = vec![];
+    let mut previous_level = 0;
+    for (index, level) in levels.iter().enumerate() {
+        // the last level also collects chips folded players put in above it
+        let upper = if index == levels.len() - 1 {
+            u32::MAX
+        } else {
+            *level
+        };
+        let amount = contributions
+            .iter()
+            .map(|contribution| (*contribution).min(upper).saturating_sub(previous_level))
+            .sum();
+        let eligible = (0..contributions.len())
+            .filter(|player| !folded[*player] && contributions[*player] >= *level)
+            .collect();
+        pots.push(Pot { amount, eligible });
+        previous_level = *level;
+    }
+
+    pots
+}
+
+/// Resolves an Omaha Hi-Lo showdown. `holes` holds the 4 hole cards of each player at showdown,
+/// ordered starting from the first player to the left of the button, since that is the order
+/// odd chips are handed out in.
+pub fn resolve_omaha_hi_lo(
+    holes: Vec<CardCollection>,
+    board: CardCollection,
+    pots: Vec<Pot>,
+) -> Vec<HiLoPayout> {
+    let high_rankings = assign_omaha_hand_rankings(holes.clone(), board.clone());
+    let low_rankings = assign_omaha_low_hand_rankings(holes, board, LowballRules::EightOrBetter);
+
+    split_hi_lo_pots(pots, high_rankings, low_rankings)
+}
+
+/// Resolves a Stud Hi-Lo showdown. `hands` holds the 7 cards of each player at showdown, ordered
+/// by who should receive odd chips first.
+pub fn resolve_stud_hi_lo(hands: Vec<CardCollection>, pots: Vec<Pot>) -> Vec<HiLoPayout> {
+    let high_rankings = assign_hand_rankings(hands.clone());
+    let low_rankings = assign_low_hand_rankings(hands, LowballRules::EightOrBetter);
+
+    split_hi_lo_pots(pots, high_rankings, low_rankings)
+}
+
+/// Given the pots and the rankings of every player at showdown, returns how many chips each
+/// player wins when the whole pot goes to the best high hand. Odd chips go to the tied winners
+/// in the order the players were given.
+pub fn split_pots(pots: Vec<Pot>, rankings: Vec<u8>) -> Vec<u32> {
+    let low_rankings = vec![None; rankings.len()];
+
+    split_hi_lo_pots(pots, rankings, low_rankings)
+        .iter()
+        .map(|payout| payout.total())
+        .collect()
+}
+
+/// Given the pots and the rankings of every player on each run of the board, returns how many
+/// chips each player wins when every pot is split evenly between the runs. Odd chips from a pot
+/// go to the earliest runs.
+pub fn split_runs(pots: Vec<Pot>, run_rankings: Vec<Vec<u8>>) -> Vec<u32> {
+    let runs = run_rankings.len() as u32;
+    let mut winnings = vec![];
+    for (run, rankings) in run_rankings.into_iter().enumerate() {
+        let run_winnings = split_pots(run_pots(&pots, runs, run as u32), rankings);
+        if winnings.is_empty() {
+            winnings = run_winnings;
+        } else {
+            for (total, amount) in winnings.iter_mut().zip(run_winnings) {
+                *total += amount;
+            }
+        }
+    }
+
+    winnings
+}
+
+/// Returns the part of each pot played for on one run of the board, counting from 0, when every
+/// pot is split evenly between `runs` runs. Odd chips go to the earliest runs.
+pub fn run_pots(pots: &[Pot], runs: u32, run: u32) -> Vec<Pot> {
+    pots.iter()
+        .map(|pot| Pot {
+            amount: pot.amount / runs + u32::from(run < pot.amount % runs),
+            eligible: pot.eligible.clone(),
+        })
+        .collect()
+}
+
+/// Given the pots and the high and low rankings of every player at showdown, returns how many
+/// chips each player wins from the high and low halves. Each pot is split in half between the
+/// best high hand and the best qualifying low hand among the players eligible for it. If no
+/// eligible player qualifies for low the high hand scoops the pot. When a pot doesn't split
+/// evenly the odd chip goes to the high half, and odd chips within a half go to the tied winners
+/// in the order the players were given.
+pub fn split_hi_lo_pots(
+    pots: Vec<Pot>,
+    high_rankings: Vec<u8>,
+    low_rankings: Vec<Option<u8>>,
+) -> Vec<HiLoPayout> {
+    let mut payouts: Vec<HiLoPayout> = vec![HiLoPayout::default(); high_rankings.len()];
+    for pot in pots {
+        let high_winners = get_high_winners(&pot, &high_rankings);
+        let low_winners = get_low_winners(&pot, &low_rankings);
+
+        if low_winners.is_empty() {
+            for (player, chips) in split_chips(pot.amount, &high_winners) {
+                payouts[player].high += chips;
+            }
+            continue;
+        }
+
+        let low_half = pot.amount / 2;
+        let high_half = pot.amount - low_half;
+        for (player, chips) in split_chips(high_half, &high_winners) {
+            payouts[player].high += chips;
+        }
+        for (player, chips) in split_chips(low_half, &low_winners) {
+            payouts[player].low += chips;
+        }
+    }
+
+    payouts
+}
+
+/// Returns the eligible players holding the best high hand, in player order.
+fn get_high_winners(pot: &Pot, high_rankings: &[u8]) -> Vec<usize> {
+    let mut eligible = pot.eligible.clone();
+    eligible.sort();
+    let best = eligible.iter().map(|player| high_rankings[*player]).min();
+
+    eligible
+        .into_iter()
+        .filter(|player| Some(high_rankings[*player]) == best)
+        .collect()
+}
+
+/// Returns the eligible players holding the best qualifying low hand, in player order.
+fn get_low_winners(pot: &Pot, low_rankings: &[Option<u8>]) -> Vec<usize> {
+    let mut eligible = pot.eligible.clone();
+    eligible.sort();
+    let best = eligible
+        .iter()
+        .filter_map(|player| low_rankings[*player])
+        .min();
+    if best.is_none() {
+        return vec![];
+    }
+
+    eligible
+        .into_iter()
+        .filter(|player| low_rankings[*player] == best)
+        .collect()
+}
+
+/// Splits the chips evenly between the winners, giving any odd chips to the earliest winners.
+fn split_chips(amount: u32, winners: &[usize]) -> Vec<(usize, u32)> {
+    if winners.is_empty() {
+        return vec![];
+    }
+    let share = amount / winners.len() as u32;
+    let odd_chips = amount as usize % winners.len();
+
+    winners
+        .iter()
+        .enumerate()
+        .map(|(index, player)| {
+            if index < odd_chips {
+                (*player, share + 1)
+            } else {
+                (*player, share)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(codes: &str) -> CardCollection {
+        CardCollection(codes.split(' ').filter_map(Card::parse).collect())
+    }
+
+    fn pots_of(pots: &[Pot]) -> Vec<(u32, Vec<usize>)> {
+        pots.iter()
+            .map(|pot| (pot.amount, pot.eligible.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn all_ins_make_side_pots() {
+        let pots = build_pots(&[50, 100, 200, 200], &[false; 4]);
+        assert_eq!(
+            pots_of(&pots),
+            vec![
+                (200, vec![0, 1, 2, 3]),
+                (150, vec![1, 2, 3]),
+                (200, vec![2, 3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn folded_chips_stay_in_the_pots() {
+        // the folded player put in more than the short all in, and more than anyone called
+        let pots = build_pots(&[30, 60, 40, 80], &[false, true, false, false]);
+        assert_eq!(
+            pots_of(&pots),
+            vec![(120, vec![0, 2, 3]), (30, vec![2, 3]), (60, vec![3])]
+        );
+        let total: u32 = pots.iter().map(|pot| pot.amount).sum();
+        assert_eq!(total, 210);
+    }
+
+    #[test]
+    fn side_pots_go_to_the_best_eligible_hand() {
+        let pots = build_pots(&[50, 100, 100], &[false; 3]);
+        // the short stack has the best hand and only wins the main pot
+        assert_eq!(split_pots(pots.clone(), vec![1, 3, 2]), vec![150, 0, 100]);
+        assert_eq!(split_pots(pots, vec![1, 2, 2]), vec![150, 50, 50]);
+    }
+
+    #[test]
+    fn odd_chips_go_to_the_earliest_tied_winners() {
+        let pots = vec![Pot {
+            amount: 101,
+            eligible: vec![2, 0, 1],
+        }];
+        assert_eq!(split_pots(pots.clone(), vec![1, 1, 1]), vec![34, 34, 33]);
+        assert_eq!(split_pots(pots, vec![2, 1, 1]), vec![0, 51, 50]);
+    }
+
+    #[test]
+    fn runs_split_each_pot_with_odd_chips_to_the_earliest_runs() {
+        let pots = vec![Pot {
+            amount: 101,
+            eligible: vec![0, 1],
+        }];
+        assert_eq!(
+            split_runs(pots.clone(), vec![vec![1, 2], vec![2, 1]]),
+            vec![51, 50]
+        );
+        assert_eq!(
+            split_runs(pots, vec![vec![2, 1], vec![1, 2], vec![1, 1]]),
+            vec![51, 50]
+        );
+    }
+
+    #[test]
+    fn hi_lo_pots_split_between_high_and_qualifying_low() {
+        let pot = Pot {
+            amount: 101,
+            eligible: vec![0, 1, 2],
+        };
+        let payouts = split_hi_lo_pots(
+            vec![pot.clone()],
+            vec![1, 2, 3],
+            vec![None, Some(1), Some(1)],
+        );
+        let totals: Vec<(u32, u32)> = payouts.iter().map(|p| (p.high, p.low)).collect();
+        assert_eq!(totals, vec![(51, 0), (0, 25), (0, 25)]);
+
+        // nobody qualifies for low so the high hand scoops
+        let payouts = split_hi_lo_pots(vec![pot], vec![1, 2, 3], vec![None; 3]);
+        assert_eq!(payouts[0].total(), 101);
+    }
+
+    #[test]
+    fn omaha_hi_lo_scoops_and_splits() {
+        let board = cards("2c 5d 8h Kc Kd");
+        let pots = build_pots(&[100, 100], &[false; 2]);
+        // trip kings for high against a low
+        let payouts = resolve_omaha_hi_lo(
+            vec![cards("Ks Qh Jd Tc"), cards("Ah 3s 9c 9d")],
+            board,
+            pots,
+        );
+        assert_eq!((payouts[0].high, payouts[0].low), (100, 0));
+        assert_eq!((payouts[1].high, payouts[1].low), (0, 100));
+    }
+}
@@ -0,0 +1,313 @@
+use crate::betting::BettingStructure;
+use crate::bots::BotKind;
+use crate::holdem::HoldemGame;
+use crate::lobby::{GameType, TableSettings};
+use crate::strategy::{play_hand, Strategy};
+use crate::timer::TimerSettings;
+use std::thread;
+
+const SMALL_BLIND: u32 = 1;
+const BIG_BLIND: u32 = 2;
+
+/// How a self-play simulation between bots is run.
+#[derive(Clone)]
+pub struct SimulationConfig {
+    /// The bots playing, one per seat.
+    pub bots: Vec<BotKind>,
+    pub structure: BettingStructure,
+    /// The number of hands to play.
+    pub hands: u64,
+    /// The stack every bot starts each hand with, in big blinds.
+    pub stack: u32,
+    /// Whether to deal every hand once for each way of rotating the bots around the table, so
+    /// each bot gets every seat's cards.
+    pub duplicate: bool,
+    /// The seed the decks are shuffled from. The same seed deals the same hands.
+    pub seed: u64,
+    pub threads: usize,
+}
+
+/// How a bot did over a simulation.
+#[derive(Clone)]
+pub struct BotResult {
+    pub bot: BotKind,
+    pub hands: u64,
+    /// The big blinds the bot won per 100 hands.
+    pub bb_per_100: f64,
+    /// The half width of the 95% confidence interval around `bb_per_100`.
+    pub confidence: f64,
+}
+
+/// The running totals of a bot's samples, enough to work out their mean and variance.
+#[derive(Clone, Copy, Default)]
+struct Samples {
+    count: u64,
+    sum: f64,
+    sum_of_squares: f64,
+}
+
+impl Samples {
+    fn add(&mut self, sample: f64) {
+        self.count += 1;
+        self.sum += sample;
+        self.sum_of_squares += sample * sample;
+    }
+
+    fn merge(&mut self, other: &Samples) {
+        self.count += other.count;
+        self.sum += other.sum;
+        self.sum_of_squares += other.sum_of_squares;
+    }
+
+    fn mean(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.sum / self.count as f64
+    }
+
+    /// Returns the half width of the 95% confidence interval around the mean.
+    fn confidence(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        let count = self.count as f64;
+        let variance = (self.sum_of_squares - self.sum * self.sum / count) / (count - 1.0);
+
+        1.96 * (variance.max(0.0) / count).sqrt()
+    }
+}
+
+/// Plays the bots against each other with fresh stacks every hand and returns how each of them
+/// did. The bots move round the table one seat every hand, or under duplicate play every deal
+/// is played once for each rotation, which cancels out most of the luck of the cards. The
+/// hands are split between the threads, and every deal's cards come from the seed and the
+/// deal's number, so the same seed deals the same cards however many threads there are.
+pub fn simulate(config: &SimulationConfig) -> Result<Vec<BotResult>, String> {
+    let players = config.bots.len();
+    if players < 2 {
+        return Err("a simulation needs at least two bots".to_string());
+    }
+    if config.stack == 0 {
+        return Err("the bots need a stack to play with".to_string());
+    }
+    // under duplicate play a deal is played once per rotation, and each bot's result over the
+    // deal is one sample
+    let deals = if config.duplicate {
+        config.hands.div_ceil(players as u64)
+    } else {
+        config.hands
+    };
+    let threads = config.threads.max(1) as u64;
+
+    let totals = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|thread| scope.spawn(move || play_deals(config, thread, threads, deals)))
+            .collect();
+        let mut totals = vec![Samples::default(); players];
+        for worker in workers {
+            let samples = worker.join().map_err(|_| "a simulation thread panicked")?;
+            for (total, samples) in totals.iter_mut().zip(samples?) {
+                total.merge(&samples);
+            }
+        }
+
+        Ok::<_, String>(totals)
+    })?;
+
+    let hands_per_deal = if config.duplicate { players as u64 } else { 1 };
+    Ok(config
+        .bots
+        .iter()
+        .zip(totals)
+        .map(|(bot, samples)| BotResult {
+            bot: *bot,
+            hands: samples.count * hands_per_deal,
+            bb_per_100: samples.mean() * 100.0,
+            confidence: samples.confidence() * 100.0,
+        })
+        .collect())
+}
+
+/// Plays every `step`th deal starting with `first`, returning each bot's samples in big blinds
+/// won per hand.
+fn play_deals(
+    config: &SimulationConfig,
+    first: u64,
+    step: u64,
+    deals: u64,
+) -> Result<Vec<Samples>, String> {
+    let players = config.bots.len();
+    let settings = TableSettings {
+        game: GameType::Holdem,
+        structure: config.structure.with_stakes(SMALL_BLIND, BIG_BLIND),
+        small_blind: SMALL_BLIND,
+        big_blind: BIG_BLIND,
+        seats: players,
+        broadcast_delay: None,
+        timer: TimerSettings::default(),
+        max_orbits_sat_out: None,
+        max_runs: 1,
+        min_buy_in: 0,
+        max_buy_in: u32::MAX,
+    };
+    let stack = config.stack * BIG_BLIND;
+    let mut bots: Vec<Option<Box<dyn Strategy + Send>>> =
+        config.bots.iter().map(|bot| Some(bot.create())).collect();
+    let mut samples = vec![Samples::default(); players];
+
+    let mut deal = first;
+    while deal < deals {
+        let rotations = if config.duplicate {
+            0..players
+        } else {
+            let rotation = (deal % players as u64) as usize;
+            rotation..rotation + 1
+        };
+        let mut won = vec![0.0; players];
+        for rotation in rotations.clone() {
+            let mut game = HoldemGame::new(settings);
+            game.seed_deck(config.seed.wrapping_add(deal));
+            for seat in 0..players {
+                game.sit_down(seat, stack)?;
+            }
+            // the bots move round the table, so seat 0 gets the bot numbered by the rotation
+            bots.rotate_left(rotation);
+            let played = play_hand(&mut game, &mut bots);
+            bots.rotate_right(rotation);
+            played?;
+
+            for seat in 0..players {
+                let chips = game.seats()[seat].as_ref().map_or(0, |player| player.chips);
+                won[(seat + rotation) % players] +=
+                    (chips as f64 - stack as f64) / BIG_BLIND as f64;
+            }
+        }
+        let hands = rotations.len() as f64;
+        for (bot, won) in won.into_iter().enumerate() {
+            samples[bot].add(won / hands);
+        }
+        deal += step;
+    }
+
+    Ok(samples)
+}
+
+/// Runs the simulation and prints how each bot did.
+pub fn run(config: &SimulationConfig) -> Result<(), String> {
+    let results = simulate(config)?;
+    let hands = results.first().map_or(0, |result| result.hands);
+    let kind = if config.duplicate {
+        " of duplicate"
+    } else {
+        ""
+    };
+    println!("Played {} hands{} with seed {}", hands, kind, config.seed);
+    println!(" #  Bot        bb/100     95% CI");
+    for (number, result) in results.iter().enumerate() {
+        println!(
+            "{:>2}  {:<8} {:>8.2}  ± {:>7.2}",
+            number + 1,
+            result.bot.to_string(),
+            result.bb_per_100,
+            result.confidence
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(bots: Vec<BotKind>, duplicate: bool, threads: usize) -> SimulationConfig {
+        SimulationConfig {
+            bots,
+            structure: BettingStructure::NoLimit { big_blind: 0 },
+            hands: 60,
+            stack: 100,
+            duplicate,
+            seed: 44,
+            threads,
+        }
+    }
+
+    fn results(config: &SimulationConfig) -> Vec<(u64, f64, f64)> {
+        simulate(config)
+            .unwrap()
+            .iter()
+            .map(|result| (result.hands, result.bb_per_100, result.confidence))
+            .collect()
+    }
+
+    #[test]
+    fn duplicate_play_gives_every_bot_the_same_cards_and_seats() {
+        // identical bots each play every seat of every deal, so whatever one seat wins another
+        // loses and every bot breaks exactly even
+        let bots = vec![BotKind::CallingStation; 3];
+        for (hands, bb_per_100, confidence) in results(&config(bots.clone(), true, 2)) {
+            assert_eq!(hands, 60);
+            assert_eq!(bb_per_100, 0.0);
+            assert_eq!(confidence, 0.0);
+        }
+        // which they don't when each deal is only played once
+        let results = results(&config(bots, false, 2));
+        assert!(results.iter().any(|&(_, bb_per_100, _)| bb_per_100 != 0.0));
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_results_on_any_number_of_threads() {
+        let bots = vec![BotKind::TightAggressive, BotKind::CallingStation];
+        for duplicate in [false, true] {
+            let single = results(&config(bots.clone(), duplicate, 1));
+            assert_eq!(single, results(&config(bots.clone(), duplicate, 3)));
+            assert_eq!(single, results(&config(bots.clone(), duplicate, 7)));
+        }
+
+        let mut other_seed = config(bots.clone(), false, 1);
+        other_seed.seed += 1;
+        assert_ne!(results(&config(bots, false, 1)), results(&other_seed));
+    }
+
+    #[test]
+    fn samples_give_their_mean_and_confidence_interval() {
+        let mut samples = Samples::default();
+        assert_eq!(samples.mean(), 0.0);
+        samples.add(1.0);
+        // one sample has no spread to measure
+        assert_eq!(samples.confidence(), 0.0);
+
+        let mut more = Samples::default();
+        more.add(2.0);
+        more.add(6.0);
+        samples.merge(&more);
+        assert_eq!(samples.count, 3);
+        assert_eq!(samples.mean(), 3.0);
+        // the sample variance of 1, 2 and 6 is 7
+        assert!((samples.confidence() - 1.96 * (7.0f64 / 3.0).sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn a_single_hand_reports_what_was_won_per_100_hands() {
+        let mut one_hand = config(vec![BotKind::CallingStation; 2], false, 1);
+        one_hand.hands = 1;
+        let results = results(&one_hand);
+        // what one bot won in big blinds, times a hundred, the other lost
+        assert_eq!(results[0].1, -results[1].1);
+        assert_eq!(results[0].1 % 50.0, 0.0);
+        assert_ne!(results[0].1, 0.0);
+        for (hands, _, confidence) in results {
+            assert_eq!(hands, 1);
+            assert_eq!(confidence, 0.0);
+        }
+    }
+
+    #[test]
+    fn simulations_need_two_bots_with_chips() {
+        assert!(simulate(&config(vec![BotKind::CallingStation], false, 1)).is_err());
+        let mut no_stack = config(vec![BotKind::CallingStation; 2], false, 1);
+        no_stack.stack = 0;
+        assert!(simulate(&no_stack).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use crate::betting::{Action, LegalActions};
+use crate::deck::CardCollection;
+use crate::draw::{numbered_hand, parse_discards};
+use crate::events::TableEvent;
+use crate::hand::HandRules;
+use crate::holdem::{HoldemGame, Street};
+use std::io::{Read, Write};
+
+/// What a player gets to see when it is their turn to act.
+#[derive(Clone)]
+pub struct Decision {
+    pub seat: usize,
+    pub hole_cards: CardCollection,
+    pub board: CardCollection,
+    /// The street being played in games with a board, `None` in stud.
+    pub street: Option<Street>,
+    /// The cards each seat is showing face up, as in stud, empty in games without up cards.
+    pub up_cards: Vec<CardCollection>,
+    /// The chips in the pot, counting the bets of the current round.
+    pub pot: u32,
+    /// The biggest total bet anyone has made in the current round.
+    pub bet: u32,
+    /// The chips the player has behind.
+    pub chips: u32,
+    pub big_blind: u32,
+    /// The number of other players still in the hand.
+    pub opponents: usize,
+    pub rules: HandRules,
+    pub legal: LegalActions,
+}
+
+impl Decision {
+    /// Describes the decision to the player making it: their cards, the board, the pot and
+    /// what they can do.
+    pub fn describe(&self) -> String {
+        let mut description = format!("your cards are\n{}", self.hole_cards);
+        for (seat, cards) in self.up_cards.iter().enumerate() {
+            if !cards.0.is_empty() {
+                description.push_str(&format!("Seat {} shows\n{}", seat + 1, cards));
+            }
+        }
+        if !self.board.0.is_empty() {
+            description.push_str(&format!("The board is\n{}", self.board));
+        }
+        description.push_str(&format!(
+            "The pot is {} and you have {} chips behind\nYou can {}",
+            self.pot, self.chips, self.legal
+        ));
+
+        description
+    }
+}
+
+/// Makes the decisions for a seat. People and bots both play through this, so the table
+/// doesn't need to know which one it is waiting on.
+pub trait Strategy {
+    /// Picks the action to take. Tables take the default action instead of an illegal one.
+    fn decide(&mut self, decision: &Decision) -> Action;
+
+    /// Picks how many times, up to `max_runs`, to run the board when all in.
+    fn choose_runs(&mut self, _max_runs: u32) -> u32 {
+        1
+    }
+
+    /// Picks which cards of the hand to throw away in the draw, as indices of the hand. Standing
+    /// pat by default.
+    fn choose_discards(&mut self, _hand: &CardCollection) -> Vec<usize> {
+        vec![]
+    }
+
+    /// Picks the action if it is ready to, without waiting. Tables that can't stop for one
+    /// player, like the server's, ask this way until they get an answer, with `first` set the
+    /// first time they ask each turn.
+    fn poll_decide(&mut self, decision: &Decision, _first: bool) -> Option<Action> {
+        Some(self.decide(decision))
+    }
+
+    /// Picks how many times to run the board if it is ready to, the way `poll_decide` does.
+    fn poll_runs(&mut self, max_runs: u32, _first: bool) -> Option<u32> {
+        Some(self.choose_runs(max_runs))
+    }
+
+    /// Picks the cards to discard if it is ready to, the way `poll_decide` does.
+    fn poll_discards(&mut self, hand: &CardCollection, _first: bool) -> Option<Vec<usize>> {
+        Some(self.choose_discards(hand))
+    }
+}
+
+/// A person making their decisions by typing lines, such as at the terminal or over a
+/// connection. They are shown their cards and the actions they can take when it is their
+/// turn.
+pub struct LinePlayer<R: Read, W: Write> {
+    name: String,
+    reader: R,
+    writer: W,
+}
+
+impl<R: Read, W: Write> LinePlayer<R, W> {
+    pub fn new(name: &str, reader: R, writer: W) -> Self {
+        LinePlayer {
+            name: name.to_string(),
+            reader,
+            writer,
+        }
+    }
+
+    /// Reads the next line a byte at a time, so nothing past it is taken from the reader.
+    /// Returns `None` once the reader is closed.
+    fn read_line(&mut self) -> Option<String> {
+        let mut line = vec![];
+        let mut byte = [0; 1];
+        loop {
+            match self.reader.read(&mut byte) {
+                Ok(0) if line.is_empty() => return None,
+                Ok(0) => break,
+                Ok(_) if byte[0] == b'\n' => break,
+                Ok(_) => line.push(byte[0]),
+                Err(_) => return None,
+            }
+        }
+
+        Some(String::from_utf8_lossy(&line).trim().to_string())
+    }
+}
+
+impl<R: Read, W: Write> Strategy for LinePlayer<R, W> {
+    fn decide(&mut self, decision: &Decision) -> Action {
+        let _ = writeln!(self.writer, "{}, {}", self.name, decision.describe());
+
+        loop {
+            let Some(line) = self.read_line() else {
+                return decision.legal.default_action();
+            };
+            if let Some(action) = Action::parse(&line) {
+                return action;
+            }
+            let _ = writeln!(self.writer, "Type fold, check, call, or raise <total bet>");
+        }
+    }
+
+    fn choose_runs(&mut self, max_runs: u32) -> u32 {
+        let _ = writeln!(
+            self.writer,
+            "{}, how many times do you want to run the board? (1-{})",
+            self.name, max_runs
+        );
+        loop {
+            let Some(line) = self.read_line() else {
+                return 1;
+            };
+            match line.parse() {
+                Ok(runs) if (1..=max_runs).contains(&runs) => return runs,
+                _ => {
+                    let _ = writeln!(self.writer, "Type a number from 1 to {}", max_runs);
+                }
+            }
+        }
+    }
+
+    fn choose_discards(&mut self, hand: &CardCollection) -> Vec<usize> {
+        let _ = writeln!(
+            self.writer,
+            "{}, which cards do you want to discard?\n{}",
+            self.name,
+            numbered_hand(hand)
+        );
+        loop {
+            let Some(line) = self.read_line() else {
+                return vec![];
+            };
+            if let Some(discards) = parse_discards(&line) {
+                return discards;
+            }
+            let _ = writeln!(
+                self.writer,
+                "Type the positions of the cards to discard, or nothing to stand pat"
+            );
+        }
+    }
+}
+
+/// Plays a whole hand at the table, asking the strategy in each seat for its decisions, and
+/// returns everything that happened. Seats without a strategy take the default action.
+pub fn play_hand<S: Strategy + ?Sized>(
+    game: &mut HoldemGame,
+    players: &mut [Option<Box<S>>],
+) -> Result<Vec<TableEvent>, String> {
+    let mut events = game.start_hand()?;
+    loop {
+        if let Some(decision) = game.decision() {
+            let action = match players.get_mut(decision.seat) {
+                Some(Some(player)) => player.decide(&decision),
+                _ => decision.legal.default_action(),
+            };
+            let acted = match game.act(action) {
+                Ok(acted) => acted,
+                Err(_) => game.act(decision.legal.default_action())?,
+            };
+            events.extend(acted);
+            continue;
+        }
+
+        let Some(seat) = game.awaiting_runs().first().copied() else {
+            break;
+        };
+        let runs = match players.get_mut(seat) {
+            Some(Some(player)) => player.choose_runs(game.max_runs()),
+            _ => 1,
+        };
+        let chosen = match game.choose_runs(seat, runs) {
+            Ok(chosen) => chosen,
+            Err(_) => game.choose_runs(seat, 1)?,
+        };
+        events.extend(chosen);
+    }
+
+    Ok(events)
+}
@@ -0,0 +1,982 @@
+use crate::betting::{Action, BettingRound};
+use crate::deck::*;
+use crate::events::TableEvent;
+use crate::hand::{assign_hand_rankings, HandRules};
+use crate::lobby::{TableGame, TableSettings};
+use crate::lowball::{assign_low_hand_rankings, LowballRules};
+use crate::showdown::{build_pots, split_hi_lo_pots, split_pots};
+use crate::strategy::Decision;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum StudStreet {
+    Third,
+    Fourth,
+    Fifth,
+    Sixth,
+    Seventh,
+}
+
+#[derive(Clone)]
+pub struct StudPlayer {
+    pub chips: u32,
+    pub down_cards: CardCollection,
+    pub up_cards: CardCollection,
+    pub folded: bool,
+    // the chips this player has put in over the hand
+    contributed: u32,
+}
+
+/// A game of Seven-Card Stud. Each hand starts with antes, two down cards and one up card, a
+/// bring-in from the lowest up card, and then an up card on each of fourth through sixth street
+/// and a final down card on seventh street. Hi-lo games split each pot between the best high
+/// hand and the best eight-or-better low.
+pub struct StudGame {
+    deck: Deck,
+    players: Vec<StudPlayer>,
+    street: Option<StudStreet>,
+    // dealt face up for everyone to share when the deck can't give every player a river card
+    community_card: Option<Card>,
+    ante: u32,
+    bring_in: u32,
+    hi_lo: bool,
+}
+
+impl StudGame {
+    /// Creates a new game with a player seated for each of the starting stacks.
+    pub fn new(stacks: Vec<u32>, ante: u32, bring_in: u32, hi_lo: bool) -> Self {
+        let players = stacks
+            .into_iter()
+            .map(|chips| StudPlayer {
+                chips,
+                down_cards: CardCollection(vec![]),
+                up_cards: CardCollection(vec![]),
+                folded: false,
+                contributed: 0,
+            })
+            .collect();
+
+        StudGame {
+            deck: Deck::new(),
+            players,
+            street: None,
+            community_card: None,
+            ante,
+            bring_in,
+            hi_lo,
+        }
+    }
+
+    /// Seeds the deck, so games seeded the same way deal the same cards.
+    pub fn seed_deck(&mut self, seed: u64) {
+        self.deck.seed(seed);
+    }
+
+    /// Changes the player's stack between hands.
+    pub fn set_chips(&mut self, player: usize, chips: u32) {
+        self.players[player].chips = chips;
+    }
+
+    pub fn players(&self) -> &Vec<StudPlayer> {
+        &self.players
+    }
+
+    pub fn street(&self) -> Option<StudStreet> {
+        self.street
+    }
+
+    pub fn community_card(&self) -> Option<&Card> {
+        self.community_card.as_ref()
+    }
+
+    /// Returns the total number of chips in the pot.
+    pub fn pot(&self) -> u32 {
+        self.players.iter().map(|player| player.contributed).sum()
+    }
+
+    /// Shuffles the deck, collects the antes and deals third street. Players without any chips
+    /// sit the hand out.
+    pub fn start_hand(&mut self) {
+        self.deck.shuffle();
+        self.street = Some(StudStreet::Third);
+        self.community_card = None;
+        for player in self.players.iter_mut() {
+            player.down_cards = CardCollection(vec![]);
+            player.up_cards = CardCollection(vec![]);
+            player.contributed = 0;
+            player.folded = player.chips == 0;
+        }
+
+        for index in 0..self.players.len() {
+            if !self.players[index].folded {
+                self.put_in_pot(index, self.ante);
+            }
+        }
+        for index in 0..self.players.len() {
+            if !self.players[index].folded {
+                let down_cards = self.pop_cards(2);
+                let up_card = self.pop_cards(1);
+                self.players[index].down_cards = down_cards;
+                self.players[index].up_cards = up_card;
+            }
+        }
+    }
+
+    /// Returns the player that has to post the bring-in, which is the player showing the lowest
+    /// up card on third street. Ties are broken by suit, clubs being the lowest followed by
+    /// diamonds, hearts and spades.
+    pub fn bring_in_player(&self) -> usize {
+        let mut lowest: Option<(usize, &Card)> = None;
+        for (index, player) in self.players.iter().enumerate() {
+            if player.folded {
+                continue;
+            }
+            let card = &player.up_cards.0[0];
+            lowest = match lowest {
+                Some((_, cur)) if compare_bring_in_cards(card, cur) != Ordering::Less => lowest,
+                _ => Some((index, card)),
+            };
+        }
+
+        match lowest {
+            Some((index, _)) => index,
+            None => panic!("tried to find the bring-in without any players in the hand"),
+        }
+    }
+
+    /// Makes the bring-in player post the bring-in and returns that player.
+    pub fn post_bring_in(&mut self) -> usize {
+        let player = self.bring_in_player();
+        self.put_in_pot(player, self.bring_in);
+
+        player
+    }
+
+    /// Returns the player that acts first on the current street. On third street that is the
+    /// bring-in, and from fourth street on it is the player showing the best board, with ties
+    /// going to the player in the earliest seat.
+    pub fn first_to_act(&self) -> usize {
+        if self.street == Some(StudStreet::Third) {
+            return self.bring_in_player();
+        }
+
+        let mut best: Option<(usize, VisibleBoard)> = None;
+        for (index, player) in self.players.iter().enumerate() {
+            if player.folded {
+                continue;
+            }
+            let board = VisibleBoard::new(&player.up_cards);
+            let better = match &best {
+                Some((_, cur)) => board > *cur,
+                None => true,
+            };
+            if better {
+                best = Some((index, board));
+            }
+        }
+
+        match best {
+            Some((index, _)) => index,
+            None => panic!("tried to find the first player to act without any players in the hand"),
+        }
+    }
+
+    /// Moves chips from the player's stack into the pot, returning how many were actually put in
+    /// since a player can't bet more than they have.
+    pub fn put_in_pot(&mut self, player: usize, amount: u32) -> u32 {
+        let player = &mut self.players[player];
+        let amount = amount.min(player.chips);
+        player.chips -= amount;
+        player.contributed += amount;
+
+        amount
+    }
+
+    pub fn fold(&mut self, player: usize) {
+        self.players[player].folded = true;
+    }
+
+    /// Deals the next street to every player still in the hand and returns it, or `None` if all
+    /// streets have already been dealt.
+    pub fn deal_next_street(&mut self) -> Option<StudStreet> {
+        let street = match self.street? {
+            StudStreet::Third => StudStreet::Fourth,
+            StudStreet::Fourth => StudStreet::Fifth,
+            StudStreet::Fifth => StudStreet::Sixth,
+            StudStreet::Sixth => StudStreet::Seventh,
+            StudStreet::Seventh => return None,
+        };
+        self.street = Some(street);
+
+        let active: Vec<usize> = (0..self.players.len())
+            .filter(|index| !self.players[*index].folded)
+            .collect();
+        if street == StudStreet::Seventh {
+            // if there aren't enough cards left for everyone, a single community card is used
+            if self.deck.cards_left() < active.len() {
+                self.community_card = Some(self.pop_cards(1).0.remove(0));
+                return Some(street);
+            }
+            for index in active {
+                let card = self.pop_cards(1);
+                let player = &mut self.players[index];
+                player.down_cards = CardCollection::concat(player.down_cards.clone(), card);
+            }
+        } else {
+            for index in active {
+                let card = self.pop_cards(1);
+                let player = &mut self.players[index];
+                player.up_cards = CardCollection::concat(player.up_cards.clone(), card);
+            }
+        }
+
+        Some(street)
+    }
+
+    /// Returns all the cards the player can use to make a hand.
+    pub fn player_cards(&self, player: usize) -> CardCollection {
+        let player = &self.players[player];
+        let mut cards = CardCollection::concat(player.down_cards.clone(), player.up_cards.clone());
+        if let Some(card) = &self.community_card {
+            cards.0.push(card.clone());
+        }
+
+        cards
+    }
+
+    /// Awards the pot to the players still in the hand, splitting it into side pots as needed,
+    /// and returns how many chips each player won. This is called once seventh street has been
+    /// dealt, or earlier when all but one player folded.
+    pub fn showdown(&mut self) -> Vec<u32> {
+        let contributions: Vec<u32> = self.players.iter().map(|p| p.contributed).collect();
+        let folded: Vec<bool> = self.players.iter().map(|p| p.folded).collect();
+        let pots = build_pots(&contributions, &folded);
+
+        let active: Vec<usize> = (0..self.players.len())
+            .filter(|index| !folded[*index])
+            .collect();
+        let mut rankings = vec![u8::MAX; self.players.len()];
+        let winnings = if active.len() == 1 {
+            rankings[active[0]] = 1;
+            split_pots(pots, rankings)
+        } else {
+            let hands: Vec<CardCollection> = active
+                .iter()
+                .map(|index| self.player_cards(*index))
+                .collect();
+            for (index, ranking) in active.iter().zip(assign_hand_rankings(hands.clone())) {
+                rankings[*index] = ranking;
+            }
+            let mut low_rankings = vec![None; self.players.len()];
+            if self.hi_lo {
+                let lows = assign_low_hand_rankings(hands, LowballRules::EightOrBetter);
+                for (index, ranking) in active.iter().zip(lows) {
+                    low_rankings[*index] = ranking;
+                }
+            }
+            split_hi_lo_pots(pots, rankings, low_rankings)
+                .iter()
+                .map(|payout| payout.total())
+                .collect()
+        };
+        for (player, chips) in self.players.iter_mut().zip(&winnings) {
+            player.chips += chips;
+            player.contributed = 0;
+        }
+        self.street = None;
+
+        winnings
+    }
+
+    /// Returns the table as seen by the given player: their own down cards, and the up cards of
+    /// every player still in the hand. Passing `None` shows only the up cards.
+    pub fn view(&self, viewer: Option<usize>) -> String {
+        let mut view = String::new();
+        for (index, player) in self.players.iter().enumerate() {
+            if player.folded {
+                view.push_str(&format!("Player {} (folded)\n", index + 1));
+                continue;
+            }
+            view.push_str(&format!("Player {} ({} chips)\n", index + 1, player.chips));
+            if viewer == Some(index) {
+                view.push_str(&format!("{}", player.down_cards));
+            }
+            view.push_str(&format!("{}", player.up_cards));
+        }
+        if let Some(card) = &self.community_card {
+            view.push_str("Community card\n");
+            view.push_str(&format!("{}", card));
+        }
+
+        view
+    }
+
+    fn pop_cards(&mut self, num: u8) -> CardCollection {
+        match self.deck.pop_cards(num) {
+            Some(cards) => cards,
+            None => panic!("ran out of cards while dealing seven-card stud"),
+        }
+    }
+}
+
+/// A player sitting at a stud table.
+#[derive(Clone)]
+struct StudSeat {
+    chips: u32,
+    sitting_out: bool,
+    // the hands dealt since the player sat out
+    hands_sat_out: u32,
+}
+
+/// A stud table, dealing each hand on a `StudGame` between the players sitting in with chips
+/// and betting each street with a `BettingRound`. The table's small blind is the ante and half
+/// its big blind, rounded up, is the bring-in. Fixed limit bets are the small bet on third and
+/// fourth street and the big bet from fifth street on, and completing the bring-in to the small
+/// bet counts as the first bet. Stud hi-lo tables split each pot between high and low.
+///
+/// With no blinds to miss, players sit out and back in as they like, and are removed once they
+/// have sat out `max_orbits_sat_out` orbits, counting an orbit as a hand for each seat.
+pub struct StudTable {
+    settings: TableSettings,
+    game: StudGame,
+    seats: Vec<Option<StudSeat>>,
+    // whether each seat was dealt into the hand in progress or the last hand
+    dealt: Vec<bool>,
+    round: Option<BettingRound>,
+    hands_played: u32,
+}
+
+impl StudTable {
+    pub fn new(settings: TableSettings) -> Self {
+        StudTable {
+            settings,
+            game: StudGame::new(
+                vec![0; settings.seats],
+                settings.small_blind,
+                bring_in(&settings),
+                settings.game.is_hi_lo(),
+            ),
+            seats: vec![None; settings.seats],
+            dealt: vec![false; settings.seats],
+            round: None,
+            hands_played: 0,
+        }
+    }
+
+    /// Returns the game of the hand in progress, or of the last hand between hands.
+    pub fn game(&self) -> &StudGame {
+        &self.game
+    }
+
+    /// Seeds the deck, so tables seeded the same way deal the same cards to the same seats.
+    pub fn seed_deck(&mut self, seed: u64) {
+        self.game.seed_deck(seed);
+    }
+
+    pub fn hands_played(&self) -> u32 {
+        self.hands_played
+    }
+
+    /// Returns the total number of chips in the pot, including the current round's bets.
+    pub fn pot(&self) -> u32 {
+        let bets: u32 = match &self.round {
+            Some(round) => round.bets().iter().sum(),
+            None => 0,
+        };
+
+        self.game.pot() + bets
+    }
+
+    /// Plays the hand on until someone has to act, dealing the next streets as betting rounds
+    /// complete and settling the pot once the hand is over.
+    fn progress(&mut self, events: &mut Vec<TableEvent>) {
+        loop {
+            let Some(round) = &self.round else {
+                return;
+            };
+            if let Some(seat) = round.to_act() {
+                events.push(TableEvent::ToAct {
+                    seat,
+                    pot: self.pot(),
+                });
+                return;
+            }
+            let bets = round.bets().clone();
+            self.round = None;
+            for (seat, bet) in bets.into_iter().enumerate() {
+                self.game.put_in_pot(seat, bet);
+            }
+
+            let in_hand = self.game.players().iter().filter(|p| !p.folded).count();
+            if in_hand <= 1 {
+                return self.settle(events);
+            }
+            let Some(street) = self.game.deal_next_street() else {
+                return self.settle(events);
+            };
+            for (seat, player) in self.game.players().iter().enumerate() {
+                if player.folded {
+                    continue;
+                }
+                let event = match street {
+                    StudStreet::Seventh if self.game.community_card().is_some() => continue,
+                    StudStreet::Seventh => TableEvent::HoleCards {
+                        seat,
+                        cards: last_card(&player.down_cards),
+                    },
+                    _ => TableEvent::UpCards {
+                        seat,
+                        cards: last_card(&player.up_cards),
+                    },
+                };
+                events.push(event);
+            }
+            if let Some(card) = self.game.community_card() {
+                events.push(TableEvent::Board {
+                    cards: CardCollection(vec![card.clone()]),
+                });
+            }
+            let first_to_act = self.game.first_to_act();
+            self.start_round(street, first_to_act);
+        }
+    }
+
+    /// Starts the betting round of the street with the players still in the hand.
+    fn start_round(&mut self, street: StudStreet, first_to_act: usize) {
+        let (stacks, folded) = self
+            .game
+            .players()
+            .iter()
+            .map(|player| (player.chips, player.folded))
+            .unzip();
+        let big_bet_street = !matches!(street, StudStreet::Third | StudStreet::Fourth);
+        self.round = Some(BettingRound::new(
+            self.settings.structure,
+            stacks,
+            folded,
+            self.game.pot(),
+            big_bet_street,
+            first_to_act,
+        ));
+    }
+
+    /// Shows the hands left in at showdown, splits the pot and ends the hand.
+    fn settle(&mut self, events: &mut Vec<TableEvent>) {
+        let active: Vec<usize> = (0..self.seats.len())
+            .filter(|seat| self.dealt[*seat] && !self.game.players()[*seat].folded)
+            .collect();
+        if active.len() > 1 {
+            for seat in active {
+                let player = &self.game.players()[seat];
+                events.push(TableEvent::Showdown {
+                    seat,
+                    cards: CardCollection::concat(
+                        player.down_cards.clone(),
+                        player.up_cards.clone(),
+                    ),
+                });
+            }
+        }
+
+        let winnings = self.game.showdown();
+        for (seat, amount) in winnings.into_iter().enumerate() {
+            if amount > 0 {
+                events.push(TableEvent::PotWon { seat, amount });
+            }
+        }
+        self.sync_stacks();
+        events.push(TableEvent::HandFinished {
+            hand: self.hands_played,
+        });
+    }
+
+    /// Copies the stacks of the players still in the hand back from the betting round, or from
+    /// the game between rounds.
+    fn sync_stacks(&mut self) {
+        for (seat, player) in self.seats.iter_mut().enumerate() {
+            let Some(player) = player else {
+                continue;
+            };
+            if !self.dealt[seat] || self.game.players()[seat].folded {
+                continue;
+            }
+            player.chips = match &self.round {
+                Some(round) => round.stacks()[seat],
+                None => self.game.players()[seat].chips,
+            };
+        }
+    }
+
+    /// Returns whether the seat was dealt into the hand in progress and hasn't folded.
+    fn in_hand(&self, seat: usize) -> bool {
+        self.hand_in_progress() && self.dealt[seat] && !self.game.players()[seat].folded
+    }
+
+    fn seat_mut(&mut self, seat: usize) -> Result<&mut StudSeat, String> {
+        match self.seats.get_mut(seat) {
+            Some(Some(player)) => Ok(player),
+            _ => Err(format!("nobody is sitting in seat {}", seat + 1)),
+        }
+    }
+}
+
+impl TableGame for StudTable {
+    fn hand_in_progress(&self) -> bool {
+        self.game.street().is_some()
+    }
+
+    fn stacks(&self) -> Vec<u32> {
+        self.seats
+            .iter()
+            .map(|player| player.as_ref().map_or(0, |player| player.chips))
+            .collect()
+    }
+
+    fn hole_cards(&self, seat: usize) -> CardCollection {
+        match self.game.players().get(seat) {
+            Some(player) if self.dealt[seat] => player.down_cards.clone(),
+            _ => CardCollection(vec![]),
+        }
+    }
+
+    fn sit_down(&mut self, seat: usize, chips: u32) -> Result<(), String> {
+        match self.seats.get(seat) {
+            None => return Err(format!("there is no seat {}", seat + 1)),
+            Some(Some(_)) => return Err(format!("seat {} is taken", seat + 1)),
+            Some(None) => {}
+        }
+        if chips == 0 {
+            return Err("can't sit down without any chips".to_string());
+        }
+        self.seats[seat] = Some(StudSeat {
+            chips,
+            sitting_out: false,
+            hands_sat_out: 0,
+        });
+
+        Ok(())
+    }
+
+    fn add_chips(&mut self, seat: usize, chips: u32) -> Result<(), String> {
+        if self.in_hand(seat) {
+            return Err("can't add chips in the middle of a hand".to_string());
+        }
+        let player = self.seat_mut(seat)?;
+        player.chips = player
+            .chips
+            .checked_add(chips)
+            .ok_or("that's more chips than a stack can hold")?;
+
+        Ok(())
+    }
+
+    fn leaving_chips(&self, seat: usize) -> Result<u32, String> {
+        let Some(Some(player)) = self.seats.get(seat) else {
+            return Err(format!("nobody is sitting in seat {}", seat + 1));
+        };
+        if self.in_hand(seat) {
+            return Err("can't leave in the middle of a hand".to_string());
+        }
+
+        Ok(player.chips)
+    }
+
+    fn stand_up(&mut self, seat: usize) -> Result<u32, String> {
+        let chips = self.leaving_chips(seat)?;
+        self.seats[seat] = None;
+        self.dealt[seat] = false;
+
+        Ok(chips)
+    }
+
+    fn sit_out(&mut self, seat: usize) -> Result<(), String> {
+        let player = self.seat_mut(seat)?;
+        if player.sitting_out {
+            return Err("already sitting out".to_string());
+        }
+        player.sitting_out = true;
+        player.hands_sat_out = 0;
+
+        Ok(())
+    }
+
+    fn sit_in(&mut self, seat: usize, _post_missed_blinds: bool) -> Result<(), String> {
+        let player = self.seat_mut(seat)?;
+        if !player.sitting_out {
+            return Err("already playing".to_string());
+        }
+        player.sitting_out = false;
+
+        Ok(())
+    }
+
+    fn start_hand(&mut self) -> Result<Vec<TableEvent>, String> {
+        if self.hand_in_progress() {
+            return Err("a hand is already being played".to_string());
+        }
+        let ready = |seat: &Option<StudSeat>| {
+            seat.as_ref()
+                .is_some_and(|player| !player.sitting_out && player.chips > 0)
+        };
+        if self.seats.iter().filter(|seat| ready(seat)).count() < 2 {
+            return Err("need at least two players to start a hand".to_string());
+        }
+        self.hands_played += 1;
+        let mut events = vec![TableEvent::HandStarted {
+            hand: self.hands_played,
+        }];
+
+        let seats = self.seats.len();
+        let most_hands = self
+            .settings
+            .max_orbits_sat_out
+            .map(|orbits| orbits * seats as u32);
+        for seat in 0..seats {
+            let Some(player) = self.seats[seat].as_mut() else {
+                continue;
+            };
+            if !player.sitting_out {
+                continue;
+            }
+            player.hands_sat_out += 1;
+            if most_hands.is_some_and(|most| player.hands_sat_out >= most) {
+                let chips = player.chips;
+                self.seats[seat] = None;
+                events.push(TableEvent::Removed { seat, chips });
+            }
+        }
+
+        for seat in 0..seats {
+            self.dealt[seat] = ready(&self.seats[seat]);
+            let chips = match &self.seats[seat] {
+                Some(player) if self.dealt[seat] => player.chips,
+                _ => 0,
+            };
+            self.game.set_chips(seat, chips);
+        }
+        self.game.start_hand();
+        for seat in (0..seats).filter(|seat| self.dealt[*seat]) {
+            let amount = self.settings.small_blind.min(self.stacks()[seat]);
+            if amount > 0 {
+                events.push(TableEvent::Ante { seat, amount });
+            }
+        }
+        for seat in (0..seats).filter(|seat| self.dealt[*seat]) {
+            let player = &self.game.players()[seat];
+            events.push(TableEvent::HoleCards {
+                seat,
+                cards: player.down_cards.clone(),
+            });
+            events.push(TableEvent::UpCards {
+                seat,
+                cards: player.up_cards.clone(),
+            });
+        }
+
+        let bring_in_seat = self.game.bring_in_player();
+        self.start_round(StudStreet::Third, bring_in_seat + 1);
+        if let Some(round) = self.round.as_mut() {
+            let amount = round.post_blind(bring_in_seat, bring_in(&self.settings));
+            if amount > 0 {
+                events.push(TableEvent::BringIn {
+                    seat: bring_in_seat,
+                    amount,
+                });
+            }
+        }
+        self.sync_stacks();
+        self.progress(&mut events);
+
+        Ok(events)
+    }
+
+    fn decision(&self) -> Option<Decision> {
+        let round = self.round.as_ref()?;
+        let seat = round.to_act()?;
+        let players = self.game.players();
+        let up_cards = players
+            .iter()
+            .map(|player| {
+                if player.folded {
+                    CardCollection(vec![])
+                } else {
+                    player.up_cards.clone()
+                }
+            })
+            .collect();
+
+        Some(Decision {
+            seat,
+            hole_cards: players[seat].down_cards.clone(),
+            board: CardCollection(self.game.community_card().into_iter().cloned().collect()),
+            street: None,
+            up_cards,
+            pot: self.pot(),
+            bet: round.bets().iter().max().copied().unwrap_or(0),
+            chips: round.stacks()[seat],
+            big_blind: self.settings.big_blind,
+            opponents: players.iter().filter(|player| !player.folded).count() - 1,
+            rules: HandRules::Standard,
+            legal: round.legal_actions()?,
+        })
+    }
+
+    fn to_act(&self) -> Option<usize> {
+        self.round.as_ref()?.to_act()
+    }
+
+    fn act(&mut self, action: Action) -> Result<Vec<TableEvent>, String> {
+        let Some(round) = self.round.as_mut() else {
+            return Err("there isn't a hand being played".to_string());
+        };
+        let Some(seat) = round.to_act() else {
+            return Err("nobody is left to act".to_string());
+        };
+        round.act(action)?;
+        if action == Action::Fold {
+            self.game.fold(seat);
+        }
+        self.sync_stacks();
+
+        let mut events = vec![TableEvent::Action { seat, action }];
+        self.progress(&mut events);
+
+        Ok(events)
+    }
+}
+
+/// Returns the bring-in at the table's stakes, half the big blind rounded up.
+fn bring_in(settings: &TableSettings) -> u32 {
+    settings.big_blind.div_ceil(2)
+}
+
+/// Returns the last card of the cards, the one just dealt.
+fn last_card(cards: &CardCollection) -> CardCollection {
+    CardCollection(cards.0.last().into_iter().cloned().collect())
+}
+
+/// The strength of the up cards a player is showing. Only pairs, sets and four of a kinds count,
+/// with ties broken by the card values.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct VisibleBoard {
+    // the number of cards in the largest group of equal values
+    largest_group: u8,
+    // the number of groups of that size, so two pair beats one pair
+    groups: u8,
+    // the values ordered from most to least significant
+    values: Vec<u8>,
+}
+
+impl VisibleBoard {
+    fn new(up_cards: &CardCollection) -> Self {
+        let mut value_map = HashMap::new();
+        for card in &up_cards.0 {
+            let count = value_map.entry(card.value.value()).or_insert(0u8);
+            *count += 1
+        }
+        let mut groups: Vec<(u8, u8)> = value_map.into_iter().collect();
+        groups.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| b.0.cmp(&a.0)));
+
+        let largest_group = groups[0].1;
+        let mut values = vec![];
+        for (val, count) in &groups {
+            for _ in 0..*count {
+                values.push(*val);
+            }
+        }
+
+        VisibleBoard {
+            largest_group,
+            groups: groups.iter().filter(|g| g.1 == largest_group).count() as u8,
+            values,
+        }
+    }
+}
+
+/// Orders cards for the bring-in by value and then by suit.
+fn compare_bring_in_cards(a: &Card, b: &Card) -> Ordering {
+    a.value
+        .value()
+        .cmp(&b.value.value())
+        .then_with(|| bring_in_suit_rank(&a.suit).cmp(&bring_in_suit_rank(&b.suit)))
+}
+
+fn bring_in_suit_rank(suit: &CardSuit) -> u8 {
+    match suit {
+        CardSuit::Club => 1,
+        CardSuit::Diamond => 2,
+        CardSuit::Heart => 3,
+        CardSuit::Spade => 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::betting::BettingStructure;
+    use crate::lobby::GameType;
+    use crate::timer::TimerSettings;
+
+    fn cards(codes: &str) -> CardCollection {
+        CardCollection(codes.split_whitespace().filter_map(Card::parse).collect())
+    }
+
+    fn dealt(players: usize) -> StudGame {
+        let mut game = StudGame::new(vec![100; players], 1, 1, false);
+        game.start_hand();
+        game
+    }
+
+    #[test]
+    fn lowest_up_card_brings_in_with_ties_broken_by_suit() {
+        let mut game = dealt(3);
+        game.players[0].up_cards = cards("5s");
+        game.players[1].up_cards = cards("2h");
+        game.players[2].up_cards = cards("2d");
+        assert_eq!(game.bring_in_player(), 2);
+        assert_eq!(game.first_to_act(), 2);
+        game.fold(2);
+        assert_eq!(game.bring_in_player(), 1);
+    }
+
+    #[test]
+    fn best_board_showing_acts_first_on_later_streets() {
+        let mut game = dealt(3);
+        game.deal_next_street();
+        game.players[0].up_cards = cards("Ks 9d");
+        game.players[1].up_cards = cards("4c 4h");
+        game.players[2].up_cards = cards("Ah Kd");
+        assert_eq!(game.first_to_act(), 1);
+        // ties go to the earliest seat
+        game.players[1].up_cards = cards("Ac Kh");
+        assert_eq!(game.first_to_act(), 1);
+        game.players[0].up_cards = cards("As Kc");
+        assert_eq!(game.first_to_act(), 0);
+    }
+
+    #[test]
+    fn community_card_is_dealt_when_the_deck_runs_short() {
+        // eight players take 48 cards by sixth street, leaving 4 for the river
+        let mut game = dealt(8);
+        while game.street() != Some(StudStreet::Seventh) {
+            game.deal_next_street();
+        }
+        let community = game.community_card().cloned().unwrap();
+        for player in 0..8 {
+            assert_eq!(game.players()[player].down_cards.0.len(), 2);
+            let hand = game.player_cards(player);
+            assert_eq!(hand.0.len(), 7);
+            assert!(hand.0.contains(&community));
+        }
+        assert_eq!(game.showdown().iter().sum::<u32>(), 8);
+    }
+
+    fn table(players: usize) -> StudTable {
+        let mut table = StudTable::new(TableSettings {
+            game: GameType::Stud,
+            structure: BettingStructure::parse("fl", 1, 2).unwrap(),
+            small_blind: 1,
+            big_blind: 2,
+            seats: players,
+            broadcast_delay: None,
+            timer: TimerSettings::default(),
+            max_orbits_sat_out: Some(1),
+            max_runs: 1,
+            min_buy_in: 40,
+            max_buy_in: 200,
+        });
+        for seat in 0..players {
+            table.sit_down(seat, 100).unwrap();
+        }
+        table.seed_deck(7);
+        table
+    }
+
+    #[test]
+    fn bring_in_is_posted_and_completed_to_the_small_bet() {
+        let mut table = table(3);
+        let events = table.start_hand().unwrap();
+        let antes = events
+            .iter()
+            .filter(|event| matches!(event, TableEvent::Ante { amount: 1, .. }))
+            .count();
+        assert_eq!(antes, 3);
+        let bring_in = table.game().bring_in_player();
+        assert!(events.iter().any(
+            |event| matches!(event, TableEvent::BringIn { seat, amount: 1 } if *seat == bring_in)
+        ));
+
+        let decision = table.decision().unwrap();
+        assert_eq!(decision.seat, (bring_in + 1) % 3);
+        assert_eq!(decision.pot, 4);
+        assert_eq!(decision.legal.call, 1);
+        assert_eq!(decision.legal.raise, Some((2, 2)));
+        assert_eq!(decision.up_cards.len(), 3);
+        assert!(decision.up_cards.iter().all(|up| up.0.len() == 1));
+        assert_eq!(decision.hole_cards.0.len(), 2);
+    }
+
+    #[test]
+    fn hand_is_played_to_a_showdown_on_seventh_street() {
+        let mut table = table(3);
+        let mut events = table.start_hand().unwrap();
+        while let Some(decision) = table.decision() {
+            let action = if decision.legal.call == 0 {
+                Action::Check
+            } else {
+                Action::Call
+            };
+            events.extend(table.act(action).unwrap());
+        }
+        assert!(!table.hand_in_progress());
+
+        let up_cards = events
+            .iter()
+            .filter(|event| matches!(event, TableEvent::UpCards { .. }))
+            .count();
+        let shown: Vec<usize> = events
+            .iter()
+            .filter_map(|event| match event {
+                TableEvent::Showdown { cards, .. } => Some(cards.0.len()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(up_cards, 12);
+        assert_eq!(shown, vec![7, 7, 7]);
+        // the antes and the called bring-in
+        let won: u32 = events
+            .iter()
+            .filter_map(|event| match event {
+                TableEvent::PotWon { amount, .. } => Some(*amount),
+                _ => None,
+            })
+            .sum();
+        assert_eq!(won, 6);
+        assert_eq!(table.stacks().iter().sum::<u32>(), 300);
+    }
+
+    #[test]
+    fn players_sitting_out_are_removed_after_an_orbit() {
+        let mut table = table(3);
+        table.sit_out(2).unwrap();
+        for hand in 1..=3 {
+            let events = table.start_hand().unwrap();
+            assert_eq!(table.hole_cards(2).0.len(), 0);
+            let removed = events.iter().any(|event| {
+                matches!(
+                    event,
+                    TableEvent::Removed {
+                        seat: 2,
+                        chips: 100
+                    }
+                )
+            });
+            assert_eq!(removed, hand == 3);
+            while table.decision().is_some() {
+                table.act(Action::Fold).unwrap();
+            }
+        }
+        assert_eq!(table.stacks()[2], 0);
+    }
+}
@@ -0,0 +1,280 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Where the action timers get the current time from, so they can be driven by a fake clock.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The real clock.
+#[derive(Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when it is told to.
+pub struct ManualClock {
+    now: Mutex<Instant>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        ManualClock {
+            now: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        if let Ok(mut now) = self.now.lock() {
+            *now += by;
+        }
+    }
+
+    /// Moves the clock to the given time.
+    pub fn set(&self, to: Instant) {
+        if let Ok(mut now) = self.now.lock() {
+            *now = to;
+        }
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        match self.now.lock() {
+            Ok(now) => *now,
+            Err(poisoned) => *poisoned.into_inner(),
+        }
+    }
+}
+
+impl<C: Clock> Clock for &C {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+impl<C: Clock> Clock for Arc<C> {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+/// How long players get to act, picked per table.
+#[derive(Clone, Copy)]
+pub struct TimerSettings {
+    /// The time a player gets for each action before their time bank starts running.
+    pub action_time: Duration,
+    /// The time bank each player starts with. Zero turns the time bank off.
+    pub time_bank: Duration,
+    /// Time added back to every player's time bank, up to its starting size, every
+    /// `refill_every` hands.
+    pub refill: Duration,
+    pub refill_every: u32,
+}
+
+impl Default for TimerSettings {
+    fn default() -> Self {
+        TimerSettings {
+            action_time: Duration::from_secs(30),
+            time_bank: Duration::from_secs(60),
+            refill: Duration::from_secs(10),
+            refill_every: 10,
+        }
+    }
+}
+
+struct Turn {
+    seat: usize,
+    started: Instant,
+}
+
+/// Times the player whose turn it is at a table, drawing down their time bank once their
+/// action time runs out.
+pub struct ActionTimer<C: Clock> {
+    clock: C,
+    settings: TimerSettings,
+    // the time bank left for each seat
+    time_banks: Vec<Duration>,
+    hands_played: u32,
+    turn: Option<Turn>,
+}
+
+impl<C: Clock> ActionTimer<C> {
+    pub fn new(clock: C, settings: TimerSettings, seats: usize) -> Self {
+        ActionTimer {
+            clock,
+            settings,
+            time_banks: vec![settings.time_bank; seats],
+            hands_played: 0,
+            turn: None,
+        }
+    }
+
+    /// Starts the clock for the seat's turn to act.
+    pub fn start_turn(&mut self, seat: usize) {
+        self.turn = Some(Turn {
+            seat,
+            started: self.clock.now(),
+        });
+    }
+
+    /// Stops the clock once the player has acted, taking any time used past their action time
+    /// out of their time bank.
+    pub fn end_turn(&mut self) {
+        let Some(turn) = self.turn.take() else {
+            return;
+        };
+        let used = self.clock.now().duration_since(turn.started);
+        let over = used.saturating_sub(self.settings.action_time);
+        let bank = &mut self.time_banks[turn.seat];
+        *bank = bank.saturating_sub(over);
+    }
+
+    /// Returns the seat whose turn it is along with the action time and time bank they have
+    /// left, for the countdown shown to players.
+    pub fn remaining(&self) -> Option<(usize, Duration, Duration)> {
+        let turn = self.turn.as_ref()?;
+        let used = self.clock.now().duration_since(turn.started);
+        let action_left = self.settings.action_time.saturating_sub(used);
+        let over = used.saturating_sub(self.settings.action_time);
+        let bank_left = self.time_banks[turn.seat].saturating_sub(over);
+
+        Some((turn.seat, action_left, bank_left))
+    }
+
+    /// Returns the seat whose turn it is if they have used up both their action time and their
+    /// time bank, in which case the table should take the default action for them. This also
+    /// ends their turn.
+    pub fn check_timeout(&mut self) -> Option<usize> {
+        let (seat, action_left, bank_left) = self.remaining()?;
+        if !action_left.is_zero() || !bank_left.is_zero() {
+            return None;
+        }
+        self.end_turn();
+
+        Some(seat)
+    }
+
+    /// Records that a hand finished, refilling the time banks every `refill_every` hands.
+    pub fn hand_finished(&mut self) {
+        self.hands_played += 1;
+        if self.settings.refill_every == 0
+            || !self.hands_played.is_multiple_of(self.settings.refill_every)
+        {
+            return;
+        }
+        for bank in self.time_banks.iter_mut() {
+            *bank = (*bank + self.settings.refill).min(self.settings.time_bank);
+        }
+    }
+
+    /// Gives the seat a full time bank, such as when a new player sits down.
+    pub fn reset_seat(&mut self, seat: usize) {
+        self.time_banks[seat] = self.settings.time_bank;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> TimerSettings {
+        TimerSettings {
+            action_time: Duration::from_secs(30),
+            time_bank: Duration::from_secs(60),
+            refill: Duration::from_secs(10),
+            refill_every: 2,
+        }
+    }
+
+    #[test]
+    fn times_out_once_the_action_time_and_time_bank_run_out() {
+        let clock = ManualClock::new();
+        let mut timer = ActionTimer::new(&clock, settings(), 2);
+        timer.start_turn(1);
+        clock.advance(Duration::from_secs(29));
+        assert_eq!(timer.check_timeout(), None);
+        assert_eq!(
+            timer.remaining(),
+            Some((1, Duration::from_secs(1), Duration::from_secs(60)))
+        );
+
+        // past the action time the time bank runs down
+        clock.advance(Duration::from_secs(31));
+        assert_eq!(
+            timer.remaining(),
+            Some((1, Duration::ZERO, Duration::from_secs(30)))
+        );
+        assert_eq!(timer.check_timeout(), None);
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(timer.check_timeout(), Some(1));
+        // the turn is over once it has timed out
+        assert_eq!(timer.remaining(), None);
+        assert_eq!(timer.check_timeout(), None);
+    }
+
+    #[test]
+    fn time_bank_keeps_what_is_left_between_turns() {
+        let clock = ManualClock::new();
+        let mut timer = ActionTimer::new(&clock, settings(), 2);
+        timer.start_turn(0);
+        clock.advance(Duration::from_secs(50));
+        timer.end_turn();
+
+        // acting within the action time leaves the time bank alone
+        timer.start_turn(0);
+        clock.advance(Duration::from_secs(10));
+        timer.end_turn();
+
+        timer.start_turn(0);
+        assert_eq!(
+            timer.remaining(),
+            Some((0, Duration::from_secs(30), Duration::from_secs(40)))
+        );
+        clock.advance(Duration::from_secs(70));
+        assert_eq!(timer.check_timeout(), Some(0));
+
+        timer.start_turn(1);
+        assert_eq!(
+            timer.remaining(),
+            Some((1, Duration::from_secs(30), Duration::from_secs(60)))
+        );
+    }
+
+    #[test]
+    fn time_banks_refill_every_few_hands_up_to_their_size() {
+        let clock = ManualClock::new();
+        let mut timer = ActionTimer::new(&clock, settings(), 2);
+        timer.start_turn(0);
+        clock.advance(Duration::from_secs(75));
+        timer.end_turn();
+
+        timer.hand_finished();
+        timer.start_turn(0);
+        assert_eq!(timer.remaining().unwrap().2, Duration::from_secs(15));
+        timer.end_turn();
+        timer.hand_finished();
+        timer.start_turn(0);
+        assert_eq!(timer.remaining().unwrap().2, Duration::from_secs(25));
+        timer.end_turn();
+
+        // full time banks stay full
+        timer.start_turn(1);
+        assert_eq!(timer.remaining().unwrap().2, Duration::from_secs(60));
+        timer.end_turn();
+
+        timer.reset_seat(0);
+        timer.start_turn(0);
+        assert_eq!(timer.remaining().unwrap().2, Duration::from_secs(60));
+    }
+}
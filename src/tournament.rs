@@ -0,0 +1,1058 @@
+use crate::betting::{Action, BettingStructure};
+use crate::bots::BotKind;
+use crate::events::{ordinal, Recipient, TableEvent};
+use crate::hand_log::HandLog;
+use crate::holdem::HoldemGame;
+use crate::icm::{chip_equity, icm_equity, round_equity};
+use crate::lobby::{GameType, TableSettings};
+use crate::strategy::{LinePlayer, Strategy};
+use crate::timer::{Clock, SystemClock, TimerSettings};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// How long a blind level lasts.
+#[derive(Clone, Copy, PartialEq)]
+pub enum LevelLength {
+    Time(Duration),
+    /// The level ends once any one table has started this many hands in it, so the blinds go
+    /// up with the table dealing the fastest.
+    Hands(u32),
+}
+
+#[derive(Clone, Copy)]
+pub struct BlindLevel {
+    pub small_blind: u32,
+    pub big_blind: u32,
+    pub ante: u32,
+    pub length: LevelLength,
+}
+
+/// The structure of a tournament, usually loaded from a config file.
+#[derive(Clone)]
+pub struct TournamentConfig {
+    pub game: GameType,
+    pub structure: BettingStructure,
+    pub seats: usize,
+    pub buy_in: u32,
+    pub starting_stack: u32,
+    /// The share of the prize pool paid to each place in percent, starting with 1st.
+    pub payouts: Vec<u32>,
+    /// The blind levels in the order they are played. The last level lasts until the end.
+    pub levels: Vec<BlindLevel>,
+}
+
+impl TournamentConfig {
+    /// Parses a tournament config, which has one setting per line and `#` comments:
+    ///
+    /// ```text
+    /// game holdem nl          # the game and betting limit, holdem nl if not given
+    /// seats 9                 # the seats at the table, 9 if not given
+    /// buy_in 10
+    /// starting_stack 1500
+    /// payouts 50 30 20        # percent of the prize pool paid to 1st, 2nd and 3rd
+    /// level 10 20 0 10min     # small blind, big blind, ante and how long the level lasts
+    /// level 25 50 5 12hands   # levels can also last a number of hands
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = TournamentConfig {
+            game: GameType::Holdem,
+            structure: BettingStructure::NoLimit { big_blind: 0 },
+            seats: 9,
+            buy_in: 0,
+            starting_stack: 0,
+            payouts: vec![],
+            levels: vec![],
+        };
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let parsed = match words.as_slice() {
+                [] => Ok(()),
+                ["game", game, limit] => parse_game(game, limit).map(|(game, structure)| {
+                    config.game = game;
+                    config.structure = structure;
+                }),
+                ["seats", seats] => number(seats).map(|seats| config.seats = seats),
+                ["buy_in", buy_in] => number(buy_in).map(|buy_in| config.buy_in = buy_in),
+                ["starting_stack", stack] => {
+                    number(stack).map(|stack| config.starting_stack = stack)
+                }
+                ["payouts", payouts @ ..] => payouts
+                    .iter()
+                    .map(|payout| number(payout))
+                    .collect::<Result<Vec<u32>, String>>()
+                    .map(|payouts| config.payouts = payouts),
+                ["level", small, big, ante, length] => {
+                    parse_level(small, big, ante, length).map(|level| config.levels.push(level))
+                }
+                _ => Err(format!("don't know what '{}' means", line.trim())),
+            };
+            if let Err(err) = parsed {
+                return Err(format!("line {}: {}", index + 1, err));
+            }
+        }
+
+        if config.starting_stack == 0 {
+            return Err("the starting stack must be set".to_string());
+        }
+        if config.levels.is_empty() {
+            return Err("there must be at least one blind level".to_string());
+        }
+        if config.seats < 2 || config.seats > 10 {
+            return Err("a table must have between 2 and 10 seats".to_string());
+        }
+        if config.payouts.iter().sum::<u32>() != 100 {
+            return Err("the payouts must add up to 100 percent".to_string());
+        }
+
+        Ok(config)
+    }
+
+    /// Reads and parses the config file at the path.
+    pub fn load(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(err) => Err(format!("couldn't read {}: {}", path, err)),
+        }
+    }
+
+    /// Returns the prize for each paid place, starting with 1st, when the given number of players
+    /// entered. Chips lost to rounding go to 1st place. The prize pool must fit in a `u32`,
+    /// which `Tournament::new` checks.
+    pub fn prizes(&self, entrants: usize) -> Vec<u32> {
+        let pool = self.prize_pool(entrants);
+        let mut prizes: Vec<u64> = self
+            .payouts
+            .iter()
+            .take(entrants)
+            .map(|percent| pool * *percent as u64 / 100)
+            .collect();
+        let rounding = pool - prizes.iter().sum::<u64>();
+        if let Some(first) = prizes.first_mut() {
+            *first += rounding;
+        }
+
+        prizes.into_iter().map(|prize| prize as u32).collect()
+    }
+
+    /// Returns the buy-ins of the given number of entrants added up.
+    fn prize_pool(&self, entrants: usize) -> u64 {
+        self.buy_in as u64 * entrants as u64
+    }
+}
+
+/// A tournament played over one or more tables, such as a sit-and-go or a multi-table event.
+/// The entrants are dealt out to as few tables as fit them, and the blinds go up between hands
+/// as the levels run out. Players who run out of chips are eliminated, with players busting in
+/// the same hand placed by the chips they started it with.
+///
+/// As players bust the tables are kept within one player of each other by moving the player
+/// due to post the big blind next from the biggest table, and a table is broken up as soon as
+/// the players left fit on one table fewer. Moves only happen between a table's hands. On the
+/// bubble the tables play hand-for-hand, where each table plays one hand and waits for the
+/// others, and everyone busting in the same round is placed by the chips they started their
+/// hand with, the same as players busting in one hand.
+pub struct Tournament<C: Clock> {
+    config: TournamentConfig,
+    clock: C,
+    // the tables in play, `None` once broken up
+    tables: Vec<Option<HoldemGame>>,
+    entrants: Vec<String>,
+    // the table and seat of each entrant still playing
+    seating: Vec<Option<(usize, usize)>>,
+    level: usize,
+    level_started: Instant,
+    // the hands each table has started in the current level
+    level_hands: Vec<u32>,
+    // the entrants eliminated so far, from the first player out
+    eliminated: Vec<usize>,
+    // each entrant's chips at the start of their last hand
+    starting_stacks: Vec<u32>,
+    hand_for_hand: bool,
+    // the tables that have played their hand in the current hand-for-hand round
+    played_round: Vec<bool>,
+    // the prize each player left agreed to take when the tournament was chopped
+    deal: Option<Vec<(usize, u32)>>,
+    // the logs of the hands finished since they were last taken, when hands are logged
+    hand_logs: Option<Vec<HandLog>>,
+}
+
+impl<C: Clock> Tournament<C> {
+    pub fn new(config: TournamentConfig, entrants: Vec<String>, clock: C) -> Result<Self, String> {
+        if entrants.len() < 2 {
+            return Err("a tournament needs at least two entrants".to_string());
+        }
+        if config.prize_pool(entrants.len()) > u32::MAX as u64 {
+            return Err("the prize pool is too big".to_string());
+        }
+        let level = config.levels[0];
+        let settings = TableSettings {
+            game: config.game,
+            structure: config
+                .structure
+                .with_stakes(level.small_blind, level.big_blind),
+            small_blind: level.small_blind,
+            big_blind: level.big_blind,
+            seats: config.seats,
+            broadcast_delay: None,
+            timer: TimerSettings::default(),
+            max_orbits_sat_out: None,
+            max_runs: 1,
+            min_buy_in: config.starting_stack,
+            max_buy_in: config.starting_stack,
+        };
+        let table_count = entrants.len().div_ceil(config.seats);
+        let mut tables = vec![];
+        for _ in 0..table_count {
+            let mut table = HoldemGame::new(settings);
+            table.set_stakes(level.small_blind, level.big_blind, level.ante);
+            tables.push(Some(table));
+        }
+        // deal the entrants around the tables so they start out balanced
+        let mut seating = vec![];
+        for entrant in 0..entrants.len() {
+            let (table, seat) = (entrant % table_count, entrant / table_count);
+            if let Some(game) = tables[table].as_mut() {
+                game.sit_down(seat, config.starting_stack)?;
+            }
+            seating.push(Some((table, seat)));
+        }
+
+        Ok(Tournament {
+            level_started: clock.now(),
+            starting_stacks: vec![config.starting_stack; entrants.len()],
+            config,
+            clock,
+            tables,
+            seating,
+            entrants,
+            level: 0,
+            level_hands: vec![0; table_count],
+            eliminated: vec![],
+            hand_for_hand: false,
+            played_round: vec![false; table_count],
+            deal: None,
+            hand_logs: None,
+        })
+    }
+
+    /// Returns the table, or `None` if there is no such table or it has been broken up.
+    pub fn table(&self, table: usize) -> Option<&HoldemGame> {
+        self.tables.get(table)?.as_ref()
+    }
+
+    /// Logs every hand from the next one on, at every table.
+    pub fn log_hands(&mut self) {
+        for game in self.tables.iter_mut().flatten() {
+            game.log_hands();
+        }
+        self.hand_logs.get_or_insert_with(Vec::new);
+    }
+
+    /// Returns the logs of the hands finished since the last time they were taken.
+    pub fn take_hand_logs(&mut self) -> Vec<HandLog> {
+        self.hand_logs
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Returns the tables still in play.
+    pub fn tables(&self) -> Vec<usize> {
+        (0..self.tables.len())
+            .filter(|table| self.tables[*table].is_some())
+            .collect()
+    }
+
+    pub fn entrants(&self) -> &Vec<String> {
+        &self.entrants
+    }
+
+    /// Returns the entrant sitting in the seat at the table.
+    pub fn entrant_at(&self, table: usize, seat: usize) -> Option<usize> {
+        self.seating
+            .iter()
+            .position(|seating| *seating == Some((table, seat)))
+    }
+
+    /// Returns the entrants eliminated so far, from the first player out.
+    pub fn eliminated(&self) -> &Vec<usize> {
+        &self.eliminated
+    }
+
+    pub fn level(&self) -> &BlindLevel {
+        &self.config.levels[self.level]
+    }
+
+    pub fn hand_for_hand(&self) -> bool {
+        self.hand_for_hand
+    }
+
+    /// Returns whether only one player has chips left or the players left agreed to a chop.
+    pub fn is_finished(&self) -> bool {
+        self.entrants.len() - self.eliminated.len() <= 1 || self.deal.is_some()
+    }
+
+    /// Returns whether every player left is in the money.
+    pub fn in_the_money(&self) -> bool {
+        self.entrants.len() - self.eliminated.len() <= self.paid_places()
+    }
+
+    /// Proposes splitting the prizes still to be won between the players left by their ICM
+    /// equity. Returns the prize for each player left, or `None` if there is nothing to chop.
+    pub fn chop(&self) -> Option<Vec<(usize, u32)>> {
+        let left = self.entrants.len() - self.eliminated.len();
+        if left < 2 || self.deal.is_some() {
+            return None;
+        }
+        let chips = self.chips();
+        let players: Vec<usize> = self.finishing_order().into_iter().take(left).collect();
+        let stacks: Vec<u32> = players.iter().map(|entrant| chips[*entrant]).collect();
+        let prizes: Vec<u32> = self
+            .config
+            .prizes(self.entrants.len())
+            .into_iter()
+            .take(left)
+            .collect();
+        if prizes.iter().all(|prize| *prize == 0) {
+            return None;
+        }
+        let deal = round_equity(&icm_equity(&stacks, &prizes), &prizes);
+
+        Some(players.into_iter().zip(deal).collect())
+    }
+
+    /// Describes the ICM chop next to what a chop by chip count alone would give each player.
+    pub fn chop_proposal(&self) -> Option<String> {
+        let deal = self.chop()?;
+        let chips = self.chips();
+        let stacks: Vec<u32> = deal.iter().map(|(entrant, _)| chips[*entrant]).collect();
+        let prizes: Vec<u32> = self
+            .config
+            .prizes(self.entrants.len())
+            .into_iter()
+            .take(deal.len())
+            .collect();
+        let chip_chop = round_equity(&chip_equity(&stacks, &prizes), &prizes);
+
+        let mut proposal = String::from("Proposed chop (ICM, by chip count)\n");
+        for ((entrant, prize), chip_prize) in deal.iter().zip(chip_chop) {
+            proposal.push_str(&format!(
+                "  {} ({} chips): {}, {}\n",
+                self.entrants[*entrant], chips[*entrant], prize, chip_prize
+            ));
+        }
+
+        Some(proposal)
+    }
+
+    /// Ends the tournament with the players left taking the ICM chop. This can only be done
+    /// between hands.
+    pub fn accept_chop(&mut self) -> Result<(), String> {
+        if self.tables().iter().any(|table| self.is_in_hand(*table)) {
+            return Err("can't chop in the middle of a hand".to_string());
+        }
+        let Some(deal) = self.chop() else {
+            return Err("there is nothing to chop".to_string());
+        };
+        self.deal = Some(deal);
+
+        Ok(())
+    }
+
+    /// Moves on to the next blind level if the current one is over and starts a hand at the
+    /// table. Returns the events of each table keyed by table. During hand-for-hand play a table
+    /// that has played its hand has to wait for the others to finish theirs.
+    pub fn start_hand(&mut self, table: usize) -> Result<Vec<(usize, TableEvent)>, String> {
+        if self.is_finished() {
+            return Err("the tournament is over".to_string());
+        }
+        if self.hand_for_hand && self.played_round.get(table) == Some(&true) {
+            return Err("waiting for the other tables to finish their hand".to_string());
+        }
+        if self.level_over() && self.level + 1 < self.config.levels.len() {
+            self.level += 1;
+            self.level_started = self.clock.now();
+            self.level_hands = vec![0; self.tables.len()];
+        }
+        let level = self.config.levels[self.level];
+        let Some(game) = self.tables.get_mut(table).and_then(|game| game.as_mut()) else {
+            return Err(format!("there is no table {}", table + 1));
+        };
+
+        let mut events = vec![];
+        let settings = game.settings();
+        if settings.small_blind != level.small_blind
+            || settings.big_blind != level.big_blind
+            || game.ante() != level.ante
+        {
+            game.set_stakes(level.small_blind, level.big_blind, level.ante);
+            events.push(TableEvent::BlindsUp {
+                small_blind: level.small_blind,
+                big_blind: level.big_blind,
+                ante: level.ante,
+            });
+        }
+        let stacks: Vec<(usize, u32)> = game
+            .seats()
+            .iter()
+            .enumerate()
+            .filter_map(|(seat, player)| player.as_ref().map(|player| (seat, player.chips)))
+            .collect();
+        events.extend(game.start_hand()?);
+        self.keep_hand_log(table);
+        for (seat, chips) in stacks {
+            if let Some(entrant) = self.entrant_at(table, seat) {
+                self.starting_stacks[entrant] = chips;
+            }
+        }
+        self.level_hands[table] += 1;
+
+        let mut events: Vec<(usize, TableEvent)> =
+            events.into_iter().map(|event| (table, event)).collect();
+        self.hand_over(table, &mut events);
+
+        Ok(events)
+    }
+
+    /// Takes the action for the player to act at the table. Once the hand is over the players
+    /// left without chips are eliminated and the tables rebalanced.
+    pub fn act(
+        &mut self,
+        table: usize,
+        action: Action,
+    ) -> Result<Vec<(usize, TableEvent)>, String> {
+        let Some(game) = self.tables.get_mut(table).and_then(|game| game.as_mut()) else {
+            return Err(format!("there is no table {}", table + 1));
+        };
+        let mut events: Vec<(usize, TableEvent)> = game
+            .act(action)?
+            .into_iter()
+            .map(|event| (table, event))
+            .collect();
+        self.keep_hand_log(table);
+        self.hand_over(table, &mut events);
+
+        Ok(events)
+    }
+
+    /// Keeps the log of the table's hand if it is over, before the table can be broken up.
+    fn keep_hand_log(&mut self, table: usize) {
+        let log = match self.table(table) {
+            Some(game) if game.street().is_none() => game.hand_log().cloned(),
+            _ => None,
+        };
+        if let (Some(logs), Some(log)) = (self.hand_logs.as_mut(), log) {
+            logs.push(log);
+        }
+    }
+
+    /// Returns the entrants from the best placed to the worst: the players left by their
+    /// chips, followed by the eliminated players from the last out to the first.
+    pub fn finishing_order(&self) -> Vec<usize> {
+        let chips = self.chips();
+        let mut order: Vec<usize> = (0..self.entrants.len())
+            .filter(|entrant| !self.eliminated.contains(entrant))
+            .collect();
+        order.sort_by_key(|entrant| std::cmp::Reverse(chips[*entrant]));
+        order.extend(self.eliminated.iter().rev());
+
+        order
+    }
+
+    /// Returns the chip counts and tables of the players left and the places of the players
+    /// eliminated.
+    pub fn standings(&self) -> String {
+        let chips = self.chips();
+        let mut standings = format!(
+            "Standings (blinds {}/{}, ante {})\n",
+            self.level().small_blind,
+            self.level().big_blind,
+            self.level().ante
+        );
+        for (index, entrant) in self.finishing_order().into_iter().enumerate() {
+            let place = ordinal(index + 1);
+            let name = &self.entrants[entrant];
+            match self.seating[entrant] {
+                Some((table, seat)) => standings.push_str(&format!(
+                    "{:>4}  {} ({} chips, table {} seat {})\n",
+                    place,
+                    name,
+                    chips[entrant],
+                    table + 1,
+                    seat + 1
+                )),
+                None => standings.push_str(&format!("{:>4}  {} (out)\n", place, name)),
+            }
+        }
+
+        standings
+    }
+
+    /// Returns the finishing places along with what each paid place won.
+    pub fn results(&self) -> String {
+        let prizes = self.config.prizes(self.entrants.len());
+        let mut results = String::from("Results\n");
+        for (index, entrant) in self.finishing_order().into_iter().enumerate() {
+            results.push_str(&format!(
+                "{:>4}  {}",
+                ordinal(index + 1),
+                self.entrants[entrant]
+            ));
+            let chopped = self
+                .deal
+                .iter()
+                .flatten()
+                .find(|(player, _)| *player == entrant);
+            if let Some((_, prize)) = chopped {
+                results.push_str(&format!(" wins {} in a chop", prize));
+            } else if let Some(prize) = prizes.get(index).filter(|prize| **prize > 0) {
+                results.push_str(&format!(" wins {}", prize));
+            }
+            results.push('\n');
+        }
+
+        results
+    }
+
+    fn level_over(&self) -> bool {
+        match self.level().length {
+            LevelLength::Time(length) => {
+                self.clock.now().duration_since(self.level_started) >= length
+            }
+            LevelLength::Hands(hands) => self.level_hands.iter().any(|played| *played >= hands),
+        }
+    }
+
+    /// Once the table's hand is over, eliminates the players without chips and rebalances the
+    /// tables. During hand-for-hand play that waits until every table has finished its hand.
+    fn hand_over(&mut self, table: usize, events: &mut Vec<(usize, TableEvent)>) {
+        if self.is_in_hand(table) {
+            return;
+        }
+        if !self.hand_for_hand {
+            self.eliminate(&[table], events);
+            self.rebalance(events);
+            return;
+        }
+
+        self.played_round[table] = true;
+        let tables = self.tables();
+        let round_over = tables.iter().all(|table| {
+            !self.is_in_hand(*table)
+                && (self.played_round[*table]
+                    || self.table(*table).map_or(0, |game| game.players_seated()) < 2)
+        });
+        if round_over {
+            self.played_round = vec![false; self.tables.len()];
+            self.eliminate(&tables, events);
+            self.rebalance(events);
+        }
+    }
+
+    /// Eliminates the players without chips at the tables, placing the players who started
+    /// their hand with fewer chips below the others, whichever table they played at. Starts hand-for-hand play when the field
+    /// reaches the bubble.
+    fn eliminate(&mut self, tables: &[usize], events: &mut Vec<(usize, TableEvent)>) {
+        let chips = self.chips();
+        let mut busted: Vec<usize> = (0..self.entrants.len())
+            .filter(|entrant| match self.seating[*entrant] {
+                Some((table, _)) => tables.contains(&table) && chips[*entrant] == 0,
+                None => false,
+            })
+            .collect();
+        busted.sort_by_key(|entrant| self.starting_stacks[*entrant]);
+
+        for entrant in busted {
+            let Some((table, seat)) = self.seating[entrant] else {
+                continue;
+            };
+            let place = self.entrants.len() - self.eliminated.len();
+            if let Some(game) = self.tables[table].as_mut() {
+                if game.stand_up(seat).is_err() {
+                    continue;
+                }
+            }
+            self.seating[entrant] = None;
+            self.eliminated.push(entrant);
+            events.push((table, TableEvent::Eliminated { seat, place }));
+        }
+
+        let left = self.entrants.len() - self.eliminated.len();
+        let on_bubble = left == self.paid_places() + 1 && self.tables().len() > 1;
+        if on_bubble && !self.hand_for_hand {
+            self.played_round = vec![false; self.tables.len()];
+            for table in self.tables() {
+                events.push((table, TableEvent::HandForHand));
+            }
+        }
+        self.hand_for_hand = on_bubble;
+    }
+
+    /// Breaks up tables once the players left fit on fewer of them, and moves players so no
+    /// table has more than one player more than another. Tables in the middle of a hand are left
+    /// alone until their hand is over.
+    fn rebalance(&mut self, events: &mut Vec<(usize, TableEvent)>) {
+        loop {
+            let tables = self.tables();
+            let left = self.entrants.len() - self.eliminated.len();
+            if tables.len() > left.div_ceil(self.config.seats) {
+                // break up the smallest table that isn't in a hand
+                let Some(broken) = tables
+                    .iter()
+                    .copied()
+                    .filter(|table| !self.is_in_hand(*table))
+                    .min_by_key(|table| self.players_at(*table))
+                else {
+                    return;
+                };
+                let seats: Vec<usize> = (0..self.config.seats)
+                    .filter(|seat| self.entrant_at(broken, *seat).is_some())
+                    .collect();
+                for seat in seats {
+                    let Some(to) = tables
+                        .iter()
+                        .copied()
+                        .filter(|table| *table != broken)
+                        .filter(|table| self.table(*table).and_then(|t| t.open_seat()).is_some())
+                        .min_by_key(|table| self.players_at(*table))
+                    else {
+                        return;
+                    };
+                    self.move_player(broken, seat, to, events);
+                }
+                self.tables[broken] = None;
+                if let [final_table] = self.tables().as_slice() {
+                    events.push((*final_table, TableEvent::FinalTable));
+                    self.hand_for_hand = false;
+                }
+                continue;
+            }
+
+            let Some(biggest) = tables.iter().copied().max_by_key(|t| self.players_at(*t)) else {
+                return;
+            };
+            let Some(smallest) = tables.iter().copied().min_by_key(|t| self.players_at(*t)) else {
+                return;
+            };
+            if self.players_at(biggest) <= self.players_at(smallest) + 1 || self.is_in_hand(biggest)
+            {
+                return;
+            }
+            let Some(seat) = self.table(biggest).and_then(|game| game.next_big_blind()) else {
+                return;
+            };
+            self.move_player(biggest, seat, smallest, events);
+        }
+    }
+
+    /// Moves the player in the seat to an open seat at another table, along with their chips.
+    fn move_player(
+        &mut self,
+        from: usize,
+        seat: usize,
+        to: usize,
+        events: &mut Vec<(usize, TableEvent)>,
+    ) {
+        let Some(entrant) = self.entrant_at(from, seat) else {
+            return;
+        };
+        let Some(to_seat) = self.table(to).and_then(|game| game.open_seat()) else {
+            return;
+        };
+        let Some(Ok(chips)) = self.tables[from].as_mut().map(|game| game.stand_up(seat)) else {
+            return;
+        };
+        if let Some(game) = self.tables[to].as_mut() {
+            if game.sit_down(to_seat, chips).is_err() {
+                return;
+            }
+        }
+        self.seating[entrant] = Some((to, to_seat));
+        events.push((
+            from,
+            TableEvent::Moved {
+                seat,
+                table: to,
+                to_seat,
+            },
+        ));
+        events.push((
+            to,
+            TableEvent::Seated {
+                seat: to_seat,
+                chips,
+            },
+        ));
+    }
+
+    fn paid_places(&self) -> usize {
+        self.config
+            .prizes(self.entrants.len())
+            .iter()
+            .filter(|prize| **prize > 0)
+            .count()
+    }
+
+    fn is_in_hand(&self, table: usize) -> bool {
+        self.table(table)
+            .is_some_and(|game| game.street().is_some())
+    }
+
+    fn players_at(&self, table: usize) -> usize {
+        self.table(table).map_or(0, |game| game.players_seated())
+    }
+
+    /// Returns the chips of each entrant, 0 for those eliminated.
+    fn chips(&self) -> Vec<u32> {
+        self.seating
+            .iter()
+            .map(|seating| {
+                let Some((table, seat)) = seating else {
+                    return 0;
+                };
+                match self.table(*table).map(|game| &game.seats()[*seat]) {
+                    Some(Some(player)) => player.chips,
+                    _ => 0,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Plays a tournament at the terminal, with the players taking turns at the keyboard and the
+/// tables taking turns to play a hand. Hole cards are only shown to the player whose turn it
+/// is. Every hand is added to the log file as JSON Lines if there is one.
+pub fn run(
+    config: &str,
+    players: Vec<String>,
+    bots: Vec<String>,
+    log: Option<&str>,
+) -> Result<(), String> {
+    let config = TournamentConfig::load(config)?;
+    let humans = players.len();
+    let mut entrants = players.clone();
+    let mut strategies: Vec<Box<dyn Strategy>> = players
+        .iter()
+        .map(|name| Box::new(LinePlayer::new(name, io::stdin(), io::stdout())) as Box<dyn Strategy>)
+        .collect();
+    for (number, bot) in bots.iter().enumerate() {
+        let Some(kind) = BotKind::parse(bot) else {
+            return Err(format!("{} isn't a bot", bot));
+        };
+        entrants.push(format!("{}-bot-{}", kind, number + 1));
+        strategies.push(kind.create());
+    }
+    let mut tournament = Tournament::new(config, entrants, SystemClock)?;
+    let mut log = match log {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|err| format!("couldn't open {}: {}", path, err))?;
+            tournament.log_hands();
+            Some(file)
+        }
+        None => None,
+    };
+
+    println!("{}", tournament.standings());
+    while !tournament.is_finished() {
+        let mut played = false;
+        for table in tournament.tables() {
+            if tournament.is_finished() {
+                break;
+            }
+            let eliminated = tournament.eliminated().len();
+            let Ok(events) = tournament.start_hand(table) else {
+                continue;
+            };
+            played = true;
+            print_events(&tournament, &events);
+
+            while let Some(decision) = tournament.table(table).and_then(|game| game.decision()) {
+                let Some(entrant) = tournament.entrant_at(table, decision.seat) else {
+                    break;
+                };
+                let action = strategies[entrant].decide(&decision);
+                match tournament.act(table, action) {
+                    Ok(events) => print_events(&tournament, &events),
+                    Err(err) => println!("{}", err),
+                }
+            }
+            if let Some(file) = log.as_mut() {
+                for hand in tournament.take_hand_logs() {
+                    file.write_all(hand.to_json_lines().as_bytes())
+                        .map_err(|err| format!("couldn't write the hand log: {}", err))?;
+                }
+            }
+            if tournament.eliminated().len() <= eliminated {
+                continue;
+            }
+            println!("{}", tournament.standings());
+            if humans == 0 || !tournament.in_the_money() {
+                continue;
+            }
+            if let Some(proposal) = tournament.chop_proposal() {
+                println!(
+                    "{}Type chop to take the ICM chop, or anything else to play on",
+                    proposal
+                );
+                let mut line = String::new();
+                if io::stdin()
+                    .read_line(&mut line)
+                    .map_err(|err| err.to_string())?
+                    == 0
+                {
+                    return Err("ran out of input".to_string());
+                }
+                if line.trim() == "chop" {
+                    tournament.accept_chop()?;
+                }
+            }
+        }
+        if !played {
+            return Err("none of the tables could start a hand".to_string());
+        }
+    }
+    println!("{}", tournament.results());
+
+    Ok(())
+}
+
+fn print_events<C: Clock>(tournament: &Tournament<C>, events: &[(usize, TableEvent)]) {
+    let tables = tournament.tables.len();
+    for (table, event) in events {
+        let event = event.filter_for(Recipient::Spectator);
+        if tables > 1 {
+            println!("Table {}: {}", table + 1, event);
+        } else {
+            println!("{}", event);
+        }
+    }
+}
+
+fn parse_game(game: &str, limit: &str) -> Result<(GameType, BettingStructure), String> {
+    let game = match GameType::parse(game) {
+        Some(game @ (GameType::Holdem | GameType::ShortDeck)) => game,
+        Some(_) => return Err("tournaments are only played as holdem or shortdeck".to_string()),
+        None => return Err(format!("{} isn't a game", game)),
+    };
+    match BettingStructure::parse(limit, 0, 0) {
+        Some(structure) => Ok((game, structure)),
+        None => Err(format!("{} isn't a betting limit", limit)),
+    }
+}
+
+fn parse_level(small: &str, big: &str, ante: &str, length: &str) -> Result<BlindLevel, String> {
+    let length = if let Some(minutes) = length.strip_suffix("min") {
+        LevelLength::Time(Duration::from_secs(number::<u64>(minutes)? * 60))
+    } else if let Some(hands) = length.strip_suffix("hands") {
+        LevelLength::Hands(number(hands)?)
+    } else {
+        return Err(format!(
+            "{} isn't a level length, use minutes like 10min or hands like 12hands",
+            length
+        ));
+    };
+
+    Ok(BlindLevel {
+        small_blind: number(small)?,
+        big_blind: number(big)?,
+        ante: number(ante)?,
+        length,
+    })
+}
+
+fn number<T: FromStr>(word: &str) -> Result<T, String> {
+    word.parse().map_err(|_| format!("{} isn't a number", word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timer::ManualClock;
+
+    fn tournament(config: &str, entrants: usize) -> Tournament<ManualClock> {
+        let config = TournamentConfig::parse(config).unwrap();
+        let entrants = (1..=entrants).map(|n| format!("player{}", n)).collect();
+        Tournament::new(config, entrants, ManualClock::new()).unwrap()
+    }
+
+    /// Plays the table's hand out with everyone going all in or calling, returning its events.
+    fn play_all_in(
+        tournament: &mut Tournament<ManualClock>,
+        table: usize,
+    ) -> Vec<(usize, TableEvent)> {
+        let mut events = tournament.start_hand(table).unwrap();
+        while let Some(decision) = tournament.table(table).and_then(|game| game.decision()) {
+            let action = match decision.legal.raise {
+                Some((_, max)) => Action::Raise(max),
+                None => Action::Call,
+            };
+            events.extend(tournament.act(table, action).unwrap());
+        }
+        events
+    }
+
+    #[test]
+    fn prizes_split_pools_bigger_than_a_u32_product() {
+        let config = TournamentConfig::parse(
+            "buy_in 1000000000\nstarting_stack 1500\npayouts 50 30 20\nlevel 10 20 0 10min",
+        )
+        .unwrap();
+        assert_eq!(
+            config.prizes(4),
+            vec![2_000_000_000, 1_200_000_000, 800_000_000]
+        );
+        let entrants = (0..5).map(|n| n.to_string()).collect();
+        assert!(Tournament::new(config, entrants, ManualClock::new()).is_err());
+    }
+
+    #[test]
+    fn prizes_give_rounding_to_first() {
+        let config = TournamentConfig::parse(
+            "buy_in 10\nstarting_stack 1500\npayouts 50 30 20\nlevel 10 20 0 10min",
+        )
+        .unwrap();
+        assert_eq!(config.prizes(3), vec![15, 9, 6]);
+        assert_eq!(config.prizes(7), vec![35, 21, 14]);
+        assert_eq!(config.prizes(2), vec![14, 6]);
+    }
+
+    #[test]
+    fn hand_levels_go_up_when_any_table_reaches_them() {
+        let mut tournament = tournament(
+            "seats 2\nstarting_stack 1500\npayouts 100\nlevel 10 20 0 2hands\nlevel 20 40 0 2hands",
+            4,
+        );
+        for _ in 0..2 {
+            tournament.start_hand(0).unwrap();
+            tournament.act(0, Action::Fold).unwrap();
+        }
+        assert_eq!(tournament.level().big_blind, 20);
+        // the second table hasn't played a hand, but the first has played the level out
+        let events = tournament.start_hand(1).unwrap();
+        assert_eq!(tournament.level().big_blind, 40);
+        assert!(events
+            .iter()
+            .any(|(_, event)| matches!(event, TableEvent::BlindsUp { big_blind: 40, .. })));
+    }
+
+    #[test]
+    fn hand_for_hand_busts_are_placed_by_starting_stack() {
+        let mut tournament = tournament(
+            "seats 2\nstarting_stack 1500\npayouts 100\nlevel 10 20 0 10min",
+            4,
+        );
+        // the first table plays for 1000 each and the second for 500 each
+        for (table, chips) in [(0, 1000), (1, 500)] {
+            let game = tournament.tables[table].as_mut().unwrap();
+            for seat in 0..2 {
+                game.stand_up(seat).unwrap();
+                game.sit_down(seat, chips).unwrap();
+            }
+            game.seed_deck(table as u64 + 3);
+        }
+        tournament.hand_for_hand = true;
+
+        play_all_in(&mut tournament, 0);
+        // nobody is placed until the second table has played its hand too
+        assert!(tournament.eliminated().is_empty());
+        play_all_in(&mut tournament, 1);
+        assert_eq!(tournament.eliminated().len(), 2);
+        // the player who busted from 500 finishes below the one who busted from 1000
+        let starting: Vec<u32> = tournament
+            .eliminated()
+            .iter()
+            .map(|entrant| tournament.starting_stacks[*entrant])
+            .collect();
+        assert_eq!(starting, vec![500, 1000]);
+    }
+
+    /// Checks that every player left sits in exactly one seat of a table in play, that the
+    /// tables are as few as the players fit on and within one player of each other, and that
+    /// no chips went missing.
+    fn assert_balanced(tournament: &Tournament<ManualClock>) {
+        let left = tournament.entrants.len() - tournament.eliminated.len();
+        let tables = tournament.tables();
+        assert_eq!(tables.len(), left.div_ceil(tournament.config.seats));
+
+        let mut seated = vec![];
+        for (entrant, seating) in tournament.seating.iter().enumerate() {
+            assert_eq!(seating.is_none(), tournament.eliminated.contains(&entrant));
+            if let Some((table, seat)) = seating {
+                assert!(tables.contains(table));
+                assert!(tournament.table(*table).unwrap().seats()[*seat].is_some());
+                seated.push((*table, *seat));
+            }
+        }
+        seated.sort();
+        seated.dedup();
+        assert_eq!(seated.len(), left);
+        let sizes: Vec<usize> = tables
+            .iter()
+            .map(|table| tournament.players_at(*table))
+            .collect();
+        assert_eq!(sizes.iter().sum::<usize>(), left);
+        let (most, fewest) = (sizes.iter().max().unwrap(), sizes.iter().min().unwrap());
+        assert!(most - fewest <= 1, "unbalanced tables {:?}", sizes);
+
+        let chips: u32 = tournament.chips().iter().sum();
+        assert_eq!(chips, 1500 * tournament.entrants.len() as u32);
+    }
+
+    #[test]
+    fn tables_are_balanced_broken_and_merged_as_players_bust() {
+        let mut tournament = tournament(
+            "seats 4\nstarting_stack 1500\npayouts 50 30 20\nlevel 10 20 0 10min",
+            10,
+        );
+        assert_eq!(tournament.tables(), vec![0, 1, 2]);
+        for table in 0..3 {
+            tournament.tables[table]
+                .as_mut()
+                .unwrap()
+                .seed_deck(6 + table as u64);
+        }
+        assert_balanced(&tournament);
+
+        let mut balancing_moves = 0;
+        let mut broken = vec![];
+        let mut final_table = false;
+        while !tournament.is_finished() {
+            // every table plays a hand, so hand-for-hand rounds are over before checking
+            let mut events = vec![];
+            for table in tournament.tables() {
+                if tournament.players_at(table) < 2 {
+                    continue;
+                }
+                events.extend(play_all_in(&mut tournament, table));
+            }
+            for (from, event) in &events {
+                match event {
+                    TableEvent::Moved { .. } if tournament.table(*from).is_some() => {
+                        balancing_moves += 1
+                    }
+                    TableEvent::FinalTable => final_table = true,
+                    _ => {}
+                }
+            }
+            for table in 0..3 {
+                if tournament.table(table).is_none() && !broken.contains(&table) {
+                    broken.push(table);
+                }
+            }
+            assert_balanced(&tournament);
+            tournament.clock.advance(Duration::from_secs(60));
+        }
+
+        assert!(balancing_moves > 0);
+        assert_eq!(broken.len(), 2);
+        assert!(final_table);
+        assert_eq!(tournament.eliminated().len(), 9);
+    }
+}